@@ -0,0 +1,200 @@
+//! Breadth-first distances over the grid, for when every step costs the same.
+//! Searches start from any number of sources at once, and only step onto
+//! cells that a passability predicate allows, so the same search measures
+//! distances from a single start, from every exit of a maze at once, or
+//! whether two cells are connected at all.
+//!
+//! Searches record `bfs.expanded` (cells whose neighbors were explored) with
+//! [`stats`](crate::stats).
+
+use std::{
+    collections::{HashMap, VecDeque, hash_map::Entry},
+    fmt::{self, Display, Formatter},
+};
+
+use gridly::prelude::*;
+
+use crate::{grid::Rect, stats::Counter};
+
+/// The number of orthogonal steps from the nearest of `sources` to every
+/// cell reachable from them through cells where `passable` is true. Sources
+/// that aren't passable are skipped. The predicate has to rule out all but
+/// finitely many cells, or the search never ends; use [`distance_field`] to
+/// search within fixed bounds instead.
+pub fn distances(
+    sources: impl IntoIterator<Item = Location>,
+    mut passable: impl FnMut(Location) -> bool,
+) -> HashMap<Location, isize> {
+    let mut expanded = Counter::new("bfs.expanded");
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for source in sources {
+        if passable(source) && distances.insert(source, 0).is_none() {
+            queue.push_back(source);
+        }
+    }
+
+    while let Some(location) = queue.pop_front() {
+        let distance = distances[&location];
+        expanded.increment();
+
+        for direction in EACH_DIRECTION {
+            let neighbor = location + direction;
+
+            if let Entry::Vacant(slot) = distances.entry(neighbor)
+                && passable(neighbor)
+            {
+                slot.insert(distance + 1);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    distances
+}
+
+/// Like [`distances`], but only searching within `bounds`, and storing the
+/// result densely, so that lookups don't need any hashing
+pub fn distance_field(
+    bounds: Rect,
+    sources: impl IntoIterator<Item = Location>,
+    mut passable: impl FnMut(Location) -> bool,
+) -> DistanceField {
+    let mut expanded = Counter::new("bfs.expanded");
+    let mut field = DistanceField {
+        bounds,
+        distances: vec![None; bounds.area()],
+    };
+    let mut queue = VecDeque::new();
+
+    let mut visit = |field: &mut DistanceField, location: Location, distance: isize| {
+        let Some(index) = field.index(location) else {
+            return false;
+        };
+
+        let unvisited = field.distances[index].is_none() && passable(location);
+
+        if unvisited {
+            field.distances[index] = Some(distance);
+        }
+
+        unvisited
+    };
+
+    for source in sources {
+        if visit(&mut field, source, 0) {
+            queue.push_back(source);
+        }
+    }
+
+    while let Some(location) = queue.pop_front() {
+        let distance = field.get(location).expect("queued cells have a distance");
+        expanded.increment();
+
+        for direction in EACH_DIRECTION {
+            let neighbor = location + direction;
+
+            if visit(&mut field, neighbor, distance + 1) {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    field
+}
+
+/// The distance to every cell within some bounds, as computed by
+/// [`distance_field`]. Displays as a grid of right-aligned distances, with
+/// `.` for unreachable cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistanceField {
+    bounds: Rect,
+
+    /// Distances, in row-major order
+    distances: Vec<Option<isize>>,
+}
+
+impl DistanceField {
+    fn index(&self, location: Location) -> Option<usize> {
+        let offset = self.bounds.check_location(location).ok()? - self.bounds.root();
+        Some((offset.rows.0 * self.bounds.dimensions().columns.0 + offset.columns.0) as usize)
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    /// The distance to `location`, or None if it's out of bounds or
+    /// unreachable
+    pub fn get(&self, location: Location) -> Option<isize> {
+        self.distances[self.index(location)?]
+    }
+
+    /// Every reachable location, along with its distance, row by row
+    pub fn reachable(&self) -> impl Iterator<Item = (Location, isize)> + '_ {
+        self.bounds
+            .locations()
+            .zip(&self.distances)
+            .filter_map(|(location, &distance)| Some((location, distance?)))
+    }
+}
+
+impl Display for DistanceField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self
+            .reachable()
+            .map(|(_, distance)| distance.to_string().len())
+            .max()
+            .unwrap_or(1);
+
+        let columns = self.bounds.dimensions().columns.0.max(1) as usize;
+
+        self.distances.chunks(columns).try_for_each(|row| {
+            for (column, distance) in row.iter().enumerate() {
+                if column > 0 {
+                    f.write_str(" ")?;
+                }
+
+                match distance {
+                    Some(distance) => write!(f, "{distance:>width$}")?,
+                    None => write!(f, "{:>width$}", ".")?,
+                }
+            }
+
+            writeln!(f)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_come_from_the_nearest_source() {
+        let bounds = Rect::new(Location::zero(), Vector::new(3, 5));
+        let wall = Location::new(1, 2);
+        let sources = [Location::new(0, 0), Location::new(2, 4)];
+
+        let field = distance_field(bounds, sources, |location| location != wall);
+
+        assert_eq!(field.get(Location::new(0, 0)), Some(0));
+        assert_eq!(field.get(Location::new(0, 2)), Some(2));
+        assert_eq!(field.get(Location::new(0, 4)), Some(2));
+        assert_eq!(field.get(wall), None);
+        assert_eq!(field.get(Location::new(5, 5)), None);
+        assert_eq!(field.to_string(), "0 1 2 3 2\n1 2 . 2 1\n2 3 2 1 0\n");
+
+        let hashed = distances(sources, |location| {
+            bounds.contains(location) && location != wall
+        });
+
+        assert_eq!(hashed.len(), 14);
+        assert!(
+            field
+                .reachable()
+                .all(|(location, distance)| hashed[&location] == distance)
+        );
+    }
+}
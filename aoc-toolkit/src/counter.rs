@@ -1,3 +1,6 @@
+//! Counting things: a multiset, backed by a hash map, a btree map, or an
+//! enum map.
+
 use std::{
     borrow::Borrow,
     cmp::Reverse,
@@ -10,7 +13,7 @@ use std::{
 use enum_map::{EnumArray, EnumMap};
 use itertools::Itertools;
 
-use super::try_build_iter;
+use crate::iter::try_build_iter;
 
 /// The underlying store for the `Counter` type. Implements a simple key-value
 /// store.
@@ -47,6 +50,12 @@ pub trait CounterStore: Sized {
     #[must_use]
     fn len(&self) -> usize;
 
+    /// Test if there are no keys with nonzero counts in this store
+    #[must_use]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Iterate over all keys and their counts. This might include keys with
     /// a count of 0.
     #[must_use]
@@ -68,13 +77,11 @@ impl<T: Hash + Eq> CounterStore for HashMap<T, usize> {
         T: 'a;
 
     #[inline]
-    #[must_use]
     fn new() -> Self {
         HashMap::new()
     }
 
     #[inline]
-    #[must_use]
     fn get(&self, key: &Self::Item) -> usize {
         self.get(key).copied().unwrap_or(0)
     }
@@ -85,13 +92,11 @@ impl<T: Hash + Eq> CounterStore for HashMap<T, usize> {
     }
 
     #[inline]
-    #[must_use]
     fn len(&self) -> usize {
         self.len()
     }
 
     #[inline]
-    #[must_use]
     fn iter(&self) -> Self::Iter<'_> {
         self.iter()
     }
@@ -114,13 +119,11 @@ impl<T: Ord> CounterStore for BTreeMap<T, usize> {
         T: 'a;
 
     #[inline]
-    #[must_use]
     fn new() -> Self {
         BTreeMap::new()
     }
 
     #[inline]
-    #[must_use]
     fn get(&self, key: &Self::Item) -> usize {
         self.get(key).copied().unwrap_or(0)
     }
@@ -131,13 +134,11 @@ impl<T: Ord> CounterStore for BTreeMap<T, usize> {
     }
 
     #[inline]
-    #[must_use]
     fn len(&self) -> usize {
         self.len()
     }
 
     #[inline]
-    #[must_use]
     fn iter(&self) -> Self::Iter<'_> {
         self.iter()
     }
@@ -160,13 +161,11 @@ impl<T: EnumArray<usize> + Copy> CounterStore for EnumMap<T, usize> {
         T: 'a;
 
     #[inline]
-    #[must_use]
     fn new() -> Self {
         Self::default()
     }
 
     #[inline]
-    #[must_use]
     fn get(&self, key: &Self::Item) -> usize {
         self[*key]
     }
@@ -177,13 +176,11 @@ impl<T: EnumArray<usize> + Copy> CounterStore for EnumMap<T, usize> {
     }
 
     #[inline]
-    #[must_use]
     fn len(&self) -> usize {
         self.iter().filter(|&(_, &count)| count > 0).count()
     }
 
     #[inline]
-    #[must_use]
     fn iter(&self) -> Self::Iter<'_> {
         self.iter()
     }
@@ -219,6 +216,13 @@ impl<T, Store: CounterStore<Item = T>> Counter<T, Store> {
         self.counts.len()
     }
 
+    /// Test if there are no items in the collection
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
     /// Test if there's at least one of `value` in the store
     #[inline]
     #[must_use]
@@ -273,13 +277,13 @@ impl<T, Store: CounterStore<Item = T>> Counter<T, Store> {
         }
 
         iter.for_each(|(item, count)| {
-            if let Some(last) = buffer.last_mut() {
-                if last.1 < count {
-                    *last = (item, count);
+            if let Some(last) = buffer.last_mut()
+                && last.1 < count
+            {
+                *last = (item, count);
 
-                    if N > 1 {
-                        buffer.sort_unstable_by_key(|&(_, count)| Reverse(count));
-                    }
+                if N > 1 {
+                    buffer.sort_unstable_by_key(|&(_, count)| Reverse(count));
                 }
             }
         });
@@ -297,7 +301,6 @@ impl<T, Store: CounterStore<Item = T>> Counter<T, Store> {
 
 impl<T, Store: CounterStore<Item = T>> Default for Counter<T, Store> {
     #[inline]
-    #[must_use]
     fn default() -> Self {
         Self::new()
     }
@@ -353,3 +356,40 @@ where
 
 pub type HashCounter<T> = Counter<T, HashMap<T, usize>>;
 pub type EnumCounter<T> = Counter<T, EnumMap<T, usize>>;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn counts_items() {
+        let counter: Counter<char> = "mississippi".chars().collect();
+
+        assert_eq!(counter.get(&'s'), 4);
+        assert_eq!(counter.get(&'m'), 1);
+        assert_eq!(counter.get(&'z'), 0);
+        assert!(!counter.contains(&'z'));
+        assert_eq!(counter.len(), 4);
+    }
+
+    #[test]
+    fn top_finds_the_most_common() {
+        let counter: Counter<char, BTreeMap<char, usize>> = "mississippi".chars().collect();
+        let [(first, first_count), (second, second_count)] = counter.top().unwrap();
+
+        assert_eq!((*first, first_count.get()), ('i', 4));
+        assert_eq!((*second, second_count.get()), ('s', 4));
+        assert!(counter.top::<5>().is_none());
+    }
+
+    #[test]
+    fn extends_with_counts() {
+        let mut counter: Counter<&str> = Counter::new();
+        counter.extend([("a", 3), ("b", 0), ("a", 2)]);
+
+        assert_eq!(counter.get(&"a"), 5);
+        assert!(!counter.contains(&"b"));
+    }
+}
@@ -0,0 +1,241 @@
+//! Dijkstra's algorithm and A* over arbitrary state graphs. Both searches
+//! record `dijkstra.expanded` (states whose successors were explored),
+//! `dijkstra.pushes` (entries added to the queue), and `dijkstra.stale`
+//! (entries popped for states that were already expanded) with
+//! [`stats`](crate::stats).
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, hash_map::Entry},
+    hash::Hash,
+    iter,
+};
+
+use crate::stats::Counter;
+
+/// A state in the queue. Ordered only by cost, so that states themselves
+/// don't need to be `Ord`.
+#[derive(Debug, Clone)]
+struct QueueEntry<S> {
+    cost: i64,
+    state: S,
+}
+
+impl<S> Ord for QueueEntry<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Sort entries such that the "larger" entry has a lower cost
+        Ord::cmp(&other.cost, &self.cost)
+    }
+}
+
+impl<S> PartialOrd for QueueEntry<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> Eq for QueueEntry<S> {}
+
+impl<S> PartialEq for QueueEntry<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+/// Compute the minimal cost from any of the `starts` to every reachable
+/// state. `successors` returns each state reachable in one step from a given
+/// state, along with the (non-negative) cost of that step.
+pub fn distances<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+) -> HashMap<S, i64>
+where
+    S: Hash + Eq + Clone,
+    I: IntoIterator<Item = (S, i64)>,
+{
+    let mut expanded = Counter::new("dijkstra.expanded");
+    let mut pushes = Counter::new("dijkstra.pushes");
+    let mut stale = Counter::new("dijkstra.stale");
+
+    let mut costs = HashMap::new();
+    let mut queue: BinaryHeap<QueueEntry<S>> = starts
+        .into_iter()
+        .map(|state| QueueEntry { cost: 0, state })
+        .collect();
+
+    pushes.add(queue.len() as u64);
+
+    while let Some(QueueEntry { cost, state }) = queue.pop() {
+        match costs.entry(state) {
+            // Already found a path here that's at least as cheap
+            Entry::Occupied(_) => stale.increment(),
+            Entry::Vacant(slot) => {
+                let state = slot.key().clone();
+                slot.insert(cost);
+                expanded.increment();
+
+                queue.extend(
+                    successors(&state)
+                        .into_iter()
+                        .filter(|(next, _)| !costs.contains_key(next))
+                        .map(|(next, step)| QueueEntry {
+                            cost: cost + step,
+                            state: next,
+                        })
+                        .inspect(|_| pushes.increment()),
+                );
+            }
+        }
+    }
+
+    costs
+}
+
+/// A cheapest route found by `shortest_path`
+#[derive(Debug, Clone)]
+pub struct Route<S> {
+    pub cost: i64,
+
+    /// Every state along the route, from the start to the goal inclusive
+    pub states: Vec<S>,
+}
+
+/// A state reached during an A* search, along with how it was reached
+#[derive(Debug, Clone)]
+struct Step<S> {
+    state: S,
+    cost: i64,
+    previous: Option<S>,
+}
+
+/// Find a cheapest route from `start` to any state for which `is_goal` is
+/// true, with A*. `successors` is as for `distances`. `heuristic` estimates
+/// the remaining cost from a state to the nearest goal; it must never
+/// overestimate, and must be consistent, so that each state's cost is final
+/// the first time it's reached. A heuristic that's always 0 gives Dijkstra.
+pub fn shortest_path<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> i64,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<Route<S>>
+where
+    S: Hash + Eq + Clone,
+    I: IntoIterator<Item = (S, i64)>,
+{
+    let mut expanded = Counter::new("dijkstra.expanded");
+    let mut pushes = Counter::new("dijkstra.pushes");
+    let mut stale = Counter::new("dijkstra.stale");
+
+    // Map from each visited state to the state it was reached from
+    let mut previous: HashMap<S, Option<S>> = HashMap::new();
+
+    let mut queue = BinaryHeap::from([QueueEntry {
+        cost: heuristic(&start),
+        state: Step {
+            state: start,
+            cost: 0,
+            previous: None,
+        },
+    }]);
+
+    pushes.increment();
+
+    while let Some(QueueEntry { state: step, .. }) = queue.pop() {
+        match previous.entry(step.state) {
+            Entry::Occupied(_) => stale.increment(),
+            Entry::Vacant(slot) => {
+                let state = slot.key().clone();
+                slot.insert(step.previous);
+                expanded.increment();
+
+                if is_goal(&state) {
+                    let mut states: Vec<S> = iter::successors(Some(state), |state| {
+                        previous.get(state).cloned().flatten()
+                    })
+                    .collect();
+
+                    states.reverse();
+
+                    return Some(Route {
+                        cost: step.cost,
+                        states,
+                    });
+                }
+
+                for (next, cost) in successors(&state) {
+                    if previous.contains_key(&next) {
+                        continue;
+                    }
+
+                    let cost = step.cost + cost;
+                    pushes.increment();
+
+                    queue.push(QueueEntry {
+                        cost: cost + heuristic(&next),
+                        state: Step {
+                            state: next,
+                            cost,
+                            previous: Some(state.clone()),
+                        },
+                    });
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A line of states, where each step forward costs 1 and a shortcut from
+    /// 0 to 5 costs 3
+    fn line(&state: &i32) -> Vec<(i32, i64)> {
+        let mut next = vec![(state + 1, 1)];
+
+        if state == 0 {
+            next.push((5, 3));
+        }
+
+        next.retain(|&(next, _)| next <= 8);
+        next
+    }
+
+    #[test]
+    fn distances_take_the_cheapest_step() {
+        let costs = distances([0], line);
+
+        assert_eq!(costs[&4], 4);
+        assert_eq!(costs[&5], 3);
+        assert_eq!(costs[&8], 6);
+        assert_eq!(costs.len(), 9);
+    }
+
+    #[test]
+    fn distances_from_several_starts() {
+        let costs = distances([0, 7], line);
+        assert_eq!(costs[&8], 1);
+    }
+
+    #[test]
+    fn shortest_path_follows_the_shortcut() {
+        let route = shortest_path(
+            0,
+            line,
+            |&state| i64::from(8 - state).min(0),
+            |&state| state == 8,
+        )
+        .unwrap();
+
+        assert_eq!(route.cost, 6);
+        assert_eq!(route.states, [0, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn shortest_path_to_nowhere() {
+        assert!(shortest_path(0, line, |_| 0, |&state| state == 100).is_none());
+    }
+}
@@ -1,7 +1,10 @@
+//! A map with a value for each of the four orthogonal directions
+
 use std::ops::{Index, IndexMut};
 
 use gridly::direction::*;
 
+/// A value for each of [`Up`], [`Right`], [`Down`] and [`Left`]
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct DirectionMap<T> {
     values: [T; 4],
@@ -32,7 +35,6 @@ impl<T> Index<Direction> for DirectionMap<T> {
     type Output = T;
 
     #[inline]
-    #[must_use]
     fn index(&self, index: Direction) -> &Self::Output {
         match index {
             Up => &self.values[0],
@@ -45,7 +47,6 @@ impl<T> Index<Direction> for DirectionMap<T> {
 
 impl<T> IndexMut<Direction> for DirectionMap<T> {
     #[inline]
-    #[must_use]
     fn index_mut(&mut self, index: Direction) -> &mut Self::Output {
         match index {
             Up => &mut self.values[0],
@@ -56,14 +57,29 @@ impl<T> IndexMut<Direction> for DirectionMap<T> {
     }
 }
 
+/// Build a [`DirectionMap`] by matching each direction against a list of
+/// patterns, like a `match` expression.
+///
+/// ```
+/// use aoc_toolkit::direction_map;
+/// use gridly::direction::*;
+///
+/// let vertical = direction_map! {
+///     Up | Down => true,
+///     Left | Right => false,
+/// };
+///
+/// assert!(vertical[Up]);
+/// assert!(!vertical[Left]);
+/// ```
 #[macro_export]
 macro_rules! direction_map {
     ( $( $direction:pat $(if $condition:expr )? => $value:expr ),+ $(,)? ) => {
-        $crate::library::direction_map::DirectionMap::from_array([
-            match gridly::direction::Up { $($direction $(if $condition )? => $value,)+ },
-            match gridly::direction::Right { $($direction $(if $condition )? => $value,)+ },
-            match gridly::direction::Down { $($direction $(if $condition )? => $value,)+ },
-            match gridly::direction::Left { $($direction $(if $condition )? => $value,)+ },
+        $crate::direction_map::DirectionMap::from_array([
+            match ::gridly::direction::Up { $($direction $(if $condition )? => $value,)+ },
+            match ::gridly::direction::Right { $($direction $(if $condition )? => $value,)+ },
+            match ::gridly::direction::Down { $($direction $(if $condition )? => $value,)+ },
+            match ::gridly::direction::Left { $($direction $(if $condition )? => $value,)+ },
         ])
     };
 }
@@ -0,0 +1,682 @@
+//! A library for doing dynamic programming in a non-recursive way. `execute`
+//! records `dynamic.solves` (calls to `Task::solve`), `dynamic.restarts`
+//! (solves interrupted by a missing dependency), `dynamic.cache_hits`
+//! (subtask solutions found in the store), and `dynamic.stored` (solutions
+//! added to the store) with [`stats`](crate::stats).
+//!
+//! Goals that are expensive to hash or compare, like long strings, can be
+//! solved with [`execute_interned`] instead, which assigns each distinct goal
+//! a small [`GoalId`] with an [`Interner`]. The dependency stack and the store
+//! then only ever deal in ids, so each goal is hashed once per request, rather
+//! than again for every store lookup, insertion, and cycle check.
+
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+};
+
+use crate::stats::{self, Counter};
+
+pub trait SubtaskStore<K, V> {
+    /// Add a new subtask solution to the store. Return the old solution, if
+    /// present.
+    fn add(&mut self, goal: K, solution: V) -> Option<V>;
+
+    /// Fetch a known solution for a subtask, if possible
+    fn get(&self, goal: &K) -> Option<&V>;
+
+    /// Check if a subtask has a known solution
+    fn contains(&self, goal: &K) -> bool;
+}
+
+impl<K, V, T> SubtaskStore<K, V> for &mut T
+where
+    T: SubtaskStore<K, V>,
+{
+    fn add(&mut self, goal: K, solution: V) -> Option<V> {
+        T::add(*self, goal, solution)
+    }
+
+    fn get(&self, goal: &K) -> Option<&V> {
+        T::get(*self, goal)
+    }
+
+    fn contains(&self, goal: &K) -> bool {
+        T::contains(*self, goal)
+    }
+}
+
+impl<K, V, S> SubtaskStore<K, V> for HashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: Default + BuildHasher,
+{
+    fn add(&mut self, goal: K, solution: V) -> Option<V> {
+        self.insert(goal, solution)
+    }
+
+    fn get(&self, goal: &K) -> Option<&V> {
+        self.get(goal)
+    }
+
+    fn contains(&self, goal: &K) -> bool {
+        self.contains_key(goal)
+    }
+}
+
+/// A dense store for interned goals, indexed by id
+impl<V> SubtaskStore<GoalId, V> for Vec<Option<V>> {
+    fn add(&mut self, GoalId(id): GoalId, solution: V) -> Option<V> {
+        let id = id as usize;
+
+        if self.len() <= id {
+            self.resize_with(id + 1, || None);
+        }
+
+        self[id].replace(solution)
+    }
+
+    fn get(&self, &GoalId(id): &GoalId) -> Option<&V> {
+        <[_]>::get(self, id as usize)?.as_ref()
+    }
+
+    fn contains(&self, goal: &GoalId) -> bool {
+        SubtaskStore::get(self, goal).is_some()
+    }
+}
+
+impl<K: Ord, V> SubtaskStore<K, V> for BTreeMap<K, V> {
+    fn add(&mut self, goal: K, solution: V) -> Option<V> {
+        self.insert(goal, solution)
+    }
+
+    fn get(&self, goal: &K) -> Option<&V> {
+        self.get(goal)
+    }
+
+    fn contains(&self, goal: &K) -> bool {
+        self.contains_key(goal)
+    }
+}
+
+/// A small integer standing in for a goal, assigned by an [`Interner`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GoalId(u32);
+
+/// Assigns each distinct goal a [`GoalId`], in the order they're first seen.
+/// An interner can be reused between calls to [`execute_interned`], along
+/// with the store, as long as the two are always used together.
+#[derive(Debug, Clone)]
+pub struct Interner<K> {
+    ids: HashMap<K, GoalId>,
+    goals: Vec<K>,
+}
+
+impl<K> Default for Interner<K> {
+    fn default() -> Self {
+        Self {
+            ids: HashMap::new(),
+            goals: Vec::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone> Interner<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the id for a goal, assigning it a new one if it hasn't been seen
+    /// before
+    pub fn intern(&mut self, goal: K) -> GoalId {
+        if let Some(&id) = self.ids.get(&goal) {
+            return id;
+        }
+
+        let id = GoalId(
+            self.goals
+                .len()
+                .try_into()
+                .expect("interned more than u32::MAX goals"),
+        );
+
+        self.goals.push(goal.clone());
+        self.ids.insert(goal, id);
+        id
+    }
+
+    /// Get the id for a goal, if it's been interned
+    pub fn id(&self, goal: &K) -> Option<GoalId> {
+        self.ids.get(goal).copied()
+    }
+
+    /// Get the goal with a given id
+    pub fn goal(&self, GoalId(id): GoalId) -> &K {
+        &self.goals[id as usize]
+    }
+
+    /// The number of distinct goals interned so far
+    pub fn len(&self) -> usize {
+        self.goals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.goals.is_empty()
+    }
+}
+
+#[derive(Debug)]
+pub struct Dependency<'a, K> {
+    key: K,
+    lifetime: PhantomData<&'a K>,
+}
+
+#[derive(Debug)]
+pub enum TaskInterrupt<'a, K, E> {
+    Dependency(Dependency<'a, K>),
+    Error(E),
+    Tail(K),
+}
+
+impl<'a, K, E> From<Dependency<'a, K>> for TaskInterrupt<'a, K, E> {
+    fn from(dep: Dependency<'a, K>) -> Self {
+        TaskInterrupt::Dependency(dep)
+    }
+}
+
+pub trait Subtask<Goal, Solution> {
+    fn precheck(&self, goals: impl IntoIterator<Item = Goal>) -> Result<(), Dependency<'_, Goal>>;
+    fn solve(&self, goal: Goal) -> Result<&Solution, Dependency<'_, Goal>>;
+}
+
+pub trait Task<Goal, Solution, Error> {
+    type State;
+
+    fn solve<'sub>(
+        &self,
+        goal: &Goal,
+        subtasker: &'sub impl Subtask<Goal, Solution>,
+        state: &mut Option<Self::State>,
+    ) -> Result<Solution, TaskInterrupt<'sub, Goal, Error>>;
+}
+
+pub trait StatelessTask<Goal, Solution, Error> {
+    fn solve<'sub>(
+        &self,
+        goal: &Goal,
+        subtasker: &'sub impl Subtask<Goal, Solution>,
+    ) -> Result<Solution, TaskInterrupt<'sub, Goal, Error>>;
+}
+
+impl<Goal, Solution, Error, T> Task<Goal, Solution, Error> for T
+where
+    T: StatelessTask<Goal, Solution, Error>,
+{
+    type State = Infallible;
+
+    #[inline(always)]
+    fn solve<'sub>(
+        &self,
+        goal: &Goal,
+        subtasker: &'sub impl Subtask<Goal, Solution>,
+        _state: &mut Option<Infallible>,
+    ) -> Result<Solution, TaskInterrupt<'sub, Goal, Error>> {
+        StatelessTask::solve(self, goal, subtasker)
+    }
+}
+
+#[derive(Debug)]
+pub enum DynamicError<K, E> {
+    /// The solver found a circular dependency while solving
+    CircularDependency(K),
+
+    /// The solver itself returned an error
+    Error(E),
+}
+
+impl<K: Debug, E> Display for DynamicError<K, E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            DynamicError::CircularDependency(ref dep) => {
+                write!(f, "goal {:?} has a circular dependency on itself", dep)
+            }
+            DynamicError::Error(..) => write!(f, "solver encountered an error"),
+        }
+    }
+}
+
+impl<K: Debug, E: Error + 'static> Error for DynamicError<K, E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            DynamicError::CircularDependency(..) => None,
+            DynamicError::Error(ref err) => Some(err),
+        }
+    }
+}
+
+/// How `run` refers to goals internally: either as themselves, or by an
+/// interned id
+trait Keys<Goal> {
+    type Key: PartialEq;
+
+    /// Get the key for a goal, registering it if necessary
+    fn key(&mut self, goal: Goal) -> Self::Key;
+
+    /// Get the goal for a key
+    fn goal<'a>(&'a self, key: &'a Self::Key) -> &'a Goal;
+
+    /// Convert a key back into its goal, to report it in an error
+    fn to_goal(&self, key: Self::Key) -> Goal;
+
+    /// Look up a goal's solution in the store, without registering it
+    fn lookup<'s, V>(
+        &self,
+        goal: &Goal,
+        store: &'s impl SubtaskStore<Self::Key, V>,
+    ) -> Option<&'s V>;
+}
+
+/// Goals are their own keys
+struct Direct;
+
+impl<Goal: PartialEq> Keys<Goal> for Direct {
+    type Key = Goal;
+
+    fn key(&mut self, goal: Goal) -> Goal {
+        goal
+    }
+
+    fn goal<'a>(&'a self, key: &'a Goal) -> &'a Goal {
+        key
+    }
+
+    fn to_goal(&self, key: Goal) -> Goal {
+        key
+    }
+
+    fn lookup<'s, V>(&self, goal: &Goal, store: &'s impl SubtaskStore<Goal, V>) -> Option<&'s V> {
+        store.get(goal)
+    }
+}
+
+impl<Goal: Eq + Hash + Clone> Keys<Goal> for &mut Interner<Goal> {
+    type Key = GoalId;
+
+    fn key(&mut self, goal: Goal) -> GoalId {
+        self.intern(goal)
+    }
+
+    fn goal<'a>(&'a self, &key: &'a GoalId) -> &'a Goal {
+        Interner::goal(self, key)
+    }
+
+    fn to_goal(&self, key: GoalId) -> Goal {
+        Interner::goal(self, key).clone()
+    }
+
+    fn lookup<'s, V>(&self, goal: &Goal, store: &'s impl SubtaskStore<GoalId, V>) -> Option<&'s V> {
+        store.get(&self.id(goal)?)
+    }
+}
+
+struct Subtasker<S, G> {
+    store: S,
+    keys: G,
+
+    /// The number of subtask solutions found in the store
+    hits: Cell<u64>,
+}
+
+impl<K, V, S, G> Subtask<K, V> for Subtasker<S, G>
+where
+    G: Keys<K>,
+    S: SubtaskStore<G::Key, V>,
+{
+    fn precheck(&self, goals: impl IntoIterator<Item = K>) -> Result<(), Dependency<'_, K>> {
+        goals
+            .into_iter()
+            .try_for_each(|goal| match self.keys.lookup(&goal, &self.store) {
+                Some(_) => Ok(()),
+                None => Err(Dependency {
+                    key: goal,
+                    lifetime: PhantomData,
+                }),
+            })
+    }
+
+    fn solve(&self, goal: K) -> Result<&V, Dependency<'_, K>> {
+        let Some(solution) = self.keys.lookup(&goal, &self.store) else {
+            return Err(Dependency {
+                key: goal,
+                lifetime: PhantomData,
+            });
+        };
+
+        self.hits.set(self.hits.get() + 1);
+        Ok(solution)
+    }
+}
+
+// TODO: add a mechanism to request a set of dependencies as a block
+
+/// A suspended goal in the dependency stack of `execute`
+struct Frame<Goal, State> {
+    goal: Goal,
+
+    /// Goals that tail-called into this one, and therefore share its
+    /// solution
+    aliases: Vec<Goal>,
+    state: Option<State>,
+}
+
+impl<Goal: PartialEq, State> Frame<Goal, State> {
+    fn new(goal: Goal) -> Self {
+        Self {
+            goal,
+            aliases: Vec::new(),
+            state: None,
+        }
+    }
+
+    fn contains(&self, goal: &Goal) -> bool {
+        self.goal == *goal || self.aliases.contains(goal)
+    }
+}
+
+/// Solve a dynamic algorithm.
+///
+/// This will run task.solve(&goal, subtasker). The task can request subgoal
+/// solutions by calling `subtasker.solve(subgoal)?`; this will halt the
+/// function and call task.solve(&subgoal, subtasker). In this way, execute
+/// performs a depth-first traversal of the problem space. Solutions to subtasks
+/// are stored in the store and are provided by the subtasker to the caller
+/// when available; this ensures that each subtask is solved at most once.
+///
+/// Note that every time a subtask is requested but not available, the ? will
+/// return a dependency request from the solver. This means the solver will be
+/// restarted from scratch once for each dependency it requests, until the
+/// store can fulfill them all. To prevent wasting work finding a partial
+/// solution, you can call `subtasker.precheck(iter)?` at the beginning of
+/// your Task::solve implementation with an iterator over all the subgoal
+/// dependencies you're expecting
+///
+/// If a goal's solution is exactly the solution of some other goal, the task
+/// can instead return `TaskInterrupt::Tail(subgoal)`. The solver will then
+/// solve `subgoal` in place of the original goal, without suspending and
+/// later restarting it, and store the solution for both goals.
+pub fn execute<Goal, Solution, Error>(
+    goal: Goal,
+    task: &impl Task<Goal, Solution, Error>,
+    store: impl SubtaskStore<Goal, Solution>,
+) -> Result<Solution, DynamicError<Goal, Error>>
+where
+    Goal: PartialEq,
+    Solution: Clone,
+{
+    run(
+        goal,
+        task,
+        Subtasker {
+            store,
+            keys: Direct,
+            hits: Cell::new(0),
+        },
+    )
+}
+
+/// Solve a dynamic algorithm, like [`execute`], but with every goal interned
+/// by `interner`. The store, the dependency stack, and cycle detection all
+/// work with [`GoalId`]s, so a goal is hashed once each time it's requested,
+/// and a new goal is cloned once, into the interner. A `Vec<Option<_>>` is
+/// the natural store.
+///
+/// The ids in the store are only meaningful to the interner that assigned
+/// them, so if either is reused for several calls, the other must be too.
+pub fn execute_interned<Goal, Solution, Error>(
+    goal: Goal,
+    task: &impl Task<Goal, Solution, Error>,
+    interner: &mut Interner<Goal>,
+    store: impl SubtaskStore<GoalId, Solution>,
+) -> Result<Solution, DynamicError<Goal, Error>>
+where
+    Goal: Eq + Hash + Clone,
+    Solution: Clone,
+{
+    run(
+        goal,
+        task,
+        Subtasker {
+            store,
+            keys: interner,
+            hits: Cell::new(0),
+        },
+    )
+}
+
+fn run<Goal, Solution, Error, S, G>(
+    goal: Goal,
+    task: &impl Task<Goal, Solution, Error>,
+    mut subtasker: Subtasker<S, G>,
+) -> Result<Solution, DynamicError<Goal, Error>>
+where
+    G: Keys<Goal>,
+    S: SubtaskStore<G::Key, Solution>,
+    Solution: Clone,
+{
+    let mut solves = Counter::new("dynamic.solves");
+    let mut restarts = Counter::new("dynamic.restarts");
+    let mut stored = Counter::new("dynamic.stored");
+
+    // TODO: use an ordered hash map for faster circular checks
+    let mut dependency_stack: Vec<Frame<G::Key, _>> = vec![];
+    let mut current = Frame::new(subtasker.keys.key(goal));
+
+    let result = loop {
+        // NOTE: We could check if the current goal is already in the store,
+        // but it should be impossible for that to be the case at this point,
+        // since the only way to add things to the store is with a Dependency,
+        // and the only way to get a Dependency is if the store reports that
+        // it *doesn't* already contain that solution.
+        //
+        // This means that the only time this could happen is if the store
+        // contains the solution for the *original* goal, which we assume
+        // doesn't happen. Tail goals are explicitly checked against the store
+        // before they're adopted.
+
+        solves.increment();
+
+        let goal = subtasker.keys.goal(&current.goal);
+
+        let solution = match task.solve(goal, &subtasker, &mut current.state) {
+            Ok(solution) => solution,
+            Err(TaskInterrupt::Error(err)) => break Err(DynamicError::Error(err)),
+            Err(TaskInterrupt::Dependency(Dependency { key: subgoal, .. })) => {
+                restarts.increment();
+                let subgoal = subtasker.keys.key(subgoal);
+
+                if current.contains(&subgoal)
+                    || dependency_stack
+                        .iter()
+                        .any(|frame| frame.contains(&subgoal))
+                {
+                    break Err(DynamicError::CircularDependency(
+                        subtasker.keys.to_goal(subgoal),
+                    ));
+                }
+
+                dependency_stack.push(current);
+                current = Frame::new(subgoal);
+                continue;
+            }
+            Err(TaskInterrupt::Tail(tail_goal)) => {
+                let tail_goal = subtasker.keys.key(tail_goal);
+
+                if current.contains(&tail_goal)
+                    || dependency_stack
+                        .iter()
+                        .any(|frame| frame.contains(&tail_goal))
+                {
+                    break Err(DynamicError::CircularDependency(
+                        subtasker.keys.to_goal(tail_goal),
+                    ));
+                }
+
+                match subtasker.store.get(&tail_goal) {
+                    Some(solution) => {
+                        subtasker.hits.set(subtasker.hits.get() + 1);
+                        solution.clone()
+                    }
+                    None => {
+                        let Frame {
+                            goal, mut aliases, ..
+                        } = current;
+
+                        aliases.push(goal);
+
+                        current = Frame {
+                            goal: tail_goal,
+                            aliases,
+                            state: None,
+                        };
+
+                        continue;
+                    }
+                }
+            }
+        };
+
+        match dependency_stack.pop() {
+            None => break Ok(solution),
+            Some(dependent) => {
+                stored.add(current.aliases.len() as u64 + 1);
+
+                for alias in current.aliases {
+                    subtasker.store.add(alias, solution.clone());
+                }
+
+                subtasker.store.add(current.goal, solution);
+                current = dependent;
+            }
+        }
+    };
+
+    stats::add("dynamic.cache_hits", subtasker.hits.get());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fibonacci;
+
+    impl StatelessTask<u64, u64, Infallible> for Fibonacci {
+        fn solve<'sub>(
+            &self,
+            &goal: &u64,
+            subtasker: &'sub impl Subtask<u64, u64>,
+        ) -> Result<u64, TaskInterrupt<'sub, u64, Infallible>> {
+            match goal {
+                0 | 1 => Ok(goal),
+                _ => {
+                    subtasker.precheck([goal - 1, goal - 2])?;
+                    Ok(subtasker.solve(goal - 1)? + subtasker.solve(goal - 2)?)
+                }
+            }
+        }
+    }
+
+    /// Every goal depends on the next one, around in a loop
+    struct Cycle;
+
+    impl StatelessTask<u8, u8, Infallible> for Cycle {
+        fn solve<'sub>(
+            &self,
+            &goal: &u8,
+            subtasker: &'sub impl Subtask<u8, u8>,
+        ) -> Result<u8, TaskInterrupt<'sub, u8, Infallible>> {
+            subtasker.solve((goal + 1) % 3).copied().map_err(Into::into)
+        }
+    }
+
+    /// Count the ways to build a design out of towels, one suffix at a time
+    struct Arrangements<'a> {
+        towels: &'a [&'a str],
+    }
+
+    impl<'a> StatelessTask<&'a str, u64, Infallible> for Arrangements<'a> {
+        fn solve<'sub>(
+            &self,
+            &design: &&'a str,
+            subtasker: &'sub impl Subtask<&'a str, u64>,
+        ) -> Result<u64, TaskInterrupt<'sub, &'a str, Infallible>> {
+            if design.is_empty() {
+                return Ok(1);
+            }
+
+            let suffixes = || {
+                self.towels
+                    .iter()
+                    .filter_map(move |towel| design.strip_prefix(towel))
+            };
+
+            subtasker.precheck(suffixes())?;
+
+            suffixes().try_fold(0, |count, suffix| Ok(count + subtasker.solve(suffix)?))
+        }
+    }
+
+    #[test]
+    fn solves_deep_dependencies() {
+        let mut store = HashMap::new();
+        let solution = execute(90, &Fibonacci, &mut store).unwrap();
+
+        assert_eq!(solution, 2_880_067_194_370_816_120);
+        assert_eq!(store[&50], 12_586_269_025);
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let result = execute(0, &Cycle, BTreeMap::new());
+        assert!(matches!(result, Err(DynamicError::CircularDependency(0))));
+    }
+
+    #[test]
+    fn interned_goals_share_a_store() {
+        let task = Arrangements {
+            towels: &["r", "wr", "b", "g", "bwu", "rb", "gb", "br"],
+        };
+
+        let designs = [
+            "brwrr", "bggr", "gbbr", "rrbgbr", "ubwu", "bwurrg", "brgr", "bbrwb",
+        ];
+
+        let mut interner = Interner::new();
+        let mut store = Vec::new();
+
+        let counts: Vec<u64> = designs
+            .iter()
+            .map(|&design| execute_interned(design, &task, &mut interner, &mut store).unwrap())
+            .collect();
+
+        assert_eq!(counts, [2, 1, 4, 6, 0, 1, 2, 0]);
+
+        let id = interner.id(&"r").unwrap();
+        assert_eq!(*interner.goal(id), "r");
+        assert_eq!(store[id.0 as usize], Some(1));
+    }
+
+    #[test]
+    fn detects_interned_cycles() {
+        let mut interner = Interner::new();
+        let result = execute_interned(1, &Cycle, &mut interner, Vec::new());
+
+        assert!(matches!(result, Err(DynamicError::CircularDependency(1))));
+        assert_eq!(interner.len(), 3);
+    }
+}
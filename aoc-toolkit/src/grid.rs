@@ -0,0 +1,561 @@
+//! Shapes on the grid, built out of gridly's locations and vectors: [`Path`]s
+//! through it, [`Rect`]angles covering parts of it, and dense
+//! [`LocationSet`]s of cells within a rectangle. Also helpers for visiting
+//! every cell of a grid, or of a drawing of one, along with its location,
+//! without nesting a loop over rows and a loop over columns.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    iter::Copied,
+    slice,
+};
+
+use gridly::prelude::*;
+use itertools::Itertools;
+
+use crate::{IterExt, render};
+
+/// Pair every item in a sequence of rows with its location, with the first
+/// item of the first row at the origin
+pub fn iter_located<R: IntoIterator>(
+    rows: impl IntoIterator<Item = R>,
+) -> impl Iterator<Item = (Location, R::Item)> {
+    rows.into_iter().with_rows(Row(0)).flat_map(|(row, items)| {
+        items
+            .into_iter()
+            .with_columns(Column(0))
+            .map(move |(column, item)| (row.combine(column), item))
+    })
+}
+
+/// Every character of a drawing of a grid, like most puzzle inputs, with its
+/// location. Whitespace around each line is ignored, so that indentation or
+/// stray carriage returns can't shift any columns.
+pub fn located_chars(text: &str) -> impl Iterator<Item = (Location, char)> + '_ {
+    iter_located(text.lines().map(|line| line.trim().chars()))
+}
+
+/// Like [`located_chars`], but with each cell as a byte, for drawings that
+/// are all ASCII
+pub fn located_bytes(text: &str) -> impl Iterator<Item = (Location, u8)> + '_ {
+    iter_located(text.lines().map(|line| line.trim().bytes()))
+}
+
+/// Extra methods for gridly grids
+pub trait GridExt: Grid {
+    /// Every cell in the grid, with its location, row by row
+    fn iter_located(&self) -> impl Iterator<Item = (Location, &Self::Item)> {
+        self.rows().iter().flat_map(|row| row.iter_with_locations())
+    }
+}
+
+impl<G: Grid> GridExt for G {}
+
+/// The arrow pointing in `direction`, as the puzzles draw them
+pub fn arrow(direction: Direction) -> char {
+    match direction {
+        Up => '^',
+        Right => '>',
+        Down => 'v',
+        Left => '<',
+    }
+}
+
+/// A walk through the grid, as the sequence of locations it visits, from
+/// the start to the end inclusive. Searches that find a route return one of
+/// these, so that every route can be measured, compared, and drawn the same
+/// way.
+///
+/// Nothing requires consecutive locations to be adjacent, but
+/// [`directions`][Path::directions] only works if they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Path {
+    locations: Vec<Location>,
+}
+
+impl Path {
+    pub fn new(locations: Vec<Location>) -> Self {
+        Self { locations }
+    }
+
+    /// The path that starts at `start` and takes one step in each of
+    /// `directions` in turn
+    pub fn walk(start: Location, directions: impl IntoIterator<Item = Direction>) -> Self {
+        let mut location = start;
+
+        [start]
+            .into_iter()
+            .chain(directions.into_iter().map(|direction| {
+                location += direction;
+                location
+            }))
+            .collect()
+    }
+
+    pub fn locations(&self) -> &[Location] {
+        &self.locations
+    }
+
+    pub fn into_locations(self) -> Vec<Location> {
+        self.locations
+    }
+
+    pub fn iter(&self) -> Copied<slice::Iter<'_, Location>> {
+        self.locations.iter().copied()
+    }
+
+    pub fn start(&self) -> Option<Location> {
+        self.locations.first().copied()
+    }
+
+    pub fn end(&self) -> Option<Location> {
+        self.locations.last().copied()
+    }
+
+    /// The number of locations on the path, counting both ends
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    /// The number of moves it takes to walk the path, which is one fewer
+    /// than the number of locations on it
+    pub fn steps(&self) -> usize {
+        self.locations.len().saturating_sub(1)
+    }
+
+    /// True if the path visits `location` at any point. This is a linear
+    /// scan; collect the path into a set for repeated lookups.
+    pub fn contains(&self, location: Location) -> bool {
+        self.locations.contains(&location)
+    }
+
+    /// The smallest rectangle that contains the whole path, or None if it's
+    /// empty
+    pub fn bounding_box(&self) -> Option<Rect> {
+        Rect::bounding(self.iter())
+    }
+
+    /// Remove consecutive repeats of the same location, like the ones left
+    /// behind by a search that can turn in place
+    pub fn dedup(&mut self) {
+        self.locations.dedup();
+    }
+
+    /// The direction of each step along the path. Fails at the first pair of
+    /// consecutive locations that aren't orthogonally adjacent.
+    pub fn directions(&self) -> Result<Vec<Direction>, NotAdjacent> {
+        self.locations
+            .iter()
+            .tuple_windows()
+            .map(|(&from, &to)| {
+                EACH_DIRECTION
+                    .into_iter()
+                    .find(|&direction| from + direction == to)
+                    .ok_or(NotAdjacent { from, to })
+            })
+            .collect()
+    }
+
+    /// Each location after the start, paired with an arrow showing which
+    /// way the path moved to reach it. Steps that aren't between adjacent
+    /// locations are skipped. Useful for marking the path on a drawing.
+    pub fn arrows(&self) -> impl Iterator<Item = (Location, char)> + '_ {
+        self.locations
+            .iter()
+            .tuple_windows()
+            .filter_map(|(&from, &to)| {
+                EACH_DIRECTION
+                    .into_iter()
+                    .find(|&direction| from + direction == to)
+                    .map(|direction| (to, arrow(direction)))
+            })
+    }
+
+    /// Draw the path over a grid with its top-left corner at the origin,
+    /// with `background` for the cells that aren't on it. The start is drawn
+    /// as `S`, and every other cell on the path as an arrow.
+    pub fn render(
+        &self,
+        rows: Rows,
+        columns: Columns,
+        mut background: impl FnMut(Location) -> char,
+    ) -> String {
+        let marks: HashMap<Location, char> = self
+            .start()
+            .map(|start| (start, 'S'))
+            .into_iter()
+            .chain(self.arrows())
+            .collect();
+
+        render::grid(rows, columns, |location| match marks.get(&location) {
+            Some(&mark) => mark,
+            None => background(location),
+        })
+    }
+}
+
+impl From<Vec<Location>> for Path {
+    fn from(locations: Vec<Location>) -> Self {
+        Self::new(locations)
+    }
+}
+
+impl FromIterator<Location> for Path {
+    fn from_iter<I: IntoIterator<Item = Location>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a Path {
+    type Item = Location;
+    type IntoIter = Copied<slice::Iter<'a, Location>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Two consecutive locations on a [`Path`] that aren't one step apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotAdjacent {
+    pub from: Location,
+    pub to: Location,
+}
+
+impl Display for NotAdjacent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self { from, to } = self;
+
+        write!(
+            f,
+            "path jumps from {},{} to {},{}",
+            from.column.0, from.row.0, to.column.0, to.row.0
+        )
+    }
+}
+
+impl Error for NotAdjacent {}
+
+/// A rectangle of cells, given by its top-left corner and its dimensions. A
+/// rectangle with no rows or no columns is empty, and contains nothing.
+/// Rectangles are gridly grid bounds, so they also have all of the
+/// `GridBounds` helpers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    root: Location,
+    dimensions: Vector,
+}
+
+impl Rect {
+    /// The rectangle with its top-left corner at `root`. Negative dimensions
+    /// are treated as 0.
+    pub fn new(root: Location, dimensions: Vector) -> Self {
+        Self {
+            root,
+            dimensions: Vector::new(dimensions.rows.0.max(0), dimensions.columns.0.max(0)),
+        }
+    }
+
+    /// The rectangle with opposite corners at `a` and `b`, including both
+    pub fn from_corners(a: Location, b: Location) -> Self {
+        let root = Location::new(a.row.min(b.row), a.column.min(b.column));
+        let last = Location::new(a.row.max(b.row), a.column.max(b.column));
+
+        Self::new(root, last - root + Vector::new(1, 1))
+    }
+
+    /// The smallest rectangle that contains every location in `locations`,
+    /// or None if there aren't any
+    pub fn bounding(locations: impl IntoIterator<Item = Location>) -> Option<Self> {
+        let mut locations = locations.into_iter();
+        let first = locations.next()?;
+
+        Some(
+            locations.fold(Self::from_corners(first, first), |rect, location| {
+                rect.union(&Self::from_corners(location, location))
+            }),
+        )
+    }
+
+    /// The number of cells in the rectangle
+    pub fn area(&self) -> usize {
+        (self.dimensions.rows.0 * self.dimensions.columns.0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.area() == 0
+    }
+
+    pub fn contains(&self, location: Location) -> bool {
+        self.location_in_bounds(location)
+    }
+
+    /// The cells in both rectangles, or None if they don't overlap
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let root = Location::new(
+            self.root.row.max(other.root.row),
+            self.root.column.max(other.root.column),
+        );
+
+        let end = Location::new(
+            self.outer_bound().row.min(other.outer_bound().row),
+            self.outer_bound().column.min(other.outer_bound().column),
+        );
+
+        let rect = Self::new(root, end - root);
+        (!rect.is_empty()).then_some(rect)
+    }
+
+    /// The smallest rectangle that contains both rectangles. Empty
+    /// rectangles don't contribute anything.
+    pub fn union(&self, other: &Self) -> Self {
+        match (self.is_empty(), other.is_empty()) {
+            (_, true) => *self,
+            (true, false) => *other,
+            (false, false) => {
+                let root = Location::new(
+                    self.root.row.min(other.root.row),
+                    self.root.column.min(other.root.column),
+                );
+
+                let end = Location::new(
+                    self.outer_bound().row.max(other.outer_bound().row),
+                    self.outer_bound().column.max(other.outer_bound().column),
+                );
+
+                Self::new(root, end - root)
+            }
+        }
+    }
+
+    /// Every location in the rectangle, row by row
+    pub fn locations(&self) -> impl Iterator<Item = Location> + Clone + use<> {
+        let Self { root, dimensions } = *self;
+
+        (0..dimensions.rows.0).flat_map(move |row| {
+            (0..dimensions.columns.0).map(move |column| root + Vector::new(row, column))
+        })
+    }
+}
+
+impl GridBounds for Rect {
+    fn dimensions(&self) -> Vector {
+        self.dimensions
+    }
+
+    fn root(&self) -> Location {
+        self.root
+    }
+}
+
+/// A set of locations within a fixed [`Rect`], stored as one bit per cell.
+/// Much faster than a `HashSet<Location>` for dense sets, like the walls of a
+/// maze, since lookups are just an index into the bitset.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct LocationSet {
+    bounds: Rect,
+    bits: Vec<u64>,
+    len: usize,
+}
+
+impl LocationSet {
+    /// Create an empty set that can hold any location in `bounds`
+    pub fn new(bounds: Rect) -> Self {
+        Self {
+            bounds,
+            bits: vec![0; bounds.area().div_ceil(64)],
+            len: 0,
+        }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    /// The bit for `location`, as an index into `bits` and a mask, or None
+    /// if it's out of bounds
+    fn bit(&self, location: Location) -> Option<(usize, u64)> {
+        let offset = self.bounds.check_location(location).ok()? - self.bounds.root();
+        let index =
+            (offset.rows.0 * self.bounds.dimensions().columns.0 + offset.columns.0) as usize;
+
+        Some((index / 64, 1 << (index % 64)))
+    }
+
+    /// Add `location` to the set, returning true if it wasn't already there.
+    /// Panics if `location` is outside the set's bounds.
+    pub fn insert(&mut self, location: Location) -> bool {
+        let (word, mask) = self
+            .bit(location)
+            .unwrap_or_else(|| panic!("{location:?} is outside of {:?}", self.bounds));
+
+        let added = self.bits[word] & mask == 0;
+        self.bits[word] |= mask;
+        self.len += added as usize;
+        added
+    }
+
+    /// Remove `location` from the set, returning true if it was there
+    pub fn remove(&mut self, location: Location) -> bool {
+        let Some((word, mask)) = self.bit(location) else {
+            return false;
+        };
+
+        let removed = self.bits[word] & mask != 0;
+        self.bits[word] &= !mask;
+        self.len -= removed as usize;
+        removed
+    }
+
+    /// True if `location` is in the set. Locations outside the set's bounds
+    /// never are.
+    pub fn contains(&self, location: Location) -> bool {
+        self.bit(location)
+            .is_some_and(|(word, mask)| self.bits[word] & mask != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Every location in the set, row by row
+    pub fn iter(&self) -> impl Iterator<Item = Location> + '_ {
+        self.bounds
+            .locations()
+            .filter(|&location| self.contains(location))
+    }
+}
+
+/// Shows the locations in the set, rather than its bits
+impl Debug for LocationSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn located_cells_count_from_the_origin() {
+        let cells: Vec<(Location, char)> = located_chars("ab\n  c\r\n").collect();
+
+        assert_eq!(
+            cells,
+            [
+                (Location::new(0, 0), 'a'),
+                (Location::new(0, 1), 'b'),
+                (Location::new(1, 0), 'c'),
+            ]
+        );
+
+        let ragged: Vec<(Location, i32)> = iter_located([vec![1], vec![], vec![2, 3]]).collect();
+
+        assert_eq!(
+            ragged,
+            [
+                (Location::new(0, 0), 1),
+                (Location::new(2, 0), 2),
+                (Location::new(2, 1), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn walked_paths_round_trip_their_directions() {
+        let directions = [Right, Right, Down, Left, Down];
+        let path = Path::walk(Location::new(1, 1), directions);
+
+        assert_eq!(path.len(), 6);
+        assert_eq!(path.steps(), 5);
+        assert_eq!(path.end(), Some(Location::new(3, 2)));
+        assert!(path.contains(Location::new(2, 3)));
+        assert_eq!(path.directions(), Ok(directions.to_vec()));
+        assert_eq!(
+            path.bounding_box(),
+            Some(Rect::new(Location::new(1, 1), Vector::new(3, 3)))
+        );
+    }
+
+    #[test]
+    fn dedup_removes_turns_in_place() {
+        let mut path = Path::walk(Location::zero(), [Down, Right]);
+        let turning: Path = path.iter().flat_map(|location| [location; 2]).collect();
+
+        assert_eq!(
+            turning.directions(),
+            Err(NotAdjacent {
+                from: Location::zero(),
+                to: Location::zero()
+            })
+        );
+
+        let mut deduped = turning.clone();
+        deduped.dedup();
+        path.dedup();
+        assert_eq!(deduped, path);
+    }
+
+    #[test]
+    fn render_marks_the_path() {
+        let path = Path::walk(Location::zero(), [Right, Down, Down]);
+
+        assert_eq!(path.render(Rows(3), Columns(3), |_| '.'), "S>.\n.v.\n.v.\n");
+    }
+
+    #[test]
+    fn rect_set_operations() {
+        let a = Rect::from_corners(Location::new(3, 0), Location::new(0, 2));
+        let b = Rect::new(Location::new(2, 1), Vector::new(3, 3));
+
+        assert_eq!(a, Rect::new(Location::zero(), Vector::new(4, 3)));
+        assert_eq!(a.area(), 12);
+        assert_eq!(a.locations().count(), 12);
+        assert!(a.locations().all(|location| a.contains(location)));
+        assert!(!a.contains(Location::new(0, 3)));
+
+        assert_eq!(
+            a.intersection(&b),
+            Some(Rect::new(Location::new(2, 1), Vector::new(2, 2)))
+        );
+        assert_eq!(a.union(&b), Rect::new(Location::zero(), Vector::new(5, 4)));
+
+        let far = Rect::new(Location::new(10, 10), Vector::new(1, 1));
+        assert_eq!(a.intersection(&far), None);
+        assert_eq!(a.union(&Rect::default()), a);
+        assert_eq!(
+            Rect::bounding([Location::new(10, 10), Location::new(-1, 12)]),
+            Some(Rect::new(Location::new(-1, 10), Vector::new(12, 3)))
+        );
+        assert_eq!(Rect::bounding([]), None);
+    }
+
+    #[test]
+    fn location_set_stays_in_bounds() {
+        let bounds = Rect::new(Location::new(-2, 5), Vector::new(9, 9));
+        let mut set = LocationSet::new(bounds);
+
+        assert!(set.insert(Location::new(-2, 5)));
+        assert!(set.insert(Location::new(6, 13)));
+        assert!(!set.insert(Location::new(6, 13)));
+        assert_eq!(set.len(), 2);
+
+        assert!(set.contains(Location::new(6, 13)));
+        assert!(!set.contains(Location::new(6, 14)));
+        assert!(!set.remove(Location::new(100, 100)));
+
+        assert!(set.remove(Location::new(-2, 5)));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [Location::new(6, 13)]);
+    }
+}
@@ -0,0 +1,264 @@
+//! Histograms of integer samples, like the scores a search gave each of its
+//! candidates, or how much each of a puzzle's shortcuts saves. Samples are
+//! counted into buckets of a fixed width, and a histogram can report
+//! percentiles and draw itself as a compact bar chart for the terminal.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+
+/// Counts of samples, grouped into buckets that each cover `width`
+/// consecutive values. Every bucket starts at a multiple of the width, so
+/// with the default width of 1, every distinct value has its own bucket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    width: i64,
+
+    /// The number of samples in each nonempty bucket, by where the bucket
+    /// starts
+    counts: BTreeMap<i64, usize>,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::with_width(1)
+    }
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a histogram with buckets `width` values wide. Panics if the
+    /// width isn't positive.
+    pub fn with_width(width: i64) -> Self {
+        assert!(width > 0, "histogram buckets must have a positive width");
+
+        Self {
+            width,
+            counts: BTreeMap::new(),
+        }
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    /// Where the bucket containing `value` starts
+    fn bucket(&self, value: i64) -> i64 {
+        value.div_euclid(self.width) * self.width
+    }
+
+    pub fn add(&mut self, value: i64) {
+        self.add_count(value, 1);
+    }
+
+    /// Add `count` samples of `value` at once
+    pub fn add_count(&mut self, value: i64, count: usize) {
+        if count > 0 {
+            *self.counts.entry(self.bucket(value)).or_default() += count;
+        }
+    }
+
+    /// Combine the samples from two histograms with the same bucket width,
+    /// like the partial histograms from a parallel fold. Panics if the
+    /// widths differ.
+    pub fn merge(mut self, other: Self) -> Self {
+        assert_eq!(
+            self.width, other.width,
+            "can't merge histograms with different bucket widths"
+        );
+
+        other
+            .counts
+            .into_iter()
+            .for_each(|(start, count)| self.add_count(start, count));
+
+        self
+    }
+
+    /// The same samples, counted into buckets `width` values wide. Panics
+    /// unless the new width is a multiple of the current one, since the
+    /// samples in each bucket can't be split up.
+    pub fn rebucket(&self, width: i64) -> Self {
+        assert!(
+            width > 0 && width % self.width == 0,
+            "can't rebucket a histogram from width {} to width {width}",
+            self.width
+        );
+
+        let mut histogram = Self::with_width(width);
+
+        self.counts
+            .iter()
+            .for_each(|(&start, &count)| histogram.add_count(start, count));
+
+        histogram
+    }
+
+    /// The number of samples in the bucket containing `value`
+    pub fn count(&self, value: i64) -> usize {
+        self.counts.get(&self.bucket(value)).copied().unwrap_or(0)
+    }
+
+    /// The total number of samples
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Every nonempty bucket, as where it starts and how many samples are in
+    /// it, from lowest to highest
+    pub fn buckets(&self) -> impl Iterator<Item = (i64, usize)> + '_ {
+        self.counts.iter().map(|(&start, &count)| (start, count))
+    }
+
+    /// Where the lowest nonempty bucket starts
+    pub fn min(&self) -> Option<i64> {
+        self.counts.keys().next().copied()
+    }
+
+    /// Where the highest nonempty bucket starts
+    pub fn max(&self) -> Option<i64> {
+        self.counts.keys().next_back().copied()
+    }
+
+    /// Where the bucket containing the `percentile`th sample starts, using
+    /// the nearest rank, or None if there are no samples. `percentile` is
+    /// clamped to `0.0..=100.0`.
+    pub fn percentile(&self, percentile: f64) -> Option<i64> {
+        let total = self.total();
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * total as f64).ceil() as usize;
+        let rank = rank.max(1);
+
+        let mut seen = 0;
+
+        self.buckets().find_map(|(start, count)| {
+            seen += count;
+            (seen >= rank).then_some(start)
+        })
+    }
+
+    pub fn median(&self) -> Option<i64> {
+        self.percentile(50.0)
+    }
+
+    /// Draw the histogram as a bar chart, one line per nonempty bucket, with
+    /// the longest bar `width` characters long
+    pub fn bars(&self, width: usize) -> Bars<'_> {
+        Bars {
+            histogram: self,
+            width,
+        }
+    }
+
+    fn label(&self, start: i64) -> String {
+        match self.width {
+            1 => start.to_string(),
+            width => format!("{start}..{}", start + width),
+        }
+    }
+}
+
+impl FromIterator<i64> for Histogram {
+    fn from_iter<I: IntoIterator<Item = i64>>(iter: I) -> Self {
+        let mut histogram = Self::new();
+        histogram.extend(iter);
+        histogram
+    }
+}
+
+impl Extend<i64> for Histogram {
+    fn extend<I: IntoIterator<Item = i64>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|value| self.add(value));
+    }
+}
+
+/// A histogram drawn as a bar chart, as returned by [`Histogram::bars`].
+/// Each line has the bucket, its bar, and its count. Bars are drawn with
+/// eighth-width block characters, so small differences still show up.
+#[derive(Debug, Clone, Copy)]
+pub struct Bars<'a> {
+    histogram: &'a Histogram,
+    width: usize,
+}
+
+impl Display for Bars<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+        let histogram = self.histogram;
+        let most = histogram.counts.values().copied().max().unwrap_or(0);
+
+        let label_width = histogram
+            .buckets()
+            .map(|(start, _)| histogram.label(start).len())
+            .max()
+            .unwrap_or(0);
+
+        histogram.buckets().try_for_each(|(start, count)| {
+            let eighths = (count * self.width * 8).div_ceil(most);
+            let full = "█".repeat(eighths / 8);
+
+            let bar = match eighths % 8 {
+                0 => full,
+                part => full + &EIGHTHS[part].to_string(),
+            };
+
+            writeln!(
+                f,
+                "{:>label_width$} │{bar:width$} {count}",
+                histogram.label(start),
+                width = self.width
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let histogram: Histogram = (1..=10).collect();
+
+        assert_eq!(histogram.total(), 10);
+        assert_eq!(histogram.median(), Some(5));
+        assert_eq!(histogram.percentile(90.0), Some(9));
+        assert_eq!(histogram.percentile(100.0), Some(10));
+        assert_eq!(histogram.percentile(0.0), Some(1));
+        assert_eq!(Histogram::new().median(), None);
+    }
+
+    #[test]
+    fn buckets_start_at_multiples_of_the_width() {
+        let mut histogram = Histogram::with_width(10);
+        histogram.extend([-1, 0, 9, 10, 25]);
+
+        assert_eq!(
+            histogram.buckets().collect::<Vec<_>>(),
+            [(-10, 1), (0, 2), (10, 1), (20, 1)]
+        );
+        assert_eq!(histogram.count(5), 2);
+        assert_eq!(
+            histogram.rebucket(20).buckets().collect::<Vec<_>>(),
+            [(-20, 1), (0, 3), (20, 1)]
+        );
+    }
+
+    #[test]
+    fn bars_scale_to_the_largest_bucket() {
+        let histogram: Histogram = [2, 2, 2, 2, 4, 64].into_iter().collect();
+
+        assert_eq!(
+            histogram.bars(4).to_string(),
+            " 2 │████ 4\n 4 │█    1\n64 │█    1\n"
+        );
+    }
+}
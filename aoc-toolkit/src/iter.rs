@@ -0,0 +1,541 @@
+//! Iterator adapters: fixed-size chunks and windows that yield arrays,
+//! collecting into arrays, and a few helpers for threading errors and grid
+//! coordinates through an iterator pipeline.
+
+use std::{iter::FusedIterator, mem, ops::ControlFlow};
+
+use brownstone::move_builder::{ArrayBuilder, PushResult};
+use gridly::location::{Column, Row};
+
+/// An iterator over non-overlapping arrays of `N` items; see
+/// [`IterExt::streaming_chunks`]
+#[derive(Debug, Default, Clone)]
+pub struct Chunks<I, const N: usize> {
+    iterator: I,
+}
+
+impl<I: Iterator, const N: usize> Iterator for Chunks<I, N> {
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(brownstone::build![self.iterator.next()?])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.iterator.size_hint();
+
+        (min / N, max.map(|max| max / N))
+    }
+
+    fn count(self) -> usize
+    where
+        Self: Sized,
+    {
+        self.iterator.count() / N
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let builder = match ArrayBuilder::start() {
+            PushResult::Full(array) => return Some(array),
+            PushResult::NotFull(builder) => builder,
+        };
+
+        let n = n.checked_mul(N).expect("usize overflow");
+
+        let mut builder = match builder.push(self.iterator.nth(n)?) {
+            PushResult::Full(array) => return Some(array),
+            PushResult::NotFull(builder) => builder,
+        };
+
+        loop {
+            builder = match builder.push(self.iterator.next()?) {
+                PushResult::Full(array) => return Some(array),
+                PushResult::NotFull(builder) => builder,
+            }
+        }
+    }
+
+    fn fold<B, F>(self, init: B, mut func: F) -> B
+    where
+        Self: Sized,
+        F: FnMut(B, Self::Item) -> B,
+    {
+        let builder = match ArrayBuilder::start() {
+            PushResult::Full(_array) => panic!("called Chunks::fold but N is 0"),
+            PushResult::NotFull(builder) => builder,
+        };
+
+        let (_, accum) =
+            self.iterator
+                .fold((builder, init), |(builder, accum), item| {
+                    match builder.push(item) {
+                        PushResult::NotFull(builder) => (builder, accum),
+                        PushResult::Full(array) => match ArrayBuilder::start() {
+                            PushResult::Full(_arr) => unreachable!(),
+                            PushResult::NotFull(builder) => (builder, func(accum, array)),
+                        },
+                    }
+                });
+
+        accum
+    }
+
+    fn try_fold<B, F, R>(&mut self, init: B, mut func: F) -> R
+    where
+        Self: Sized,
+        F: FnMut(B, Self::Item) -> R,
+        R: std::ops::Try<Output = B>,
+    {
+        let builder = match ArrayBuilder::start() {
+            PushResult::Full(_array) => panic!("called Chunks::try_fold but N is 0"),
+            PushResult::NotFull(builder) => builder,
+        };
+
+        let out = self
+            .iterator
+            .try_fold((builder, init), |(builder, accum), item| {
+                match builder.push(item) {
+                    PushResult::NotFull(builder) => ControlFlow::Continue((builder, accum)),
+                    PushResult::Full(array) => func(accum, array).branch().map_continue(|accum| {
+                        (
+                            match ArrayBuilder::start() {
+                                PushResult::Full(_arr) => unreachable!(),
+                                PushResult::NotFull(builder) => builder,
+                            },
+                            accum,
+                        )
+                    }),
+                }
+            });
+
+        match out {
+            ControlFlow::Continue((_, accum)) => R::from_output(accum),
+            ControlFlow::Break(residual) => R::from_residual(residual),
+        }
+    }
+}
+
+impl<T: FusedIterator, const N: usize> FusedIterator for Chunks<T, N> {}
+
+impl<T: ExactSizeIterator, const N: usize> ExactSizeIterator for Chunks<T, N> {
+    fn len(&self) -> usize {
+        self.iterator.len() / N
+    }
+}
+
+/// Extra adapters for every iterator
+pub trait IterExt: Iterator + Sized {
+    /// Yield the items of this iterator in non-overlapping arrays of `N`.
+    /// Any items left over at the end that don't fill a whole array are
+    /// dropped.
+    ///
+    /// ```
+    /// use aoc_toolkit::IterExt;
+    ///
+    /// let chunks: Vec<[i32; 2]> = (1..=5).streaming_chunks().collect();
+    /// assert_eq!(chunks, [[1, 2], [3, 4]]);
+    /// ```
+    fn streaming_chunks<const N: usize>(self) -> Chunks<Self, N> {
+        Chunks { iterator: self }
+    }
+
+    /// Yield every overlapping window of `N` consecutive items, as arrays.
+    /// Yields nothing if there are fewer than `N` items.
+    ///
+    /// ```
+    /// use aoc_toolkit::IterExt;
+    ///
+    /// let windows: Vec<[i32; 3]> = (1..=4).streaming_windows().collect();
+    /// assert_eq!(windows, [[1, 2, 3], [2, 3, 4]]);
+    /// ```
+    fn streaming_windows<const N: usize>(mut self) -> Windows<Self, N> {
+        Windows {
+            state: match try_build_iter(&mut self) {
+                Some(buffer) => State::Buffered(buffer),
+                None => State::Done,
+            },
+            iter: self,
+        }
+    }
+
+    /// Turn an iterator of `Result`s into an iterator of the `Ok` values,
+    /// which stops at the first `Err` and stores it in `destination`. Lets a
+    /// fallible iterator be passed to things that expect plain values, like
+    /// `sum`.
+    fn disgorge_error<T, E>(self, destination: &mut Result<(), E>) -> DisgorgeError<'_, Self, E>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+    {
+        DisgorgeError {
+            iterator: self,
+            error: destination,
+        }
+    }
+
+    /// Pair each item with a grid coordinate, counting up from `root`
+    fn with_coordinate<C: gridly::location::Component>(
+        self,
+        root: C,
+    ) -> EnumerateCoordinate<Self, C> {
+        EnumerateCoordinate {
+            iter: self,
+            coordinate: root,
+        }
+    }
+
+    /// Pair each item with a [`Row`], counting up from `row`
+    fn with_rows(self, row: Row) -> EnumerateCoordinate<Self, Row> {
+        self.with_coordinate(row)
+    }
+
+    /// Pair each item with a [`Column`], counting up from `column`
+    fn with_columns(self, column: Column) -> EnumerateCoordinate<Self, Column> {
+        self.with_coordinate(column)
+    }
+}
+
+impl<T: Iterator + Sized> IterExt for T {}
+
+#[derive(Debug, Clone, Copy)]
+enum State<T, const N: usize> {
+    Buffered([T; N]),
+    Done,
+}
+
+impl<T, const N: usize> State<T, N> {
+    fn take(&mut self) -> Self {
+        mem::replace(self, State::Done)
+    }
+}
+
+/// An iterator over overlapping arrays of `N` items; see
+/// [`IterExt::streaming_windows`]
+#[derive(Debug, Clone, Copy)]
+pub struct Windows<I: Iterator, const N: usize> {
+    iter: I,
+    state: State<I::Item, N>,
+}
+
+/// Collect the first `N` items of an iterator into an array, or return None
+/// if it has fewer than `N` items
+pub fn try_build_iter<I, const N: usize>(iter: I) -> Option<[I::Item; N]>
+where
+    I: IntoIterator,
+{
+    let builder = match ArrayBuilder::start() {
+        PushResult::Full(array) => return Some(array),
+        PushResult::NotFull(builder) => builder,
+    };
+
+    let result = iter
+        .into_iter()
+        .try_fold(builder, |builder, item| match builder.push(item) {
+            PushResult::Full(array) => ControlFlow::Break(array),
+            PushResult::NotFull(builder) => ControlFlow::Continue(builder),
+        });
+
+    result.break_value()
+}
+
+/// Collect the first `N` items of an iterator into an array.
+///
+/// # Panics
+///
+/// If the iterator has fewer than `N` items
+pub fn build_iter<I, const N: usize>(iter: I) -> [I::Item; N]
+where
+    I: IntoIterator,
+{
+    try_build_iter(iter).expect("iterator wasn't long enough")
+}
+
+impl<I: Iterator, const N: usize> Iterator for Windows<I, N>
+where
+    I::Item: Clone,
+{
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let buffer = match self.state.take() {
+            State::Buffered(buffer) => buffer,
+            State::Done => return None,
+        };
+
+        if let Some(next) = self.iter.next() {
+            self.state = State::Buffered(build_iter(buffer[1..].iter().cloned().chain([next])))
+        }
+
+        Some(buffer)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.state {
+            State::Buffered(_) => {
+                let (min, max) = self.iter.size_hint();
+                (
+                    min.saturating_add(1),
+                    max.and_then(|max| max.checked_add(1)),
+                )
+            }
+            State::Done => (0, Some(0)),
+        }
+    }
+
+    fn count(self) -> usize
+    where
+        Self: Sized,
+    {
+        match self.state {
+            State::Buffered(_) => self.iter.count() + 1,
+            State::Done => 0,
+        }
+    }
+
+    fn fold<B, F>(self, init: B, mut func: F) -> B
+    where
+        Self: Sized,
+        F: FnMut(B, Self::Item) -> B,
+    {
+        let buffer = match self.state {
+            State::Buffered(buffer) => buffer,
+            State::Done => return init,
+        };
+
+        let (accum, buffer) = self.iter.fold((init, buffer), |(accum, buffer), item| {
+            let new_buffer = build_iter(buffer[1..].iter().cloned().chain([item]));
+            (func(accum, buffer), new_buffer)
+        });
+
+        func(accum, buffer)
+    }
+
+    fn try_fold<B, F, R>(&mut self, init: B, mut f: F) -> R
+    where
+        Self: Sized,
+        F: FnMut(B, Self::Item) -> R,
+        R: std::ops::Try<Output = B>,
+    {
+        let buffer = match self.state.take() {
+            State::Buffered(buffer) => buffer,
+            State::Done => return R::from_output(init),
+        };
+
+        let out = self.iter.try_fold((init, buffer), |(accum, buffer), item| {
+            let new_buffer = build_iter(buffer[1..].iter().cloned().chain([item]));
+
+            match f(accum, buffer).branch() {
+                ControlFlow::Continue(cont) => ControlFlow::Continue((cont, new_buffer)),
+                ControlFlow::Break(brk) => ControlFlow::Break((brk, new_buffer)),
+            }
+        });
+
+        match out {
+            ControlFlow::Continue((accum, buffer)) => f(accum, buffer),
+            ControlFlow::Break((out, buffer)) => {
+                self.state = State::Buffered(buffer);
+                R::from_residual(out)
+            }
+        }
+    }
+}
+
+impl<I: Iterator, const N: usize> FusedIterator for Windows<I, N> where I::Item: Clone {}
+
+impl<I: ExactSizeIterator, const N: usize> ExactSizeIterator for Windows<I, N>
+where
+    I::Item: Clone,
+{
+    fn len(&self) -> usize {
+        match self.state {
+            State::Buffered(_) => self.iter.len() + 1,
+            State::Done => 0,
+        }
+    }
+}
+
+/// An iterator over the `Ok` values of a fallible iterator; see
+/// [`IterExt::disgorge_error`]
+#[derive(Debug)]
+pub struct DisgorgeError<'a, I, E> {
+    iterator: I,
+    error: &'a mut Result<(), E>,
+}
+
+impl<I, T, E> Iterator for DisgorgeError<'_, I, E>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.error.as_ref().ok()?;
+
+        self.iterator
+            .next()?
+            .map_err(|err| {
+                *self.error = Err(err);
+            })
+            .ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.error.is_err() {
+            (0, Some(0))
+        } else {
+            let (_, max) = self.iterator.size_hint();
+            (0, max)
+        }
+    }
+
+    fn count(self) -> usize {
+        self.iterator.filter(|item| item.is_ok()).count()
+    }
+
+    fn fold<B, F>(mut self, init: B, mut func: F) -> B
+    where
+        Self: Sized,
+        F: FnMut(B, Self::Item) -> B,
+    {
+        match *self.error {
+            Err(_) => init,
+            Ok(()) => match self.iterator.try_fold(init, |accum, item| match item {
+                Ok(item) => ControlFlow::Continue(func(accum, item)),
+                Err(err) => ControlFlow::Break((accum, err)),
+            }) {
+                ControlFlow::Continue(accum) => accum,
+                ControlFlow::Break((accum, err)) => {
+                    *self.error = Err(err);
+                    accum
+                }
+            },
+        }
+    }
+}
+
+/// An iterator that pairs each item with a grid coordinate; see
+/// [`IterExt::with_coordinate`]
+#[derive(Debug, Clone)]
+pub struct EnumerateCoordinate<I, C> {
+    iter: I,
+    coordinate: C,
+}
+
+impl<I, C> Iterator for EnumerateCoordinate<I, C>
+where
+    I: Iterator,
+    C: gridly::location::Component,
+{
+    type Item = (C, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        let coordinate = self.coordinate;
+        self.coordinate = coordinate.add_distance(1);
+
+        Some((coordinate, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let item = self.iter.nth(n)?;
+        let coordinate = self.coordinate.add_distance(n as isize);
+        self.coordinate = coordinate.add_distance(1);
+
+        Some((coordinate, item))
+    }
+
+    fn fold<B, F>(self, init: B, mut func: F) -> B
+    where
+        Self: Sized,
+        F: FnMut(B, Self::Item) -> B,
+    {
+        self.iter
+            .fold((init, self.coordinate), |(accum, coordinate), item| {
+                (func(accum, (coordinate, item)), coordinate.add_distance(1))
+            })
+            .0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_drop_the_remainder() {
+        let chunks: Vec<[i32; 3]> = (0..8).streaming_chunks().collect();
+        assert_eq!(chunks, [[0, 1, 2], [3, 4, 5]]);
+    }
+
+    #[test]
+    fn chunks_nth_skips_whole_chunks() {
+        let mut chunks = (0..10).streaming_chunks::<2>();
+        assert_eq!(chunks.nth(2), Some([4, 5]));
+        assert_eq!(chunks.next(), Some([6, 7]));
+        assert_eq!(chunks.len(), 1);
+    }
+
+    #[test]
+    fn chunks_try_fold_stops_early() {
+        let sum = (0..10).streaming_chunks::<2>().try_fold(0, |sum, [a, b]| {
+            if a < 6 { Some(sum + a * b) } else { None }
+        });
+
+        assert_eq!(sum, None);
+    }
+
+    #[test]
+    fn windows_overlap() {
+        let windows: Vec<[i32; 2]> = (1..=4).streaming_windows().collect();
+        assert_eq!(windows, [[1, 2], [2, 3], [3, 4]]);
+    }
+
+    #[test]
+    fn windows_of_a_short_iterator() {
+        assert_eq!((0..2).streaming_windows::<3>().count(), 0);
+        assert_eq!((0..2).streaming_windows::<3>().next(), None);
+    }
+
+    #[test]
+    fn windows_fold_matches_next() {
+        let folded = (0..6)
+            .streaming_windows::<3>()
+            .fold(Vec::new(), |list, window| {
+                crate::express!(list.push(window))
+            });
+        let collected: Vec<[i32; 3]> = (0..6).streaming_windows().collect();
+
+        assert_eq!(folded, collected);
+    }
+
+    #[test]
+    fn build_iter_takes_a_prefix() {
+        assert_eq!(build_iter(0..10), [0, 1, 2]);
+        assert_eq!(try_build_iter::<_, 4>(0..3), None);
+    }
+
+    #[test]
+    fn disgorge_error_stops_at_the_first_error() {
+        let mut error = Ok(());
+        let sum: i32 = [Ok(1), Ok(2), Err("bad"), Ok(4)]
+            .into_iter()
+            .disgorge_error(&mut error)
+            .sum();
+
+        assert_eq!(sum, 3);
+        assert_eq!(error, Err("bad"));
+    }
+
+    #[test]
+    fn with_rows_counts_up() {
+        let rows: Vec<(Row, char)> = "abc".chars().with_rows(Row(5)).collect();
+        assert_eq!(rows, [(Row(5), 'a'), (Row(6), 'b'), (Row(7), 'c')]);
+
+        let mut columns = "abc".chars().with_columns(Column(0));
+        assert_eq!(columns.nth(1), Some((Column(1), 'b')));
+        assert_eq!(columns.next(), Some((Column(2), 'c')));
+    }
+}
@@ -0,0 +1,95 @@
+//! Reusable pieces for solving Advent of Code puzzles: iterator adapters,
+//! nom parsing helpers, counters, histograms, graph search, breadth-first
+//! distance fields, grid geometry, paths, rectangles, and sets of cells on
+//! the grid, mazes, grid rendering, topological sorting, and algorithm
+//! statistics. Nothing in here knows about any particular puzzle.
+//!
+//! The most commonly used items from [`iter`] and [`parse`] are re-exported
+//! at the crate root.
+
+#![feature(try_trait_v2)]
+
+pub mod bfs;
+pub mod counter;
+pub mod dijkstra;
+pub mod direction_map;
+pub mod dynamic;
+pub mod grid;
+pub mod histogram;
+pub mod iter;
+pub mod manhattan;
+pub mod maze;
+pub mod modular;
+pub mod parse;
+pub mod render;
+pub mod stats;
+pub mod svg;
+pub mod toposort;
+pub mod union_find;
+
+pub use crate::{
+    iter::{
+        Chunks, DisgorgeError, EnumerateCoordinate, IterExt, Windows, build_iter, try_build_iter,
+    },
+    parse::{
+        Definitely, ErrorWithLocation, ITResult, normalize_input, split_once_parser, split_parser,
+        split_parser_fold, split_parser_in,
+    },
+};
+
+/// Call a series of `&mut self` methods on a value, then evaluate to the
+/// value. Useful in folds and closures where a method returns `()`.
+///
+/// ```
+/// use aoc_toolkit::express;
+///
+/// let list = Vec::new();
+/// let list = express!(list.push(1).push(2));
+/// assert_eq!(list, [1, 2]);
+/// ```
+#[macro_export]
+macro_rules! express {
+    ($receiver:ident $(.$method:ident($($args:tt)*))*) => {
+        {
+            #[allow(unused_mut)]
+            let mut receiver = $receiver;
+            $(
+                receiver.$method($($args)*);
+            )*
+            receiver
+        }
+    }
+}
+
+/// Compare several pairs of values in order, stopping at the first pair that
+/// isn't equal. Useful for hand-written `Ord` implementations.
+///
+/// ```
+/// use std::cmp::Ordering;
+///
+/// use aoc_toolkit::cmp_all;
+///
+/// let ordering = cmp_all! {
+///     1, 1;
+///     "b", "a";
+///     0, 5;
+/// };
+/// assert_eq!(ordering, Ordering::Greater);
+/// ```
+#[macro_export]
+macro_rules! cmp_all {
+    (
+        $($lhs:expr, $rhs:expr;)*
+    ) => {{
+        let out = ::std::cmp::Ordering::Equal;
+
+        $(
+            let out = match out {
+                ::std::cmp::Ordering::Equal => ::std::cmp::Ord::cmp(&$lhs, &$rhs),
+                out => out,
+            };
+        )*
+
+        out
+    }}
+}
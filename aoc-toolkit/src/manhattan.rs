@@ -0,0 +1,146 @@
+//! Helpers for working with Manhattan (taxicab) distances on the grid,
+//! including every offset or location within (or at exactly) some distance
+
+use std::ops::RangeInclusive;
+
+use gridly::prelude::*;
+use itertools::Itertools;
+
+use crate::grid::Rect;
+
+/// The Manhattan length of a vector: the number of orthogonal steps it takes
+/// to travel it.
+pub fn length(vector: Vector) -> isize {
+    vector.rows.0.abs() + vector.columns.0.abs()
+}
+
+/// Iterate over every vector with a Manhattan length of at most `radius`,
+/// including the zero vector, row by row. Yields nothing if `radius` is
+/// negative.
+pub fn disk(radius: isize) -> impl Iterator<Item = Vector> + Clone {
+    (-radius..=radius).flat_map(move |row| {
+        let span = radius - row.abs();
+        (-span..=span).map(move |column| Vector::new(row, column))
+    })
+}
+
+/// Iterate over every vector with a Manhattan length of exactly `radius`,
+/// row by row. The ring of radius 0 is just the zero vector. Yields nothing
+/// if `radius` is negative.
+pub fn ring(radius: isize) -> impl Iterator<Item = Vector> + Clone {
+    (-radius..=radius).flat_map(move |row| {
+        let span = radius - row.abs();
+
+        // At the top and bottom of the ring, both sides are the same cell
+        [-span, span]
+            .into_iter()
+            .dedup()
+            .map(move |column| Vector::new(row, column))
+    })
+}
+
+/// The rows within `radius` of `center` that are also in `bounds`
+fn rows_within(center: Location, radius: isize, bounds: Rect) -> RangeInclusive<isize> {
+    let first = (center.row.0 - radius).max(bounds.root().row.0);
+    let last = (center.row.0 + radius).min(bounds.outer_bound().row.0 - 1);
+
+    first..=last
+}
+
+/// Iterate over every location in `bounds` within a Manhattan distance of
+/// `radius` from `center`, row by row. This is the same as offsetting
+/// `center` by each vector in [`disk`] and keeping the ones in bounds, but
+/// never generates the locations that would be thrown away, which matters
+/// when the radius is large compared to the grid or `center` is near its
+/// edge.
+pub fn disk_within(
+    center: Location,
+    radius: isize,
+    bounds: Rect,
+) -> impl Iterator<Item = Location> + Clone {
+    let columns = bounds.root().column.0..bounds.outer_bound().column.0;
+
+    rows_within(center, radius, bounds).flat_map(move |row| {
+        let span = radius - (row - center.row.0).abs();
+        let first = (center.column.0 - span).max(columns.start);
+        let last = (center.column.0 + span).min(columns.end - 1);
+
+        (first..=last).map(move |column| Location::new(row, column))
+    })
+}
+
+/// Iterate over every location in `bounds` at a Manhattan distance of
+/// exactly `radius` from `center`, row by row. Like [`disk_within`], this
+/// skips the rows that are out of bounds without generating them.
+pub fn ring_within(
+    center: Location,
+    radius: isize,
+    bounds: Rect,
+) -> impl Iterator<Item = Location> + Clone {
+    rows_within(center, radius, bounds).flat_map(move |row| {
+        let span = radius - (row - center.row.0).abs();
+
+        [-span, span]
+            .into_iter()
+            .dedup()
+            .map(move |column| Location::new(row, center.column.0 + column))
+            .filter(move |&location| bounds.contains(location))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disk_contains_every_short_vector() {
+        for radius in 0..5 {
+            let vectors: Vec<Vector> = disk(radius).collect();
+
+            assert_eq!(vectors.len() as isize, 2 * radius * (radius + 1) + 1);
+            assert!(vectors.iter().all(|&vector| length(vector) <= radius));
+        }
+
+        assert_eq!(disk(-1).count(), 0);
+    }
+
+    #[test]
+    fn ring_is_the_edge_of_the_disk() {
+        for radius in 0..5 {
+            let ring: Vec<Vector> = ring(radius).collect();
+            let edge: Vec<Vector> = disk(radius)
+                .filter(|&vector| length(vector) == radius)
+                .collect();
+
+            assert_eq!(ring, edge);
+        }
+
+        assert_eq!(ring(3).count(), 12);
+        assert_eq!(ring(-1).count(), 0);
+    }
+
+    #[test]
+    fn bounded_iterators_match_filtering() {
+        let bounds = Rect::new(Location::new(-1, 2), Vector::new(6, 4));
+
+        for center in Rect::new(Location::new(-4, -1), Vector::new(12, 10)).locations() {
+            for radius in 0..6 {
+                let offsets = |vectors: &mut dyn Iterator<Item = Vector>| -> Vec<Location> {
+                    vectors
+                        .map(|vector| center + vector)
+                        .filter(|&location| bounds.contains(location))
+                        .collect()
+                };
+
+                assert_eq!(
+                    disk_within(center, radius, bounds).collect::<Vec<_>>(),
+                    offsets(&mut disk(radius))
+                );
+                assert_eq!(
+                    ring_within(center, radius, bounds).collect::<Vec<_>>(),
+                    offsets(&mut ring(radius))
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Mazes: a rectangle of walls and open floor, with a start and an end, like
+//! the ones drawn with `#`, `.`, `S`, and `E` in so many puzzles. A [`Maze`]
+//! parses that drawing, answers wall queries from a [`LocationSet`], draws
+//! itself back out, and searches itself with [`bfs`](crate::bfs) and
+//! [`dijkstra`](crate::dijkstra), so that days only have to write the parts
+//! of the search that are particular to their puzzle.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use gridly::prelude::*;
+
+use crate::{
+    bfs::{self, DistanceField},
+    dijkstra,
+    grid::{LocationSet, Path, Rect, located_chars},
+    manhattan, render,
+};
+
+/// A maze, as walls within a rectangle, with a start and an end
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Maze {
+    walls: LocationSet,
+    start: Location,
+    end: Location,
+}
+
+impl Maze {
+    /// Create a maze with no walls at all
+    pub fn new(bounds: Rect, start: Location, end: Location) -> Self {
+        Self {
+            walls: LocationSet::new(bounds),
+            start,
+            end,
+        }
+    }
+
+    /// Parse a maze drawn with `#` for walls, `.` for open floor, and `S` and
+    /// `E` for the start and end (which are also open). The maze's bounds
+    /// cover every drawn cell, with the top-left corner at the origin.
+    /// Whitespace around each line is ignored.
+    pub fn parse(input: &str) -> Result<Self, ParseMazeError> {
+        let lines: Vec<&str> = input.lines().map(str::trim).collect();
+
+        let rows = lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(0, |last| last + 1);
+        let columns = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+
+        let mut walls = LocationSet::new(Rect::new(
+            Location::zero(),
+            Vector::new(rows as isize, columns as isize),
+        ));
+        let mut start = None;
+        let mut end = None;
+
+        for (location, cell) in located_chars(input) {
+            match cell {
+                '#' => {
+                    walls.insert(location);
+                }
+                '.' => {}
+                'S' if start.is_some() => return Err(ParseMazeError::MultipleStarts),
+                'S' => start = Some(location),
+                'E' if end.is_some() => return Err(ParseMazeError::MultipleEnds),
+                'E' => end = Some(location),
+                cell => return Err(ParseMazeError::InvalidCell { location, cell }),
+            }
+        }
+
+        Ok(Self {
+            walls,
+            start: start.ok_or(ParseMazeError::NoStart)?,
+            end: end.ok_or(ParseMazeError::NoEnd)?,
+        })
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.walls.bounds()
+    }
+
+    pub fn start(&self) -> Location {
+        self.start
+    }
+
+    pub fn end(&self) -> Location {
+        self.end
+    }
+
+    pub fn walls(&self) -> &LocationSet {
+        &self.walls
+    }
+
+    /// Put a wall at `location`. Fails if it's outside of the maze.
+    pub fn add_wall(&mut self, location: Location) -> Result<(), BoundsError> {
+        self.bounds().check_location(location)?;
+        self.walls.insert(location);
+        Ok(())
+    }
+
+    pub fn is_wall(&self, location: Location) -> bool {
+        self.walls.contains(location)
+    }
+
+    /// True if `location` is inside the maze, and not a wall
+    pub fn is_open(&self, location: Location) -> bool {
+        self.bounds().contains(location) && !self.is_wall(location)
+    }
+
+    /// Every open cell orthogonally adjacent to `location`
+    pub fn neighbors(&self, location: Location) -> impl Iterator<Item = Location> + '_ {
+        EACH_DIRECTION
+            .into_iter()
+            .map(move |direction| location + direction)
+            .filter(|&neighbor| self.is_open(neighbor))
+    }
+
+    /// The number of steps from `root` to every open cell reachable from it
+    pub fn distances_from(&self, root: Location) -> DistanceField {
+        bfs::distance_field(self.bounds(), [root], |location| !self.is_wall(location))
+    }
+
+    /// A shortest path from the start to the end, including both, or None if
+    /// the end can't be reached
+    pub fn shortest_path(&self) -> Option<Path> {
+        let route = dijkstra::shortest_path(
+            self.start,
+            |&location| self.neighbors(location).map(|neighbor| (neighbor, 1)),
+            |&location| manhattan::length(self.end - location) as i64,
+            |&location| location == self.end,
+        )?;
+
+        Some(Path::new(route.states))
+    }
+
+    /// How `location` is drawn: `#`, `.`, `S`, or `E`
+    pub fn cell(&self, location: Location) -> char {
+        if self.is_wall(location) {
+            '#'
+        } else if location == self.start {
+            'S'
+        } else if location == self.end {
+            'E'
+        } else {
+            '.'
+        }
+    }
+}
+
+/// Draws the maze the way it's parsed, relative to its top-left corner
+impl Display for Maze {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let bounds = self.bounds();
+        let offset = bounds.root() - Location::zero();
+
+        f.write_str(&render::grid(
+            bounds.num_rows(),
+            bounds.num_columns(),
+            |location| self.cell(location + offset),
+        ))
+    }
+}
+
+/// The ways a drawing of a maze can be invalid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMazeError {
+    InvalidCell { location: Location, cell: char },
+    MultipleStarts,
+    MultipleEnds,
+    NoStart,
+    NoEnd,
+}
+
+impl Display for ParseMazeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseMazeError::InvalidCell { location, cell } => write!(
+                f,
+                "invalid cell {cell:?} at row {}, column {}",
+                location.row.0, location.column.0
+            ),
+            ParseMazeError::MultipleStarts => f.write_str("multiple start locations"),
+            ParseMazeError::MultipleEnds => f.write_str("multiple end locations"),
+            ParseMazeError::NoStart => f.write_str("no start location"),
+            ParseMazeError::NoEnd => f.write_str("no end location"),
+        }
+    }
+}
+
+impl Error for ParseMazeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAZE: &str = "\
+#####
+#S..#
+#.#.#
+#..E#
+#####
+";
+
+    #[test]
+    fn parse_and_draw_round_trip() {
+        let maze = Maze::parse(MAZE).unwrap();
+
+        assert_eq!(maze.start(), Location::new(1, 1));
+        assert_eq!(maze.end(), Location::new(3, 3));
+        assert_eq!(maze.walls().len(), 17);
+        assert_eq!(maze.to_string(), MAZE);
+
+        assert_eq!(
+            Maze::parse("S.E\n.x.\n"),
+            Err(ParseMazeError::InvalidCell {
+                location: Location::new(1, 1),
+                cell: 'x'
+            })
+        );
+        assert_eq!(Maze::parse("S..\n"), Err(ParseMazeError::NoEnd));
+    }
+
+    #[test]
+    fn searches_stay_on_open_floor() {
+        let mut maze = Maze::parse(MAZE).unwrap();
+
+        let path = maze.shortest_path().unwrap();
+        assert_eq!(path.steps(), 4);
+        assert!(path.iter().all(|location| maze.is_open(location)));
+        assert_eq!(maze.distances_from(maze.start()).reachable().count(), 8);
+
+        maze.add_wall(Location::new(1, 2)).unwrap();
+        maze.add_wall(Location::new(2, 1)).unwrap();
+        assert_eq!(maze.shortest_path(), None);
+        assert!(maze.add_wall(Location::new(5, 0)).is_err());
+    }
+}
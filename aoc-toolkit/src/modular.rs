@@ -0,0 +1,130 @@
+//! Modular arithmetic helpers
+
+/// Extended euclidean algorithm. Returns `(g, x, y)` such that
+/// `a*x + b*y == g`, where `g` is the (non-negative) gcd of `a` and `b`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a.abs(), a.signum(), 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a.rem_euclid(b));
+        (g, y, x - a.div_euclid(b) * y)
+    }
+}
+
+/// A congruence `x ≡ residue (mod modulus)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Congruence {
+    pub residue: i64,
+    pub modulus: i64,
+}
+
+/// Combine a set of congruences with the Chinese remainder theorem. The
+/// moduli don't need to be coprime. Returns the combined congruence, with
+/// the residue normalized to `0..modulus`, or None if the congruences are
+/// inconsistent (or the combined modulus overflows).
+pub fn chinese_remainder(congruences: impl IntoIterator<Item = Congruence>) -> Option<Congruence> {
+    congruences.into_iter().try_fold(
+        Congruence {
+            residue: 0,
+            modulus: 1,
+        },
+        |accum, congruence| {
+            let (m1, r1) = (i128::from(accum.modulus), i128::from(accum.residue));
+            let (m2, r2) = (
+                i128::from(congruence.modulus),
+                i128::from(congruence.residue),
+            );
+
+            let (g, p, _) = extended_gcd(m1, m2);
+            let difference = r2 - r1;
+
+            if difference % g != 0 {
+                return None;
+            }
+
+            let modulus = m1 / g * m2;
+            let residue = (r1 + m1 * (difference / g * p).rem_euclid(m2 / g)).rem_euclid(modulus);
+
+            Some(Congruence {
+                residue: residue.try_into().ok()?,
+                modulus: modulus.try_into().ok()?,
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extended_gcd_is_bezout() {
+        for (a, b) in [(240, 46), (17, 5), (0, 9), (-12, 18)] {
+            let (g, x, y) = extended_gcd(a, b);
+
+            assert!(g >= 0);
+            assert_eq!(a * x + b * y, g);
+        }
+    }
+
+    #[test]
+    fn chinese_remainder_with_coprime_moduli() {
+        let combined = chinese_remainder([
+            Congruence {
+                residue: 2,
+                modulus: 3,
+            },
+            Congruence {
+                residue: 3,
+                modulus: 5,
+            },
+            Congruence {
+                residue: 2,
+                modulus: 7,
+            },
+        ]);
+
+        assert_eq!(
+            combined,
+            Some(Congruence {
+                residue: 23,
+                modulus: 105
+            })
+        );
+    }
+
+    #[test]
+    fn chinese_remainder_with_shared_factors() {
+        let consistent = chinese_remainder([
+            Congruence {
+                residue: 1,
+                modulus: 4,
+            },
+            Congruence {
+                residue: 3,
+                modulus: 6,
+            },
+        ]);
+
+        assert_eq!(
+            consistent,
+            Some(Congruence {
+                residue: 9,
+                modulus: 12
+            })
+        );
+
+        let inconsistent = chinese_remainder([
+            Congruence {
+                residue: 0,
+                modulus: 4,
+            },
+            Congruence {
+                residue: 1,
+                modulus: 6,
+            },
+        ]);
+
+        assert_eq!(inconsistent, None);
+    }
+}
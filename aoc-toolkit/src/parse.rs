@@ -0,0 +1,373 @@
+//! Helpers for writing nom parsers over puzzle inputs
+
+use std::{borrow::Cow, convert::Infallible};
+
+use bumpalo::Bump;
+use nom::{IResult, Parser, error::ParseError};
+use nom_supreme::{error::ErrorTree, tag::TagError};
+
+/// Build a parser out of a sequence of parsers, run one after another. Each
+/// parser's output can be bound to a name with `=> name`; the expression
+/// after the `;` builds the final output from those names. Every parser must
+/// implement [`nom::Parser`], which must be in scope.
+///
+/// ```
+/// use aoc_toolkit::{ITResult, parser};
+/// use nom::{Parser, character::complete::{char, digit1}};
+/// use nom_supreme::ParserExt;
+///
+/// fn pair(input: &str) -> ITResult<&str, (i32, i32)> {
+///     let mut pair = parser! {
+///         digit1.parse_from_str_cut() => a,
+///         char(','),
+///         digit1.parse_from_str_cut() => b;
+///         (a, b)
+///     };
+///
+///     pair(input)
+/// }
+///
+/// assert_eq!(pair("12,34").unwrap(), ("", (12, 34)));
+/// ```
+#[macro_export]
+macro_rules! parser {
+    (
+        $(
+            $parser:expr $(=> $bind:ident)?
+        ),* ;
+        $map:expr
+    ) => {
+        move |input| -> ::nom::IResult<_, _, _> {
+            $(
+                let (input, value) = match $parser.parse(input) {
+                    Ok(out) => out,
+                    Err(err) => return Err(err),
+                };
+                $(
+                    let $bind = value;
+                    let value = ();
+                )?
+                let _ = value;
+            )*
+
+            Ok((input, $map))
+        }
+    };
+}
+
+/// The result of a parse (or anything else) that can't fail
+pub type Definitely<T> = Result<T, Infallible>;
+
+/// A parse error that can have the input locations it refers to rewritten,
+/// for errors from a parser that ran on a slice of the original input
+pub trait ErrorWithLocation<I> {
+    fn map_location(self, map: impl Fn(I) -> I) -> Self;
+}
+
+impl<I> ErrorWithLocation<I> for ErrorTree<I> {
+    fn map_location(self, map: impl Fn(I) -> I) -> Self {
+        self.map_locations(map)
+    }
+}
+
+/// Run `item_parser` on each `separator`-separated piece of the input,
+/// folding the results together. Each item parser must consume its entire
+/// piece. The input may optionally end with a trailing separator.
+pub fn split_parser_fold<'i, 's, O, T, E>(
+    mut item_parser: impl Parser<&'i str, O, E> + 's,
+    separator: &'s str,
+    mut init: impl FnMut() -> T + 's,
+    mut fold: impl FnMut(T, O) -> T + 's,
+) -> impl Parser<&'i str, T, E> + 's
+where
+    E: ErrorWithLocation<&'i str>,
+    E: TagError<&'i str, &'s str>,
+    E: ParseError<&'i str>,
+{
+    if separator.is_empty() {
+        panic!("can't create a split parser with an empty separator")
+    }
+
+    move |input: &'i str| split_fold_helper(input, separator, &mut item_parser, init(), &mut fold)
+}
+
+fn split_fold_helper<'i, 's, O, T, E>(
+    mut input: &'i str,
+    separator: &'s str,
+    item_parser: &mut impl Parser<&'i str, O, E>,
+    mut accum: T,
+    fold: &mut impl FnMut(T, O) -> T,
+) -> IResult<&'i str, T, E>
+where
+    E: ErrorWithLocation<&'i str>,
+    E: TagError<&'i str, &'s str>,
+    E: ParseError<&'i str>,
+{
+    loop {
+        let (tail, item) = match split_once_parser_helper(input, separator, item_parser) {
+            SplitOnceParserOutput::Success(out) => out,
+            SplitOnceParserOutput::NoSplit if input.is_empty() => return Ok(("", accum)),
+            SplitOnceParserOutput::NoSplit => {
+                return item_parser
+                    .parse(input)
+                    .map(|(tail, item)| (tail, fold(accum, item)));
+            }
+            SplitOnceParserOutput::Err(err) => return Err(err),
+        };
+
+        accum = fold(accum, item);
+        input = tail;
+    }
+}
+
+/// Run `item_parser` on each `separator`-separated piece of the input, and
+/// collect the results.
+///
+/// ```
+/// use aoc_toolkit::{ITResult, split_parser};
+/// use nom::{Parser, character::complete::digit1};
+/// use nom_supreme::ParserExt;
+///
+/// fn number(input: &str) -> ITResult<&str, i32> {
+///     digit1.parse_from_str_cut().parse(input)
+/// }
+///
+/// fn numbers(input: &str) -> ITResult<&str, Vec<i32>> {
+///     split_parser(number, ",").parse(input)
+/// }
+///
+/// assert_eq!(numbers("1,22,333").unwrap(), ("", vec![1, 22, 333]));
+/// ```
+pub fn split_parser<'i, 's, O, T, E>(
+    item_parser: impl Parser<&'i str, O, E> + 's,
+    separator: &'s str,
+) -> impl Parser<&'i str, T, E> + 's
+where
+    E: ErrorWithLocation<&'i str>,
+    E: TagError<&'i str, &'s str>,
+    E: ParseError<&'i str>,
+    T: Default + Extend<O> + 's,
+{
+    split_parser_fold(item_parser, separator, T::default, |collection, item| {
+        crate::express!(collection.extend([item]))
+    })
+}
+
+/// Run `item_parser` on each `separator`-separated piece of the input, and
+/// collect the results into a slice allocated in `arena`, rather than a
+/// `Vec` of their own. The arena never runs destructors, so this is meant
+/// for items that don't own anything, like numbers or other arena slices.
+///
+/// ```
+/// use aoc_toolkit::{ITResult, split_parser_in};
+/// use bumpalo::Bump;
+/// use nom::{Parser, character::complete::digit1};
+/// use nom_supreme::ParserExt;
+///
+/// fn number(input: &str) -> ITResult<&str, i32> {
+///     digit1.parse_from_str_cut().parse(input)
+/// }
+///
+/// let arena = Bump::new();
+/// let (_, numbers) = split_parser_in(&arena, number, ",").parse("1,22,333").unwrap();
+/// assert_eq!(numbers, [1, 22, 333]);
+/// ```
+pub fn split_parser_in<'a, 'i, 's, O: 'a, E>(
+    arena: &'a Bump,
+    mut item_parser: impl Parser<&'i str, O, E>,
+    separator: &'s str,
+) -> impl Parser<&'i str, &'a mut [O], E>
+where
+    E: ErrorWithLocation<&'i str>,
+    E: TagError<&'i str, &'s str>,
+    E: ParseError<&'i str>,
+{
+    if separator.is_empty() {
+        panic!("can't create a split parser with an empty separator")
+    }
+
+    move |input: &'i str| {
+        split_fold_helper(
+            input,
+            separator,
+            &mut item_parser,
+            bumpalo::collections::Vec::new_in(arena),
+            &mut |items, item| crate::express!(items.push(item)),
+        )
+        .map(|(tail, items)| (tail, items.into_bump_slice_mut()))
+    }
+}
+
+enum SplitOnceParserOutput<I, O, E> {
+    Success((I, O)),
+    NoSplit,
+    Err(E),
+}
+
+fn split_once_parser_helper<'i, 's, T, E>(
+    input: &'i str,
+    separator: &'s str,
+    parser: &mut impl Parser<&'i str, T, E>,
+) -> SplitOnceParserOutput<&'i str, T, nom::Err<E>>
+where
+    E: TagError<&'i str, &'s str>,
+    E: ErrorWithLocation<&'i str>,
+    E: ParseError<&'i str>,
+{
+    let Some((left, right)) = input.split_once(separator) else {
+        return SplitOnceParserOutput::NoSplit;
+    };
+
+    let rebuild_tail = |local_tail: &str| {
+        let rebuilt_tail_len = local_tail.len() + separator.len() + right.len();
+        let parsed_len = input.len() - rebuilt_tail_len;
+        &input[parsed_len..]
+    };
+
+    match parser.parse(left) {
+        Ok(("", item)) => SplitOnceParserOutput::Success((right, item)),
+        Ok((tail, _)) => {
+            SplitOnceParserOutput::Err(nom::Err::Error(E::from_tag(rebuild_tail(tail), separator)))
+        }
+        Err(nom::Err::Incomplete(_)) => SplitOnceParserOutput::Err(nom::Err::Error(
+            E::from_error_kind(rebuild_tail(""), nom::error::ErrorKind::Complete),
+        )),
+        Err(err) => SplitOnceParserOutput::Err(
+            err.map(|err| err.map_location(|local_tail| rebuild_tail(local_tail))),
+        ),
+    }
+}
+
+/// Split the input string up to the `separator`, then run the `parser` on
+/// the prefix. The `parser` *must* consume the entire prefix string.
+pub fn split_once_parser<'i, 's, T, E>(
+    mut parser: impl Parser<&'i str, T, E> + 's,
+    separator: &'s str,
+) -> impl Parser<&'i str, T, E> + 's
+where
+    E: ErrorWithLocation<&'i str>,
+    E: TagError<&'i str, &'s str>,
+    E: ParseError<&'i str>,
+{
+    if separator.is_empty() {
+        panic!("can't create a split parser with an empty separator")
+    }
+
+    move |input: &'i str| match split_once_parser_helper(input, separator, &mut parser) {
+        SplitOnceParserOutput::Success(out) => Ok(out),
+        SplitOnceParserOutput::NoSplit => Err(nom::Err::Error(E::from_error_kind(
+            "",
+            nom::error::ErrorKind::Eof,
+        ))),
+        SplitOnceParserOutput::Err(err) => Err(err),
+    }
+}
+
+/// Put a puzzle input into the shape every parser expects: lines ending in
+/// `\n` rather than `\r\n`, no trailing whitespace on any line, and exactly
+/// one newline at the end, with any trailing blank lines removed. Inputs
+/// saved on Windows, or pasted from a browser, often break these rules; an
+/// input that already follows them is returned as-is.
+///
+/// ```
+/// use aoc_toolkit::parse::normalize_input;
+///
+/// assert_eq!(normalize_input("1 2\r\n3 4  \r\n\r\n\n"), "1 2\n3 4\n");
+/// assert_eq!(normalize_input("1 2\n3 4"), "1 2\n3 4\n");
+/// assert_eq!(normalize_input(" \n\n"), "");
+/// ```
+pub fn normalize_input(input: &str) -> Cow<'_, str> {
+    let content = input.trim_end();
+
+    if content.is_empty() {
+        return Cow::Borrowed("");
+    }
+
+    let normalized = input.len() == content.len() + 1
+        && input.ends_with('\n')
+        && content.split('\n').all(|line| line.trim_end() == line);
+
+    match normalized {
+        true => Cow::Borrowed(input),
+        false => Cow::Owned(
+            content
+                .split('\n')
+                .flat_map(|line| [line.trim_end(), "\n"])
+                .collect(),
+        ),
+    }
+}
+
+/// The result of a parser that reports errors as an [`ErrorTree`]
+pub type ITResult<I, O> = IResult<I, O, ErrorTree<I>>;
+
+#[cfg(test)]
+mod tests {
+    use nom::character::complete::digit1;
+    use nom_supreme::ParserExt;
+
+    use super::*;
+
+    fn number(input: &str) -> ITResult<&str, i32> {
+        digit1.parse_from_str_cut().parse(input)
+    }
+
+    #[test]
+    fn split_parser_collects_every_item() {
+        let (tail, numbers): (&str, Vec<i32>) =
+            split_parser(number, ", ").parse("1, 2, 3").unwrap();
+
+        assert_eq!(tail, "");
+        assert_eq!(numbers, [1, 2, 3]);
+    }
+
+    #[test]
+    fn split_parser_allows_a_trailing_separator() {
+        let (tail, numbers): (&str, Vec<i32>) = split_parser(number, "\n").parse("1\n2\n").unwrap();
+
+        assert_eq!(tail, "");
+        assert_eq!(numbers, [1, 2]);
+    }
+
+    #[test]
+    fn split_parser_fold_folds() {
+        let (_, sum) = split_parser_fold(number, ",", || 0, |sum, n| sum + n)
+            .parse("10,20,30")
+            .unwrap();
+
+        assert_eq!(sum, 60);
+    }
+
+    #[test]
+    fn split_parser_rejects_a_partial_item() {
+        let result: ITResult<&str, Vec<i32>> = split_parser(number, ",").parse("1,2x,3");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn split_once_parser_leaves_the_rest() {
+        let (tail, first) = split_once_parser(number, "\n\n")
+            .parse("12\n\nrest")
+            .unwrap();
+
+        assert_eq!(first, 12);
+        assert_eq!(tail, "rest");
+    }
+
+    #[test]
+    fn split_once_parser_needs_the_separator() {
+        let result = split_once_parser(number, "\n\n").parse("12");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn normalize_input_borrows_a_normal_input() {
+        let input = "1,2\n\n3,4\n";
+        assert!(matches!(normalize_input(input), Cow::Borrowed(normal) if normal == input));
+    }
+
+    #[test]
+    fn normalize_input_keeps_blank_lines_and_indentation() {
+        assert_eq!(normalize_input("  a\r\n\r\nb\t\r\n"), "  a\n\nb\n");
+    }
+}
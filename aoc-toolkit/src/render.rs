@@ -0,0 +1,19 @@
+//! Shared helpers for drawing grids as text
+
+use gridly::prelude::*;
+
+/// Draw a grid with its top-left corner at the origin, one character per
+/// cell and one line per row.
+pub fn grid(rows: Rows, columns: Columns, mut cell: impl FnMut(Location) -> char) -> String {
+    let mut out = String::new();
+
+    for row in (0..rows.0).map(Row) {
+        for column in (0..columns.0).map(Column) {
+            out.push(cell(row + column));
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
@@ -0,0 +1,175 @@
+//! Counters that algorithms record as they run, like the number of states a
+//! search expanded or how often a cache was hit, so that their work can be
+//! measured without sprinkling prints around. Recording does nothing until
+//! it's turned on with [`init`]; a runner can then [`take`] the counters once
+//! a solution is done and show them.
+//!
+//! Hot loops should count into a local [`Counter`], which adds its total to
+//! the shared counter when it's dropped, rather than calling [`add`] for
+//! every event.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    sync::{Mutex, MutexGuard, OnceLock},
+};
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Every counter recorded so far. Algorithms might record from any thread.
+static COUNTERS: Mutex<BTreeMap<&'static str, u64>> = Mutex::new(BTreeMap::new());
+
+fn lock_counters() -> MutexGuard<'static, BTreeMap<&'static str, u64>> {
+    COUNTERS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Turn recording on or off. Should be called exactly once, before anything
+/// is recorded.
+pub fn init(enabled: bool) {
+    if ENABLED.set(enabled).is_err() {
+        panic!("stats::init called more than once")
+    }
+}
+
+/// True if counters are being recorded. Algorithms can check this before
+/// doing any extra work to compute what they record.
+pub fn enabled() -> bool {
+    ENABLED.get().copied().unwrap_or(false)
+}
+
+/// Add `amount` to the counter called `name`
+pub fn add(name: &'static str, amount: u64) {
+    if enabled() {
+        *lock_counters().entry(name).or_default() += amount;
+    }
+}
+
+/// Take every counter recorded so far, resetting them all to 0
+pub fn take() -> Stats {
+    Stats {
+        counters: std::mem::take(&mut *lock_counters()),
+    }
+}
+
+/// A local count of some event, added to the shared counter called `name`
+/// when it's dropped. Counting is cheap whether or not recording is on.
+#[derive(Debug)]
+pub struct Counter {
+    name: &'static str,
+    count: u64,
+}
+
+impl Counter {
+    pub fn new(name: &'static str) -> Self {
+        Self { name, count: 0 }
+    }
+
+    pub fn increment(&mut self) {
+        self.count += 1;
+    }
+
+    pub fn add(&mut self, amount: u64) {
+        self.count += amount;
+    }
+
+    /// The number of events counted locally so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl Drop for Counter {
+    fn drop(&mut self) {
+        add(self.name, self.count);
+    }
+}
+
+/// A set of named counters, as returned by [`take`]. Displays as one line
+/// per counter, in name order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    counters: BTreeMap<&'static str, u64>,
+}
+
+impl Stats {
+    /// The value of a counter, or 0 if it was never recorded
+    pub fn get(&self, name: &str) -> u64 {
+        self.counters.get(name).copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+
+    /// Every counter and its value, in name order
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        self.counters.iter().map(|(&name, &count)| (name, count))
+    }
+}
+
+impl FromIterator<(&'static str, u64)> for Stats {
+    fn from_iter<I: IntoIterator<Item = (&'static str, u64)>>(iter: I) -> Self {
+        let mut counters = BTreeMap::new();
+
+        for (name, count) in iter {
+            *counters.entry(name).or_default() += count;
+        }
+
+        Self { counters }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self
+            .counters
+            .keys()
+            .map(|name| name.len())
+            .max()
+            .unwrap_or(0);
+
+        self.iter()
+            .try_for_each(|(name, count)| writeln!(f, "{name:width$}  {count}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Nothing here turns recording on, since it can only be turned on once
+    // per process
+
+    #[test]
+    fn nothing_is_recorded_until_enabled() {
+        let mut counter = Counter::new("tests.counter");
+        counter.add(3);
+        counter.increment();
+        assert_eq!(counter.count(), 4);
+        drop(counter);
+
+        add("tests.add", 1);
+
+        let stats = take();
+        assert_eq!(stats.get("tests.counter"), 0);
+        assert_eq!(stats.get("tests.add"), 0);
+    }
+
+    #[test]
+    fn stats_merge_repeated_names() {
+        let stats: Stats = [("b", 1), ("a", 2), ("b", 3)].into_iter().collect();
+
+        assert_eq!(stats.get("a"), 2);
+        assert_eq!(stats.get("b"), 4);
+        assert_eq!(stats.get("c"), 0);
+    }
+
+    #[test]
+    fn stats_display_aligned_in_name_order() {
+        let stats: Stats = [("search.pushes", 12), ("cache", 3)].into_iter().collect();
+
+        assert_eq!(stats.to_string(), "cache          3\nsearch.pushes  12\n");
+    }
+}
@@ -0,0 +1,279 @@
+//! Render grids as SVG images, for when text isn't pretty enough
+
+use std::{borrow::Cow, fmt::Write};
+
+use gridly::prelude::*;
+
+use crate::grid::GridExt;
+
+/// The shape a cell is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// Fill the whole cell
+    Square,
+
+    /// A slightly inset square with rounded corners
+    Rounded,
+
+    /// A circle inscribed in the cell
+    Circle,
+}
+
+/// How to draw a single cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellStyle {
+    /// The fill color, as any SVG color. If None, nothing is drawn, and the
+    /// background shows through.
+    pub fill: Option<Cow<'static, str>>,
+    pub shape: Shape,
+
+    /// Text drawn in the center of the cell
+    pub label: Option<String>,
+}
+
+impl CellStyle {
+    /// A cell that isn't drawn at all
+    pub const BLANK: Self = Self {
+        fill: None,
+        shape: Shape::Square,
+        label: None,
+    };
+
+    pub fn new(shape: Shape, fill: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            fill: Some(fill.into()),
+            shape,
+            label: None,
+        }
+    }
+
+    pub fn with_label(self, label: impl Into<String>) -> Self {
+        Self {
+            label: Some(label.into()),
+            ..self
+        }
+    }
+}
+
+/// An SVG rendering of a grid. Each cell is drawn according to a style
+/// function; sets of cells can then be highlighted, and paths drawn through
+/// the centers of cells, on top of the grid.
+pub struct Document<'a, G, F> {
+    grid: &'a G,
+    style: F,
+    cell_size: f64,
+    background: Cow<'static, str>,
+    highlights: Vec<(Vec<Location>, Cow<'static, str>)>,
+    paths: Vec<(Vec<Location>, Cow<'static, str>)>,
+}
+
+impl<'a, G, F> Document<'a, G, F>
+where
+    G: Grid,
+    F: Fn(&G::Item) -> CellStyle,
+{
+    pub fn new(grid: &'a G, style: F) -> Self {
+        Self {
+            grid,
+            style,
+            cell_size: 16.0,
+            background: "white".into(),
+            highlights: Vec::new(),
+            paths: Vec::new(),
+        }
+    }
+
+    /// Set the width and height of each cell, in pixels (default 16)
+    pub fn cell_size(self, cell_size: f64) -> Self {
+        Self { cell_size, ..self }
+    }
+
+    pub fn background(self, color: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            background: color.into(),
+            ..self
+        }
+    }
+
+    /// Shade a set of cells with a translucent color
+    pub fn highlight(
+        mut self,
+        locations: impl IntoIterator<Item = Location>,
+        color: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.highlights
+            .push((locations.into_iter().collect(), color.into()));
+        self
+    }
+
+    /// Draw a line through the centers of a sequence of cells
+    pub fn path(
+        mut self,
+        locations: impl IntoIterator<Item = Location>,
+        color: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.paths
+            .push((locations.into_iter().collect(), color.into()));
+        self
+    }
+
+    /// The top-left corner of a cell, in pixels
+    fn corner(&self, location: Location) -> (f64, f64) {
+        let offset = location - self.grid.root();
+        (
+            offset.columns.0 as f64 * self.cell_size,
+            offset.rows.0 as f64 * self.cell_size,
+        )
+    }
+
+    fn center(&self, location: Location) -> (f64, f64) {
+        let (x, y) = self.corner(location);
+        (x + self.cell_size / 2.0, y + self.cell_size / 2.0)
+    }
+
+    pub fn render(&self) -> String {
+        let size = self.cell_size;
+        let width = self.grid.num_columns().0 as f64 * size;
+        let height = self.grid.num_rows().0 as f64 * size;
+
+        let mut out = String::new();
+
+        // Writing to a String can't fail
+        let _ = self.write(&mut out, width, height, size);
+        out
+    }
+
+    fn write(&self, out: &mut String, width: f64, height: f64, size: f64) -> std::fmt::Result {
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        )?;
+        writeln!(
+            out,
+            r#"<rect width="100%" height="100%" fill="{}"/>"#,
+            escape(&self.background)
+        )?;
+
+        for (location, cell) in self.grid.iter_located() {
+            self.write_cell(out, location, &(self.style)(cell))?;
+        }
+
+        for (locations, color) in &self.highlights {
+            for &location in locations {
+                let (x, y) = self.corner(location);
+                writeln!(
+                    out,
+                    r#"<rect x="{x}" y="{y}" width="{size}" height="{size}" fill="{}" fill-opacity="0.5"/>"#,
+                    escape(color)
+                )?;
+            }
+        }
+
+        for (locations, color) in &self.paths {
+            let points: Vec<String> = locations
+                .iter()
+                .map(|&location| {
+                    let (x, y) = self.center(location);
+                    format!("{x},{y}")
+                })
+                .collect();
+
+            writeln!(
+                out,
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                points.join(" "),
+                escape(color),
+                size / 4.0
+            )?;
+        }
+
+        writeln!(out, "</svg>")
+    }
+
+    fn write_cell(
+        &self,
+        out: &mut String,
+        location: Location,
+        style: &CellStyle,
+    ) -> std::fmt::Result {
+        let size = self.cell_size;
+        let (x, y) = self.corner(location);
+        let (cx, cy) = self.center(location);
+
+        if let Some(fill) = &style.fill {
+            let fill = escape(fill);
+
+            match style.shape {
+                Shape::Square => writeln!(
+                    out,
+                    r#"<rect x="{x}" y="{y}" width="{size}" height="{size}" fill="{fill}"/>"#
+                )?,
+                Shape::Rounded => {
+                    let inset = size / 10.0;
+                    writeln!(
+                        out,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" fill="{fill}"/>"#,
+                        x + inset,
+                        y + inset,
+                        size - inset * 2.0,
+                        size - inset * 2.0,
+                        size / 5.0,
+                    )?
+                }
+                Shape::Circle => writeln!(
+                    out,
+                    r#"<circle cx="{cx}" cy="{cy}" r="{}" fill="{fill}"/>"#,
+                    size * 0.4
+                )?,
+            }
+        }
+
+        if let Some(label) = &style.label {
+            writeln!(
+                out,
+                r#"<text x="{cx}" y="{cy}" font-family="monospace" font-size="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                size * 0.75,
+                escape(label)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Escape text for use in SVG attributes and text
+fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"']) {
+        return Cow::Borrowed(text);
+    }
+
+    Cow::Owned(
+        text.chars()
+            .map(|c| match c {
+                '&' => "&amp;".to_owned(),
+                '<' => "&lt;".to_owned(),
+                '>' => "&gt;".to_owned(),
+                '"' => "&quot;".to_owned(),
+                c => c.to_string(),
+            })
+            .collect(),
+    )
+}
+
+/// A default style for text frames, covering the characters the days use:
+/// `#` is a wall, `.` and spaces are empty, and `O`, `@`, box edges, and
+/// path marks get their own colors. Anything else is drawn as a label.
+pub fn char_style(&cell: &char) -> CellStyle {
+    match cell {
+        '.' | ' ' => CellStyle::BLANK,
+        '#' => CellStyle::new(Shape::Square, "#3b3b3b"),
+        'O' => CellStyle::new(Shape::Circle, "#2a7ab0"),
+        '@' => CellStyle::new(Shape::Circle, "#c0392b"),
+        '[' | ']' | '=' => CellStyle::new(Shape::Rounded, "#b5835a"),
+        'X' => CellStyle::new(Shape::Square, "#f5b041"),
+        '^' | '>' | 'v' | '<' => CellStyle::new(Shape::Square, "#aed6f1").with_label(cell),
+        'S' => CellStyle::new(Shape::Rounded, "#58d68d").with_label(cell),
+        'E' => CellStyle::new(Shape::Rounded, "#ec7063").with_label(cell),
+        cell => CellStyle::new(Shape::Rounded, "#f7dc6f").with_label(cell),
+    }
+}
@@ -0,0 +1,126 @@
+//! Topological sorting with Kahn's algorithm: put a set of nodes in an order
+//! where, for every `(before, after)` edge, `before` comes first. Whenever
+//! there's a choice, the smallest available node goes next, so the order is
+//! always the same for the same edges.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap},
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+/// The edges had a cycle, so there's no order. `remaining` is every node
+/// that couldn't be placed: the ones on a cycle, and the ones after them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<N> {
+    pub remaining: Vec<N>,
+}
+
+impl<N> Display for Cycle<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the ordering has a cycle; {} nodes couldn't be placed",
+            self.remaining.len()
+        )
+    }
+}
+
+impl<N: Debug> Error for Cycle<N> {}
+
+#[derive(Debug)]
+struct Node<N> {
+    /// The number of edges into this node from nodes that aren't placed yet
+    unplaced_predecessors: usize,
+    successors: Vec<N>,
+}
+
+impl<N> Default for Node<N> {
+    fn default() -> Self {
+        Self {
+            unplaced_predecessors: 0,
+            successors: Vec::new(),
+        }
+    }
+}
+
+/// Sort `nodes`, along with every node mentioned in `edges`, so that each
+/// edge's first node comes before its second. Duplicate nodes and edges are
+/// fine.
+pub fn topological_sort<N: Ord + Clone>(
+    nodes: impl IntoIterator<Item = N>,
+    edges: impl IntoIterator<Item = (N, N)>,
+) -> Result<Vec<N>, Cycle<N>> {
+    let mut graph: BTreeMap<N, Node<N>> = nodes
+        .into_iter()
+        .map(|node| (node, Node::default()))
+        .collect();
+
+    for (before, after) in edges {
+        let successors = &mut graph.entry(before.clone()).or_default().successors;
+
+        if !successors.contains(&after) {
+            successors.push(after.clone());
+            graph.entry(after).or_default().unplaced_predecessors += 1;
+        }
+    }
+
+    let mut ready: BinaryHeap<Reverse<N>> = graph
+        .iter()
+        .filter(|(_, node)| node.unplaced_predecessors == 0)
+        .map(|(key, _)| Reverse(key.clone()))
+        .collect();
+
+    let mut order = Vec::with_capacity(graph.len());
+
+    while let Some(Reverse(key)) = ready.pop() {
+        let successors =
+            std::mem::take(&mut graph.get_mut(&key).expect("ready nodes exist").successors);
+
+        for successor in successors {
+            let node = graph.get_mut(&successor).expect("successors exist");
+            node.unplaced_predecessors -= 1;
+
+            if node.unplaced_predecessors == 0 {
+                ready.push(Reverse(successor));
+            }
+        }
+
+        order.push(key);
+    }
+
+    match order.len() == graph.len() {
+        true => Ok(order),
+        false => Err(Cycle {
+            remaining: graph
+                .into_iter()
+                .filter(|(_, node)| node.unplaced_predecessors > 0)
+                .map(|(key, _)| key)
+                .collect(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smallest_available_node_goes_first() {
+        let order = topological_sort([9], [(3, 1), (2, 1), (1, 0), (3, 1)]).unwrap();
+        assert_eq!(order, [2, 3, 1, 0, 9]);
+    }
+
+    #[test]
+    fn cycles_leave_nodes_behind() {
+        let result = topological_sort([], [(0, 1), (1, 2), (2, 1), (2, 3)]);
+
+        assert_eq!(
+            result,
+            Err(Cycle {
+                remaining: vec![1, 2, 3]
+            })
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! A simple disjoint-set forest over dense `usize` keys, with path compression
+//! and union by size.
+
+#[derive(Debug, Clone, Default)]
+pub struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+/// The outcome of a `union` call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Union {
+    /// The two keys were already in the same set, with this root
+    Same(usize),
+
+    /// The two sets were merged. `root` is the root of the merged set, and
+    /// `absorbed` is the old root of the other set, which is no longer a root.
+    Merged { root: usize, absorbed: usize },
+}
+
+impl UnionFind {
+    /// Create a new union-find with `len` singleton sets, `0..len`
+    pub fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+            sizes: vec![1; len],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Add a new singleton set, and return its key
+    pub fn push(&mut self) -> usize {
+        let key = self.parents.len();
+        self.parents.push(key);
+        self.sizes.push(1);
+        key
+    }
+
+    /// Find the root of the set containing `key`
+    pub fn find(&mut self, key: usize) -> usize {
+        let mut root = key;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Path compression
+        let mut key = key;
+        while self.parents[key] != root {
+            key = std::mem::replace(&mut self.parents[key], root);
+        }
+
+        root
+    }
+
+    /// Merge the sets containing `a` and `b`
+    pub fn union(&mut self, a: usize, b: usize) -> Union {
+        let a = self.find(a);
+        let b = self.find(b);
+
+        if a == b {
+            return Union::Same(a);
+        }
+
+        let (root, absorbed) = match self.sizes[a] >= self.sizes[b] {
+            true => (a, b),
+            false => (b, a),
+        };
+
+        self.parents[absorbed] = root;
+        self.sizes[root] += self.sizes[absorbed];
+
+        Union::Merged { root, absorbed }
+    }
+
+    /// Check if `a` and `b` are in the same set
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// The number of keys in the set containing `key`
+    pub fn set_size(&mut self, key: usize) -> usize {
+        let root = self.find(key);
+        self.sizes[root]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_sets() {
+        let mut sets = UnionFind::new(5);
+
+        assert!(matches!(sets.union(0, 1), Union::Merged { .. }));
+        assert!(matches!(sets.union(3, 4), Union::Merged { .. }));
+        assert!(matches!(sets.union(1, 0), Union::Same(_)));
+
+        assert!(sets.connected(0, 1));
+        assert!(!sets.connected(1, 3));
+        assert_eq!(sets.set_size(4), 2);
+
+        sets.union(1, 4);
+        assert!(sets.connected(0, 3));
+        assert_eq!(sets.set_size(0), 4);
+        assert_eq!(sets.set_size(2), 1);
+    }
+
+    #[test]
+    fn pushes_new_singletons() {
+        let mut sets = UnionFind::default();
+        assert!(sets.is_empty());
+
+        let a = sets.push();
+        let b = sets.push();
+
+        assert_eq!(sets.len(), 2);
+        assert!(!sets.connected(a, b));
+        assert_eq!(sets.find(b), b);
+    }
+}
@@ -0,0 +1,200 @@
+//! Benchmarks for every day, run against the example inputs in
+//! `inputs/examples`. Each day gets a group with separate benchmarks for
+//! parsing the input and for solving each part from an already parsed input.
+//! Days with alternative algorithms (see `library::validate`) also get a
+//! group for each choice, comparing every variant on the same input, and
+//! days that can parse into an arena get a group comparing that with their
+//! usual parser.
+//!
+//! Run them with `cargo bench`, or `cargo bench -- day16` for a single day.
+
+#![feature(array_windows)]
+// Cargo checks benchmarks without a test harness under `cfg(test)`, which
+// compiles the days' unit test modules without any of their tests
+#![cfg_attr(test, allow(unused))]
+
+include!(concat!(env!("OUT_DIR"), "/days.rs"));
+
+// The days find the library at `crate::library`, as they do in the runner
+#[path = "../src"]
+mod runner {
+    pub mod library;
+}
+
+use std::{fs, hint::black_box, path::Path};
+
+use aoc_toolkit::{direction_map, express, parser};
+use bumpalo::Bump;
+use criterion::{
+    BatchSize, BenchmarkGroup, Criterion, criterion_group, criterion_main, measurement::WallTime,
+};
+
+use crate::runner::library::{self, params, solver::Solver, stream::Solution};
+
+type Params = &'static [(&'static str, &'static str)];
+
+/// Parameters a day needs to make sense of its example input, which is
+/// usually much smaller than a real one
+fn example_params(day: u8) -> Params {
+    match day {
+        14 => &[("room", "11x7")],
+        18 => &[("size", "7"), ("bytes", "12")],
+        20 => &[("threshold", "20")],
+        _ => &[],
+    }
+}
+
+/// Parts, as (day, part), that can't be solved from the example input
+const UNSOLVABLE: &[(u8, u8)] = &[
+    // The example circuit isn't an adder with swapped wires
+    (24, 2),
+    // There's no puzzle
+    (25, 2),
+];
+
+/// Alternative algorithms, as (day, part, param, variants), where each
+/// variant is selected with `--param {param}={variant}`
+const VARIANTS: &[(u8, u8, &str, &[&str])] = &[
+    (10, 2, "rating", &["memoized", "dfs"]),
+    (11, 2, "single_child", &["tail", "subtask"]),
+    (11, 2, "solver", &["memo", "graph"]),
+    (12, 1, "algorithm", &["flood", "scan", "regions"]),
+    (12, 2, "sides", &["fences", "corners", "scan", "regions"]),
+    (14, 2, "detector", &["crt", "neighbors"]),
+    (16, 2, "seats", &["distances", "search"]),
+    (18, 2, "strategy", &["search", "reverse", "incremental"]),
+    (19, 1, "matcher", &["trie", "scan"]),
+    (19, 2, "matcher", &["trie", "scan"]),
+];
+
+fn load_example(day: u8) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs/examples")
+        .join(format!("day{day}.txt"));
+
+    fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("failed to read {:?}: {err}", path.display()))
+}
+
+fn parse<S: Solver>(input: &str) -> S::Input<'_> {
+    S::parse(input).expect("failed to parse the example input")
+}
+
+/// Benchmark solving a part, not counting the time spent parsing its input.
+/// Streaming parts are run to the end.
+fn bench_solve<'a, S, A>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    name: &str,
+    input: &'a str,
+    solve: &impl Fn(S::Input<'a>) -> anyhow::Result<A>,
+) where
+    S: Solver,
+    A: Solution,
+{
+    group.bench_function(name, |bencher| {
+        bencher.iter_batched(
+            || parse::<S>(input),
+            |parsed| {
+                solve(parsed)
+                    .and_then(Solution::answer)
+                    .expect("failed to solve the example input")
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn bench_day<S: Solver>(criterion: &mut Criterion, day: u8, input: &str) {
+    let solvable = |part| !UNSOLVABLE.contains(&(day, part));
+
+    params::with_overrides(example_params(day), || {
+        let mut group = criterion.benchmark_group(format!("day{day}"));
+
+        group.bench_function("parse", |bencher| {
+            bencher.iter(|| S::parse(black_box(input)))
+        });
+
+        if solvable(1) {
+            bench_solve::<S, _>(&mut group, "part1", input, &S::part1);
+        }
+
+        if solvable(2) {
+            bench_solve::<S, _>(&mut group, "part2", input, &S::part2);
+        }
+
+        group.finish();
+
+        for &(_, part, param, variants) in VARIANTS.iter().filter(|variant| variant.0 == day) {
+            let mut group = criterion.benchmark_group(format!("day{day}-part{part}-{param}"));
+
+            for &variant in variants {
+                params::with_overrides(&[(param, variant)], || match part {
+                    1 => bench_solve::<S, _>(&mut group, variant, input, &S::part1),
+                    _ => bench_solve::<S, _>(&mut group, variant, input, &S::part2),
+                });
+            }
+
+            group.finish();
+        }
+    });
+}
+
+macro_rules! bench_days {
+    ($($module:ident $day:literal,)*) => {
+        fn days(criterion: &mut Criterion) {
+            $(
+                let input = load_example($day);
+                bench_day::<$module::Puzzle>(criterion, $day, &input);
+            )*
+        }
+    };
+}
+
+for_each_day!(bench_days);
+
+/// Compare each day's usual parser with its `parse_in`, which allocates the
+/// parsed input in an arena. The arena is reused, and reset before each
+/// parse, as it would be by something parsing many inputs in a row.
+fn bench_arena_parse<'a, I: TryFrom<&'a str>, A>(
+    criterion: &mut Criterion,
+    day: u8,
+    input: &'a str,
+    parse_in: impl for<'b> Fn(&'b Bump, &'b str) -> A,
+) {
+    let mut group = criterion.benchmark_group(format!("day{day}-parse"));
+
+    group.bench_function("vec", |bencher| {
+        bencher.iter(|| I::try_from(black_box(input)))
+    });
+
+    let mut arena = Bump::new();
+
+    group.bench_function("arena", |bencher| {
+        bencher.iter(|| {
+            arena.reset();
+            black_box(parse_in(&arena, black_box(input)));
+        })
+    });
+
+    group.finish();
+}
+
+fn arena_parsing(criterion: &mut Criterion) {
+    let input = load_example(5);
+    bench_arena_parse::<day5::Input, _>(criterion, 5, &input, |arena, input| {
+        day5::parse_in(arena, input).is_ok()
+    });
+
+    let input = load_example(15);
+    bench_arena_parse::<day15::Input, _>(criterion, 15, &input, |arena, input| {
+        day15::parse_in(arena, input).is_ok()
+    });
+
+    let input = load_example(19);
+    bench_arena_parse::<day19::Input, _>(criterion, 19, &input, |arena, input| {
+        day19::parse_in(arena, input).is_ok()
+    });
+}
+
+criterion_group!(benches, days, arena_parsing);
+criterion_main!(benches);
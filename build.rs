@@ -1,8 +1,8 @@
 use std::{
     env,
-    fs::{read_dir, File},
+    fs::{read_dir, read_to_string, File},
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use lazy_format::lazy_format;
@@ -26,7 +26,7 @@ fn main() {
 
     let items = read_dir(&source_directory).expect("couldn't open the source directory");
 
-    let days: Vec<i32> = items
+    let mut days: Vec<i32> = items
         .map(|item| item.expect("failed to read directory entry"))
         .filter(|item| item.file_type().unwrap().is_file())
         .filter_map(|item| {
@@ -42,6 +42,7 @@ fn main() {
         })
         .collect();
 
+    days.sort_unstable();
     let days = days.as_slice();
 
     let mods = lazy_format!(
@@ -50,28 +51,57 @@ fn main() {
         for day in days
     );
 
+    // Lets other crate roots (like the benchmarks) do something with every
+    // day, without having to keep their own list
+    let day_list = lazy_format!("day{day} {day}," for day in days);
+
+    let days_content = lazy_format!(
+        "
+        {mods}
+
+        #[allow(unused_macros)]
+        macro_rules! for_each_day {{
+            ($callback:ident) => {{
+                $callback! {{ {day_list} }}
+            }};
+        }}"
+    );
+
     let enum_variants = lazy_format!("Day{day},\n" for day in days);
     let match_arms = lazy_format!("{day} => Ok(Day::Day{day}),\n" for day in days);
-    let solver_match_arms = lazy_format!(
-        "#[allow(clippy::unnecessary_fallible_conversions)]
-        #[allow(clippy::useless_conversion)]
-        (Day::Day{day}, Part::Part{part}) => input
-            .try_into()
-            .inspect(|input| {{
-                if show_input {{
-                    eprintln!(\"Parsed input:\n{{input:#?}}\");
-                }}
-            }})
-            .context(\"failed to parse input\")
-            .and_then(|input| day{day}::part{part}(input).context(\"failed to compute solution after successful parse\"))
-            .context(\"failed to solve day {day}, part {part}\")
-            .map(|solution| println!(\"{{solution}}\")),\n"
-        for (day, part) in days.iter().flat_map(|&day| [(day, 1), (day, 2)])
+    let visit_match_arms = lazy_format!(
+        "Day::Day{day} => visitor.visit::<day{day}::Puzzle>(),\n"
+        for day in days
+    );
+
+    let all_days = lazy_format!("Day::Day{day}, " for day in days);
+    let number_match_arms = lazy_format!("Day::Day{day} => {day},\n" for day in days);
+
+    let sources: Vec<(i32, String)> = days
+        .iter()
+        .map(|&day| {
+            let source = read_to_string(source_directory.join(format!("day{day}.rs")))
+                .unwrap_or_else(|_| panic!("failed to read day{day}.rs"));
+
+            (day, source)
+        })
+        .collect();
+
+    let sources = sources.as_slice();
+
+    let title_match_arms = lazy_format!(
+        "Day::Day{day} => {title:?},\n"
+        for (day, title) in sources.iter().map(|(day, source)| (day, title(*day, source)))
+    );
+
+    let parts_match_arms = lazy_format!(
+        "Day::Day{day} => &[{parts}],\n"
+        for (day, parts) in sources.iter().map(|(day, source)| (day, implemented_parts(source)))
     );
 
     let generated_content = lazy_format!(
         "
-        {mods}
+        include!(concat!(env!(\"OUT_DIR\"), \"/days.rs\"));
 
         #[derive(Debug, Clone, Copy, PartialEq, Eq)]
         enum Day {{
@@ -92,22 +122,86 @@ fn main() {
         }}
 
 
-        fn run_solution(day: Day, part: Part, input: &str, show_input: bool) -> anyhow::Result<()> {{
-            match (day, part) {{
-                {solver_match_arms}
+        impl Day {{
+            const ALL: &[Day] = &[{all_days}];
+
+            fn number(self) -> u8 {{
+                match self {{
+                    {number_match_arms}
+                }}
+            }}
+
+            fn title(self) -> &'static str {{
+                match self {{
+                    {title_match_arms}
+                }}
+            }}
+
+            fn parts(self) -> &'static [u8] {{
+                match self {{
+                    {parts_match_arms}
+                }}
+            }}
+
+            /// Call `visitor` with this day's `Puzzle`
+            fn visit<V: library::solver::Visitor>(self, visitor: V) -> V::Output {{
+                match self {{
+                    {visit_match_arms}
+                }}
             }}
         }}"
     );
 
-    let generated_content = generated_content.to_string();
+    let output_directory = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set in build.rs"));
 
-    let output_path = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set in build.rs"));
-    let output_path = output_path.join("generated.rs");
+    write_output(&output_directory, "days.rs", &days_content.to_string());
+    write_output(
+        &output_directory,
+        "generated.rs",
+        &generated_content.to_string(),
+    );
+}
+
+/// A day's title, from the `//! Day N: Title` line its source starts with
+fn title(day: i32, source: &str) -> &str {
+    let prefix = format!("//! Day {day}: ");
+
+    source
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix(&prefix))
+        .unwrap_or_else(|| panic!("day{day}.rs should start with `{prefix}<title>`"))
+}
+
+/// The parts in a day's source whose functions can return an answer, as a
+/// comma separated list
+fn implemented_parts(source: &str) -> String {
+    // A part that returns `Result<Infallible>` can never produce an answer,
+    // like the template's placeholders, so it doesn't count as implemented
+    [1, 2]
+        .into_iter()
+        .filter(|part| {
+            let signature = format!("pub fn part{part}(");
+
+            source
+                .lines()
+                .find(|line| line.starts_with(&signature))
+                .is_some_and(|line| !line.contains("Infallible"))
+        })
+        .map(|part| part.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn write_output(directory: &Path, name: &str, content: &str) {
+    let mut output =
+        File::create(directory.join(name)).unwrap_or_else(|_| panic!("failed to create {name}"));
 
-    let mut output = File::create(output_path).expect("failed to create generated.rs");
     output
-        .write_all(generated_content.as_bytes())
-        .expect("failed to write to generated.rs");
+        .write_all(content.as_bytes())
+        .unwrap_or_else(|_| panic!("failed to write to {name}"));
 
-    output.flush().expect("failed to write to generated.rs");
+    output
+        .flush()
+        .unwrap_or_else(|_| panic!("failed to write to {name}"));
 }
@@ -1,3 +1,5 @@
+//! Day 1: Historian Hysteria
+
 use nom::{
     character::complete::{digit1, multispace0, multispace1, space1},
     combinator::eof,
@@ -6,13 +8,14 @@ use nom::{
 use nom_supreme::{
     error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated, ParserExt,
 };
+use serde::Serialize;
 
 use crate::{
-    library::{counter::Counter, Definitely, ITResult},
+    library::{counter::Counter, solver::solver, Definitely, ITResult},
     parser,
 };
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize)]
 pub struct Input {
     left: Vec<i32>,
     right: Vec<i32>,
@@ -75,3 +78,5 @@ pub fn part2(input: Input) -> Definitely<usize> {
         .map(|i| i as usize * counts.get(&i))
         .sum())
 }
+
+solver!(Input, i32, usize);
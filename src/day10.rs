@@ -1,4 +1,9 @@
-use std::collections::HashSet;
+//! Day 10: Hoof It
+
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+};
 
 use gridly::prelude::*;
 use gridly_grids::VecGrid;
@@ -12,36 +17,73 @@ use nom::{
 use nom_supreme::{
     ParserExt, error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated,
 };
+use serde::Serialize;
 use thiserror::Error;
 
 use crate::{
     express,
-    library::{Definitely, ITResult},
+    library::{Definitely, ITResult, dynamic, grid::GridExt, params, solver::solver, validate},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 struct Height(u8);
 
 impl Height {
-    fn is_valid_successor_from(self, origin: Self) -> bool {
-        self.0 == origin.0 + 1
+    fn from_char(c: char) -> Option<Self> {
+        c.to_digit(10).map(|n| n as u8).map(Height)
+    }
+}
+
+/// The rules defining what counts as a trail: where it starts, where it ends,
+/// and how much the height must change with each step. Steps may be negative,
+/// for descending trails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrailRules {
+    pub start: u8,
+    pub end: u8,
+    pub step: i8,
+}
+
+impl TrailRules {
+    /// The rules from the puzzle: trails climb from 0 to 9 one step at a time
+    pub const STANDARD: Self = Self {
+        start: 0,
+        end: 9,
+        step: 1,
+    };
+
+    /// Get the rules requested with `--param start=N`, `--param end=N`, and
+    /// `--param step=N`; any that aren't given are the puzzle's. A step of 0
+    /// is rejected, since a trail could then wander around a flat area
+    /// forever.
+    pub fn from_runner() -> anyhow::Result<Self> {
+        let rules = Self {
+            start: params::get_or("start", Self::STANDARD.start)?,
+            end: params::get_or("end", Self::STANDARD.end)?,
+            step: params::get_or("step", Self::STANDARD.step)?,
+        };
+
+        anyhow::ensure!(rules.step != 0, "trails must change height with each step");
+
+        Ok(rules)
     }
 
-    fn is_start(self) -> bool {
-        self.0 == 0
+    fn is_valid_successor(&self, origin: Height, height: Height) -> bool {
+        i16::from(height.0) - i16::from(origin.0) == i16::from(self.step)
     }
 
-    fn is_summit(self) -> bool {
-        self.0 == 9
+    fn is_start(&self, height: Height) -> bool {
+        height.0 == self.start
     }
 
-    fn from_char(c: char) -> Option<Self> {
-        c.to_digit(10).map(|n| n as u8).map(Height)
+    fn is_summit(&self, height: Height) -> bool {
+        height.0 == self.end
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
+    #[serde(serialize_with = "crate::library::inspect::grid")]
     grid: VecGrid<Height>,
 }
 
@@ -95,54 +137,77 @@ impl TryFrom<&str> for Input {
     }
 }
 
-/// Given a particular `location` and `height``, explore all of the neighbors
-/// of that location. For each neighbor, if it is a valid successor, call
-/// `add_summit`; otherwise, recursively call explore on that location.
-fn explore<T>(
-    grid: &impl Grid<Item = Height>,
+/// All of the neighbors of `location` that a trail could step to next
+fn successors<'a>(
+    grid: &'a impl Grid<Item = Height>,
+    rules: &'a TrailRules,
     location: Location,
     height: Height,
-    state: T,
-    add_summit: &impl Fn(T, Location) -> T,
-) -> T {
+) -> impl Iterator<Item = (Location, Height)> + 'a {
     EACH_DIRECTION
         .iter()
-        .map(|&step| location + step)
+        .map(move |&step| location + step)
         .filter_map(|new_location| {
             grid.get(new_location)
                 .ok()
                 .map(|&new_height| (new_location, new_height))
         })
-        .filter(|&(_, new_height)| new_height.is_valid_successor_from(height))
-        .fold(state, |state, (location, height)| {
-            if height.is_summit() {
-                add_summit(state, location)
+        .filter(move |&(_, new_height)| rules.is_valid_successor(height, new_height))
+}
+
+/// Given a particular `location` and `height`, explore every trail leading
+/// from that location, according to `rules`. Each time a trail reaches a
+/// summit, call `add_summit` with the location of that summit. Summits are
+/// reported once per distinct trail, so a summit reachable by several trails
+/// is reported several times.
+///
+/// This uses an explicit stack rather than recursion, so arbitrarily long
+/// trails won't overflow the call stack.
+fn explore<T>(
+    grid: &impl Grid<Item = Height>,
+    rules: &TrailRules,
+    location: Location,
+    height: Height,
+    state: T,
+    add_summit: &impl Fn(T, Location) -> T,
+) -> T {
+    let mut stack = vec![(location, height)];
+    let mut state = state;
+
+    while let Some((location, height)) = stack.pop() {
+        for (location, height) in successors(grid, rules, location, height) {
+            if rules.is_summit(height) {
+                state = add_summit(state, location);
             } else {
-                explore(grid, location, height, state, add_summit)
+                stack.push((location, height));
             }
-        })
+        }
+    }
+
+    state
 }
 
-/// Solve the puzzle by iterating each start point, using `explore` to explore
-/// those start points, then adding together the outputs from `count_trails`.
+/// Solve the puzzle by iterating each start point (as defined by `rules`),
+/// using `explore` to explore those start points, then adding together the
+/// outputs from `count_trails`.
 /// For each start point, we use `init_trail` to create some state, pass
 /// `add_summit` to explore to explore with that state, then use `count_trails`
 /// to summarize the exploration results.
 fn solve<T>(
     input: &Input,
+    rules: &TrailRules,
     init_trail: impl Fn() -> T,
     add_summit: impl Fn(T, Location) -> T,
     count_trails: impl Fn(T) -> usize,
 ) -> Definitely<usize> {
     Ok(input
         .grid
-        .rows()
-        .iter()
-        .flat_map(|row| row.iter_with_locations())
-        .filter(|&(_, &height)| height.is_start())
+        .iter_located()
+        .filter(|&(_, &height)| rules.is_start(height))
         .map(move |(location, &height)| {
             count_trails(explore(
                 &input.grid,
+                rules,
                 location,
                 height,
                 init_trail(),
@@ -152,20 +217,105 @@ fn solve<T>(
         .sum())
 }
 
-pub fn part1(input: Input) -> Definitely<usize> {
-    solve(
+#[derive(Debug, Error)]
+#[error("trail rules allow a trail to loop back on itself at {0:?}")]
+pub struct CircularTrail(Location);
+
+/// Dynamic task computing the number of distinct trails from a location to
+/// any summit. The rating of a location is the sum of the ratings of its
+/// successors, so each location's rating is computed only once, no matter how
+/// many trailheads share it.
+struct TrailRating<'a, G> {
+    grid: &'a G,
+    rules: &'a TrailRules,
+}
+
+impl<G: Grid<Item = Height>> dynamic::StatelessTask<Location, usize, Infallible>
+    for TrailRating<'_, G>
+{
+    fn solve<'sub>(
+        &self,
+        &location: &Location,
+        subtasker: &'sub impl dynamic::Subtask<Location, usize>,
+    ) -> Result<usize, dynamic::TaskInterrupt<'sub, Location, Infallible>> {
+        let &height = self
+            .grid
+            .get(location)
+            .expect("only locations in the grid are ever requested");
+
+        if self.rules.is_summit(height) {
+            return Ok(1);
+        }
+
+        let successors = || successors(self.grid, self.rules, location, height);
+
+        subtasker.precheck(successors().map(|(location, _)| location))?;
+
+        successors().try_fold(0, |total, (location, _)| {
+            Ok(total + *subtasker.solve(location)?)
+        })
+    }
+}
+
+/// Compute the sum of trailhead ratings, memoizing the rating of each
+/// location. On dense grids, where many trails share sub-trails, this is
+/// much faster than exploring each trail separately with `explore`.
+fn total_rating_memoized(input: &Input, rules: &TrailRules) -> Result<usize, CircularTrail> {
+    let task = TrailRating {
+        grid: &input.grid,
+        rules,
+    };
+
+    let mut store = HashMap::new();
+
+    input
+        .grid
+        .iter_located()
+        .filter(|&(_, &height)| rules.is_start(height))
+        .map(|(location, _)| {
+            dynamic::execute(location, &task, &mut store).map_err(|err| match err {
+                dynamic::DynamicError::CircularDependency(location) => CircularTrail(location),
+                dynamic::DynamicError::Error(err) => match err {},
+            })
+        })
+        .sum()
+}
+
+pub fn part1(input: Input) -> anyhow::Result<usize> {
+    let rules = TrailRules::from_runner()?;
+
+    Ok(solve(
         &input,
+        &rules,
         HashSet::new,
         |reachable_summits, location| express!(reachable_summits.insert(location)),
         |reachable_summits| reachable_summits.len(),
-    )
+    )?)
 }
 
-pub fn part2(input: Input) -> Definitely<usize> {
+/// Compute the sum of trailhead ratings by exploring every trail separately.
+/// This is the reference implementation for `total_rating_memoized`, selected
+/// with `--param rating=dfs`.
+fn total_rating_dfs(input: &Input, rules: &TrailRules) -> Definitely<usize> {
     solve(
-        &input,
+        input,
+        rules,
         || 0,
         |trail_count, _| trail_count + 1,
         |trail_count| trail_count,
     )
 }
+
+pub fn part2(input: Input) -> anyhow::Result<usize> {
+    let rules = TrailRules::from_runner()?;
+
+    validate::select(
+        "rating",
+        &[
+            ("memoized", &|| Ok(total_rating_memoized(&input, &rules)?)),
+            ("dfs", &|| Ok(total_rating_dfs(&input, &rules)?)),
+        ],
+    )
+}
+
+solver!(Input, usize, usize);
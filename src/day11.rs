@@ -1,4 +1,9 @@
-use std::{collections::HashMap, convert::Infallible};
+//! Day 11: Plutonian Pebbles
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+};
 
 use nom::{
     character::complete::{digit1, multispace0, space1},
@@ -9,10 +14,13 @@ use nom_supreme::{
     error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated, ParserExt,
 };
 
-use crate::library::{dynamic, ITResult};
-use crate::{day7::count_digits, library::Definitely};
+use crate::day7::count_digits;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::library::{answer::Answer, dynamic, params, solver::solver, validate, ITResult};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
     values: Vec<i64>,
 }
@@ -36,9 +44,9 @@ impl TryFrom<&str> for Input {
 }
 
 #[derive(Debug, Clone, Copy)]
-enum MaybePair {
-    One(i64),
-    Pair([i64; 2]),
+enum MaybePair<T = i64> {
+    One(T),
+    Pair([T; 2]),
 }
 
 fn split(value: i64) -> MaybePair {
@@ -67,69 +75,298 @@ struct Goal {
     depth: i32,
 }
 
-struct DynamicSolution;
+#[derive(Debug, Clone, Copy, Error)]
+#[error(
+    "stone count overflowed; try a wider count type with --param count=u128 \
+    (or count=big, with the bigint feature)"
+)]
+pub struct Overflow;
+
+/// A number of stones. Counts grow exponentially with depth, so this is
+/// generic, allowing deep blinks to use wider (or unbounded) integers.
+/// Overflow is always detected rather than wrapping.
+trait StoneCount: Clone + Debug + PartialEq + Display + Into<Answer> {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn checked_add(&self, other: &Self) -> Result<Self, Overflow>;
+}
+
+macro_rules! primitive_stone_count {
+    ($($type:ty)*) => {$(
+        impl StoneCount for $type {
+            fn zero() -> Self {
+                0
+            }
 
-impl dynamic::Task<Goal, usize, Infallible> for DynamicSolution {
-    type State = MaybePair;
+            fn one() -> Self {
+                1
+            }
 
-    fn solve<'sub>(
-        &self,
-        &Goal { value, depth }: &Goal,
-        subtasker: &'sub impl dynamic::Subtask<Goal, usize>,
-        state: &mut Option<Self::State>,
-    ) -> Result<usize, dynamic::TaskInterrupt<'sub, Goal, Infallible>> {
-        let &mut pair = match state {
-            Some(state) => state,
-            None if depth == 0 => return Ok(1),
-            None => state.insert(split(value)),
-        };
+            fn checked_add(&self, other: &Self) -> Result<Self, Overflow> {
+                <$type>::checked_add(*self, *other).ok_or(Overflow)
+            }
+        }
+    )*};
+}
 
-        // TODO: finish implementing tail calls in `dynamic.rs`
-        Ok(match pair {
-            MaybePair::One(value) => *subtasker.solve(Goal {
+primitive_stone_count! {u64 u128}
+
+#[cfg(feature = "bigint")]
+impl StoneCount for num_bigint::BigUint {
+    fn zero() -> Self {
+        Self::ZERO
+    }
+
+    fn one() -> Self {
+        Self::from(1u8)
+    }
+
+    fn checked_add(&self, other: &Self) -> Result<Self, Overflow> {
+        Ok(self + other)
+    }
+}
+
+struct DynamicSolution {
+    /// If true, a stone with a single child tail-calls into it; otherwise,
+    /// it suspends on the child like it would on a pair.
+    tail_calls: bool,
+}
+
+fn solve_goal<'sub, C: StoneCount + 'sub>(
+    &Goal { value, depth }: &Goal,
+    subtasker: &'sub impl dynamic::Subtask<Goal, C>,
+    state: &mut Option<MaybePair>,
+    tail_calls: bool,
+) -> Result<C, dynamic::TaskInterrupt<'sub, Goal, Overflow>> {
+    let &mut pair = match state {
+        Some(state) => state,
+        None if depth == 0 => return Ok(C::one()),
+        None => state.insert(split(value)),
+    };
+
+    match pair {
+        // A single child has exactly the same count as this stone, so
+        // rather than suspending on it, solve it in our place
+        MaybePair::One(value) if tail_calls => Err(dynamic::TaskInterrupt::Tail(Goal {
+            value,
+            depth: depth - 1,
+        })),
+        MaybePair::One(value) => Ok(subtasker
+            .solve(Goal {
                 value,
                 depth: depth - 1,
-            })?,
-            MaybePair::Pair([first, second]) => {
-                let first = *subtasker.solve(Goal {
-                    value: first,
-                    depth: depth - 1,
-                })?;
-
-                let second = *subtasker.solve(Goal {
-                    value: second,
-                    depth: depth - 1,
-                })?;
-
-                first + second
-            }
-        })
+            })?
+            .clone()),
+        MaybePair::Pair([first, second]) => {
+            let first = subtasker.solve(Goal {
+                value: first,
+                depth: depth - 1,
+            })?;
+
+            let second = subtasker.solve(Goal {
+                value: second,
+                depth: depth - 1,
+            })?;
+
+            first
+                .checked_add(second)
+                .map_err(dynamic::TaskInterrupt::Error)
+        }
     }
 }
 
-fn solve(values: &[i64], depth: i32) -> usize {
+// These can't be a single generic impl, because it would overlap with the
+// blanket `Task` impl for `StatelessTask`.
+macro_rules! dynamic_solution {
+    ($($type:ty)*) => {$(
+        impl dynamic::Task<Goal, $type, Overflow> for DynamicSolution {
+            type State = MaybePair;
+
+            fn solve<'sub>(
+                &self,
+                goal: &Goal,
+                subtasker: &'sub impl dynamic::Subtask<Goal, $type>,
+                state: &mut Option<Self::State>,
+            ) -> Result<$type, dynamic::TaskInterrupt<'sub, Goal, Overflow>> {
+                solve_goal(goal, subtasker, state, self.tail_calls)
+            }
+        }
+    )*};
+}
+
+dynamic_solution! {u64 u128}
+
+#[cfg(feature = "bigint")]
+dynamic_solution! {num_bigint::BigUint}
+
+fn solve<C>(values: &[i64], depth: i32, task: &DynamicSolution) -> Result<C, Overflow>
+where
+    C: StoneCount,
+    DynamicSolution: dynamic::Task<Goal, C, Overflow>,
+{
     let mut store = HashMap::new();
 
-    values
+    values.iter().try_fold(C::zero(), |total, &value| {
+        match dynamic::execute(Goal { value, depth }, task, &mut store) {
+            Ok(count) => total.checked_add(&count),
+            Err(dynamic::DynamicError::Error(err)) => Err(err),
+            Err(dynamic::DynamicError::CircularDependency(_)) => panic!(
+                "circular dependency shouldn't be possible, \
+                because each goal's subgoals are depth - 1"
+            ),
+        }
+    })
+}
+
+/// The graph of transitions between distinct stone values. Every value
+/// reachable from the initial stones gets a dense index; each node lists the
+/// indices of the stones it becomes after a single blink.
+#[derive(Debug, Default)]
+struct TransitionGraph {
+    indexes: HashMap<i64, usize>,
+    children: Vec<MaybePair<usize>>,
+}
+
+impl TransitionGraph {
+    /// Build the graph of every value reachable from `values`
+    fn build(values: &[i64]) -> Self {
+        let mut graph = Self::default();
+        let mut pending: Vec<i64> = values.to_vec();
+        let mut discovered = Vec::new();
+
+        // First, assign an index to every reachable value
+        while let Some(value) = pending.pop() {
+            if graph.indexes.contains_key(&value) {
+                continue;
+            }
+
+            graph.indexes.insert(value, discovered.len());
+            discovered.push(value);
+
+            match split(value) {
+                MaybePair::One(child) => pending.push(child),
+                MaybePair::Pair(pair) => pending.extend(pair),
+            }
+        }
+
+        // Then, record the transitions between indexes
+        graph.children = discovered
+            .iter()
+            .map(|&value| match split(value) {
+                MaybePair::One(child) => MaybePair::One(graph.indexes[&child]),
+                MaybePair::Pair([left, right]) => {
+                    MaybePair::Pair([graph.indexes[&left], graph.indexes[&right]])
+                }
+            })
+            .collect();
+
+        graph
+    }
+}
+
+/// Solve by advancing a vector of per-value stone counts one blink at a time.
+/// Unlike `solve`, memory use depends only on the number of distinct values
+/// (which is small, usually a few thousand), rather than on the depth, so this
+/// can handle depths in the thousands.
+fn solve_graph<C: StoneCount>(values: &[i64], depth: i32) -> Result<C, Overflow> {
+    let graph = TransitionGraph::build(values);
+
+    let mut counts = vec![C::zero(); graph.children.len()];
+    let mut next_counts = vec![C::zero(); graph.children.len()];
+
+    for value in values {
+        let index = graph.indexes[value];
+        counts[index] = counts[index].checked_add(&C::one())?;
+    }
+
+    for _ in 0..depth {
+        next_counts.fill(C::zero());
+
+        for (count, &children) in counts.iter().zip(&graph.children) {
+            match children {
+                MaybePair::One(child) => {
+                    next_counts[child] = next_counts[child].checked_add(count)?;
+                }
+                MaybePair::Pair([left, right]) => {
+                    next_counts[left] = next_counts[left].checked_add(count)?;
+                    next_counts[right] = next_counts[right].checked_add(count)?;
+                }
+            }
+        }
+
+        std::mem::swap(&mut counts, &mut next_counts);
+    }
+
+    counts
         .iter()
-        .map(
-            |&value| match dynamic::execute(Goal { value, depth }, &DynamicSolution, &mut store) {
-                Ok(count) => count,
-                Err(err) => match err {
-                    dynamic::DynamicError::CircularDependency(_) => panic!(
-                        "circular dependency shouldn't be possible, \
-                        because each goal's subgoals are depth - 1"
-                    ),
-                },
-            },
+        .try_fold(C::zero(), |total, count| total.checked_add(count))
+}
+
+/// Get the number of blinks, which can be overridden with `--param depth=N`
+fn get_depth(default: i32) -> anyhow::Result<i32> {
+    let depth = params::get_or("depth", default)?;
+
+    if depth < 0 {
+        anyhow::bail!("blink depth must not be negative; got {depth}")
+    }
+
+    Ok(depth)
+}
+
+/// Solve with the solver selected by `--param solver=memo|graph`. `memo`
+/// (the default) memoizes each (value, depth) pair; `graph` advances counts
+/// over the value transition graph, and is better for very large depths.
+/// With `memo`, `--param single_child=subtask` solves stones with a single
+/// child as ordinary subtasks, rather than with tail calls, for comparison.
+fn solve_with_solver<C>(values: &[i64], depth: i32) -> anyhow::Result<Answer>
+where
+    C: StoneCount,
+    DynamicSolution: dynamic::Task<Goal, C, Overflow>,
+{
+    let memo = |tail_calls| -> anyhow::Result<C> {
+        Ok(solve(values, depth, &DynamicSolution { tail_calls })?)
+    };
+
+    let memoized = || {
+        validate::select(
+            "single_child",
+            &[("tail", &|| memo(true)), ("subtask", &|| memo(false))],
         )
-        .sum()
+    };
+
+    let count: C = validate::select(
+        "solver",
+        &[
+            ("memo", &memoized),
+            ("graph", &|| Ok(solve_graph(values, depth)?)),
+        ],
+    )?;
+
+    Ok(count.into())
 }
 
-pub fn part1(input: Input) -> Definitely<usize> {
-    Ok(solve(&input.values, 25))
+/// Solve with the count type selected by `--param count=u64|u128|big`.
+/// `big` requires the `bigint` feature.
+fn solve_with_selected(values: &[i64], default_depth: i32) -> anyhow::Result<Answer> {
+    let depth = get_depth(default_depth)?;
+
+    match params::get_raw("count").unwrap_or("u64") {
+        "u64" => solve_with_solver::<u64>(values, depth),
+        "u128" => solve_with_solver::<u128>(values, depth),
+        #[cfg(feature = "bigint")]
+        "big" => solve_with_solver::<num_bigint::BigUint>(values, depth),
+        #[cfg(not(feature = "bigint"))]
+        "big" => anyhow::bail!("count=big requires building with the bigint feature"),
+        count => anyhow::bail!("unknown count type {count:?}; expected u64, u128, or big"),
+    }
+}
+
+pub fn part1(input: Input) -> anyhow::Result<Answer> {
+    solve_with_selected(&input.values, 25)
 }
 
-pub fn part2(input: Input) -> Definitely<usize> {
-    Ok(solve(&input.values, 75))
+pub fn part2(input: Input) -> anyhow::Result<Answer> {
+    solve_with_selected(&input.values, 75)
 }
+
+solver!(Input, Answer, Answer);
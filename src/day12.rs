@@ -1,20 +1,35 @@
+//! Day 12: Garden Groups
+//!
+//! Garden regions. Besides the puzzle solutions, `regions` computes the full
+//! details of every region, for validation and visualization, and
+//! `render_regions` draws them. Pass `--param render=plain|color` (and
+//! optionally `--param fences=true`) to print the rendering to stderr.
+
 use std::{
     collections::{HashMap, HashSet},
     convert::Infallible,
-    fmt::Debug,
+    fmt::{Debug, Write},
     ops::Add,
 };
 
 use gridly::prelude::*;
+use serde::Serialize;
 
-use crate::library::direction_map::DirectionMap;
+use crate::library::{direction_map::DirectionMap, solver::solver, union_find::UnionFind};
 use crate::{
     direction_map,
-    library::{Definitely, IterExt},
+    library::{grid::iter_located, params, validate},
 };
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-struct PlotID(u8);
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlotID(u8);
+
+impl PlotID {
+    /// The letter identifying this kind of plant
+    pub fn letter(self) -> char {
+        self.0 as char
+    }
+}
 
 impl Debug for PlotID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -23,29 +38,30 @@ impl Debug for PlotID {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
+    #[serde(skip)]
     map: HashMap<Location, PlotID>,
+
+    /// The raw rows of the map, for the row-scanning algorithm
+    #[serde(serialize_with = "crate::library::inspect::byte_rows")]
+    rows: Vec<Vec<u8>>,
 }
 
 impl TryFrom<&str> for Input {
     type Error = Infallible;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let map = value
+        let rows: Vec<Vec<u8>> = value
             .lines()
-            .map(|line| line.trim().as_bytes())
-            .with_rows(Row(0))
-            .flat_map(|(row, line)| {
-                line.iter()
-                    .copied()
-                    .map(PlotID)
-                    .with_columns(Column(0))
-                    .map(move |(column, id)| (row.combine(column), id))
-            })
+            .map(|line| line.trim().as_bytes().to_vec())
             .collect();
 
-        Ok(Input { map })
+        let map = iter_located(&rows)
+            .map(|(location, &id)| (location, PlotID(id)))
+            .collect();
+
+        Ok(Input { map, rows })
     }
 }
 
@@ -92,36 +108,39 @@ impl Add<Self> for Region {
     }
 }
 
+/// Explore the region containing `location` (which should already be in
+/// `explored_territory`), adding every plot in it to `explored_territory`.
 fn explore(
     territory: &HashMap<Location, PlotID>,
     location: Location,
     plot: PlotID,
     explored_territory: &mut HashSet<Location>,
 ) -> Region {
-    EACH_DIRECTION
-        .iter()
-        .map(|&direction| location + direction)
-        .fold(
-            Region {
-                area: 1,
-                perimeter: 0,
-            },
-            |region, neighbor| {
-                if is_different_region(territory, plot, &neighbor) {
-                    region.add_border()
-                } else if explored_territory.replace(neighbor).is_none() {
-                    region + explore(territory, neighbor, plot, explored_territory)
-                } else {
-                    region
-                }
-            },
-        )
+    let mut stack = vec![location];
+    let mut region = Region {
+        area: 0,
+        perimeter: 0,
+    };
+
+    while let Some(location) = stack.pop() {
+        region.area += 1;
+
+        for neighbor in EACH_DIRECTION.iter().map(|&direction| location + direction) {
+            if is_different_region(territory, plot, &neighbor) {
+                region = region.add_border()
+            } else if explored_territory.replace(neighbor).is_none() {
+                stack.push(neighbor)
+            }
+        }
+    }
+
+    region
 }
 
-pub fn part1(input: Input) -> Definitely<i64> {
+fn part1_flood(input: &Input) -> i64 {
     let mut explored_territory = HashSet::with_capacity(input.map.len());
 
-    Ok(input
+    input
         .map
         .iter()
         .filter_map(
@@ -131,7 +150,26 @@ pub fn part1(input: Input) -> Definitely<i64> {
             },
         )
         .map(|region| region.price())
-        .sum())
+        .sum()
+}
+
+/// Solve part 1 with the algorithm selected by
+/// `--param algorithm=flood|scan|regions`
+pub fn part1(input: Input) -> anyhow::Result<i64> {
+    print_requested_render(&input)?;
+
+    validate::select(
+        "algorithm",
+        &[
+            ("flood", &|| Ok(part1_flood(&input))),
+            ("scan", &|| {
+                Ok(scan_regions(input.rows.iter().map(|row| row.as_slice())).price)
+            }),
+            ("regions", &|| {
+                Ok(regions(&input).iter().map(RegionInfo::price).sum())
+            }),
+        ],
+    )
 }
 
 fn similar(dir1: Direction, dir2: Direction) -> bool {
@@ -151,12 +189,16 @@ fn count_matching_fences(
         .count() as i64
 }
 
-fn explore2(
+/// Compute the fences for a single plot, and count its sides. Any fence that
+/// continues a fence on a plot already in `counted_fences` is part of an
+/// already-counted side, so it isn't counted again. Returns the side count
+/// along with the plot's neighbors in the same region.
+fn count_plot_sides(
     territory: &HashMap<Location, PlotID>,
     location: Location,
     plot: PlotID,
     counted_fences: &mut HashMap<Location, DirectionMap<bool>>,
-) -> Region {
+) -> (i64, DirectionMap<Option<Location>>) {
     let this_region = direction_map! {
         direction => {
             let neighbor = location + direction;
@@ -175,9 +217,9 @@ fn explore2(
 
     let perimeter = borders.iter().filter(|&(_, &border)| border).count() as i64;
 
-    // Subtract any fences already counted during earlier iterations. Be sure
-    // to do this loop before the true recursion; otherwise fences get double
-    // counted
+    // Subtract any fences already counted for plots processed earlier. The
+    // order in which plots are processed doesn't matter, so long as each one
+    // is processed exactly once.
     let perimeter = this_region
         .iter()
         .filter_map(|(direction, neighbor)| {
@@ -189,26 +231,48 @@ fn explore2(
             perimeter - count_matching_fences(borders, neighbor_fences, direction)
         });
 
-    // Now we just need to recurse
-    this_region
-        .iter()
-        // Filter neighbor locations that are part of this region.
-        .filter_map(|(_, &neighbor)| neighbor)
-        .fold(
-            Region { area: 1, perimeter },
-            |region, neighbor| match counted_fences.get(&neighbor) {
-                Some(_) => region,
-                // The neighbor is part of this region and hasn't been visited
-                // yet. Explore it.
-                None => region + explore2(territory, neighbor, plot, counted_fences),
-            },
-        )
+    (perimeter, this_region)
+}
+
+/// Explore the region containing `location`, counting its area and number of
+/// sides. Every plot in the region is added to `counted_fences`.
+fn explore2(
+    territory: &HashMap<Location, PlotID>,
+    location: Location,
+    plot: PlotID,
+    counted_fences: &mut HashMap<Location, DirectionMap<bool>>,
+) -> Region {
+    let mut stack = vec![location];
+    let mut region = Region {
+        area: 0,
+        perimeter: 0,
+    };
+
+    while let Some(location) = stack.pop() {
+        // A plot can be pushed more than once before it's processed
+        if counted_fences.contains_key(&location) {
+            continue;
+        }
+
+        let (perimeter, this_region) = count_plot_sides(territory, location, plot, counted_fences);
+
+        region = region + Region { area: 1, perimeter };
+
+        stack.extend(
+            this_region
+                .iter()
+                .filter_map(|(_, &neighbor)| neighbor)
+                .filter(|neighbor| !counted_fences.contains_key(neighbor)),
+        );
+    }
+
+    region
 }
 
-pub fn part2(input: Input) -> Definitely<i64> {
+fn part2_fences(input: &Input) -> i64 {
     let mut explored_territory = HashSet::with_capacity(input.map.len());
 
-    Ok(input
+    input
         .map
         .iter()
         .filter_map(|(&location, &id)| {
@@ -222,5 +286,583 @@ pub fn part2(input: Input) -> Definitely<i64> {
             }
         })
         .map(|region| region.price())
-        .sum())
+        .sum()
+}
+
+/// Count the corners of a single plot. A polygon has exactly as many corners
+/// as sides, so summing this over a region counts the region's sides. For
+/// each pair of adjacent directions, the plot has a convex corner if neither
+/// neighbor is in the region, and a concave corner if both are but the
+/// diagonal between them isn't.
+fn count_corners(territory: &HashMap<Location, PlotID>, location: Location, plot: PlotID) -> i64 {
+    EACH_DIRECTION
+        .iter()
+        .map(|&direction| (direction, direction.clockwise()))
+        .filter(|&(first, second)| {
+            let first_fenced = is_different_region(territory, plot, &(location + first));
+            let second_fenced = is_different_region(territory, plot, &(location + second));
+
+            match (first_fenced, second_fenced) {
+                (true, true) => true,
+                (false, false) => {
+                    is_different_region(territory, plot, &(location + first + second))
+                }
+                _ => false,
+            }
+        })
+        .count() as i64
+}
+
+/// Explore the region containing `location` (which should already be in
+/// `explored_territory`), counting its area and number of sides. This is an
+/// independent alternative to `explore2`, counting corners instead of
+/// matching up fences.
+fn explore_corners(
+    territory: &HashMap<Location, PlotID>,
+    location: Location,
+    plot: PlotID,
+    explored_territory: &mut HashSet<Location>,
+) -> Region {
+    let mut stack = vec![location];
+    let mut region = Region {
+        area: 0,
+        perimeter: 0,
+    };
+
+    while let Some(location) = stack.pop() {
+        region.area += 1;
+        region.perimeter += count_corners(territory, location, plot);
+
+        stack.extend(
+            EACH_DIRECTION
+                .iter()
+                .map(|&direction| location + direction)
+                .filter(|neighbor| !is_different_region(territory, plot, neighbor))
+                .filter(|&neighbor| explored_territory.replace(neighbor).is_none()),
+        );
+    }
+
+    region
+}
+
+fn part2_corners(input: &Input) -> i64 {
+    let mut explored_territory = HashSet::with_capacity(input.map.len());
+
+    input
+        .map
+        .iter()
+        .filter_map(
+            |(&location, &id)| match explored_territory.replace(location) {
+                None => Some(explore_corners(
+                    &input.map,
+                    location,
+                    id,
+                    &mut explored_territory,
+                )),
+                Some(_) => None,
+            },
+        )
+        .map(|region| region.price())
+        .sum()
+}
+
+/// Solve part 2, counting sides with the algorithm selected by
+/// `--param sides=fences|corners|scan|regions`
+pub fn part2(input: Input) -> anyhow::Result<i64> {
+    print_requested_render(&input)?;
+
+    validate::select(
+        "sides",
+        &[
+            ("fences", &|| Ok(part2_fences(&input))),
+            ("corners", &|| Ok(part2_corners(&input))),
+            ("scan", &|| {
+                Ok(scan_regions(input.rows.iter().map(|row| row.as_slice())).bulk_price)
+            }),
+            ("regions", &|| {
+                Ok(regions(&input).iter().map(RegionInfo::bulk_price).sum())
+            }),
+        ],
+    )
+}
+
+solver!(Input, i64, i64);
+
+/// Everything known about a single region
+#[derive(Debug, Clone)]
+pub struct RegionInfo {
+    pub plot: PlotID,
+
+    /// Every location in the region, in row-major order
+    pub members: Vec<Location>,
+    pub area: i64,
+    pub perimeter: i64,
+    pub sides: i64,
+}
+
+impl RegionInfo {
+    pub fn price(&self) -> i64 {
+        self.area * self.perimeter
+    }
+
+    pub fn bulk_price(&self) -> i64 {
+        self.area * self.sides
+    }
+}
+
+/// Compute the details of every region in the map. Regions are sorted by
+/// their first member in row-major order.
+pub fn regions(input: &Input) -> Vec<RegionInfo> {
+    let mut explored_territory = HashSet::with_capacity(input.map.len());
+
+    let mut regions: Vec<RegionInfo> = input
+        .map
+        .iter()
+        .filter_map(|(&location, &plot)| {
+            if explored_territory.contains(&location) {
+                return None;
+            }
+
+            let mut fences = HashMap::new();
+            let Region {
+                area,
+                perimeter: sides,
+            } = explore2(&input.map, location, plot, &mut fences);
+
+            let perimeter = fences
+                .values()
+                .flat_map(|borders| borders.iter())
+                .filter(|&(_, &border)| border)
+                .count() as i64;
+
+            let mut members: Vec<Location> = fences.into_keys().collect();
+            members.sort_by_key(|location| (location.row, location.column));
+            explored_territory.extend(members.iter().copied());
+
+            Some(RegionInfo {
+                plot,
+                members,
+                area,
+                perimeter,
+                sides,
+            })
+        })
+        .collect();
+
+    regions.sort_by_key(|region| {
+        let first = region.members[0];
+        (first.row, first.column)
+    });
+
+    regions
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderOptions {
+    /// Color each region with an ANSI terminal color. Otherwise, each region
+    /// is drawn with its own symbol, so that disconnected regions of the same
+    /// plant are still distinguishable.
+    pub color: bool,
+
+    /// Draw fences between regions
+    pub fences: bool,
+}
+
+/// Symbols used for regions when rendering without color
+const REGION_SYMBOLS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Background colors used for regions when rendering with color; chosen from
+/// the 256-color palette to be reasonably distinct from each other.
+const REGION_COLORS: &[u8] = &[
+    196, 46, 21, 226, 201, 51, 208, 93, 118, 33, 160, 220, 129, 37, 166, 63,
+];
+
+/// Render the map, with each region drawn distinctly. With fences, each plot
+/// is drawn in the middle of a 2x2 cell, with fences and fence posts drawn
+/// around it where it borders another region.
+pub fn render_regions(input: &Input, options: &RenderOptions) -> String {
+    let regions = regions(input);
+
+    let region_index: HashMap<Location, usize> = regions
+        .iter()
+        .enumerate()
+        .flat_map(|(index, region)| {
+            region
+                .members
+                .iter()
+                .map(move |&location| (location, index))
+        })
+        .collect();
+
+    let (Some(max_row), Some(max_column)) = (
+        input.map.keys().map(|location| location.row).max(),
+        input.map.keys().map(|location| location.column).max(),
+    ) else {
+        return String::new();
+    };
+
+    let draw_plot = |out: &mut String, location: Location| {
+        let Some(&index) = region_index.get(&location) else {
+            out.push(' ');
+            return;
+        };
+
+        let letter = regions[index].plot.letter();
+
+        if options.color {
+            let color = REGION_COLORS[index % REGION_COLORS.len()];
+            write!(out, "\x1b[48;5;{color}m\x1b[30m{letter}\x1b[0m").unwrap();
+        } else {
+            out.push(REGION_SYMBOLS[index % REGION_SYMBOLS.len()] as char);
+        }
+    };
+
+    let fenced = |a: Location, b: Location| region_index.get(&a) != region_index.get(&b);
+
+    let mut out = String::new();
+
+    for row in (0..=max_row.0).map(Row) {
+        if options.fences {
+            // The line above this row, with horizontal fences
+            for column in (0..=max_column.0).map(Column) {
+                let location = row + column;
+                out.push('+');
+                out.push(match fenced(location, location + Up) {
+                    true => '-',
+                    false => ' ',
+                });
+            }
+            out.push_str("+\n");
+        }
+
+        for column in (0..=max_column.0).map(Column) {
+            let location = row + column;
+
+            if options.fences {
+                out.push(match fenced(location, location + Left) {
+                    true => '|',
+                    false => ' ',
+                });
+            }
+
+            draw_plot(&mut out, location);
+        }
+
+        if options.fences {
+            out.push('|');
+        }
+        out.push('\n');
+    }
+
+    if options.fences {
+        for _ in 0..=max_column.0 {
+            out.push_str("+-");
+        }
+        out.push_str("+\n");
+    }
+
+    out
+}
+
+/// If `--param render=plain|color` was given, render the regions to stderr
+fn print_requested_render(input: &Input) -> anyhow::Result<()> {
+    let color = match params::get_raw("render") {
+        None => return Ok(()),
+        Some("plain") => false,
+        Some("color") => true,
+        Some(render) => anyhow::bail!("unknown render mode {render:?}; expected plain or color"),
+    };
+
+    let fences = params::get_or("fences", false)?;
+
+    eprint!(
+        "{}",
+        render_regions(input, &RenderOptions { color, fences })
+    );
+    Ok(())
+}
+
+/// Statistics for a region, accumulated by `scan_regions`
+#[derive(Debug, Clone, Copy, Default)]
+struct ScanStats {
+    area: i64,
+    perimeter: i64,
+    sides: i64,
+}
+
+impl Add<Self> for ScanStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        ScanStats {
+            area: self.area + rhs.area,
+            perimeter: self.perimeter + rhs.perimeter,
+            sides: self.sides + rhs.sides,
+        }
+    }
+}
+
+/// The total prices of every region in a map
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Prices {
+    /// The sum of area * perimeter (part 1)
+    pub price: i64,
+
+    /// The sum of area * sides (part 2)
+    pub bulk_price: i64,
+}
+
+/// Does the plot `this`, at one corner of a 2x2 block of plots, have a corner
+/// at the center of the block? `horizontal` and `vertical` are its neighbors
+/// in the block, and `diagonal` is the plot opposite it.
+fn is_corner(this: u8, horizontal: Option<u8>, vertical: Option<u8>, diagonal: Option<u8>) -> bool {
+    let this = Some(this);
+
+    match (horizontal == this, vertical == this) {
+        (false, false) => true,
+        (true, true) => diagonal != this,
+        _ => false,
+    }
+}
+
+/// Compute the total prices of every region in a map, given as a sequence of
+/// rows, in a single pass. Only two rows are examined at a time, and only
+/// O(width) working memory is used, so this is suitable for very large maps.
+///
+/// Each row is split into runs of identical plots, which are joined with the
+/// runs above them in a union-find. Area and perimeter are counted per plot,
+/// and sides are counted as corners, which are found by examining each 2x2
+/// block of plots spanning a pair of rows. When no run in the current row
+/// belongs to a region, the region is complete, and its price is added.
+pub fn scan_regions<'a>(rows: impl IntoIterator<Item = &'a [u8]>) -> Prices {
+    let mut prices = Prices::default();
+
+    // The previous row, and the label of the region for each of its plots
+    let mut prev_row: &[u8] = &[];
+    let mut prev_labels: Vec<usize> = Vec::new();
+
+    // The stats of each region still open in the previous row, by label
+    let mut open: Vec<ScanStats> = Vec::new();
+
+    // Chain an empty row to the end, to close out the final row
+    for row in rows.into_iter().map(Some).chain([None]) {
+        let row = row.unwrap_or(&[]);
+
+        // Nodes 0..open.len() are the open regions from the previous row;
+        // the rest are runs in this row.
+        let mut sets = UnionFind::new(open.len());
+        let mut stats = open;
+
+        let mut labels = Vec::with_capacity(row.len());
+        for (column, &plot) in row.iter().enumerate() {
+            let label = match column.checked_sub(1).map(|left| (row[left], labels[left])) {
+                Some((left, label)) if left == plot => label,
+                _ => {
+                    stats.push(ScanStats::default());
+                    sets.push()
+                }
+            };
+
+            labels.push(label);
+
+            if prev_row.get(column) == Some(&plot) {
+                sets.union(prev_labels[column], label);
+            }
+        }
+
+        // Area and perimeter within this row
+        for (column, &plot) in row.iter().enumerate() {
+            let cell = &mut stats[labels[column]];
+            cell.area += 1;
+
+            let left = column.checked_sub(1).map(|left| row[left]);
+            let right = row.get(column + 1).copied();
+
+            cell.perimeter += (left != Some(plot)) as i64 + (right != Some(plot)) as i64;
+        }
+
+        // Fences and corners along the boundary between the two rows
+        let width = Ord::max(prev_row.len(), row.len());
+
+        for column in 0..width {
+            let above = prev_row.get(column).copied();
+            let below = row.get(column).copied();
+
+            if above != below {
+                if above.is_some() {
+                    stats[prev_labels[column]].perimeter += 1;
+                }
+                if below.is_some() {
+                    stats[labels[column]].perimeter += 1;
+                }
+            }
+        }
+
+        for vertex in 0..=width {
+            let get = |row: &[u8], column: Option<usize>| column.and_then(|c| row.get(c).copied());
+            let left = vertex.checked_sub(1);
+            let right = Some(vertex);
+
+            let top_left = get(prev_row, left);
+            let top_right = get(prev_row, right);
+            let bottom_left = get(row, left);
+            let bottom_right = get(row, right);
+
+            let block = [
+                (
+                    top_left,
+                    top_right,
+                    bottom_left,
+                    bottom_right,
+                    left,
+                    &prev_labels,
+                ),
+                (
+                    top_right,
+                    top_left,
+                    bottom_right,
+                    bottom_left,
+                    right,
+                    &prev_labels,
+                ),
+                (
+                    bottom_left,
+                    bottom_right,
+                    top_left,
+                    top_right,
+                    left,
+                    &labels,
+                ),
+                (
+                    bottom_right,
+                    bottom_left,
+                    top_right,
+                    top_left,
+                    right,
+                    &labels,
+                ),
+            ];
+
+            for (this, horizontal, vertical, diagonal, column, labels) in block {
+                if let (Some(this), Some(column)) = (this, column)
+                    && is_corner(this, horizontal, vertical, diagonal)
+                {
+                    stats[labels[column]].sides += 1;
+                }
+            }
+        }
+
+        // Gather the stats for each region at its root
+        let mut totals: Vec<Option<ScanStats>> = vec![None; stats.len()];
+        for (node, node_stats) in stats.into_iter().enumerate() {
+            let root = sets.find(node);
+            totals[root] = Some(totals[root].unwrap_or_default() + node_stats);
+        }
+
+        // Relabel the regions still open in this row densely
+        let mut relabel: Vec<Option<usize>> = vec![None; totals.len()];
+        open = Vec::new();
+
+        for label in &mut labels {
+            let root = sets.find(*label);
+            *label = *relabel[root].get_or_insert_with(|| {
+                open.push(totals[root].take().expect("each root is only taken once"));
+                open.len() - 1
+            });
+        }
+
+        // Any remaining roots belong to regions with no plots in this row,
+        // so they're complete
+        for region in totals.into_iter().flatten() {
+            prices.price += region.area * region.perimeter;
+            prices.bulk_price += region.area * region.sides;
+        }
+
+        prev_row = row;
+        prev_labels = labels;
+    }
+
+    prices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = "AAAA\nBBCD\nBBCC\nEEEC\n";
+    const NESTED: &str = "OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO\n";
+    const E_SHAPE: &str = "EEEEE\nEXXXX\nEEEEE\nEXXXX\nEEEEE\n";
+    const DIAGONAL: &str = "AAAAAA\nAAABBA\nAAABBA\nABBAAA\nABBAAA\nAAAAAA\n";
+
+    const LARGE: &str = "\
+RRRRIICCFF
+RRRRIICCCF
+VVRRRCCFFF
+VVRCCCJFFF
+VVVVCJJCFE
+VVIVCCJJEE
+VVIIICJJEE
+MIIIIIJJEE
+MIIISIJEEE
+MMMISSJEEE
+";
+
+    fn regions_of(text: &str) -> Vec<RegionInfo> {
+        regions(&Input::try_from(text).expect("example parses"))
+    }
+
+    /// Each region's `(plant, area, perimeter, sides)`
+    fn summary(regions: &[RegionInfo]) -> Vec<(char, i64, i64, i64)> {
+        regions
+            .iter()
+            .map(|region| {
+                let plant = region.plot.letter();
+                (plant, region.area, region.perimeter, region.sides)
+            })
+            .collect()
+    }
+
+    fn prices(text: &str) -> (i64, i64) {
+        let regions = regions_of(text);
+
+        (
+            regions.iter().map(RegionInfo::price).sum(),
+            regions.iter().map(RegionInfo::bulk_price).sum(),
+        )
+    }
+
+    /// The regions of the puzzle's first example, as it describes them
+    #[test]
+    fn small_example_regions() {
+        assert_eq!(
+            summary(&regions_of(SMALL)),
+            [
+                ('A', 4, 10, 4),
+                ('B', 4, 8, 4),
+                ('C', 4, 10, 8),
+                ('D', 1, 4, 4),
+                ('E', 3, 8, 4),
+            ]
+        );
+    }
+
+    /// The X regions inside the O region count toward both its perimeter and
+    /// its sides
+    #[test]
+    fn nested_example_regions() {
+        let regions = regions_of(NESTED);
+
+        assert_eq!(summary(&regions[..1]), [('O', 21, 36, 20)]);
+        assert_eq!(summary(&regions[1..]), [('X', 1, 4, 4); 4]);
+    }
+
+    /// The total prices the puzzle gives for each of its examples
+    #[test]
+    fn example_prices() {
+        assert_eq!(prices(SMALL), (140, 80));
+        assert_eq!(prices(NESTED), (772, 436));
+        assert_eq!(prices(LARGE), (1930, 1206));
+        assert_eq!(prices(E_SHAPE).1, 236);
+        assert_eq!(prices(DIAGONAL).1, 368);
+    }
 }
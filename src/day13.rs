@@ -1,22 +1,30 @@
-use std::ops::{Add, Mul};
+//! Day 13: Claw Contraption
 
+use std::ops::{Add, Mul, RangeInclusive};
+
+use anyhow::Context;
 use nom::{
     Parser,
-    character::complete::{char, digit1, multispace0, space0},
+    branch::alt,
+    character::complete::{char, digit1, multispace0, satisfy, space0},
     combinator::{eof, success},
     error::ParseError,
+    multi::many1,
 };
 use nom_supreme::{
     ParserExt, error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated,
     tag::complete::tag,
 };
 
+use serde::Serialize;
+use thiserror::Error;
+
 use crate::{
-    library::{Definitely, ITResult},
+    library::{ITResult, modular::extended_gcd, solver::solver},
     parser,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub struct Vector {
     pub x: i64,
     pub y: i64,
@@ -44,11 +52,21 @@ impl Mul<i64> for Vector {
     }
 }
 
-fn coordinate_parser<'i>(id: char, prefix: char) -> impl Parser<&'i str, i64, ErrorTree<&'i str>> {
-    char(id)
-        .terminated(char(prefix))
-        .precedes(digit1)
+fn parse_signed(input: &str) -> ITResult<&str, i64> {
+    digit1
+        .opt_preceded_by(char('-'))
+        .recognize()
         .parse_from_str_cut()
+        .parse(input)
+}
+
+/// Parse a coordinate, like `X+5` or `Y=10`. Negative coordinates can be
+/// given as `X-5` or `Y=-10`.
+fn coordinate_parser<'i>(id: char, prefix: char) -> impl Parser<&'i str, i64, ErrorTree<&'i str>> {
+    char(id).precedes(alt((
+        char(prefix).precedes(parse_signed),
+        char('-').and(digit1).recognize().parse_from_str_cut(),
+    )))
 }
 
 fn xy_pair_parser<'i>(prefix: char) -> impl Parser<&'i str, Vector, ErrorTree<&'i str>> {
@@ -76,37 +94,60 @@ where
     }
 }
 
-fn button_parser<'i>(id: char) -> impl Parser<&'i str, Vector, ErrorTree<&'i str>> {
-    object_parser(tag("Button ").and(char(id)), xy_pair_parser('+'))
+/// The puzzle's token costs: 3 for button A, and 1 for every other button
+fn default_cost(id: char) -> i64 {
+    match id {
+        'A' => 3,
+        _ => 1,
+    }
+}
+
+/// Parse a button, like `Button A: X+94, Y+34`. Buttons can optionally be
+/// given an explicit cost, like `Button C: X+5, Y+3, Cost=2`; otherwise the
+/// puzzle's costs are used.
+fn parse_button(input: &str) -> ITResult<&str, Button> {
+    parser! {
+        tag("Button "),
+        satisfy(|c| c.is_ascii_uppercase()) => id,
+        char(':'),
+        space0,
+        xy_pair_parser('+') => vector,
+        tag(", Cost=")
+            .precedes(digit1)
+            .parse_from_str_cut()
+            .opt() => cost;
+        Button { id, vector, cost: cost.unwrap_or_else(|| default_cost(id)) }
+    }
+    .parse(input)
 }
 
 fn parse_prize(input: &str) -> ITResult<&str, Vector> {
     object_parser(tag("Prize"), xy_pair_parser('=')).parse(input)
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Buttons {
-    a: Vector,
-    b: Vector,
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Button {
+    id: char,
+    vector: Vector,
+    cost: i64,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Serialize)]
 struct Machine {
-    buttons: Buttons,
+    buttons: Vec<Button>,
     prize: Vector,
 }
 
 fn parse_machine(input: &str) -> ITResult<&str, Machine> {
     parser! {
-        button_parser('A').terminated(multispace0) => a,
-        button_parser('B').terminated(multispace0) => b,
+        many1(parse_button.terminated(multispace0)) => buttons,
         parse_prize => prize;
-        Machine { buttons: Buttons {a, b}, prize }
+        Machine { buttons, prize }
     }
     .parse(input)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
     machines: Vec<Machine>,
 }
@@ -125,59 +166,605 @@ impl TryFrom<&str> for Input {
     }
 }
 
-fn solve_with_math(machine: &Machine) -> Option<i64> {
-    // Look, I know the algebra, so I asked wolfram alpha to rearrange the
-    // terms here to speed it up.
+fn div_floor(a: i128, b: i128) -> i128 {
+    let (quotient, remainder) = (a / b, a % b);
+    match remainder != 0 && ((remainder < 0) != (b < 0)) {
+        true => quotient - 1,
+        false => quotient,
+    }
+}
+
+fn div_ceil(a: i128, b: i128) -> i128 {
+    -div_floor(-a, b)
+}
+
+/// A vector with i128 components, wide enough that none of the intermediate
+/// products of i64 coordinates can overflow.
+type Wide = (i128, i128);
 
-    // This will return nonsense and probably panic if the slopes of the two
-    // lines are the same. We'll cross that bridge when we come to it.
+fn wide(vector: Vector) -> Wide {
+    (i128::from(vector.x), i128::from(vector.y))
+}
+
+/// Solve the one-dimensional problem `u1 * n + u2 * m == target` for the
+/// non-negative `n` and `m` minimizing `cost1 * n + cost2 * m`. This is
+/// what's left of a machine when both buttons and the prize all lie on the
+/// same line.
+fn solve_collinear(
+    (u1, cost1): (i128, i128),
+    (u2, cost2): (i128, i128),
+    target: i128,
+) -> Option<(i128, i128)> {
+    if u1 == 0 && u2 == 0 {
+        return (target == 0).then_some((0, 0));
+    }
+
+    let (g, x, y) = extended_gcd(u1, u2);
+
+    if target % g != 0 {
+        return None;
+    }
+
+    // Every solution is (n0 + k*step_n, m0 - k*step_m) for integer k
+    let scale = target / g;
+    let (n0, m0) = (x * scale, y * scale);
+    let (step_n, step_m) = (u2 / g, u1 / g);
 
-    let x = machine.prize.x;
-    let y = machine.prize.y;
+    // Find the range of k for which both n and m are non-negative
+    let mut low = None;
+    let mut high = None;
 
-    let x1 = machine.buttons.a.x;
-    let y1 = machine.buttons.a.y;
+    let mut constrain = |base: i128, step: i128| match step.signum() {
+        // base + k*step >= 0
+        1 => low = Some(Ord::max(low.unwrap_or(i128::MIN), div_ceil(-base, step))),
+        -1 => high = Some(Ord::min(high.unwrap_or(i128::MAX), div_floor(-base, step))),
+        _ => {}
+    };
 
-    let x2 = machine.buttons.b.x;
-    let y2 = machine.buttons.b.y;
+    constrain(n0, step_n);
+    constrain(m0, -step_m);
 
-    let length1 = (x2 * y - x * y2) / (x2 * y1 - x1 * y2);
-    let length2 = (x1 * y - x * y1) / (x1 * y2 - x2 * y1);
+    let cost_slope = cost1 * step_n - cost2 * step_m;
 
-    // Check that we have an integer solution. God only knows what happens if
-    // we overflowed.
-    if machine.buttons.a * length1 + machine.buttons.b * length2 != machine.prize {
+    // Pick the end of the range that minimizes the cost. The cost can't be
+    // negative, so if it's unbounded in the direction we want, the range
+    // must be empty anyway.
+    let k = match cost_slope.signum() {
+        1 => low?,
+        -1 => high?,
+        _ => low.or(high).unwrap_or(0),
+    };
+
+    if let (Some(low), Some(high)) = (low, high)
+        && low > high
+    {
         return None;
     }
 
-    let cost_a = length1 * 3;
-    let cost_b = length2;
+    Some((n0 + k * step_n, m0 - k * step_m))
+}
+
+/// Find the cheapest way to reach `prize` with exactly one button
+fn solve_one(button: &Button, (x, y): Wide) -> Option<i128> {
+    let (x1, y1) = wide(button.vector);
+
+    let presses = match (x1, y1) {
+        (0, 0) => 0,
+        (0, _) => y / y1,
+        (_, _) => x / x1,
+    };
 
-    Some(cost_a + cost_b)
+    (presses >= 0 && x1 * presses == x && y1 * presses == y)
+        .then(|| presses * i128::from(button.cost))
 }
 
-fn solve(input: &Input, adjustment: i64) -> Definitely<i64> {
-    Ok(input
-        .machines
+/// Find the cheapest way to reach `prize` with exactly two buttons. This is
+/// the closed-form fast path; unless the buttons are parallel, there's at
+/// most one solution.
+fn solve_two(a: &Button, b: &Button, (x, y): Wide) -> Option<i128> {
+    let (x1, y1) = wide(a.vector);
+    let (x2, y2) = wide(b.vector);
+    let (cost_a, cost_b) = (i128::from(a.cost), i128::from(b.cost));
+
+    let determinant = x1 * y2 - x2 * y1;
+
+    let (presses_a, presses_b) = if determinant != 0 {
+        // Look, I know the algebra, so I asked wolfram alpha to rearrange the
+        // terms here to speed it up.
+        (
+            (x2 * y - x * y2) / -determinant,
+            (x1 * y - x * y1) / determinant,
+        )
+    } else {
+        // The buttons are parallel (or zero), so there's only a solution if
+        // the prize is on the same line. In that case, all of the vectors are
+        // multiples of some common direction, so we can solve along whichever
+        // axis that direction isn't perpendicular to.
+        let on_line = [(x1, y1), (x2, y2)]
+            .iter()
+            .all(|&(button_x, button_y)| button_x * y - button_y * x == 0);
+
+        if !on_line {
+            return None;
+        }
+
+        match x1 == 0 && x2 == 0 && x == 0 {
+            false => solve_collinear((x1, cost_a), (x2, cost_b), x)?,
+            true => solve_collinear((y1, cost_a), (y2, cost_b), y)?,
+        }
+    };
+
+    // Check that we have a real solution, with whole, non-negative presses.
+    if presses_a < 0
+        || presses_b < 0
+        || x1 * presses_a + x2 * presses_b != x
+        || y1 * presses_a + y2 * presses_b != y
+    {
+        return None;
+    }
+
+    Some(presses_a * cost_a + presses_b * cost_b)
+}
+
+/// The most times `button` could possibly be pressed on the way to `prize`,
+/// given that the `rest` of the buttons will also be pressed some
+/// non-negative number of times. This requires an axis along which `button`
+/// moves and none of the `rest` move the opposite way; otherwise, the button
+/// could be pressed many times and then "undone", so there's no bound this
+/// simple, and this returns `None`.
+fn press_bound(button: &Button, rest: &[Button], (x, y): Wide) -> Option<i128> {
+    let (x1, y1) = wide(button.vector);
+
+    if (x1, y1) == (0, 0) {
+        // Pressing this button does nothing
+        return Some(0);
+    }
+
+    let axis = |step: i128, target: i128, component: fn(&Vector) -> i64| {
+        let same_way = rest
+            .iter()
+            .all(|button| i128::from(component(&button.vector)) * step.signum() >= 0);
+
+        (step != 0 && same_way).then(|| div_floor(target, step))
+    };
+
+    [axis(x1, x, |v| v.x), axis(y1, y, |v| v.y)]
+        .into_iter()
+        .flatten()
+        .min()
+        .map(|bound| Ord::max(bound, -1))
+}
+
+fn cross((x1, y1): Wide, (x2, y2): Wide) -> i128 {
+    x1 * y2 - y1 * x2
+}
+
+/// Every pair of distinct indices less than `count`
+fn pairs(count: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..count).flat_map(move |i| (i + 1..count).map(move |j| (i, j)))
+}
+
+/// A fraction, as `(numerator, denominator)`, with a positive denominator
+type Ratio = (i128, i128);
+
+fn ratio(numerator: i128, denominator: i128) -> Ratio {
+    match denominator < 0 {
+        true => (-numerator, -denominator),
+        false => (numerator, denominator),
+    }
+}
+
+/// Find the cheapest way to reach `prize` if buttons could be pressed
+/// fractional numbers of times, returning the presses of each button, or
+/// `None` if even that can't reach the prize. This is a linear program with
+/// one constraint per axis, so some cheapest solution presses at most two
+/// buttons; this just tries every single button and every pair of buttons.
+fn relaxed_presses(buttons: &[Button], prize: Wide) -> Option<Vec<Ratio>> {
+    let vectors: Vec<Wide> = buttons.iter().map(|button| wide(button.vector)).collect();
+    let nothing = (prize == (0, 0)).then(Vec::new);
+
+    let singles = vectors.iter().enumerate().filter_map(|(i, &(x1, y1))| {
+        let presses = match (x1, y1) {
+            (0, 0) => return None,
+            (0, _) => ratio(prize.1, y1),
+            (_, _) => ratio(prize.0, x1),
+        };
+
+        (cross((x1, y1), prize) == 0 && presses.0 >= 0).then(|| vec![(i, presses)])
+    });
+
+    let doubles = pairs(vectors.len()).filter_map(|(i, j)| {
+        let determinant = cross(vectors[i], vectors[j]);
+
+        if determinant == 0 {
+            return None;
+        }
+
+        let presses_i = ratio(cross(prize, vectors[j]), determinant);
+        let presses_j = ratio(cross(vectors[i], prize), determinant);
+
+        (presses_i.0 >= 0 && presses_j.0 >= 0).then(|| vec![(i, presses_i), (j, presses_j)])
+    });
+
+    // All of the presses in a candidate share a denominator, so the cost
+    // does too.
+    let cost = |candidate: &[(usize, Ratio)]| -> Ratio {
+        let denominator = candidate
+            .first()
+            .map_or(1, |&(_, (_, denominator))| denominator);
+        let numerator = candidate
+            .iter()
+            .map(|&(i, (presses, _))| presses * i128::from(buttons[i].cost))
+            .sum();
+
+        (numerator, denominator)
+    };
+
+    let best = nothing
+        .into_iter()
+        .chain(singles)
+        .chain(doubles)
+        .min_by(|lhs, rhs| {
+            let ((n1, d1), (n2, d2)) = (cost(lhs), cost(rhs));
+            (n1 * d2).cmp(&(n2 * d1))
+        })?;
+
+    let mut presses = vec![(0, 1); buttons.len()];
+
+    for (i, ratio) in best {
+        presses[i] = ratio;
+    }
+
+    Some(presses)
+}
+
+/// The range of presses of each button that needs to be searched, or `None`
+/// if the prize can't be reached at all. By the proximity theorem of Cook,
+/// Gerards, Schrijver and Tardos, some cheapest whole-number solution is
+/// within `n * delta` presses of the relaxed solution (see
+/// `relaxed_presses`) on every button, where `n` is the number of buttons
+/// and `delta` is the largest absolute value of any coordinate, or of the
+/// determinant of any two buttons. `press_bound` narrows this further when
+/// it applies, which keeps nearby prizes cheap to search.
+fn press_ranges(buttons: &[Button], prize: Wide) -> Option<Vec<RangeInclusive<i128>>> {
+    let relaxed = relaxed_presses(buttons, prize)?;
+    let vectors: Vec<Wide> = buttons.iter().map(|button| wide(button.vector)).collect();
+
+    let coordinates = vectors.iter().flat_map(|&(x, y)| [x.abs(), y.abs()]);
+    let determinants = pairs(vectors.len()).map(|(i, j)| cross(vectors[i], vectors[j]).abs());
+    let delta = coordinates.chain(determinants).fold(1, Ord::max);
+    let radius = buttons.len() as i128 * delta;
+
+    let ranges = relaxed
         .iter()
-        .filter_map(|machine| {
-            let machine = Machine {
-                buttons: machine.buttons,
-                prize: machine.prize
-                    + Vector {
-                        x: adjustment,
-                        y: adjustment,
-                    },
+        .enumerate()
+        .map(|(i, &(numerator, denominator))| {
+            let low = div_ceil(numerator - radius * denominator, denominator);
+            let high = div_floor(numerator + radius * denominator, denominator);
+
+            let mut rest = buttons.to_vec();
+            let button = rest.remove(i);
+
+            let high = match press_bound(&button, &rest, prize) {
+                Some(bound) => Ord::min(high, bound),
+                None => high,
             };
-            solve_with_math(&machine)
+
+            Ord::max(low, 0)..=high
+        })
+        .collect();
+
+    Some(ranges)
+}
+
+/// Find the cheapest way to reach `prize` with `extra` pressed some number
+/// of times in `presses`, plus the non-parallel buttons `a` and `b`. For a
+/// given number of presses of `extra`, the presses of `a` and `b` are found
+/// by Cramer's rule, and they're only whole numbers when the press count is
+/// in certain residue classes mod the determinant of `a` and `b`. Within
+/// each class, every press count is a multiple of the period apart, and the
+/// presses of all three buttons (and so the cost) are linear in that
+/// multiple, so the cheapest solution in the class is at one end of the
+/// range where all of the presses are non-negative. This means only one
+/// period of press counts needs to be searched, rather than all of them.
+///
+/// Returns `None` if `a` and `b` are parallel.
+fn solve_periodic(
+    extra: &Button,
+    a: &Button,
+    b: &Button,
+    prize: Wide,
+    presses: RangeInclusive<i128>,
+) -> Option<Option<i128>> {
+    let (va, vb, ve) = (wide(a.vector), wide(b.vector), wide(extra.vector));
+    let determinant = cross(va, vb);
+
+    if determinant == 0 {
+        return None;
+    }
+
+    // presses_a * determinant == cross(prize, vb) - presses * cross(ve, vb),
+    // and likewise for b.
+    let (target_a, slope_a) = (cross(prize, vb), cross(ve, vb));
+    let (target_b, slope_b) = (cross(va, prize), cross(va, ve));
+
+    let step = |slope: i128| determinant.abs() / extended_gcd(determinant, slope).0;
+    let (step_a, step_b) = (step(slope_a), step(slope_b));
+    let period = step_a / extended_gcd(step_a, step_b).0 * step_b;
+
+    let (cost_e, cost_a, cost_b) = (
+        i128::from(extra.cost),
+        i128::from(a.cost),
+        i128::from(b.cost),
+    );
+
+    let (start, end) = (*presses.start(), *presses.end());
+
+    let best = (start..=Ord::min(end, start + period - 1))
+        .filter_map(|first| {
+            let numerator_a = target_a - first * slope_a;
+            let numerator_b = target_b - first * slope_b;
+
+            if numerator_a % determinant != 0 || numerator_b % determinant != 0 {
+                return None;
+            }
+
+            // With presses = first + k * period, each button's presses are
+            // base + k * delta, and k ranges from 0 up to the end of the
+            // range.
+            let buttons = [
+                (first, period),
+                (numerator_a / determinant, -(period * slope_a / determinant)),
+                (numerator_b / determinant, -(period * slope_b / determinant)),
+            ];
+
+            let mut low = 0;
+            let mut high = (end - first) / period;
+
+            for (base, delta) in buttons {
+                match delta.signum() {
+                    1 => low = Ord::max(low, div_ceil(-base, delta)),
+                    -1 => high = Ord::min(high, div_floor(-base, delta)),
+                    _ if base < 0 => return None,
+                    _ => {}
+                }
+            }
+
+            if low > high {
+                return None;
+            }
+
+            let cost_at = |k: i128| {
+                let [e, a, b] = buttons.map(|(base, delta)| base + k * delta);
+                e * cost_e + a * cost_a + b * cost_b
+            };
+
+            Some(Ord::min(cost_at(low), cost_at(high)))
+        })
+        .min();
+
+    Some(best)
+}
+
+/// Find the cheapest way to reach `prize`, searching every number of presses
+/// of all but the last two buttons within their ranges. When the last two
+/// buttons aren't parallel, the button before them only needs one period of
+/// its range to be searched (see `solve_periodic`). The ranges of the last
+/// two buttons are ignored, since they're solved for directly.
+fn search(buttons: &[(Button, RangeInclusive<i128>)], prize: Wide) -> Option<i128> {
+    if let [(extra, presses), (a, _), (b, _)] = buttons
+        && let Some(cost) = solve_periodic(extra, a, b, prize, presses.clone())
+    {
+        return cost;
+    }
+
+    match buttons {
+        [] => (prize == (0, 0)).then_some(0),
+        [(button, _)] => solve_one(button, prize),
+        [(a, _), (b, _)] => solve_two(a, b, prize),
+        [(first, presses), rest @ ..] => {
+            let (x1, y1) = wide(first.vector);
+            let cost = i128::from(first.cost);
+
+            presses
+                .clone()
+                .filter_map(|presses| {
+                    let remaining = (prize.0 - x1 * presses, prize.1 - y1 * presses);
+                    search(rest, remaining).map(|rest_cost| rest_cost + presses * cost)
+                })
+                .min()
+        }
+    }
+}
+
+/// Find the cheapest way to reach `prize` with any number of buttons. Two or
+/// fewer buttons are solved directly; with more, every button's presses are
+/// bounded (see `press_ranges`) and searched. The buttons are reordered so
+/// that the last two are the non-parallel pair with the smallest
+/// determinant, and the widest range comes just before them, so that
+/// `solve_periodic` covers that range in a single (short) period.
+fn min_cost(buttons: &[Button], prize: Wide) -> Option<i128> {
+    match buttons {
+        [] => (prize == (0, 0)).then_some(0),
+        [button] => solve_one(button, prize),
+        [a, b] => solve_two(a, b, prize),
+        _ => {
+            let ranges = press_ranges(buttons, prize)?;
+            let mut buttons: Vec<_> = buttons.iter().copied().zip(ranges).collect();
+
+            let basis = pairs(buttons.len())
+                .map(|(i, j)| {
+                    let determinant = cross(wide(buttons[i].0.vector), wide(buttons[j].0.vector));
+                    (determinant.abs(), i, j)
+                })
+                .filter(|&(determinant, _, _)| determinant != 0)
+                .min();
+
+            let basis = basis.map(|(_, i, j)| {
+                let b = buttons.remove(j);
+                let a = buttons.remove(i);
+                [a, b]
+            });
+
+            buttons.sort_by_key(|(_, presses)| presses.end() - presses.start());
+            buttons.extend(basis.into_iter().flatten());
+
+            search(&buttons, prize)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Error)]
+pub enum MachineError {
+    #[error("adjusting the prize location by {adjustment} overflowed")]
+    Prize { adjustment: i64 },
+
+    #[error("the cost of winning the prize doesn't fit in an i64")]
+    Cost,
+
+    #[error("the total cost of all prizes doesn't fit in an i64")]
+    Total,
+}
+
+/// Compute the cost of winning the prize, or None if it can't be won
+fn solve_with_math(machine: &Machine) -> Result<Option<i64>, MachineError> {
+    min_cost(&machine.buttons, wide(machine.prize))
+        .map(|cost| cost.try_into().map_err(|_| MachineError::Cost))
+        .transpose()
+}
+
+/// Compute the cost of winning the prize after adjusting its location, or
+/// None if it can't be won
+fn solve_adjusted(machine: &Machine, adjustment: i64) -> Result<Option<i64>, MachineError> {
+    let adjust = |value: i64| {
+        value
+            .checked_add(adjustment)
+            .ok_or(MachineError::Prize { adjustment })
+    };
+
+    solve_with_math(&Machine {
+        buttons: machine.buttons.clone(),
+        prize: Vector {
+            x: adjust(machine.prize.x)?,
+            y: adjust(machine.prize.y)?,
+        },
+    })
+}
+
+fn solve(input: &Input, adjustment: i64) -> anyhow::Result<i64> {
+    input
+        .machines
+        .iter()
+        .enumerate()
+        .try_fold(0i64, |total, (index, machine)| {
+            let cost = solve_adjusted(machine, adjustment)
+                .with_context(|| format!("error solving machine #{}", index + 1))?
+                .unwrap_or(0);
+
+            total.checked_add(cost).ok_or(MachineError::Total.into())
         })
-        .sum())
 }
 
-pub fn part1(input: Input) -> Definitely<i64> {
+pub fn part1(input: Input) -> anyhow::Result<i64> {
     solve(&input, 0)
 }
 
-pub fn part2(input: Input) -> Definitely<i64> {
+pub fn part2(input: Input) -> anyhow::Result<i64> {
     solve(&input, 10000000000000)
 }
+
+solver!(Input, i64, i64);
+
+/// A brute-force version of part 1, which tries every number of presses of
+/// every button. The number of presses is bounded by the prize, so this only
+/// works for buttons that never move the claw backward. Part 2's prizes are
+/// much too far away for this.
+#[cfg(feature = "naive")]
+#[allow(dead_code)]
+pub mod naive {
+    use super::{Button, Input, Vector};
+
+    fn min_cost(buttons: &[Button], prize: Vector) -> anyhow::Result<Option<i64>> {
+        let Some((first, rest)) = buttons.split_first() else {
+            return Ok((prize == Vector { x: 0, y: 0 }).then_some(0));
+        };
+
+        let Vector { x, y } = first.vector;
+
+        anyhow::ensure!(
+            x >= 0 && y >= 0,
+            "button {} moves the claw backward",
+            first.id
+        );
+
+        let most = match (x, y) {
+            (0, 0) => 0,
+            (0, y) => prize.y / y,
+            (x, 0) => prize.x / x,
+            (x, y) => Ord::min(prize.x / x, prize.y / y),
+        };
+
+        (0..=most).try_fold(None, |best: Option<i64>, presses| {
+            let cost = min_cost(rest, prize + first.vector * -presses)?
+                .map(|rest_cost| rest_cost + presses * first.cost);
+
+            Ok(best.into_iter().chain(cost).min())
+        })
+    }
+
+    pub fn part1(input: Input) -> anyhow::Result<i64> {
+        input.machines.iter().try_fold(0, |total, machine| {
+            Ok(total + min_cost(&machine.buttons, machine.prize)?.unwrap_or(0))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Input, part1, part2};
+
+    /// A machine with a third button, whose prize in part 2 is much too far
+    /// away to try every number of presses of it
+    #[test]
+    fn three_buttons_far_prize() {
+        let text = "Button A: X+94, Y+34\n\
+            Button B: X+22, Y+67\n\
+            Button C: X+5, Y+3\n\
+            Prize: X=8400, Y=5400\n";
+
+        let parse = || Input::try_from(text).expect("machine parses");
+
+        assert_eq!(part1(parse()).unwrap(), 280);
+        assert_eq!(part2(parse()).unwrap(), 351351352132);
+    }
+
+    /// With four buttons, two of them have to be searched in part 2, rather
+    /// than just one
+    #[test]
+    fn four_buttons_far_prize() {
+        let text = "Button A: X+94, Y+34\n\
+            Button B: X+22, Y+67\n\
+            Button C: X+5, Y+3\n\
+            Button D: X+7, Y+11\n\
+            Prize: X=8400, Y=5400\n";
+
+        let parse = || Input::try_from(text).expect("machine parses");
+
+        assert_eq!(part1(parse()).unwrap(), 280);
+        assert_eq!(part2(parse()).unwrap(), 351351351686);
+    }
+
+    /// Every button, and the prize, mirrored through the origin, so that no
+    /// button ever moves the claw forward
+    #[test]
+    fn mirrored_machine() {
+        let text = "Button A: X-94, Y-34\n\
+            Button B: X-22, Y-67\n\
+            Button C: X-5, Y-3\n\
+            Prize: X=-8400, Y=-5400\n";
+
+        let input = Input::try_from(text).expect("machine parses");
+
+        assert_eq!(part1(input).unwrap(), 280);
+    }
+}
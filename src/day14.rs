@@ -1,20 +1,39 @@
-use std::cmp::Ordering;
+//! Day 14: Restroom Redoubt
+
+use std::cmp::{Ordering, Reverse};
+use std::io::BufRead;
+use std::iter;
+use std::num::ParseIntError;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::sync::Mutex;
 
 use anyhow::Context;
 use enum_map::Enum;
-use joinery::JoinableIterator;
-use joinery::separators::Newline;
-use lazy_format::lazy_format;
+use gridly::prelude::{Columns, Location, Rows};
 use nom::Parser;
 use nom::character::complete::{char, digit1, multispace0, space0};
 use nom::combinator::{eof, success};
 use nom_supreme::ParserExt;
 use nom_supreme::multi::collect_separated_terminated;
 use nom_supreme::{error::ErrorTree, final_parser::final_parser};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::Serialize;
+use thiserror::Error;
 
 use crate::day13::Vector;
 use crate::library::ITResult;
+use crate::library::cancel;
 use crate::library::counter::EnumCounter;
+use crate::library::histogram::Histogram;
+use crate::library::modular::{Congruence, chinese_remainder};
+use crate::library::params;
+use crate::library::progress::{self, ParallelProgressIterator};
+use crate::library::solver::solver;
+use crate::library::stream::{Streaming, Updates};
+use crate::library::terminal;
+use crate::library::validate;
+use crate::library::visualize::{self, Frame, Visualize};
 use crate::parser;
 
 fn parse_coord(input: &str) -> ITResult<&str, i64> {
@@ -36,8 +55,8 @@ fn prefixed_vector_parser<'i>(prefix: char) -> impl Parser<&'i str, Vector, Erro
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Robot {
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Robot {
     position: Vector,
     velocity: Vector,
 }
@@ -52,7 +71,7 @@ fn parse_robot(input: &str) -> ITResult<&str, Robot> {
     .parse(input)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
     robots: Vec<Robot>,
 }
@@ -115,6 +134,80 @@ impl Robot {
     }
 }
 
+/// The dimensions of the room the robots move around in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoomConfig {
+    pub width: i64,
+    pub height: i64,
+}
+
+impl RoomConfig {
+    /// The room from the puzzle
+    pub const DEFAULT: Self = Self {
+        width: 101,
+        height: 103,
+    };
+
+    fn dimensions(&self) -> Vector {
+        Vector {
+            x: self.width,
+            y: self.height,
+        }
+    }
+
+    /// Get the room configuration from the runner, via `--param
+    /// room=WIDTHxHEIGHT`. If that isn't given, the deprecated
+    /// `DAY_14_ROOM_WIDTH` and `DAY_14_ROOM_HEIGHT` environment variables are
+    /// checked, and then the puzzle's default room is used.
+    pub fn from_runner() -> anyhow::Result<Self> {
+        if let Some(room) = params::get("room")? {
+            return Ok(room);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(room) = get_env_room()? {
+            eprintln!(
+                "warning: DAY_14_ROOM_WIDTH and DAY_14_ROOM_HEIGHT are deprecated; \
+                use --param room={}x{} instead",
+                room.width, room.height
+            );
+            return Ok(room);
+        }
+
+        Ok(Self::DEFAULT)
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum RoomConfigError {
+    #[error("room must be given as WIDTHxHEIGHT")]
+    Format,
+
+    #[error("failed to parse room dimension")]
+    Dimension(#[from] ParseIntError),
+
+    #[error("room dimensions must be positive")]
+    NotPositive,
+}
+
+impl FromStr for RoomConfig {
+    type Err = RoomConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s.split_once('x').ok_or(RoomConfigError::Format)?;
+        let room = RoomConfig {
+            width: width.trim().parse()?,
+            height: height.trim().parse()?,
+        };
+
+        match room.width > 0 && room.height > 0 {
+            true => Ok(room),
+            false => Err(RoomConfigError::NotPositive),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 fn get_env_int(var: &str) -> anyhow::Result<Option<i64>> {
     match std::env::var(var) {
         Err(err) => match err {
@@ -132,7 +225,8 @@ fn get_env_int(var: &str) -> anyhow::Result<Option<i64>> {
     }
 }
 
-fn get_env_room() -> anyhow::Result<Option<Vector>> {
+#[cfg(not(target_arch = "wasm32"))]
+fn get_env_room() -> anyhow::Result<Option<RoomConfig>> {
     let height = get_env_int("DAY_14_ROOM_HEIGHT").context(
         "error getting room height from \
          environment variable DAY_14_ROOM_HEIGHT",
@@ -144,61 +238,118 @@ fn get_env_room() -> anyhow::Result<Option<Vector>> {
 
     Ok(match (height, width) {
         (None, None) => None,
-        (Some(height), Some(width)) => Some(Vector {
-            x: width,
-            y: height,
-        }),
+        (Some(height), Some(width)) => Some(RoomConfig { width, height }),
         (Some(_), None) => anyhow::bail!("room height was given, but room width was omitted"),
         (None, Some(_)) => anyhow::bail!("room width was given, but room height was omitted"),
     })
 }
 
-#[expect(clippy::format_in_format_args)]
-#[allow(unused)]
+/// Draw the room, with an `O` wherever there's at least one robot
+fn room_frame(dimensions: &Vector, robots: &[Robot]) -> Frame {
+    let mut frame = Frame::new(
+        Rows(dimensions.y as isize),
+        Columns(dimensions.x as isize),
+        '.',
+    );
+
+    frame.mark(
+        robots
+            .iter()
+            .map(|robot| Location::new(robot.position.y as isize, robot.position.x as isize)),
+        'O',
+    );
+
+    frame
+}
+
 fn print_room(dimensions: &Vector, robots: &[Robot]) {
-    let room = (0..dimensions.y)
-        .map(move |y| {
-            (0..dimensions.x)
-                .map(move |x| {
-                    let pos = Vector { x, y };
-                    let any = robots.iter().any(|robot| robot.position == pos);
-                    lazy_format!(
-                        if any => "O"
-                        else => "."
-                    )
-                })
-                .join_concat()
-        })
-        .join_with(Newline);
+    eprint!("{}", room_frame(dimensions, robots));
+}
+
+/// Render the robots as a binary PPM image, with each cell of the room drawn
+/// as a `scale`x`scale` square: green where there's a robot, black otherwise.
+fn render_ppm(dimensions: &Vector, robots: &[Robot], scale: usize) -> Vec<u8> {
+    let width = dimensions.x as usize;
+    let height = dimensions.y as usize;
+
+    let mut occupied = vec![false; width * height];
+    for robot in robots {
+        occupied[robot.position.y as usize * width + robot.position.x as usize] = true;
+    }
+
+    let mut image = format!("P6\n{} {}\n255\n", width * scale, height * scale).into_bytes();
+
+    for row in occupied.chunks(width) {
+        for _ in 0..scale {
+            for &cell in row {
+                let pixel: [u8; 3] = match cell {
+                    true => [0, 200, 0],
+                    false => [0, 0, 0],
+                };
 
-    eprintln!("{}", format!("{}", room));
+                for _ in 0..scale {
+                    image.extend_from_slice(&pixel);
+                }
+            }
+        }
+    }
+
+    image
 }
 
-const DEFAULT_ROOM_HEIGHT: i64 = 103;
-const DEFAULT_ROOM_WIDTH: i64 = 101;
+/// Show the room at the detected `tick`, as requested with `--param
+/// show=true` (print to the terminal) and `--param image=PATH` (write a PPM
+/// image, scaled by `--param scale=N`).
+fn show_tick(robots: &[Robot], room: &RoomConfig, tick: i64) -> anyhow::Result<()> {
+    let show = params::get_or("show", false)?;
+    let image = params::get_raw("image");
 
-const DEFAULT_ROOM: Vector = Vector {
-    x: DEFAULT_ROOM_WIDTH,
-    y: DEFAULT_ROOM_HEIGHT,
-};
+    if !show && image.is_none() {
+        return Ok(());
+    }
 
-pub fn part1(mut input: Input) -> anyhow::Result<usize> {
-    eprintln!("got {} robots", input.robots.len());
-    let room = get_env_room()?.unwrap_or(DEFAULT_ROOM);
+    let dimensions = room.dimensions();
+    let mut robots = robots.to_vec();
+    robots
+        .iter_mut()
+        .for_each(|robot| robot.take_steps(tick, &dimensions));
+
+    if show {
+        eprintln!("Tick {tick}:");
+        print_room(&dimensions, &robots);
+    }
+
+    if let Some(path) = image {
+        let scale = params::get_or("scale", 4)?;
+
+        std::fs::write(path, render_ppm(&dimensions, &robots, scale))
+            .with_context(|| format!("failed to write image to {path:?}"))?;
+    }
+
+    Ok(())
+}
 
-    input
-        .robots
+fn safety_factor(mut robots: Vec<Robot>, room: &RoomConfig) -> usize {
+    let room = room.dimensions();
+
+    robots
         .iter_mut()
         .for_each(|robot| robot.take_steps(100, &room));
 
-    let robot_counts: EnumCounter<Quadrant> = input
-        .robots
+    let robot_counts: EnumCounter<Quadrant> = robots
         .iter()
         .filter_map(|robot| robot.compute_quadrant(&room))
         .collect();
 
     // This will be wrong if any quadrant is empty, since counters skip those.
-    Ok(robot_counts.iter().map(|(_, count)| count.get()).product())
+    robot_counts.iter().map(|(_, count)| count.get()).product()
+}
+
+pub fn part1(input: Input) -> anyhow::Result<usize> {
+    eprintln!("got {} robots", input.robots.len());
+    let room = RoomConfig::from_runner()?;
+
+    Ok(safety_factor(input.robots, &room))
 }
 
 /// Return a score indicating the chance that this is the image we want. Higher
@@ -235,22 +386,28 @@ impl<T: Clone + Ord, const COUNT: usize> Best<T, COUNT> {
         }
     }
 
-    fn insert(&mut self, item: &T) {
+    fn insert(&mut self, item: T) {
         if self.items.len() < COUNT {
-            self.items.push(item.clone());
+            self.items.push(item);
         } else {
             let Some(first) = self.items.first_mut() else {
                 return;
             };
 
-            if *first < *item {
-                *first = item.clone()
+            if *first < item {
+                *first = item
             }
         }
 
         self.items.sort_unstable();
     }
 
+    /// Combine two sets of best items, keeping the best `COUNT` of them
+    fn merge(mut self, other: Self) -> Self {
+        other.items.into_iter().for_each(|item| self.insert(item));
+        self
+    }
+
     fn best(&self) -> Option<&T> {
         self.items.first()
     }
@@ -269,15 +426,144 @@ impl RobotSnapshot {
             robots,
         }
     }
-    fn step(&mut self, room: &Vector) {
+    /// Advance every robot by some number of ticks, which may be negative
+    fn advance(&mut self, ticks: i64, room: &Vector) {
         self.robots
             .iter_mut()
-            .for_each(|robot| robot.take_steps(1, room));
+            .for_each(|robot| robot.take_steps(ticks, room));
 
         self.score = score_robots(&self.robots)
     }
 }
 
+/// A controllable robot simulation, which can be stepped forward or backward
+/// or jumped directly to any tick.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    room: Vector,
+    initial: Vec<Robot>,
+    tick: i64,
+    snapshot: RobotSnapshot,
+}
+
+impl Simulation {
+    pub fn new(robots: Vec<Robot>, room: &RoomConfig) -> Self {
+        Self {
+            room: room.dimensions(),
+            snapshot: RobotSnapshot::new(robots.clone()),
+            initial: robots,
+            tick: 0,
+        }
+    }
+
+    pub fn tick(&self) -> i64 {
+        self.tick
+    }
+
+    /// The neighbor score of the current tick; see `score_robots`
+    pub fn score(&self) -> i64 {
+        self.snapshot.score
+    }
+
+    pub fn robots(&self) -> &[Robot] {
+        &self.snapshot.robots
+    }
+
+    /// Step forward (or, if negative, backward) by some number of ticks
+    pub fn step(&mut self, ticks: i64) {
+        self.snapshot.advance(ticks, &self.room);
+        self.tick += ticks;
+    }
+
+    /// Jump directly to a specific tick
+    pub fn jump_to(&mut self, tick: i64) {
+        self.snapshot = RobotSnapshot::new(self.initial.clone());
+        self.snapshot.advance(tick, &self.room);
+        self.tick = tick;
+    }
+
+    fn caption(&self) -> String {
+        format!("Tick {} (score {}):", self.tick, self.score())
+    }
+
+    pub fn print(&self) {
+        eprintln!("{}", self.caption());
+        print_room(&self.room, self.robots());
+    }
+}
+
+/// The simulation is drawn as the room at its current tick
+impl Visualize for Simulation {
+    fn frames(&self) -> impl Iterator<Item = Frame> + '_ {
+        iter::once(room_frame(&self.room, self.robots()).with_title(self.caption()))
+    }
+}
+
+/// A simulation replayed over a range of ticks, drawn as one frame per tick
+pub struct Replay<'a> {
+    pub simulation: &'a Simulation,
+    pub ticks: RangeInclusive<i64>,
+}
+
+impl Visualize for Replay<'_> {
+    fn frames(&self) -> impl Iterator<Item = Frame> + '_ {
+        let mut simulation = self.simulation.clone();
+        simulation.jump_to(*self.ticks.start());
+
+        self.ticks.clone().map(move |tick| {
+            simulation.step(tick - simulation.tick());
+            room_frame(&simulation.room, simulation.robots()).with_title(simulation.caption())
+        })
+    }
+}
+
+/// Interactively step through a simulation, reading commands from `commands`
+/// and rendering the room to stderr after each one. Returns the tick marked
+/// as the answer, or None if the user quit without marking one.
+fn step_interactively(
+    simulation: &mut Simulation,
+    commands: impl BufRead,
+) -> anyhow::Result<Option<i64>> {
+    const HELP: &str = "commands: [enter]/n: next tick, p: previous tick, \
+        +N/-N: step N ticks, j N: jump to tick N, m: mark this tick as the answer, \
+        q: quit";
+
+    eprintln!("{HELP}");
+    simulation.print();
+
+    for line in commands.lines() {
+        let line = line.context("failed to read command")?;
+        let command = line.trim();
+
+        match command {
+            "" | "n" => simulation.step(1),
+            "p" => simulation.step(-1),
+            "m" => return Ok(Some(simulation.tick())),
+            "q" => return Ok(None),
+            _ => {
+                if let Some(tick) = command.strip_prefix("j") {
+                    match tick.trim().parse() {
+                        Ok(tick) => simulation.jump_to(tick),
+                        Err(_) => {
+                            eprintln!("invalid tick: {tick:?}");
+                            continue;
+                        }
+                    }
+                } else if let Ok(ticks) = command.parse() {
+                    simulation.step(ticks)
+                } else {
+                    eprintln!("{HELP}");
+                    continue;
+                }
+            }
+        }
+
+        simulation.print();
+    }
+
+    Ok(None)
+}
+
 impl PartialEq for RobotSnapshot {
     fn eq(&self, other: &Self) -> bool {
         matches!(Ord::cmp(self, other), Ordering::Equal)
@@ -298,22 +584,236 @@ impl Ord for RobotSnapshot {
     }
 }
 
-pub fn part2(input: Input) -> anyhow::Result<i64> {
-    let room = get_env_room()?.unwrap_or(DEFAULT_ROOM);
+/// Score every tick in the cycle by how many robots are adjacent, and return
+/// the best one. Each tick is computed directly from the initial positions,
+/// so ticks can be scored in parallel. Whenever a tick beats every tick
+/// scored so far, on any thread, it's sent as an update. With `--param
+/// scores=true`, also draw a histogram of every tick's score on stderr, to
+/// show how far the tree stands out.
+fn find_tree(robots: &[Robot], room: &RoomConfig, updates: &Updates) -> anyhow::Result<i64> {
+    let show_scores = params::get_or("scores", false)?;
+    let room = room.dimensions();
+
+    // Every robot is back where it started after `width * height` ticks, so
+    // that's the only cycle that needs scoring; it's the same range of ticks
+    // that the CRT detector can answer with.
+    let cycle_length = room.x * room.y;
+
+    let overall: Mutex<Best<_, 1>> = Mutex::new(Best::new());
+
+    let (best, scores): (Best<_, 1>, Histogram) = (0..cycle_length)
+        .into_par_iter()
+        .progress_with(progress::bar(cycle_length as u64, "ticks"))
+        .take_any_while(|_| !cancel::requested())
+        .fold(
+            || (Best::new(), Histogram::with_width(10)),
+            |(mut best, mut scores), tick| {
+                let mut robots = robots.to_vec();
+                robots
+                    .iter_mut()
+                    .for_each(|robot| robot.take_steps(tick, &room));
+
+                // Ties go to the earliest tick
+                let scored = (score_robots(&robots), Reverse(tick));
+                best.insert(scored);
+                scores.add(scored.0);
+
+                if best.best() == Some(&scored) {
+                    let mut overall = overall
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    overall.insert(scored);
+
+                    if overall.best() == Some(&scored) {
+                        updates.send(format_args!("tick {tick} (score {})", scored.0));
+                    }
+                }
 
-    // Don't want to deal with off-by-one, so just do 1.5x the cycle length
-    let cycle_length = (room.x * room.y * 3) / 2;
+                (best, scores)
+            },
+        )
+        .reduce(
+            || (Best::new(), Histogram::with_width(10)),
+            |(best1, scores1), (best2, scores2)| (best1.merge(best2), scores1.merge(scores2)),
+        );
+
+    cancel::check()?;
+
+    if show_scores {
+        eprint!("{}", scores.bars(40));
+        eprintln!(
+            "median score {}, 99th percentile {}",
+            scores.median().unwrap_or(0),
+            scores.percentile(99.0).unwrap_or(0)
+        );
+    }
+
+    best.best()
+        .map(|&(_, Reverse(tick))| tick)
+        .with_context(|| format!("a {}x{} room has no ticks to search", room.x, room.y))
+}
+
+/// The variance of a set of coordinates, scaled by the square of the number
+/// of coordinates to keep everything in integers.
+fn scaled_variance(coordinates: impl Iterator<Item = i64> + Clone) -> i64 {
+    let (count, sum, sum_squares) = coordinates.fold((0, 0, 0), |(count, sum, squares), c| {
+        (count + 1, sum + c, squares + c * c)
+    });
+
+    count * sum_squares - sum * sum
+}
+
+/// Find the tick, in `0..period`, at which the robots' positions along one
+/// axis are most tightly clustered.
+fn most_clustered_tick(robots: &[Robot], period: i64, axis: fn(&Vector) -> i64) -> i64 {
+    (0..period)
+        .min_by_key(|&tick| {
+            scaled_variance(robots.iter().map(|robot| {
+                let position = axis(&robot.position) + axis(&robot.velocity) * tick;
+                position.rem_euclid(period)
+            }))
+        })
+        .unwrap_or(0)
+}
+
+/// Find the tree by looking at each axis separately. Robots' x coordinates
+/// repeat every `width` ticks, and their y coordinates every `height` ticks,
+/// so we find the tick within one period of each at which that coordinate is
+/// most clustered, then combine them with the chinese remainder theorem. This
+/// takes `width + height` scoring passes, rather than `width * height`.
+fn find_tree_crt(robots: &[Robot], room: &RoomConfig) -> anyhow::Result<i64> {
+    let x_tick = most_clustered_tick(robots, room.width, |v| v.x);
+    let y_tick = most_clustered_tick(robots, room.height, |v| v.y);
+
+    chinese_remainder([
+        Congruence {
+            residue: x_tick,
+            modulus: room.width,
+        },
+        Congruence {
+            residue: y_tick,
+            modulus: room.height,
+        },
+    ])
+    .map(|tick| tick.residue)
+    .with_context(|| {
+        format!(
+            "x clusters at tick {x_tick} (mod {}) and y at tick {y_tick} (mod {}), \
+            but these are never simultaneously true",
+            room.width, room.height
+        )
+    })
+}
 
-    let mut best: Best<_, 1> = Best::new();
-    let mut robots = RobotSnapshot::new(input.robots);
+/// Solve with the detector selected by `--param detector=crt|neighbors`.
+/// `crt` (the default) looks for clustering along each axis separately;
+/// `neighbors` scores every tick by how many robots are adjacent, and streams
+/// the best tick so far. With `--visualize`, `--param frames=N` draws the
+/// last N ticks leading up to the tree, rather than just the tree itself.
+pub fn part2(input: Input) -> anyhow::Result<Streaming<i64>> {
+    let room = RoomConfig::from_runner()?;
+
+    Ok(Streaming::spawn(move |updates| {
+        find_and_show_tree(input, room, updates)
+    }))
+}
 
-    for tick in 1..cycle_length {
-        robots.step(&room);
+solver!(Input, usize, Streaming<i64>);
+
+/// Find the tree with the selected detector, then show it, step through the
+/// ticks around it, or visualize it, as requested
+fn find_and_show_tree(input: Input, room: RoomConfig, updates: &Updates) -> anyhow::Result<i64> {
+    let tick = validate::select(
+        "detector",
+        &[
+            ("crt", &|| find_tree_crt(&input.robots, &room)),
+            ("neighbors", &|| find_tree(&input.robots, &room, updates)),
+        ],
+    )?;
 
-        let pair = (robots, tick);
-        best.insert(&pair);
-        robots = pair.0;
+    if params::get_or("interactive", false)? {
+        let mut simulation = Simulation::new(input.robots, &room);
+        simulation.jump_to(tick);
+
+        return Ok(step_interactively(&mut simulation, terminal::open())?.unwrap_or(tick));
+    }
+
+    show_tick(&input.robots, &room, tick)?;
+
+    if visualize::enabled() {
+        let frames: i64 = params::get_or("frames", 1)?;
+        let simulation = Simulation::new(input.robots, &room);
+
+        visualize::show(&Replay {
+            simulation: &simulation,
+            ticks: (tick - frames + 1).max(0)..=tick,
+        })?;
     }
 
-    Ok(best.best().unwrap().1)
+    Ok(tick)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::stream::Solution;
+
+    const ROOM: RoomConfig = RoomConfig {
+        width: 31,
+        height: 29,
+    };
+
+    /// The tick at which the robots draw a picture, which is early enough
+    /// in the cycle that scanning past the end of the cycle would find it a
+    /// second time
+    const PICTURE_TICK: i64 = 100;
+
+    /// Robots that draw a filled 5x5 square at `PICTURE_TICK`, among others
+    /// that wander around at random
+    fn robots() -> Vec<Robot> {
+        let mut seed: i64 = 2024;
+        let mut random = |limit: i64| {
+            seed = (seed * 1103515245 + 12345).rem_euclid(1 << 31);
+            (seed >> 8) % limit
+        };
+
+        let square = (0..25).map(|i| Vector {
+            x: 13 + i % 5,
+            y: 12 + i / 5,
+        });
+        let scattered: Vec<Vector> = (0..40)
+            .map(|_| Vector {
+                x: random(ROOM.width),
+                y: random(ROOM.height),
+            })
+            .collect();
+
+        square
+            .chain(scattered)
+            .map(|picture| {
+                let velocity = Vector {
+                    x: random(ROOM.width),
+                    y: random(ROOM.height),
+                };
+
+                Robot {
+                    position: Vector {
+                        x: (picture.x - velocity.x * PICTURE_TICK).rem_euclid(ROOM.width),
+                        y: (picture.y - velocity.y * PICTURE_TICK).rem_euclid(ROOM.height),
+                    },
+                    velocity,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detectors_agree_on_a_picture() {
+        let robots = robots();
+
+        assert_eq!(find_tree_crt(&robots, &ROOM).unwrap(), PICTURE_TICK);
+
+        let neighbors = Streaming::spawn(move |updates| find_tree(&robots, &ROOM, updates));
+        assert_eq!(neighbors.answer().unwrap(), PICTURE_TICK);
+    }
 }
@@ -1,5 +1,15 @@
-use std::collections::{HashMap, HashSet};
+//! Day 15: Warehouse Woes
 
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::BufRead;
+use std::iter;
+use std::time::Duration;
+
+use anyhow::Context;
+#[cfg(test)]
+use bumpalo::{Bump, collections::Vec as BumpVec};
 use gridly::prelude::*;
 use gridly_grids::VecGrid;
 use nom::{
@@ -14,36 +24,58 @@ use nom_supreme::{
     final_parser::final_parser,
     multi::{collect_separated_terminated, parse_separated_terminated},
 };
+use serde::Serialize;
 
 use crate::{
     express,
-    library::{Definitely, ITResult, IterExt},
+    library::{
+        ITResult,
+        grid::{GridExt, iter_located},
+        params,
+        solver::solver,
+        terminal::{self, Animation},
+        visualize::{self, Frame, Visualize},
+    },
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Cell {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Cell {
     Empty,
     Wall,
-    Box,
+
+    /// Part of a box. `offset` is the distance from this cell to the box's
+    /// leftmost cell, so a box in a map with `box_width` 2 is made of
+    /// `Box { offset: 0 }` followed by `Box { offset: 1 }`.
+    Box {
+        offset: isize,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-
 enum AnyCell {
     Cell(Cell),
     Robot,
 }
 
-#[derive(Debug)]
-struct Map {
+#[derive(Debug, Clone, Serialize)]
+pub struct Map {
+    #[serde(serialize_with = "crate::library::inspect::grid")]
     contents: VecGrid<Cell>,
+    #[serde(serialize_with = "crate::library::inspect::location")]
     robot: Location,
+
+    /// The width of every box in the map. Maps are parsed with a width of 1,
+    /// and can be widened with `scaled`.
+    box_width: isize,
 }
 
-#[derive(Debug)]
-pub struct Input {
-    map: Map,
-    instructions: Vec<Direction>,
+/// The instructions are either owned, or borrowed from an arena; see
+/// `parse_in`.
+#[derive(Debug, Serialize)]
+pub struct Input<'a> {
+    pub map: Map,
+    #[serde(serialize_with = "crate::library::inspect::directions")]
+    pub instructions: Cow<'a, [Direction]>,
 }
 
 fn parse_cell(input: &str) -> ITResult<&str, AnyCell> {
@@ -53,7 +85,7 @@ fn parse_cell(input: &str) -> ITResult<&str, AnyCell> {
     alt((
         char('#').value(Cell(Wall)),
         char('.').value(Cell(Empty)),
-        char('O').value(Cell(Box)),
+        char('O').value(Cell(Box { offset: 0 })),
         char('@').value(Robot),
     ))
     .parse(input)
@@ -72,36 +104,31 @@ enum Error {
     BadDimensions,
 }
 
+/// Build a map from its parsed rows
+fn map_from_rows(lines: &[impl AsRef<[AnyCell]>]) -> Result<Map, Error> {
+    // Find the robot in the rows
+    let (robot_location, _cell) = iter_located(lines.iter().map(AsRef::as_ref))
+        .find(|(_loc, cell)| matches!(cell, AnyCell::Robot))
+        .ok_or(Error::NoRobot)?;
+
+    // Convert the rows to a VecGrid
+    VecGrid::new_from_rows(lines.iter().map(|line| {
+        line.as_ref().iter().map(|&cell| match cell {
+            AnyCell::Cell(cell) => cell,
+            AnyCell::Robot => Cell::Empty,
+        })
+    }))
+    .ok_or(Error::BadDimensions)
+    .map(|grid| Map {
+        contents: grid,
+        robot: robot_location,
+        box_width: 1,
+    })
+}
+
 fn parse_map(input: &str) -> ITResult<&str, Map> {
     collect_separated_terminated(parse_row, success(()), char('\n'))
-        // Find the robot in the rows
-        .map_res_cut(|lines: Vec<Vec<AnyCell>>| {
-            lines
-                .iter()
-                .with_rows(Row(0))
-                .flat_map(|(row, line)| {
-                    line.iter()
-                        .with_columns(Column(0))
-                        .map(move |(column, &cell)| (cell, row.combine(column)))
-                })
-                .find(|(cell, _loc)| matches!(cell, AnyCell::Robot))
-                .ok_or(Error::NoRobot)
-                .map(|(_cell, robot_location)| (lines, robot_location))
-        })
-        // Convert the rows to a VecGrid
-        .map_res_cut(|(lines, robot_location)| {
-            VecGrid::new_from_rows(lines.iter().map(|line| {
-                line.iter().map(|&cell| match cell {
-                    AnyCell::Cell(cell) => cell,
-                    AnyCell::Robot => Cell::Empty,
-                })
-            }))
-            .ok_or(Error::BadDimensions)
-            .map(|grid| Map {
-                contents: grid,
-                robot: robot_location,
-            })
-        })
+        .map_res_cut(|lines: Vec<Vec<AnyCell>>| map_from_rows(&lines))
         .parse(input)
 }
 
@@ -115,28 +142,34 @@ fn parse_instruction(input: &str) -> ITResult<&str, Direction> {
     .parse(input)
 }
 
-fn parse_instruction_list(input: &str) -> ITResult<&str, Vec<Direction>> {
+/// Parse the instructions, which may be broken up by whitespace, into the
+/// collection created by `init`
+fn parse_instructions_into<'i, C: Extend<Direction>>(
+    init: impl FnMut() -> C,
+) -> impl Parser<&'i str, C, ErrorTree<&'i str>> {
     parse_separated_terminated(
         parse_instruction.map(Some).or(multispace1.value(None)),
         success(()),
         eof,
-        Vec::new,
+        init,
         |list, instruction| match instruction {
             None => list,
-            Some(instruction) => express!(list.push(instruction)),
+            Some(instruction) => express!(list.extend([instruction])),
         },
     )
-    .parse(input)
 }
 
-fn parse_input(input: &str) -> ITResult<&str, Input> {
+fn parse_input(input: &str) -> ITResult<&str, Input<'static>> {
     parse_map
-        .and(parse_instruction_list)
-        .map(|(map, instructions)| Input { map, instructions })
+        .and(parse_instructions_into(Vec::new))
+        .map(|(map, instructions)| Input {
+            map,
+            instructions: Cow::Owned(instructions),
+        })
         .parse(input)
 }
 
-impl TryFrom<&str> for Input {
+impl TryFrom<&str> for Input<'static> {
     type Error = ErrorTree<nom_supreme::final_parser::Location>;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
@@ -144,220 +177,546 @@ impl TryFrom<&str> for Input {
     }
 }
 
-impl Map {
-    // Try to move the robot. Return failure if it couldn't move. In any case,
-    // return the robot's new location.
-    fn try_move_robot(&mut self, direction: Direction) -> Result<Location, Location> {
-        let new_location = self.robot + direction;
+/// Parse the input with the map's rows and the instructions allocated in
+/// `arena`, rather than each in a `Vec` of its own. The map itself is still
+/// a `VecGrid`. Compared against the usual parser in the benchmarks, which
+/// Cargo builds under `cfg(test)`, like the tests.
+#[cfg(test)]
+pub(crate) fn parse_in<'a>(
+    arena: &'a Bump,
+    input: &'a str,
+) -> Result<Input<'a>, ErrorTree<nom_supreme::final_parser::Location>> {
+    let row = parse_separated_terminated(
+        parse_cell,
+        success(()),
+        char('\n'),
+        || BumpVec::new_in(arena),
+        |row, cell| express!(row.push(cell)),
+    );
 
-        match self.contents.get(new_location) {
-            // Out of bounds, or hit a wall, so no movement
-            Err(_) | Ok(&Cell::Wall) => Err(self.robot),
+    let map = parse_separated_terminated(
+        row.map(BumpVec::into_bump_slice),
+        success(()),
+        char('\n'),
+        || BumpVec::new_in(arena),
+        |lines, line| express!(lines.push(line)),
+    )
+    .map_res_cut(|lines| map_from_rows(&lines));
 
-            // Cell is empty; robot moves
-            Ok(&Cell::Empty) => {
-                self.robot = new_location;
-                Ok(new_location)
-            }
+    let parse_input = map
+        .and(parse_instructions_into(|| BumpVec::new_in(arena)))
+        .map(|(map, instructions)| Input {
+            map,
+            instructions: Cow::Borrowed(instructions.into_bump_slice()),
+        });
 
-            // There's a box; try to move the box
-            Ok(&Cell::Box) => {
-                // Loop until we find an empty cell to put the box
-                let mut box_location = new_location;
+    final_parser(parse_input)(input)
+}
 
-                loop {
-                    box_location += direction;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Success,
+    Fail,
+}
+
+/// A record of everything a single robot move changed, so that it can be
+/// reverted
+#[derive(Debug, Clone)]
+struct Change {
+    robot: Location,
+
+    /// The previous contents of every cell that was written, in the order
+    /// they were written
+    cells: Vec<(Location, Cell)>,
+}
 
-                    match self.contents.get(box_location) {
-                        // Out of bounds, or hit a wall, so no movement
-                        Err(_) | Ok(&Cell::Wall) => break Err(self.robot),
+impl Map {
+    /// Create a new map, where every cell (and therefore every box, and the
+    /// robot's position) is `scale` times wider.
+    pub fn scaled(&self, scale: isize) -> Map {
+        let contents = VecGrid::new_from_rows(self.contents.rows().iter().map(|row| {
+            row.iter().flat_map(|&cell| {
+                (0..scale).map(move |i| match cell {
+                    Cell::Empty => Cell::Empty,
+                    Cell::Wall => Cell::Wall,
+                    Cell::Box { offset } => Cell::Box {
+                        offset: offset * scale + i,
+                    },
+                })
+            })
+        }))
+        .expect("Map should be a rectangle, since the original map is a rectangle");
+
+        let robot = Location {
+            row: self.robot.row,
+            column: Column(self.robot.column.0 * scale),
+        };
+
+        Map {
+            contents,
+            robot,
+            box_width: self.box_width * scale,
+        }
+    }
 
-                        // There's a box; it's part of the group being moved,
-                        // so skip it
-                        Ok(&Cell::Box) => continue,
+    /// Attempt to move a bunch of blocks, such that the robot can be at root.
+    fn attempt_block_moves(
+        &mut self,
+        root: &Location,
+        direction: Direction,
+        change: &mut Change,
+    ) -> Outcome {
+        let mut upcoming_checks = Vec::new();
+        let mut writes = HashMap::new();
+        let mut confirmed = HashSet::new();
+
+        upcoming_checks.push(*root);
+
+        while let Some(location) = upcoming_checks.pop() {
+            // For each check, we want the `location` to become empty by pushing
+            // whatever box might be there in `direction`
+
+            // Ensure we haven't already evaluated this location
+            if confirmed.replace(location).is_some() {
+                // Fun fact: I added `confirmed` because, without it, there's a
+                // risk of exponential growth if you evaluate a large "pyramid" of
+                // boxes (since, if you move a pair of boxes upward, any box above
+                // them in the center will be evaluated twice). I tested the code
+                // without this, just to see, and it's only marginally slower
+                // even on my real input. But I'm keeping it anyway out of
+                // algorithmic satisfaction.
+                continue;
+            }
 
-                        // Cell is empty; move the box
-                        Ok(&Cell::Empty) => {
-                            let _ = self.contents.set(box_location, Cell::Box);
-                            let _ = self.contents.set(new_location, Cell::Empty);
-                            self.robot = new_location;
-                            break Ok(new_location);
+            match self.contents.get(location) {
+                // Hit a wall. None of this will succeed; bail immediately.
+                Err(_) | Ok(&Cell::Wall) => return Outcome::Fail,
+
+                // This location is empty, so there are no problems. Continue
+                // with checks
+                Ok(&Cell::Empty) => continue,
+
+                Ok(&Cell::Box { offset }) => {
+                    // Compute the coordinates of the box
+                    let left = location - Columns(offset);
+                    let right = left + Columns(self.box_width - 1);
+                    let cells = (0..self.box_width).map(|i| (i, left + Columns(i)));
+
+                    // Insert the desired writes for the new position of the box.
+                    cells.clone().for_each(|(offset, cell)| {
+                        writes.insert(cell + direction, Cell::Box { offset });
+                    });
+
+                    // Replace the current location of the box with emptiness,
+                    // unless previous iterations are putting something else there
+                    // instead
+                    cells.clone().for_each(|(_, cell)| {
+                        writes.entry(cell).or_insert(Cell::Empty);
+                    });
+
+                    match direction {
+                        Up | Down => {
+                            upcoming_checks.extend(cells.map(|(_, cell)| cell + direction));
+                        }
+                        Left => {
+                            upcoming_checks.push(left + Left);
+                        }
+                        Right => {
+                            upcoming_checks.push(right + Right);
                         }
                     }
                 }
             }
         }
+
+        // All checks succeeded. Execute all writes.
+        writes
+            .iter()
+            .for_each(|(&location, &cell)| self.write(location, cell, change));
+
+        Outcome::Success
     }
-}
 
-fn compute_coordinate(location: &Location) -> isize {
-    location.row.0 * 100 + location.column.0
+    fn step(&mut self, direction: Direction, change: &mut Change) {
+        let new_location = self.robot + direction;
+
+        match self.attempt_block_moves(&new_location, direction, change) {
+            Outcome::Success => self.robot = new_location,
+            Outcome::Fail => {}
+        }
+    }
+
+    /// Write a cell, recording its previous contents in `change`
+    fn write(&mut self, location: Location, cell: Cell, change: &mut Change) {
+        let previous = self
+            .contents
+            .replace(location, cell)
+            .expect("Bounds error while moving boxes");
+
+        change.cells.push((location, previous));
+    }
+
+    /// Move the robot. Returns a record of the move that can be passed to
+    /// `revert`.
+    fn move_robot(&mut self, direction: Direction) -> Change {
+        let mut change = Change {
+            robot: self.robot,
+            cells: Vec::new(),
+        };
+
+        self.step(direction, &mut change);
+        change
+    }
+
+    /// Undo a move previously made with `move_robot`. Moves must be reverted
+    /// in the opposite order they were made.
+    fn revert(&mut self, change: Change) {
+        change.cells.into_iter().rev().for_each(|(location, cell)| {
+            self.contents
+                .set(location, cell)
+                .expect("Bounds error while reverting a move")
+        });
+
+        self.robot = change.robot;
+    }
+
+    /// The sum of the GPS coordinates of every box, measured from each box's
+    /// leftmost cell
+    pub fn gps_sum(&self) -> isize {
+        self.contents
+            .iter_located()
+            .filter(|&(_, &cell)| matches!(cell, Cell::Box { offset: 0 }))
+            .map(|(location, _)| compute_coordinate(&location))
+            .sum()
+    }
 }
 
-pub fn part1(
-    Input {
-        mut map,
-        instructions,
-    }: Input,
-) -> Definitely<isize> {
-    for &direction in &instructions {
-        let _res = map.try_move_robot(direction);
+impl Map {
+    /// Draw the warehouse like the puzzle does
+    pub fn frame(&self) -> Frame {
+        Frame::from_fn(
+            self.contents.num_rows(),
+            self.contents.num_columns(),
+            |location| match self.contents.get(location) {
+                _ if location == self.robot => '@',
+                Ok(Cell::Empty) | Err(_) => '.',
+                Ok(Cell::Wall) => '#',
+                Ok(Cell::Box { .. }) if self.box_width == 1 => 'O',
+                Ok(Cell::Box { offset: 0 }) => '[',
+                Ok(&Cell::Box { offset }) if offset == self.box_width - 1 => ']',
+                Ok(Cell::Box { .. }) => '=',
+            },
+        )
     }
+}
 
-    Ok(map
-        .contents
-        .rows()
-        .iter()
-        .flat_map(|row| row.iter_with_locations())
-        .filter(|&(_, &cell)| matches!(cell, Cell::Box))
-        .map(|(location, _)| compute_coordinate(&location))
-        .sum())
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.frame())
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum BoxHalf {
-    Left,
-    Right,
+/// A robot simulation that can be stepped forward and backward through its
+/// instructions.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    map: Map,
+    instructions: Vec<Direction>,
+
+    /// One change for every instruction that has been applied
+    history: Vec<Change>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Cell2 {
-    Empty,
-    Wall,
-    Box(BoxHalf),
+impl Simulation {
+    pub fn new(map: Map, instructions: impl Into<Vec<Direction>>) -> Self {
+        Self {
+            map,
+            instructions: instructions.into(),
+            history: Vec::new(),
+        }
+    }
+
+    pub fn into_map(self) -> Map {
+        self.map
+    }
+
+    /// The number of instructions that have been applied so far
+    pub fn position(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Apply the next instruction, and return it, or None if every
+    /// instruction has already been applied
+    pub fn step(&mut self) -> Option<Direction> {
+        let direction = *self.instructions.get(self.position())?;
+        let change = self.map.move_robot(direction);
+        self.history.push(change);
+        Some(direction)
+    }
+
+    /// Undo the most recently applied instruction, and return it, or None if
+    /// we're already at the beginning
+    pub fn undo(&mut self) -> Option<Direction> {
+        let change = self.history.pop()?;
+        self.map.revert(change);
+        Some(self.instructions[self.position()])
+    }
+
+    /// Step or undo until exactly `position` instructions have been applied
+    /// (or as close as possible, if `position` is past the end)
+    pub fn seek(&mut self, position: usize) {
+        let position = position.min(self.instructions.len());
+
+        while self.position() < position {
+            self.step();
+        }
+
+        while self.position() > position {
+            self.undo();
+        }
+    }
+
+    pub fn print(&self) {
+        eprintln!("{}", caption(&self.instructions, self.position()));
+        eprint!("{}", self.map);
+    }
 }
 
-struct Map2 {
-    contents: VecGrid<Cell2>,
-    robot: Location,
+/// Describe the state of the warehouse after `position` instructions
+fn caption(instructions: &[Direction], position: usize) -> String {
+    match position.checked_sub(1) {
+        None => "Initial state:".to_owned(),
+        Some(i) => format!(
+            "Move {position}/{} {}:",
+            instructions.len(),
+            direction_arrow(instructions[i])
+        ),
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Outcome {
-    Success,
-    Fail,
+/// The simulation is drawn as its current state, followed by the state after
+/// each remaining instruction
+impl Visualize for Simulation {
+    fn frames(&self) -> impl Iterator<Item = Frame> + '_ {
+        let start = self.position();
+        let mut map = self.map.clone();
+
+        let first = map.frame().with_title(caption(&self.instructions, start));
+
+        let rest = self.instructions[start..].iter().zip(start + 1..).map(
+            move |(&direction, position)| {
+                let _change = map.move_robot(direction);
+                map.frame()
+                    .with_title(caption(&self.instructions, position))
+            },
+        );
+
+        iter::once(first).chain(rest)
+    }
 }
 
-/// Attempt to move a bunch of blocks, such that the robot can be at root.
-fn attempt_block_moves(map: &mut VecGrid<Cell2>, root: &Location, direction: Direction) -> Outcome {
-    let mut upcoming_checks = Vec::new();
-    let mut writes = HashMap::new();
-    let mut confirmed = HashSet::new();
-
-    upcoming_checks.push(*root);
-
-    while let Some(location) = upcoming_checks.pop() {
-        // For each check, we want the `location` to become empty by pushing
-        // whatever box might be there in `direction`
-
-        // Ensure we haven't already evaluated this location
-        if confirmed.replace(location).is_some() {
-            // Fun fact: I added `confirmed` because, without it, there's a
-            // risk of exponential growth if you evaluate a large "pyramid" of
-            // boxes (since, if you move a pair of boxes upward, any box above
-            // them in the center will be evaluated twice). I tested the code
-            // without this, just to see, and it's only marginally slower
-            // even on my real input. But I'm keeping it anyway out of
-            // algorithmic satisfaction.
-            continue;
-        }
+/// Interactively step through a simulation, reading commands from `commands`
+/// and drawing the warehouse to stderr after each one.
+fn step_interactively(simulation: &mut Simulation, commands: impl BufRead) -> anyhow::Result<()> {
+    const HELP: &str = "commands: [enter]/n: next move, u: undo, +N/-N: step N moves, \
+        s N: seek to move N, q: quit";
 
-        match map.get(location) {
-            // Hit a wall. None of this will succeed; bail immediately.
-            Err(_) | Ok(&Cell2::Wall) => return Outcome::Fail,
-
-            // This location is empty, so there are no problems. Continue
-            // with checks
-            Ok(&Cell2::Empty) => continue,
-
-            Ok(&Cell2::Box(half)) => {
-                // Compute the coordinates of the box
-                let (left, right) = match half {
-                    BoxHalf::Left => (location, location + Right),
-                    BoxHalf::Right => (location + Left, location),
-                };
-
-                // Insert the desired writes for the new position of the box.
-                writes.insert(left + direction, Cell2::Box(BoxHalf::Left));
-                writes.insert(right + direction, Cell2::Box(BoxHalf::Right));
-
-                // Replace the current location of the box with emptiness,
-                // unless previous iterations are putting something else there
-                // instead
-                writes.entry(left).or_insert(Cell2::Empty);
-                writes.entry(right).or_insert(Cell2::Empty);
-
-                match direction {
-                    Up | Down => {
-                        upcoming_checks.push(left + direction);
-                        upcoming_checks.push(right + direction);
-                    }
-                    Left => {
-                        upcoming_checks.push(left + Left);
-                    }
-                    Right => {
-                        upcoming_checks.push(right + Right);
+    eprintln!("{HELP}");
+    simulation.print();
+
+    for line in commands.lines() {
+        let line = line.context("failed to read command")?;
+        let command = line.trim();
+
+        match command {
+            "" | "n" => {
+                simulation.step();
+            }
+            "u" => {
+                simulation.undo();
+            }
+            "q" => return Ok(()),
+            _ => {
+                if let Some(position) = command.strip_prefix("s") {
+                    match position.trim().parse() {
+                        Ok(position) => simulation.seek(position),
+                        Err(_) => {
+                            eprintln!("invalid move number: {position:?}");
+                            continue;
+                        }
                     }
+                } else if let Ok(moves) = command.parse::<isize>() {
+                    simulation.seek(simulation.position().saturating_add_signed(moves))
+                } else {
+                    eprintln!("{HELP}");
+                    continue;
                 }
             }
         }
+
+        simulation.print();
     }
 
-    // All checks succeeded. Execute all writes.
-    writes.iter().for_each(|(&location, &cell)| {
-        map.set(location, cell)
-            .expect("Bounds error during block moves")
+    Ok(())
+}
+
+fn direction_arrow(direction: Direction) -> char {
+    match direction {
+        Up => '^',
+        Right => '>',
+        Down => 'v',
+        Left => '<',
+    }
+}
+
+/// Run every instruction on the map, and return the warehouse after the
+/// last one
+pub fn run(mut map: Map, instructions: &[Direction]) -> Map {
+    instructions.iter().for_each(|&direction| {
+        let _change = map.move_robot(direction);
     });
 
-    Outcome::Success
+    map
 }
 
-impl Map2 {
-    fn step(&mut self, direction: Direction) {
-        let new_location = self.robot + direction;
+/// Run all the instructions and return the final map. With `--visualize`,
+/// every step is drawn with the selected backend. With `--param
+/// animate=true`, the warehouse is drawn in the terminal after every
+/// instruction, waiting `--param delay=MS` (default 50) between frames. With
+/// `--param interactive=true`, the moves can be stepped through and undone
+/// by hand.
+fn play(map: Map, instructions: &[Direction]) -> anyhow::Result<Map> {
+    if params::get_or("interactive", false)? {
+        let mut simulation = Simulation::new(map, instructions.to_vec());
+        step_interactively(&mut simulation, terminal::open())?;
+        simulation.seek(instructions.len());
+
+        return Ok(simulation.into_map());
+    }
 
-        match attempt_block_moves(&mut self.contents, &new_location, direction) {
-            Outcome::Success => self.robot = new_location,
-            Outcome::Fail => {}
-        }
+    if visualize::enabled() {
+        visualize::show(&Simulation::new(map.clone(), instructions.to_vec()))?;
     }
-}
 
-fn convert_map(map: &Map) -> Map2 {
-    use BoxHalf::*;
-    use Cell2::*;
+    if !params::get_or("animate", false)? {
+        return Ok(run(map, instructions));
+    }
 
-    let contents = VecGrid::new_from_rows(map.contents.rows().iter().map(|row| {
-        row.iter().flat_map(|&cell| match cell {
-            Cell::Empty => [Empty, Empty],
-            Cell::Wall => [Wall, Wall],
-            Cell::Box => [Box(Left), Box(Right)],
-        })
-    }))
-    .expect("Map should be a rectangle, since the original map is a rectangle");
+    let delay = Duration::from_millis(params::get_or("delay", 50)?);
 
-    let robot = Location {
-        row: map.robot.row,
-        column: Column(map.robot.column.0 * 2),
-    };
+    Animation::new(delay)
+        .play(Simulation::new(map.clone(), instructions.to_vec()).frames())
+        .context("failed to animate the warehouse")?;
 
-    Map2 { contents, robot }
+    Ok(run(map, instructions))
 }
 
-pub fn part2(input: Input) -> Definitely<isize> {
-    let mut map = convert_map(&input.map);
-
-    input
-        .instructions
-        .iter()
-        .for_each(|&direction| map.step(direction));
-
-    Ok(map
-        .contents
-        .rows()
-        .iter()
-        .flat_map(|row| row.iter_with_locations())
-        .filter(|&(_, &cell)| matches!(cell, Cell2::Box(BoxHalf::Left)))
-        .map(|(location, _)| compute_coordinate(&location))
-        .sum())
+fn compute_coordinate(location: &Location) -> isize {
+    location.row.0 * 100 + location.column.0
+}
+
+pub fn part1(input: Input) -> anyhow::Result<isize> {
+    let map = play(input.map, &input.instructions)?;
+    Ok(map.gps_sum())
+}
+
+pub fn part2(input: Input) -> anyhow::Result<isize> {
+    let map = play(input.map.scaled(2), &input.instructions)?;
+    Ok(map.gps_sum())
+}
+
+solver!(Input<'static>, isize, isize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = "\
+        ########\n\
+        #..O.O.#\n\
+        ##@.O..#\n\
+        #...O..#\n\
+        #.#.O..#\n\
+        #...O..#\n\
+        #......#\n\
+        ########\n\
+        \n\
+        <^^>>>vv<v>>v<<\n";
+
+    /// Some of the intermediate states the puzzle shows for its small
+    /// example, by the number of moves made to reach them
+    const SMALL_STATES: &[(usize, &str)] = &[
+        (
+            2,
+            "########\n\
+            #.@O.O.#\n\
+            ##..O..#\n\
+            #...O..#\n\
+            #.#.O..#\n\
+            #...O..#\n\
+            #......#\n\
+            ########\n",
+        ),
+        (
+            7,
+            "########\n\
+            #....OO#\n\
+            ##..@..#\n\
+            #...O..#\n\
+            #.#.O..#\n\
+            #...O..#\n\
+            #...O..#\n\
+            ########\n",
+        ),
+        (
+            12,
+            "########\n\
+            #....OO#\n\
+            ##.....#\n\
+            #....@O#\n\
+            #.#.O..#\n\
+            #...O..#\n\
+            #...O..#\n\
+            ########\n",
+        ),
+        (
+            15,
+            "########\n\
+            #....OO#\n\
+            ##.....#\n\
+            #.....O#\n\
+            #.#O@..#\n\
+            #...O..#\n\
+            #...O..#\n\
+            ########\n",
+        ),
+    ];
+
+    #[test]
+    fn small_example_intermediate_states() {
+        let input = Input::try_from(SMALL).expect("example parses");
+        let mut simulation = Simulation::new(input.map.clone(), input.instructions.clone());
+        assert_eq!(simulation.instructions.len(), 15);
+
+        // Rewinding passes back through the same states
+        for &(moves, expected) in SMALL_STATES.iter().chain(SMALL_STATES.iter().rev()) {
+            simulation.seek(moves);
+            assert_eq!(simulation.map.to_string(), expected, "after {moves} moves");
+        }
+
+        simulation.seek(2);
+        assert_eq!(simulation.map.robot, Location::new(1, 2));
+
+        simulation.seek(usize::MAX);
+        let final_map = simulation.into_map();
+        assert_eq!(final_map.robot, Location::new(4, 4));
+        assert_eq!(final_map.box_width, 1);
+        assert_eq!(
+            final_map.contents.get(Location::new(1, 5)),
+            Ok(&Cell::Box { offset: 0 })
+        );
+
+        let map = run(input.map, &input.instructions);
+        assert_eq!(map.to_string(), final_map.to_string());
+    }
 }
@@ -1,102 +1,312 @@
+//! Day 16: Reindeer Maze
+//!
+//! `best_path` and `MazePath` are also meant for visualizing and checking
+//! routes from outside the solutions.
+
 use std::{
     cmp::Ordering,
     collections::{BinaryHeap, HashMap, HashSet},
+    iter,
+    num::ParseIntError,
+    str::FromStr,
 };
 
+use anyhow::Context;
 use gridly::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::library::{
+    dijkstra, dump,
+    grid::{Path, Rect, located_chars},
+    inspect::AsLocation,
+    maze::{Maze, ParseMazeError},
+    params,
+    solver::solver,
+    stats::Counter,
+    svg, validate,
+    visualize::{self, Visualize},
+};
 
-use crate::library::{IterExt, direction_map::DirectionMap};
-
-#[derive(Debug)]
+/// The maze, along with everything about it that a plain [`Maze`] can't
+/// describe. Any number of tiles may be drawn as an `E`, and a route may end
+/// at whichever is cheapest; the maze's own end is the first of them. A
+/// floor tile may also be drawn as a digit, which is the extra cost of every
+/// move onto it.
+#[derive(Debug, Serialize)]
 pub struct Input {
-    start: Location,
-    end: Location,
-    walls: HashSet<Location>,
+    #[serde(flatten, serialize_with = "crate::library::inspect::maze")]
+    maze: Maze,
+
+    #[serde(serialize_with = "crate::library::inspect::locations")]
+    ends: Vec<Location>,
+
+    #[serde(
+        serialize_with = "serialize_weights",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    weights: HashMap<Location, i64>,
+}
+
+/// Serialize the weighted tiles as `[[row, column], weight]`, in row-major
+/// order
+fn serialize_weights<S: serde::Serializer>(
+    weights: &HashMap<Location, i64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut weights: Vec<(Location, i64)> = weights.iter().map(|(&l, &w)| (l, w)).collect();
+    weights.sort_unstable_by_key(|&(location, _)| (location.row, location.column));
+
+    serializer.collect_seq(
+        weights
+            .into_iter()
+            .map(|(location, weight)| (AsLocation(location), weight)),
+    )
 }
 
 impl TryFrom<&str> for Input {
-    type Error = anyhow::Error;
+    type Error = ParseMazeError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut walls = Vec::new();
+        let mut ends = Vec::new();
+        let mut weights = HashMap::new();
         let mut start = None;
-        let mut end = None;
-        let mut walls = HashSet::new();
-
-        for (row, line) in value.lines().map(|line| line.trim()).with_rows(Row(0)) {
-            for (column, &cell) in line.as_bytes().iter().with_columns(Column(0)) {
-                let location = row + column;
-
-                match cell {
-                    b'#' => {
-                        walls.insert(Location::new(row, column));
-                    }
-                    b'.' => {}
-                    b'S' if start.is_some() => anyhow::bail!("multiple start locations"),
-                    b'S' => start = Some(location),
-                    b'E' if end.is_some() => anyhow::bail!("multiple end locations"),
-                    b'E' => end = Some(location),
-                    _ => anyhow::bail!("invalid cell: {:?}", cell as char),
+        let mut bounds = Rect::default();
+
+        for (location, cell) in located_chars(value) {
+            bounds = bounds.union(&Rect::from_corners(Location::zero(), location));
+
+            match cell {
+                '#' => walls.push(location),
+                '.' => {}
+                'S' if start.is_some() => return Err(ParseMazeError::MultipleStarts),
+                'S' => start = Some(location),
+                'E' => ends.push(location),
+                '0'..='9' => {
+                    weights.insert(location, i64::from(cell as u8 - b'0'));
                 }
+                cell => return Err(ParseMazeError::InvalidCell { location, cell }),
             }
         }
 
+        let start = start.ok_or(ParseMazeError::NoStart)?;
+        let &end = ends.first().ok_or(ParseMazeError::NoEnd)?;
+        let mut maze = Maze::new(bounds, start, end);
+
+        for wall in walls {
+            maze.add_wall(wall).expect("every drawn cell is in bounds");
+        }
+
         Ok(Input {
-            start: start.ok_or_else(|| anyhow::anyhow!("no start location"))?,
-            end: end.ok_or_else(|| anyhow::anyhow!("no end location"))?,
-            walls,
+            maze,
+            ends,
+            weights,
         })
     }
 }
 
+impl Input {
+    pub fn is_end(&self, location: Location) -> bool {
+        self.ends.contains(&location)
+    }
+
+    /// The extra cost of moving onto `location`, on top of the cost model
+    pub fn weight(&self, location: Location) -> i64 {
+        self.weights.get(&location).copied().unwrap_or(0)
+    }
+
+    /// The cost of a single move from a state, turning (if necessary) to
+    /// face `direction`, and then stepping forward onto the next tile
+    fn move_cost(&self, costs: CostModel, from: State, direction: Direction) -> i64 {
+        costs.move_cost(from.direction, direction) + self.weight(from.location + direction)
+    }
+
+    /// How `location` is drawn, the way it's parsed
+    pub fn cell(&self, location: Location) -> char {
+        match self.weights.get(&location) {
+            _ if self.is_end(location) => 'E',
+            Some(&weight) => char::from_digit(weight as u32, 10).expect("weights are digits"),
+            None => self.maze.cell(location),
+        }
+    }
+}
+
+/// A reindeer's position in the maze: where it is and which way it's facing
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct State {
-    location: Location,
-    direction: Direction,
+pub struct State {
+    pub location: Location,
+    pub direction: Direction,
+}
+
+/// The costs of moving through the maze. Every move steps forward one tile,
+/// possibly after turning 90 degrees once (or twice, to turn around).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostModel {
+    pub step: i64,
+    pub turn: i64,
+}
+
+impl CostModel {
+    pub const STANDARD: Self = Self {
+        step: 1,
+        turn: 1000,
+    };
+
+    /// The number of 90 degree turns needed to go from facing `from` to
+    /// facing `to`
+    fn turns(from: Direction, to: Direction) -> i64 {
+        if from == to {
+            0
+        } else if from == to.reverse() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// The cost of a single move, which turns to face `to` (if necessary)
+    /// and then steps forward
+    pub fn move_cost(&self, from: Direction, to: Direction) -> i64 {
+        self.step + self.turn * Self::turns(from, to)
+    }
+
+    /// Get the cost model requested with `--param costs=STEP,TURN`, or the
+    /// standard one
+    pub fn from_runner() -> anyhow::Result<Self> {
+        params::get_or("costs", Self::STANDARD)
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum CostModelError {
+    #[error("costs must be given as STEP,TURN")]
+    Format,
+
+    #[error("failed to parse cost")]
+    Cost(#[from] ParseIntError),
+
+    // Dijkstra needs non-negative costs, and route counting needs every move
+    // to cost something
+    #[error("the step cost must be positive, and the turn cost non-negative")]
+    OutOfRange,
+}
+
+impl FromStr for CostModel {
+    type Err = CostModelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (step, turn) = s.split_once(',').ok_or(CostModelError::Format)?;
+        let costs = CostModel {
+            step: step.trim().parse()?,
+            turn: turn.trim().parse()?,
+        };
+
+        match costs.step > 0 && costs.turn >= 0 {
+            true => Ok(costs),
+            false => Err(CostModelError::OutOfRange),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 struct Frame {
     cost: i64,
+
+    /// A lower bound on the cost from here to the nearest end
+    estimate: i64,
     state: State,
-    end: Location,
+
+    /// The state we moved from to get here, if any
+    previous: Option<State>,
 }
 
-impl Frame {
-    fn estimate_overall_cost(&self) -> i64 {
-        let vector_to_end = self.end - self.state.location;
-
-        let distance_cost = vector_to_end.manhattan_length() as i64;
-
-        // Need to make at least one turn to move vertically
-        let turn_cost_1 =
-            if self.end.row != self.state.location.row && self.state.direction.is_horizontal() {
-                1000
-            } else {
-                0
-            };
-
-        // Need to make at least one turn to move horizontally
-        let turn_cost_2 = if self.end.column != self.state.location.column
-            && self.state.direction.is_vertical()
-        {
-            1000
+/// Estimate the cost from `state` to `end`. This never overestimates, for
+/// any cost model: every remaining tile needs a step, and each turn counted
+/// here is one that any route would have to make. Weighted tiles only add
+/// to the real cost, so they don't change that.
+fn estimate_cost_to(state: State, end: Location, costs: CostModel) -> i64 {
+    let vector_to_end = end - state.location;
+    let turn = costs.turn;
+
+    let distance_cost = vector_to_end.manhattan_length() as i64 * costs.step;
+
+    // Need to make at least one turn to move vertically
+    let turn_cost_1 = if end.row != state.location.row && state.direction.is_horizontal() {
+        turn
+    } else {
+        0
+    };
+
+    // Need to make at least one turn to move horizontally
+    let turn_cost_2 = if end.column != state.location.column && state.direction.is_vertical() {
+        turn
+    } else {
+        0
+    };
+
+    let turnaround_cost = if let Some(direction) = vector_to_end.direction() {
+        // If you're pointing exactly the wrong way, then the turn costs
+        // from before didn't apply.
+        if state.direction == direction.reverse() {
+            turn * 2
         } else {
             0
-        };
+        }
+    } else {
+        0
+    };
 
-        let turnaround_cost = if let Some(direction) = vector_to_end.direction() {
-            // If you're pointing exactly the wrong way, then the turn costs
-            // from before didn't apply.
-            if self.state.direction == direction.reverse() {
-                2000
-            } else {
-                0
-            }
-        } else {
-            0
-        };
+    distance_cost + turn_cost_1 + turn_cost_2 + turnaround_cost
+}
 
-        self.cost + distance_cost + turn_cost_1 + turn_cost_2 + turnaround_cost
+impl Frame {
+    /// A frame for `state`, reached at `cost`, with its estimate to the
+    /// nearest of the maze's ends
+    fn new(
+        input: &Input,
+        costs: CostModel,
+        cost: i64,
+        state: State,
+        previous: Option<State>,
+    ) -> Self {
+        let estimate = input
+            .ends
+            .iter()
+            .map(|&end| estimate_cost_to(state, end, costs))
+            .min()
+            .unwrap_or(0);
+
+        Self {
+            cost,
+            estimate,
+            state,
+            previous,
+        }
+    }
+
+    /// The first frame of a search, at the start
+    fn start(input: &Input, costs: CostModel) -> Self {
+        Self::new(input, costs, 0, start_state(input), None)
+    }
+
+    /// The frame after moving from this one in `direction`
+    fn advance(&self, input: &Input, costs: CostModel, direction: Direction) -> Self {
+        Self::new(
+            input,
+            costs,
+            self.cost + input.move_cost(costs, self.state, direction),
+            State {
+                location: self.state.location + direction,
+                direction,
+            },
+            Some(self.state),
+        )
+    }
+
+    /// Estimate the cost of the best route through this frame
+    fn estimate_overall_cost(&self) -> i64 {
+        self.cost + self.estimate
     }
 }
 
@@ -124,139 +334,479 @@ impl PartialEq for Frame {
     }
 }
 
-fn solve_maze(walls: &HashSet<Location>, start: Location, end: Location) -> anyhow::Result<i64> {
-    let mut seen_places = HashSet::new();
+/// An optimal route through the maze
+#[derive(Debug, Clone)]
+pub struct MazePath {
+    pub cost: i64,
 
-    let mut exploration_stack: BinaryHeap<Frame> = BinaryHeap::from([Frame {
-        cost: 0,
-        end,
-        state: State {
-            location: start,
-            direction: Right,
-        },
-    }]);
+    /// Every state along the route, from the start to the end. Turns appear
+    /// as changes in direction between consecutive states.
+    pub states: Vec<State>,
+}
+
+impl MazePath {
+    /// The number of times the route changes direction
+    pub fn turns(&self) -> usize {
+        self.states
+            .windows(2)
+            .filter(|pair| pair[0].direction != pair[1].direction)
+            .count()
+    }
+
+    /// The tiles along the route, from the start to the end
+    pub fn path(&self) -> Path {
+        let mut path: Path = self.states.iter().map(|state| state.location).collect();
+        path.dedup();
+        path
+    }
+}
+
+fn solve_maze(input: &Input, costs: CostModel) -> anyhow::Result<MazePath> {
+    let maze = &input.maze;
+    let mut expanded = Counter::new("day16.expanded");
+    let mut pushes = Counter::new("day16.pushes");
+
+    // The state each visited state was first reached from
+    let mut predecessors: HashMap<State, Option<State>> = HashMap::new();
+
+    let mut exploration_stack: BinaryHeap<Frame> = BinaryHeap::from([Frame::start(input, costs)]);
 
     while let Some(frame) = exploration_stack.pop() {
-        if frame.state.location == end {
-            return Ok(frame.cost);
+        if input.is_end(frame.state.location) {
+            let mut states = vec![frame.state];
+            let mut previous = frame.previous;
+
+            while let Some(state) = previous {
+                states.push(state);
+                previous = predecessors[&state];
+            }
+
+            states.reverse();
+
+            return Ok(MazePath {
+                cost: frame.cost,
+                states,
+            });
         }
 
-        if walls.contains(&frame.state.location) {
+        if !maze.is_open(frame.state.location) {
             continue;
         }
 
-        if seen_places.replace(frame.state).is_some() {
+        if predecessors.contains_key(&frame.state) {
             continue;
         }
 
+        predecessors.insert(frame.state, frame.previous);
+        expanded.increment();
+
         for direction in EACH_DIRECTION {
-            exploration_stack.push(Frame {
-                cost: frame.cost
-                    + if direction == frame.state.direction {
-                        1
-                    } else {
-                        1001
-                    },
-                state: State {
-                    location: frame.state.location + direction,
-                    direction,
-                },
-                end,
-            });
+            pushes.increment();
+            exploration_stack.push(frame.advance(input, costs, direction));
         }
     }
 
     anyhow::bail!("no path found")
 }
 
+/// Find an optimal route through the maze, along with its cost
+pub fn best_path(input: &Input, costs: CostModel) -> anyhow::Result<MazePath> {
+    solve_maze(input, costs)
+}
+
 pub fn part1(input: Input) -> anyhow::Result<i64> {
-    solve_maze(&input.walls, input.start, input.end)
-}
-
-fn count_path_area(
-    end: Location,
-    start: Location,
-    paths: &HashMap<Location, DirectionMap<bool>>,
-) -> usize {
-    let mut explored = HashSet::from([start, end]);
-    let mut unexplored = Vec::from([end]);
-
-    while let Some(location) = unexplored.pop() {
-        if let Some(&routes_into) = paths.get(&location) {
-            unexplored.extend(
-                routes_into
-                    .iter()
-                    .filter(|(_, enabled)| **enabled)
-                    .map(|(direction, _)| location - direction)
-                    .filter(|&neighbor| explored.replace(neighbor).is_none()),
-            );
-        }
-    }
+    let costs = CostModel::from_runner()?;
+    print_requested_render(&input, costs)?;
 
-    explored.len()
+    let path = best_path(&input, costs)?;
+    visualize::show(&MazeRoute {
+        input: &input,
+        path: &path,
+        seats: None,
+    })?;
+
+    Ok(path.cost)
 }
 
-fn count_maze_route_area(
-    walls: &HashSet<Location>,
-    start: Location,
-    end: Location,
-) -> anyhow::Result<usize> {
-    let mut seen_places = HashSet::new();
-    let mut valid_paths: HashMap<Location, DirectionMap<bool>> = HashMap::new();
+/// Count every tile on any optimal route, by searching outward from the
+/// start and recording, for each state, every predecessor that reaches it at
+/// its best cost. Once the end has been reached and no cheaper frames remain,
+/// walk those predecessors back from the end.
+fn count_maze_route_area(input: &Input, costs: CostModel) -> anyhow::Result<usize> {
+    let maze = &input.maze;
+    let mut expanded = Counter::new("day16.expanded");
+    let mut pushes = Counter::new("day16.pushes");
+
+    // The best cost of each visited state, and every state it can be reached
+    // from at that cost
+    let mut best: HashMap<State, (i64, Vec<State>)> = HashMap::new();
+    let mut end_states = Vec::new();
     let mut final_cost = None;
 
-    let mut exploration_stack: BinaryHeap<Frame> = BinaryHeap::from([Frame {
-        cost: 0,
-        end,
-        state: State {
-            location: start,
-            direction: Right,
-        },
-    }]);
+    let mut exploration_stack: BinaryHeap<Frame> = BinaryHeap::from([Frame::start(input, costs)]);
 
     while let Some(frame) = exploration_stack.pop() {
-        if let Some(final_cost) = final_cost {
-            if frame.cost > final_cost {
-                return Ok(count_path_area(end, start, &valid_paths));
-            }
+        if final_cost.is_some_and(|final_cost| frame.cost > final_cost) {
+            continue;
         }
 
-        if walls.contains(&frame.state.location) {
+        if !maze.is_open(frame.state.location) {
             continue;
         }
 
-        valid_paths.entry(frame.state.location).or_default()[frame.state.direction] = true;
+        match best.get_mut(&frame.state) {
+            Some((cost, predecessors)) => {
+                if frame.cost == *cost {
+                    predecessors.extend(frame.previous);
+                }
 
-        if frame.state.location == end {
-            final_cost = Some(frame.cost);
-            continue;
+                continue;
+            }
+            None => {
+                best.insert(frame.state, (frame.cost, Vec::from_iter(frame.previous)));
+            }
         }
 
-        if seen_places.replace(frame.state).is_some() {
+        if input.is_end(frame.state.location) {
+            final_cost.get_or_insert(frame.cost);
+            end_states.push(frame.state);
             continue;
         }
 
+        expanded.increment();
+
         for direction in EACH_DIRECTION {
-            exploration_stack.push(Frame {
-                cost: frame.cost
-                    + if direction == frame.state.direction {
-                        1
-                    } else {
-                        1001
-                    },
-                state: State {
-                    location: frame.state.location + direction,
-                    direction,
-                },
-                end,
-            });
+            pushes.increment();
+            exploration_stack.push(frame.advance(input, costs, direction));
         }
     }
 
-    anyhow::bail!("no path found")
+    let final_cost = final_cost.context("no path found")?;
+
+    let mut explored: HashSet<State> = end_states
+        .into_iter()
+        .filter(|state| best[state].0 == final_cost)
+        .collect();
+    let mut unexplored: Vec<State> = explored.iter().copied().collect();
+
+    while let Some(state) = unexplored.pop() {
+        unexplored.extend(
+            best[&state]
+                .1
+                .iter()
+                .copied()
+                .filter(|&previous| explored.insert(previous)),
+        );
+    }
+
+    Ok(explored
+        .iter()
+        .map(|state| state.location)
+        .collect::<HashSet<_>>()
+        .len())
+}
+
+/// Every state reachable in a single move from `state`, with its cost
+fn forward_moves(
+    input: &Input,
+    state: State,
+    costs: CostModel,
+) -> impl Iterator<Item = (State, i64)> + '_ {
+    EACH_DIRECTION
+        .into_iter()
+        .map(move |direction| State {
+            location: state.location + direction,
+            direction,
+        })
+        .filter(|next| input.maze.is_open(next.location))
+        .map(move |next| (next, input.move_cost(costs, state, next.direction)))
+}
+
+/// Every state from which `state` can be reached in a single move, with the
+/// cost of that move
+fn backward_moves(
+    input: &Input,
+    state: State,
+    costs: CostModel,
+) -> impl Iterator<Item = (State, i64)> + '_ {
+    let previous = state.location - state.direction;
+
+    EACH_DIRECTION
+        .into_iter()
+        .filter(move |_| input.maze.is_open(previous))
+        .map(move |direction| State {
+            location: previous,
+            direction,
+        })
+        .map(move |prev| (prev, input.move_cost(costs, prev, state.direction)))
+}
+
+/// Every state at any of the ends
+fn end_states(input: &Input) -> impl Iterator<Item = State> + '_ {
+    input.ends.iter().flat_map(|&location| {
+        EACH_DIRECTION.map(|direction| State {
+            location,
+            direction,
+        })
+    })
+}
+
+fn start_state(input: &Input) -> State {
+    State {
+        location: input.maze.start(),
+        direction: Right,
+    }
+}
+
+/// The minimal cost to reach every state from the start, along with the
+/// cost of the best route to any end
+fn forward_distances(
+    input: &Input,
+    costs: CostModel,
+) -> anyhow::Result<(HashMap<State, i64>, i64)> {
+    let distances = dijkstra::distances([start_state(input)], |&state| {
+        forward_moves(input, state, costs)
+    });
+
+    let best = end_states(input)
+        .filter_map(|state| distances.get(&state).copied())
+        .min()
+        .context("no path found")?;
+
+    Ok((distances, best))
+}
+
+/// Count the number of distinct minimal-cost routes from the start to the
+/// end. States are processed in order of their distance from the start;
+/// every move has a positive cost, so all of a state's optimal predecessors
+/// are processed (and have their final route counts) before it is.
+pub fn count_best_paths(input: &Input, costs: CostModel) -> anyhow::Result<u64> {
+    let (distances, best) = forward_distances(input, costs)?;
+
+    let mut states: Vec<(State, i64)> = distances
+        .iter()
+        .map(|(&state, &cost)| (state, cost))
+        .filter(|&(_, cost)| cost <= best)
+        .collect();
+
+    states.sort_unstable_by_key(|&(_, cost)| cost);
+
+    let mut routes: HashMap<State, u64> = HashMap::from([(start_state(input), 1)]);
+    let mut total: u64 = 0;
+
+    for (state, cost) in states {
+        let state_routes = routes.get(&state).copied().unwrap_or(0);
+
+        // Routes end as soon as they reach the end
+        if input.is_end(state.location) {
+            if cost == best {
+                total = total
+                    .checked_add(state_routes)
+                    .context("too many optimal routes to count")?;
+            }
+
+            continue;
+        }
+
+        for (next, step) in forward_moves(input, state, costs) {
+            if distances.get(&next) == Some(&(cost + step)) {
+                let count = routes.entry(next).or_default();
+                *count = count
+                    .checked_add(state_routes)
+                    .context("too many optimal routes to count")?;
+            }
+        }
+    }
+
+    Ok(total)
+}
+
+/// Count the tiles that are part of at least one optimal route. A state is
+/// on an optimal route exactly when its distance from the start plus its
+/// distance to the end is the optimal cost, so this computes both distance
+/// maps and checks every state.
+pub fn best_seats(input: &Input, costs: CostModel) -> anyhow::Result<HashSet<Location>> {
+    let (forward, best) = forward_distances(input, costs)?;
+
+    let backward = dijkstra::distances(end_states(input), |&state| {
+        backward_moves(input, state, costs)
+    });
+
+    let seats: HashSet<Location> = forward
+        .iter()
+        .filter(|&(state, &cost)| {
+            backward
+                .get(state)
+                .is_some_and(|&remaining| cost + remaining == best)
+        })
+        .map(|(state, _)| state.location)
+        .collect();
+
+    if dump::enabled() {
+        let visited: HashMap<Location, char> = forward
+            .keys()
+            .map(|state| (state.location, 'o'))
+            .chain(seats.iter().map(|&location| (location, 'O')))
+            .collect();
+
+        // The visited set is marked with `o`, and the best seats within it
+        // with `O`
+        dump::artifact("visited", maze_frame(input, &visited));
+    }
+
+    Ok(seats)
+}
+
+/// Draw the maze, with `marks` drawn over the open tiles they cover
+fn maze_frame(input: &Input, marks: &HashMap<Location, char>) -> visualize::Frame {
+    let maze = &input.maze;
+    let bounds = maze.bounds();
+
+    visualize::Frame::from_fn(
+        bounds.num_rows(),
+        bounds.num_columns(),
+        |location| match marks.get(&location) {
+            Some(&mark) if !maze.is_wall(location) => mark,
+            _ => input.cell(location),
+        },
+    )
 }
 
+/// See `maze_frame`
+pub fn render_maze(input: &Input, marks: &HashMap<Location, char>) -> String {
+    maze_frame(input, marks).to_string()
+}
+
+/// An optimal route, drawn through the maze with arrows, optionally along
+/// with every seat on any optimal route
+pub struct MazeRoute<'a> {
+    pub input: &'a Input,
+    pub path: &'a MazePath,
+    pub seats: Option<&'a HashSet<Location>>,
+}
+
+impl Visualize for MazeRoute<'_> {
+    fn frames(&self) -> impl Iterator<Item = visualize::Frame> + '_ {
+        let mut marks: HashMap<Location, char> = self
+            .seats
+            .into_iter()
+            .flatten()
+            .map(|&location| (location, 'O'))
+            .collect();
+
+        marks.extend(route_marks(self.path));
+
+        let frame = maze_frame(self.input, &marks);
+        iter::once(frame.with_title(format!(
+            "score {}, {} turns",
+            self.path.cost,
+            self.path.turns()
+        )))
+    }
+
+    /// Draw the route as a line, rather than with arrows, over the shaded
+    /// best seats
+    fn svg(&self) -> Option<String> {
+        let frame = maze_frame(self.input, &HashMap::new());
+
+        let document = svg::Document::new(&frame, svg::char_style)
+            .highlight(self.seats.into_iter().flatten().copied(), "#f9a825")
+            .path(&self.path.path(), "#c62828");
+
+        Some(document.render())
+    }
+}
+
+/// Mark each step of a route with an arrow showing the direction it faces
+fn route_marks(path: &MazePath) -> HashMap<Location, char> {
+    path.path().arrows().collect()
+}
+
+/// Draw the maze to stderr, as requested with `--param render=seats` (mark
+/// every tile on any optimal route with `O`, like the puzzle's illustration)
+/// or `--param render=path` (draw a single optimal route with arrows).
+fn print_requested_render(input: &Input, costs: CostModel) -> anyhow::Result<()> {
+    let marks: HashMap<Location, char> = match params::get_raw("render") {
+        None => return Ok(()),
+        Some("seats") => best_seats(input, costs)?
+            .into_iter()
+            .map(|location| (location, 'O'))
+            .collect(),
+        Some("path") => route_marks(&best_path(input, costs)?),
+        Some(render) => anyhow::bail!("unknown render mode {render:?}; expected seats or path"),
+    };
+
+    eprint!("{}", render_maze(input, &marks));
+    Ok(())
+}
+
+/// Count the best seats with `--param seats=distances|search`. `distances`
+/// (the default) compares forward and backward distance maps; `search` is
+/// the original single-pass search, kept for cross-validation. With `--param
+/// routes=true`, also print the number of distinct optimal routes to stderr.
 pub fn part2(input: Input) -> anyhow::Result<usize> {
-    // First, fill in dead ends.
-    count_maze_route_area(&input.walls, input.start, input.end)
+    let costs = CostModel::from_runner()?;
+    print_requested_render(&input, costs)?;
+
+    if params::get_or("routes", false)? {
+        eprintln!(
+            "{} distinct optimal routes",
+            count_best_paths(&input, costs)?
+        );
+    }
+
+    if visualize::enabled() {
+        visualize::show(&MazeRoute {
+            input: &input,
+            path: &best_path(&input, costs)?,
+            seats: Some(&best_seats(&input, costs)?),
+        })?;
+    }
+
+    validate::select(
+        "seats",
+        &[
+            ("distances", &|| {
+                best_seats(&input, costs).map(|seats| seats.len())
+            }),
+            ("search", &|| count_maze_route_area(&input, costs)),
+        ],
+    )
+}
+
+solver!(Input, i64, usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+###############
+#.......#....E#
+#.#.###.#.###.#
+#.....#.#...#.#
+#.###.#####.#.#
+#.#.#.......#.#
+#.#.#####.###.#
+#...........#.#
+###.#.#####.#.#
+#...#.....#.#.#
+#.#.#.###.#.#.#
+#.....#...#.#.#
+#.###.#.#.#.#.#
+#S..#.....#...#
+###############
+";
+
+    /// The puzzle's first example: the best route makes 7 turns and takes
+    /// 36 steps
+    #[test]
+    fn example_best_path() {
+        let input = Input::try_from(EXAMPLE).expect("example parses");
+        let path = best_path(&input, CostModel::STANDARD).expect("the maze has a route");
+
+        assert_eq!(path.cost, 7036);
+        assert_eq!(path.turns(), 7);
+        assert_eq!(path.path().len(), 37);
+    }
 }
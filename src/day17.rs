@@ -1,8 +1,17 @@
-use std::fmt::Display;
+//! Day 17: Chronospatial Computer
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    fs::File,
+    io::{self, BufRead, BufWriter, Write},
+    iter,
+    ops::ControlFlow,
+};
 
+use anyhow::Context;
 use enum_map::{EnumMap, enum_map};
-use itertools::{EitherOrBoth, Itertools};
-use joinery::{Joinable, separators::Comma};
+use joinery::{JoinableIterator, separators::Comma};
 use nom::{
     Parser,
     branch::alt,
@@ -13,16 +22,42 @@ use nom_supreme::{
     ParserExt, error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated,
     tag::complete::tag,
 };
-
-use crate::{library::ITResult, parser};
+use serde::Serialize;
+
+use crate::{
+    library::{
+        ITResult,
+        answer::Answer,
+        cancel,
+        gpu::{self, Offload},
+        params,
+        progress::{self, ProgressBar},
+        solver::solver,
+        stream::{Streaming, Updates},
+        terminal,
+    },
+    parser,
+};
 
 mod cpu {
-    use std::fmt::Display;
+    use std::{
+        collections::BTreeMap,
+        fmt::{Debug, Display},
+        io::{self, Write},
+        num::ParseIntError,
+        ops::{BitXor, ControlFlow},
+        str::FromStr,
+    };
 
     use enum_map::{Enum, EnumMap};
+    use joinery::JoinableIterator;
     use lazy_format::lazy_format;
+    use serde::{Deserialize, Serialize, de::DeserializeOwned};
+    use thiserror::Error;
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    /// Codes are serialized as their numeric values
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(into = "u8", try_from = "u8")]
     #[repr(u8)]
     pub enum Code {
         Zero,
@@ -40,19 +75,18 @@ mod cpu {
             self as usize
         }
 
-        fn combo(self, registers: &EnumMap<Register, usize>) -> usize {
+        /// The value of this code as a combo operand, or None if it's the
+        /// reserved operand 7
+        fn combo<W: Word>(self, registers: &EnumMap<Register, W>) -> Option<W> {
             use Code::*;
 
-            match self {
-                Zero => 0,
-                One => 1,
-                Two => 2,
-                Three => 3,
+            Some(match self {
+                Zero | One | Two | Three => W::from_code(self),
                 Four => registers[Register::A],
                 Five => registers[Register::B],
                 Six => registers[Register::C],
-                Seven => panic!("invalid combo"),
-            }
+                Seven => return None,
+            })
         }
 
         fn from_value(value: usize) -> Self {
@@ -89,6 +123,95 @@ mod cpu {
         }
     }
 
+    impl From<Code> for u8 {
+        fn from(code: Code) -> Self {
+            code as u8
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, Error)]
+    #[error("invalid code {0}; must be 0 through 7")]
+    pub struct InvalidCode(u8);
+
+    impl TryFrom<u8> for Code {
+        type Error = InvalidCode;
+
+        fn try_from(value: u8) -> Result<Self, Self::Error> {
+            match value {
+                0..=7 => Ok(Self::from_value(value.into())),
+                _ => Err(InvalidCode(value)),
+            }
+        }
+    }
+
+    /// An unsigned integer type for the machine's registers. Wherever a
+    /// register value becomes a code (`bst` and `out`), it's truncated to its
+    /// low 3 bits, and shifting right by at least the type's width leaves 0.
+    pub trait Word:
+        Copy
+        + Eq
+        + Default
+        + Debug
+        + Display
+        + FromStr<Err = ParseIntError>
+        + BitXor<Output = Self>
+        + Into<u128>
+        + Serialize
+        + DeserializeOwned
+        + 'static
+    {
+        const BITS: u32;
+        const ZERO: Self;
+
+        fn from_code(code: Code) -> Self;
+
+        /// The low 3 bits of this value, as a code
+        fn low_code(self) -> Code;
+
+        /// `self >> rhs`, or 0 if that would shift out every bit
+        fn shr_or_zero(self, rhs: Self) -> Self;
+
+        /// Append a 3-bit digit to this value: `self * 8 + digit`, or None if
+        /// that overflows
+        fn push_digit(self, digit: u8) -> Option<Self>;
+
+        fn from_u128(value: u128) -> Option<Self>;
+    }
+
+    macro_rules! primitive_word {
+        ($($type:ty)*) => {$(
+            impl Word for $type {
+                const BITS: u32 = <$type>::BITS;
+                const ZERO: Self = 0;
+
+                fn from_code(code: Code) -> Self {
+                    code as $type
+                }
+
+                fn low_code(self) -> Code {
+                    Code::from_value((self & 0b111) as usize)
+                }
+
+                fn shr_or_zero(self, rhs: Self) -> Self {
+                    u32::try_from(rhs)
+                        .ok()
+                        .and_then(|rhs| self.checked_shr(rhs))
+                        .unwrap_or(0)
+                }
+
+                fn push_digit(self, digit: u8) -> Option<Self> {
+                    self.checked_mul(8)?.checked_add(digit.into())
+                }
+
+                fn from_u128(value: u128) -> Option<Self> {
+                    value.try_into().ok()
+                }
+            }
+        )*};
+    }
+
+    primitive_word! {u64 u128}
+
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     enum Instruction {
         Adv,
@@ -101,7 +224,54 @@ mod cpu {
         Cdv,
     }
 
+    /// How an instruction interprets its operand
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum OperandKind {
+        Literal,
+        Combo,
+        Ignored,
+    }
+
     impl Instruction {
+        fn mnemonic(self) -> &'static str {
+            match self {
+                Self::Adv => "adv",
+                Self::Bxl => "bxl",
+                Self::Bst => "bst",
+                Self::Jnz => "jnz",
+                Self::Bxc => "bxc",
+                Self::Out => "out",
+                Self::Bdv => "bdv",
+                Self::Cdv => "cdv",
+            }
+        }
+
+        fn operand_kind(self) -> OperandKind {
+            match self {
+                Self::Bxl | Self::Jnz => OperandKind::Literal,
+                Self::Adv | Self::Bst | Self::Out | Self::Bdv | Self::Cdv => OperandKind::Combo,
+                Self::Bxc => OperandKind::Ignored,
+            }
+        }
+
+        fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+            Some(match mnemonic {
+                "adv" => Self::Adv,
+                "bxl" => Self::Bxl,
+                "bst" => Self::Bst,
+                "jnz" => Self::Jnz,
+                "bxc" => Self::Bxc,
+                "out" => Self::Out,
+                "bdv" => Self::Bdv,
+                "cdv" => Self::Cdv,
+                _ => return None,
+            })
+        }
+
+        fn code(self) -> Code {
+            Code::from_value(self as usize)
+        }
+
         fn from_code(code: Code) -> Self {
             match code {
                 Code::Zero => Self::Adv,
@@ -116,45 +286,250 @@ mod cpu {
         }
     }
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Enum, Serialize, Deserialize)]
     pub enum Register {
         A,
         B,
         C,
     }
 
+    /// Why `Machine::run` returned
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum RunEnd {
+        /// The instruction pointer moved past the end of the program
+        Halted,
+
+        /// The output sink asked the machine to stop
+        Stopped,
+
+        /// The machine executed as many instructions as it was allowed to
+        StepLimit,
+
+        /// The machine couldn't execute an instruction
+        Fault(MachineError),
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+    pub enum MachineError {
+        #[error("reserved combo operand 7 used by the instruction at address {address}")]
+        ReservedOperand { address: usize },
+    }
+
+    /// Everything about a single run of a machine
+    #[derive(Debug, Clone)]
+    pub struct RunOutcome {
+        pub end: RunEnd,
+
+        /// The number of instructions executed during this run
+        pub steps: usize,
+
+        pub outputs: Vec<Code>,
+    }
+
+    /// Somewhere for a machine's outputs to go. A sink can stop the machine
+    /// early by returning `Break`.
+    pub trait OutputSink {
+        fn output(&mut self, code: Code) -> ControlFlow<()>;
+    }
+
+    impl<S: OutputSink + ?Sized> OutputSink for &mut S {
+        fn output(&mut self, code: Code) -> ControlFlow<()> {
+            (**self).output(code)
+        }
+    }
+
+    /// Collect every output
+    impl OutputSink for Vec<Code> {
+        fn output(&mut self, code: Code) -> ControlFlow<()> {
+            self.push(code);
+            ControlFlow::Continue(())
+        }
+    }
+
+    /// Send every output to both sinks, stopping if either one stops
+    impl<A: OutputSink, B: OutputSink> OutputSink for (A, B) {
+        fn output(&mut self, code: Code) -> ControlFlow<()> {
+            self.0.output(code)?;
+            self.1.output(code)
+        }
+    }
+
+    /// Write outputs to a writer as they happen, separated by commas. Stops
+    /// the machine if a write fails; the error is kept in `error`.
+    #[derive(Debug)]
+    pub struct WriteSink<W> {
+        writer: W,
+        first: bool,
+        pub error: Option<io::Error>,
+    }
+
+    impl<W: Write> WriteSink<W> {
+        pub fn new(writer: W) -> Self {
+            Self {
+                writer,
+                first: true,
+                error: None,
+            }
+        }
+    }
+
+    impl<W: Write> OutputSink for WriteSink<W> {
+        fn output(&mut self, code: Code) -> ControlFlow<()> {
+            let separator = if self.first { "" } else { "," };
+            self.first = false;
+
+            match write!(self.writer, "{separator}{code}").and_then(|()| self.writer.flush()) {
+                Ok(()) => ControlFlow::Continue(()),
+                Err(err) => {
+                    self.error = Some(err);
+                    ControlFlow::Break(())
+                }
+            }
+        }
+    }
+
+    /// Compare outputs against an expected sequence, stopping the machine at
+    /// the first mismatch (or as soon as it outputs too much)
+    #[derive(Debug, Clone)]
+    pub struct ExpectSink<'a> {
+        expected: &'a [Code],
+        matched: usize,
+        mismatch: bool,
+    }
+
+    impl<'a> ExpectSink<'a> {
+        pub fn new(expected: &'a [Code]) -> Self {
+            Self {
+                expected,
+                matched: 0,
+                mismatch: false,
+            }
+        }
+
+        /// True if every output so far matched, and all of the expected
+        /// outputs were produced
+        pub fn is_exact_match(&self) -> bool {
+            !self.mismatch && self.matched == self.expected.len()
+        }
+    }
+
+    impl OutputSink for ExpectSink<'_> {
+        fn output(&mut self, code: Code) -> ControlFlow<()> {
+            match self.expected.get(self.matched) {
+                Some(&expected) if expected == code => {
+                    self.matched += 1;
+                    ControlFlow::Continue(())
+                }
+                _ => {
+                    self.mismatch = true;
+                    ControlFlow::Break(())
+                }
+            }
+        }
+    }
+
     #[derive(Debug, Clone, Copy)]
     pub enum MachineState {
         Output(Code),
         Halt,
+
+        /// The instruction at the instruction pointer couldn't be executed.
+        /// The machine is stuck, and will fault again if stepped.
+        Fault(MachineError),
     }
 
-    impl MachineState {
-        pub fn output(self) -> Option<Code> {
-            match self {
-                Self::Output(code) => Some(code),
-                Self::Halt => None,
+    /// A record of a single executed instruction, from `Machine::run_traced`.
+    /// Displays as one line: the step number, the instruction's address, its
+    /// mnemonic and operand (with the value of a register combo operand, as
+    /// it was when the instruction ran), the registers after it ran, and the
+    /// output, if there was one.
+    #[derive(Debug, Clone, Copy)]
+    pub struct TraceStep<W> {
+        pub step: usize,
+        pub address: usize,
+        instruction: Instruction,
+        code: Code,
+        operand: Option<W>,
+        pub registers: EnumMap<Register, W>,
+        pub output: Option<Code>,
+    }
+
+    impl<W: Word> Display for TraceStep<W> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let operand = match self.instruction.operand_kind() {
+                OperandKind::Literal => self.code.describe_literal().to_string(),
+                OperandKind::Combo => match (self.code, self.operand) {
+                    (Code::Four | Code::Five | Code::Six, Some(value)) => {
+                        format!("{}={value}", self.code.describe_combo())
+                    }
+                    _ => self.code.describe_combo().to_string(),
+                },
+                OperandKind::Ignored => String::new(),
+            };
+
+            write!(
+                f,
+                "{:>6} ip {:>3}: {} {operand:<8} ; A={} B={} C={}",
+                self.step,
+                self.address,
+                self.instruction.mnemonic(),
+                self.registers[Register::A],
+                self.registers[Register::B],
+                self.registers[Register::C],
+            )?;
+
+            match self.output {
+                Some(code) => write!(f, " out {code}"),
+                None => Ok(()),
             }
         }
     }
 
+    /// A saved copy of a machine's state (but not its program), along with
+    /// everything it had output up to that point. See `Machine::snapshot`.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Snapshot<W> {
+        pub registers: EnumMap<Register, W>,
+        pub instruction_pointer: usize,
+        pub steps: usize,
+        pub outputs: Vec<Code>,
+    }
+
     #[derive(Debug, Clone, Copy, Default)]
-    pub struct Machine<'a> {
-        registers: EnumMap<Register, usize>,
+    pub struct Machine<'a, W> {
+        registers: EnumMap<Register, W>,
         program: &'a [Code],
         instruction_pointer: usize,
+
+        /// The number of instructions executed so far
+        steps: usize,
+
+        /// If given, `run` gives up after this many instructions
+        step_limit: Option<usize>,
     }
 
-    impl<'a> Machine<'a> {
-        pub fn new(registers: EnumMap<Register, usize>, program: &'a [Code]) -> Self {
+    impl<'a, W: Word> Machine<'a, W> {
+        pub fn new(registers: EnumMap<Register, W>, program: &'a [Code]) -> Self {
             Self {
                 registers,
                 program,
                 instruction_pointer: 0,
+                steps: 0,
+                step_limit: None,
             }
         }
 
-        pub fn reinit(&mut self, value: usize) {
+        /// Limit the total number of instructions `run` will execute, so that
+        /// programs that loop forever can be stopped
+        pub fn with_step_limit(self, step_limit: Option<usize>) -> Self {
+            Self { step_limit, ..self }
+        }
+
+        pub fn steps(&self) -> usize {
+            self.steps
+        }
+
+        pub fn reinit(&mut self, value: W) {
             self.registers[Register::A] = value;
         }
 
@@ -166,15 +541,11 @@ mod cpu {
             Some((instruction, param))
         }
 
-        fn div(&mut self, code: Code, dest: Register) {
-            let lhs = self.registers[Register::A];
-            let rhs = code.combo(&self.registers);
-
-            let out = lhs >> rhs;
-            self.registers[dest] = out;
+        fn div(&mut self, rhs: W, dest: Register) {
+            self.registers[dest] = self.registers[Register::A].shr_or_zero(rhs);
         }
 
-        fn xor_with_b(&mut self, value: usize) {
+        fn xor_with_b(&mut self, value: W) {
             let lhs = self.registers[Register::B];
             let out = lhs ^ value;
             self.registers[Register::B] = out;
@@ -185,67 +556,561 @@ mod cpu {
                 return Some(MachineState::Halt);
             };
 
+            // Only evaluated for instructions that take a combo operand, so
+            // that the reserved operand is only an error where it's used
+            let combo = match instruction.operand_kind() {
+                OperandKind::Combo => match param.combo(&self.registers) {
+                    Some(value) => value,
+                    None => {
+                        return Some(MachineState::Fault(MachineError::ReservedOperand {
+                            address: self.instruction_pointer,
+                        }));
+                    }
+                },
+                OperandKind::Literal | OperandKind::Ignored => W::ZERO,
+            };
+
             let mut out = None;
 
             match instruction {
-                Instruction::Adv => self.div(param, Register::A),
-                Instruction::Bdv => self.div(param, Register::B),
-                Instruction::Cdv => self.div(param, Register::C),
+                Instruction::Adv => self.div(combo, Register::A),
+                Instruction::Bdv => self.div(combo, Register::B),
+                Instruction::Cdv => self.div(combo, Register::C),
 
-                Instruction::Bxl => self.xor_with_b(param.literal()),
+                Instruction::Bxl => self.xor_with_b(W::from_code(param)),
                 Instruction::Bxc => self.xor_with_b(self.registers[Register::C]),
 
-                Instruction::Bst => {
-                    self.registers[Register::B] = param.combo(&self.registers) & 0b111
-                }
+                Instruction::Bst => self.registers[Register::B] = W::from_code(combo.low_code()),
 
                 // Handled later, during IP update
                 Instruction::Jnz => {}
                 Instruction::Out => {
-                    out = Some(Code::from_value(param.combo(&self.registers)));
+                    out = Some(combo.low_code());
                 }
             }
 
             self.instruction_pointer = match instruction {
-                Instruction::Jnz if self.registers[Register::A] != 0 => param.literal(),
+                Instruction::Jnz if self.registers[Register::A] != W::ZERO => param.literal(),
                 _ => self.instruction_pointer + 2,
             };
 
+            self.steps += 1;
+
             out.map(MachineState::Output)
         }
 
-        pub fn run_until_state(&mut self) -> MachineState {
+        /// Run the machine, sending each output to `sink`, until it halts,
+        /// faults, reaches its step limit, or the sink asks it to stop
+        pub fn run(&mut self, sink: &mut impl OutputSink) -> RunEnd {
+            loop {
+                if self.step_limit.is_some_and(|limit| self.steps >= limit) {
+                    break RunEnd::StepLimit;
+                }
+
+                match self.step() {
+                    Some(MachineState::Halt) => break RunEnd::Halted,
+                    Some(MachineState::Fault(error)) => break RunEnd::Fault(error),
+                    Some(MachineState::Output(code)) if sink.output(code).is_break() => {
+                        break RunEnd::Stopped;
+                    }
+                    None | Some(MachineState::Output(_)) => {}
+                }
+            }
+        }
+
+        /// Like `run`, but also send a `TraceStep` for each executed
+        /// instruction to `trace`, which can stop the machine by returning
+        /// `Break`. This is slower than `run`, so it's kept separate.
+        pub fn run_traced(
+            &mut self,
+            sink: &mut impl OutputSink,
+            mut trace: impl FnMut(&TraceStep<W>) -> ControlFlow<()>,
+        ) -> RunEnd {
             loop {
-                if let Some(state) = self.step() {
-                    break state;
+                if self.step_limit.is_some_and(|limit| self.steps >= limit) {
+                    break RunEnd::StepLimit;
                 }
+
+                let address = self.instruction_pointer;
+                let loaded = self
+                    .load_instruction()
+                    .map(|(instruction, code)| (instruction, code, code.combo(&self.registers)));
+
+                let state = self.step();
+
+                if let (Some((instruction, code, operand)), None | Some(MachineState::Output(_))) =
+                    (loaded, state)
+                {
+                    let step = TraceStep {
+                        step: self.steps,
+                        address,
+                        instruction,
+                        code,
+                        operand,
+                        registers: self.registers,
+                        output: match state {
+                            Some(MachineState::Output(code)) => Some(code),
+                            _ => None,
+                        },
+                    };
+
+                    if trace(&step).is_break() {
+                        break RunEnd::Stopped;
+                    }
+                }
+
+                match state {
+                    Some(MachineState::Halt) => break RunEnd::Halted,
+                    Some(MachineState::Fault(error)) => break RunEnd::Fault(error),
+                    Some(MachineState::Output(code)) if sink.output(code).is_break() => {
+                        break RunEnd::Stopped;
+                    }
+                    None | Some(MachineState::Output(_)) => {}
+                }
+            }
+        }
+
+        /// Run the machine until it stops for any reason, and collect
+        /// everything about the run
+        pub fn run_to_outcome(&mut self) -> RunOutcome {
+            let start = self.steps;
+            let mut outputs = Vec::new();
+            let end = self.run(&mut outputs);
+
+            RunOutcome {
+                end,
+                steps: self.steps - start,
+                outputs,
             }
         }
 
-        pub fn run_iter(&mut self) -> impl Iterator<Item = Code> {
-            std::iter::from_fn(move || self.run_until_state().output())
+        pub fn instruction_pointer(&self) -> usize {
+            self.instruction_pointer
         }
 
-        #[expect(dead_code)]
-        pub fn describe(&self) -> impl Display {
-            lazy_format!(
-                ("{}\n", lazy_format!(match (Instruction::from_code(*instruction)) {
-                    Instruction::Adv => ("A >> {} -> A", code.describe_combo()),
-                    Instruction::Bdv => ("A >> {} -> B", code.describe_combo()),
-                    Instruction::Cdv => ("A >> {} -> C", code.describe_combo()),
+        pub fn registers(&self) -> &EnumMap<Register, W> {
+            &self.registers
+        }
 
-                    Instruction::Bxl => ("B ^ {} -> B", code.describe_literal()),
-                    Instruction::Bxc => "B ^ C -> B",
+        pub fn set_register(&mut self, register: Register, value: W) {
+            self.registers[register] = value;
+        }
 
-                    Instruction::Bst => ("{} & 0b111 -> B", code.describe_combo()),
+        /// Save the machine's current state. The machine doesn't keep track
+        /// of its own output, so the caller provides the output so far.
+        pub fn snapshot(&self, outputs: &[Code]) -> Snapshot<W> {
+            Snapshot {
+                registers: self.registers,
+                instruction_pointer: self.instruction_pointer,
+                steps: self.steps,
+                outputs: outputs.to_vec(),
+            }
+        }
 
-                    Instruction::Jnz => ("Jump to {} if A != 0", code.describe_literal()),
-                    Instruction::Out => ("Output {}", code.describe_combo()),
-                })
-                ) for [instruction, code] in self.program.array_chunks()
-            )
+        /// Return the machine to a saved state. The snapshot's outputs are
+        /// left for the caller to restore.
+        pub fn restore(&mut self, snapshot: &Snapshot<W>) {
+            self.registers = snapshot.registers;
+            self.instruction_pointer = snapshot.instruction_pointer;
+            self.steps = snapshot.steps;
+        }
+
+        /// Describe the instruction that will run next, or None if the
+        /// machine has halted
+        pub fn describe_next(&self) -> Option<impl Display> {
+            self.load_instruction()
+                .map(|(instruction, code)| describe_instruction(instruction, code))
+        }
+
+        /// Check if running a copy of this machine with register A set to `a`
+        /// outputs exactly `expected`
+        pub fn outputs_exactly(mut self, a: W, expected: &[Code]) -> bool {
+            self.reinit(a);
+
+            let mut sink = ExpectSink::new(expected);
+            self.run(&mut sink) == RunEnd::Halted && sink.is_exact_match()
+        }
+    }
+
+    /// A combo operand, resolved when a program is compiled
+    #[derive(Debug, Clone, Copy)]
+    enum Operand<W> {
+        Literal(W),
+        Register(Register),
+    }
+
+    impl<W: Word> Operand<W> {
+        fn new(code: Code) -> Option<Self> {
+            Some(match code {
+                Code::Four => Self::Register(Register::A),
+                Code::Five => Self::Register(Register::B),
+                Code::Six => Self::Register(Register::C),
+                Code::Seven => return None,
+                code => Self::Literal(W::from_code(code)),
+            })
+        }
+
+        fn get(self, registers: &EnumMap<Register, W>) -> W {
+            match self {
+                Self::Literal(value) => value,
+                Self::Register(register) => registers[register],
+            }
+        }
+    }
+
+    type CompiledInstruction<W> = Box<dyn Fn(&mut EnumMap<Register, W>) -> Option<Code>>;
+
+    fn compile_instruction<W: Word>(
+        instruction: Instruction,
+        code: Code,
+    ) -> CompiledInstruction<W> {
+        use Register::*;
+
+        // Only used by instructions that take a combo operand; the caller has
+        // already rejected the reserved operand for those.
+        let combo = Operand::new(code).unwrap_or(Operand::Literal(W::ZERO));
+        let literal = W::from_code(code);
+
+        match instruction {
+            Instruction::Adv => Box::new(move |r| {
+                r[A] = r[A].shr_or_zero(combo.get(r));
+                None
+            }),
+            Instruction::Bdv => Box::new(move |r| {
+                r[B] = r[A].shr_or_zero(combo.get(r));
+                None
+            }),
+            Instruction::Cdv => Box::new(move |r| {
+                r[C] = r[A].shr_or_zero(combo.get(r));
+                None
+            }),
+            Instruction::Bxl => Box::new(move |r| {
+                r[B] = r[B] ^ literal;
+                None
+            }),
+            Instruction::Bxc => Box::new(|r| {
+                r[B] = r[B] ^ r[C];
+                None
+            }),
+            Instruction::Bst => Box::new(move |r| {
+                r[B] = W::from_code(combo.get(r).low_code());
+                None
+            }),
+            Instruction::Out => Box::new(move |r| Some(combo.get(r).low_code())),
+            Instruction::Jnz => Box::new(|_| None),
+        }
+    }
+
+    /// A program compiled to a native closure, for programs with the usual
+    /// shape: a straight-line loop body that outputs exactly one value and
+    /// shifts A right, followed by `jnz 0`. The body must set B and C before
+    /// reading them, so that each pass through the loop depends only on the
+    /// value of A at the start of that pass.
+    ///
+    /// Because A shrinks on every pass, a compiled program always halts, and
+    /// can't fault; there's no step limit.
+    pub struct CompiledLoop<W> {
+        body: Box<dyn Fn(W) -> (Code, W)>,
+    }
+
+    impl<W: Word> CompiledLoop<W> {
+        /// Compile a program, or return None if it doesn't have the shape
+        /// described above (in which case it should be interpreted instead).
+        pub fn compile(program: &[Code]) -> Option<Self> {
+            let (body, &[jump, target]) = program.split_last_chunk()?;
+
+            if Instruction::from_code(jump) != Instruction::Jnz
+                || target != Code::Zero
+                || !body.len().is_multiple_of(2)
+            {
+                return None;
+            }
+
+            // Which registers have been written so far during this pass
+            let mut written = EnumMap::from_array([true, false, false]);
+            let mut outputs = 0;
+            let mut shrinks = false;
+            let mut instructions = Vec::with_capacity(body.len() / 2);
+
+            for pair in body.chunks_exact(2) {
+                let (instruction, code) = (Instruction::from_code(pair[0]), pair[1]);
+
+                let mut reads = Vec::new();
+
+                if instruction.operand_kind() == OperandKind::Combo
+                    && let Operand::Register(register) = Operand::<W>::new(code)?
+                {
+                    reads.push(register);
+                }
+
+                let writes = match instruction {
+                    Instruction::Jnz => return None,
+                    Instruction::Out => {
+                        outputs += 1;
+                        None
+                    }
+                    Instruction::Adv => {
+                        shrinks |= matches!(code, Code::One | Code::Two | Code::Three);
+                        reads.push(Register::A);
+                        Some(Register::A)
+                    }
+                    Instruction::Bdv | Instruction::Cdv => {
+                        reads.push(Register::A);
+                        Some(match instruction {
+                            Instruction::Bdv => Register::B,
+                            _ => Register::C,
+                        })
+                    }
+                    Instruction::Bxl => {
+                        reads.push(Register::B);
+                        Some(Register::B)
+                    }
+                    Instruction::Bxc => {
+                        reads.extend([Register::B, Register::C]);
+                        Some(Register::B)
+                    }
+                    Instruction::Bst => Some(Register::B),
+                };
+
+                if !reads.iter().all(|&register| written[register]) {
+                    return None;
+                }
+
+                if let Some(register) = writes {
+                    written[register] = true;
+                }
+
+                instructions.push(compile_instruction(instruction, code));
+            }
+
+            if outputs != 1 || !shrinks {
+                return None;
+            }
+
+            Some(Self {
+                body: Box::new(move |a| {
+                    let mut registers = EnumMap::from_array([a, W::ZERO, W::ZERO]);
+                    let mut output = Code::Zero;
+
+                    for instruction in &instructions {
+                        if let Some(code) = instruction(&mut registers) {
+                            output = code;
+                        }
+                    }
+
+                    (output, registers[Register::A])
+                }),
+            })
+        }
+
+        /// Check if running the program with register A set to `a` outputs
+        /// exactly `expected`; the same as running it with an `ExpectSink`.
+        pub fn outputs_exactly(&self, mut a: W, expected: &[Code]) -> bool {
+            let mut expected = expected.iter();
+
+            loop {
+                let (output, next) = (self.body)(a);
+
+                if expected.next() != Some(&output) {
+                    return false;
+                }
+
+                // The closing `jnz 0` falls through, and the program halts
+                if next == W::ZERO {
+                    return expected.next().is_none();
+                }
+
+                a = next;
+            }
         }
     }
+
+    /// A human-readable listing of a program, with one instruction per line.
+    /// Each line has the instruction's address, its mnemonic and operand, and
+    /// a description of what it does (with combo operands decoded). Lines
+    /// that are the target of a jump are annotated with where the jumps come
+    /// from.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Disassembly<'a> {
+        program: &'a [Code],
+    }
+
+    impl<'a> Disassembly<'a> {
+        pub fn new(program: &'a [Code]) -> Self {
+            Self { program }
+        }
+    }
+
+    impl Display for Disassembly<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let instructions = self.program.chunks_exact(2).map(|pair| {
+                let instruction = Instruction::from_code(pair[0]);
+                (instruction, pair[1])
+            });
+
+            // Map from each jump target to the addresses that jump to it
+            let mut jumps: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+            for (address, (instruction, code)) in instructions.clone().enumerate() {
+                if instruction == Instruction::Jnz {
+                    jumps.entry(code.literal()).or_default().push(address * 2);
+                }
+            }
+
+            for (address, (instruction, code)) in instructions.enumerate() {
+                let address = address * 2;
+                let operand = match instruction.operand_kind() {
+                    OperandKind::Literal => code.describe_literal().to_string(),
+                    OperandKind::Combo => code.describe_combo().to_string(),
+                    OperandKind::Ignored => String::new(),
+                };
+
+                let description = describe_instruction(instruction, code);
+                write!(
+                    f,
+                    "{address:>3}: {} {operand:<5} ; {description}",
+                    instruction.mnemonic()
+                )?;
+
+                if instruction == Instruction::Jnz && !code.literal().is_multiple_of(2) {
+                    write!(f, " (misaligned target)")?;
+                }
+
+                if let Some(sources) = jumps.get(&address) {
+                    write!(f, "  <- jump from {}", sources.iter().join_with(", "))?;
+                }
+
+                writeln!(f)?;
+            }
+
+            // An odd-length program has a trailing code that can never run
+            if let [trailing] = self.program.chunks_exact(2).remainder() {
+                let address = self.program.len() - 1;
+                writeln!(f, "{address:>3}: .data {trailing:<5} ; never executed")?;
+            }
+
+            Ok(())
+        }
+    }
+
+    fn describe_instruction(instruction: Instruction, code: Code) -> impl Display {
+        lazy_format!(match (instruction) {
+            Instruction::Adv => ("A >> {} -> A", code.describe_combo()),
+            Instruction::Bdv => ("A >> {} -> B", code.describe_combo()),
+            Instruction::Cdv => ("A >> {} -> C", code.describe_combo()),
+
+            Instruction::Bxl => ("B ^ {} -> B", code.describe_literal()),
+            Instruction::Bxc => "B ^ C -> B",
+
+            Instruction::Bst => ("{} & 0b111 -> B", code.describe_combo()),
+
+            Instruction::Jnz => ("Jump to {} if A != 0", code.describe_literal()),
+            Instruction::Out => ("Output {}", code.describe_combo()),
+        })
+    }
+
+    #[derive(Debug, Clone, Error)]
+    pub enum AssemblyErrorKind {
+        #[error("unknown mnemonic {0:?}")]
+        UnknownMnemonic(String),
+
+        #[error("missing operand")]
+        MissingOperand,
+
+        #[error("unexpected text after the operand: {0:?}")]
+        TrailingText(String),
+
+        #[error("invalid literal operand {0:?}; must be 0 through 7")]
+        InvalidLiteral(String),
+
+        #[error("invalid combo operand {0:?}; must be 0 through 3, A, B, or C")]
+        InvalidCombo(String),
+    }
+
+    #[derive(Debug, Clone, Error)]
+    #[error("error on line {line}")]
+    pub struct AssemblyError {
+        pub line: usize,
+
+        #[source]
+        pub kind: AssemblyErrorKind,
+    }
+
+    fn parse_literal(operand: &str) -> Result<Code, AssemblyErrorKind> {
+        match operand.parse() {
+            Ok(value @ 0..=7) => Ok(Code::from_value(value)),
+            _ => Err(AssemblyErrorKind::InvalidLiteral(operand.to_owned())),
+        }
+    }
+
+    fn parse_combo(operand: &str) -> Result<Code, AssemblyErrorKind> {
+        match operand {
+            "A" => Ok(Code::Four),
+            "B" => Ok(Code::Five),
+            "C" => Ok(Code::Six),
+            _ => match operand.parse() {
+                Ok(value @ 0..=3) => Ok(Code::from_value(value)),
+                _ => Err(AssemblyErrorKind::InvalidCombo(operand.to_owned())),
+            },
+        }
+    }
+
+    /// Assemble a single line into zero or more codes
+    fn assemble_line(line: &str, program: &mut Vec<Code>) -> Result<(), AssemblyErrorKind> {
+        // Strip comments, and the address prefix that the disassembler adds
+        let line = line.split_once(';').map_or(line, |(line, _)| line);
+        let line = line.split_once(':').map_or(line, |(_, line)| line);
+
+        let mut words = line.split_whitespace();
+
+        let Some(mnemonic) = words.next() else {
+            return Ok(());
+        };
+
+        let operand = words.next();
+
+        if let Some(extra) = words.next() {
+            return Err(AssemblyErrorKind::TrailingText(extra.to_owned()));
+        }
+
+        if mnemonic == ".data" {
+            let operand = operand.ok_or(AssemblyErrorKind::MissingOperand)?;
+            program.push(parse_literal(operand)?);
+            return Ok(());
+        }
+
+        let instruction = Instruction::from_mnemonic(mnemonic)
+            .ok_or_else(|| AssemblyErrorKind::UnknownMnemonic(mnemonic.to_owned()))?;
+
+        let operand = match (instruction.operand_kind(), operand) {
+            (OperandKind::Ignored, None) => Code::Zero,
+            (OperandKind::Ignored, Some(operand)) => parse_literal(operand)?,
+            (_, None) => return Err(AssemblyErrorKind::MissingOperand),
+            (OperandKind::Literal, Some(operand)) => parse_literal(operand)?,
+            (OperandKind::Combo, Some(operand)) => parse_combo(operand)?,
+        };
+
+        program.extend([instruction.code(), operand]);
+        Ok(())
+    }
+
+    /// Assemble a program from mnemonic text, one instruction per line, like
+    /// `bst A` or `jnz 0`. Combo operands are written as 0 through 3 or as a
+    /// register name; `bxc` doesn't need an operand; `.data N` emits a raw
+    /// code; and anything after a `;` is a comment. Disassembler output
+    /// (see `Disassembly`) can be assembled back into the same program.
+    pub fn assemble(source: &str) -> Result<Vec<Code>, AssemblyError> {
+        let mut program = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+            assemble_line(line, &mut program).map_err(|kind| AssemblyError {
+                line: index + 1,
+                kind,
+            })?;
+        }
+
+        Ok(program)
+    }
 }
 
 fn parse_code(input: &str) -> ITResult<&str, cpu::Code> {
@@ -264,7 +1129,7 @@ fn parse_code(input: &str) -> ITResult<&str, cpu::Code> {
     .parse(input)
 }
 
-fn register_parser<'i>(id: char) -> impl Parser<&'i str, usize, ErrorTree<&'i str>> {
+fn register_parser<'i>(id: char) -> impl Parser<&'i str, u128, ErrorTree<&'i str>> {
     tag("Register ")
         .terminated(char(id))
         .terminated(tag(": "))
@@ -272,7 +1137,7 @@ fn register_parser<'i>(id: char) -> impl Parser<&'i str, usize, ErrorTree<&'i st
         .parse_from_str_cut()
 }
 
-fn parse_registers(input: &str) -> ITResult<&str, enum_map::EnumMap<cpu::Register, usize>> {
+fn parse_registers(input: &str) -> ITResult<&str, enum_map::EnumMap<cpu::Register, u128>> {
     use cpu::Register;
 
     parser! {
@@ -301,15 +1166,32 @@ fn parse_program(input: &str) -> ITResult<&str, Vec<cpu::Code>> {
     .parse(input)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
-    initial_registers: EnumMap<cpu::Register, usize>,
+    /// Parsed at the widest supported width; see `Input::machine`
+    initial_registers: EnumMap<cpu::Register, u128>,
     program: Vec<cpu::Code>,
+
+    /// The step limit for every machine running this program
+    step_limit: Option<usize>,
 }
 
 impl Input {
-    fn machine(&self) -> cpu::Machine {
-        cpu::Machine::new(self.initial_registers, &self.program)
+    /// Create a machine for this program, with registers of type `W`. Fails
+    /// if an initial register value doesn't fit in `W`.
+    fn machine<W: cpu::Word>(&self) -> anyhow::Result<cpu::Machine<'_, W>> {
+        let mut registers = EnumMap::default();
+
+        for (register, &value) in &self.initial_registers {
+            registers[register] = W::from_u128(value).with_context(|| {
+                format!(
+                    "register {register:?} = {value} doesn't fit in {} bits",
+                    W::BITS
+                )
+            })?;
+        }
+
+        Ok(cpu::Machine::new(registers, &self.program).with_step_limit(self.step_limit))
     }
 }
 
@@ -320,6 +1202,7 @@ fn parse_input(input: &str) -> ITResult<&str, Input> {
         .map(|(registers, program)| Input {
             initial_registers: registers,
             program,
+            step_limit: None,
         })
         .parse(input)
 }
@@ -347,56 +1230,619 @@ impl Display for cpu::Code {
             Seven => "7",
         };
 
-        f.write_str(value)
+        f.pad(value)
     }
 }
 
-pub fn part1(input: Input) -> anyhow::Result<impl Display> {
-    let mut machine = input.machine();
+/// An interactive debugger for a `cpu::Machine`, with single stepping,
+/// breakpoints on instruction pointer values, register editing, and saved
+/// states (which can be written to and read from JSON files)
+struct Debugger<'a, W> {
+    machine: cpu::Machine<'a, W>,
+    breakpoints: BTreeSet<usize>,
+    outputs: Vec<cpu::Code>,
+    halted: bool,
+    saved: HashMap<String, cpu::Snapshot<W>>,
+}
+
+impl<'a, W: cpu::Word> Debugger<'a, W> {
+    const HELP: &'static str = "commands: s [N]: step N instructions, c: continue to the next \
+        breakpoint, b [IP]: toggle a breakpoint (or list them), r: show registers, \
+        set R V: set register R to V, o: show output, save NAME: save the current state, \
+        load NAME: return to a saved state, export PATH: write the current state to a JSON \
+        file, import PATH: load a state from a JSON file, q: quit";
+
+    fn new(machine: cpu::Machine<'a, W>) -> Self {
+        Self {
+            machine,
+            breakpoints: BTreeSet::new(),
+            outputs: Vec::new(),
+            halted: false,
+            saved: HashMap::new(),
+        }
+    }
+
+    fn snapshot(&self) -> cpu::Snapshot<W> {
+        self.machine.snapshot(&self.outputs)
+    }
+
+    fn restore(&mut self, snapshot: &cpu::Snapshot<W>) {
+        self.machine.restore(snapshot);
+        self.outputs.clone_from(&snapshot.outputs);
+        self.halted = false;
+        self.print_state();
+    }
+
+    /// Execute a single instruction, recording any output. A fault stops
+    /// the machine just like a halt does.
+    fn step(&mut self) {
+        match self.machine.step() {
+            None => {}
+            Some(cpu::MachineState::Output(code)) => self.outputs.push(code),
+            Some(cpu::MachineState::Halt) => self.halted = true,
+            Some(cpu::MachineState::Fault(error)) => {
+                eprintln!("fault: {error}");
+                self.halted = true;
+            }
+        }
+    }
+
+    fn print_state(&self) {
+        let registers = self.machine.registers();
+
+        eprintln!(
+            "step {}, ip {}: A={} B={} C={}",
+            self.machine.steps(),
+            self.machine.instruction_pointer(),
+            registers[cpu::Register::A],
+            registers[cpu::Register::B],
+            registers[cpu::Register::C],
+        );
+
+        match self.machine.describe_next() {
+            Some(next) => eprintln!("next: {next}"),
+            None => eprintln!("halted"),
+        }
+    }
+
+    /// Run a single debugger command. Returns false if the session is over.
+    fn run_command(&mut self, command: &str) -> anyhow::Result<bool> {
+        let mut words = command.split_whitespace();
+
+        match words.next() {
+            Some("s" | "step") => {
+                let count = words.next().map(str::parse).transpose()?.unwrap_or(1);
+
+                for _ in 0..count {
+                    self.step();
+
+                    if self.halted {
+                        break;
+                    }
+                }
+
+                self.print_state();
+            }
+            Some("c" | "continue") => {
+                // Always take at least one step, so that continuing from a
+                // breakpoint doesn't immediately stop there again
+                loop {
+                    self.step();
+
+                    if self.halted
+                        || self
+                            .breakpoints
+                            .contains(&self.machine.instruction_pointer())
+                    {
+                        break;
+                    }
+                }
+
+                self.print_state();
+            }
+            Some("b" | "break") => match words.next() {
+                None => eprintln!("breakpoints: {}", self.breakpoints.iter().join_with(", ")),
+                Some(ip) => {
+                    let ip = ip.parse()?;
+
+                    match self.breakpoints.insert(ip) {
+                        true => eprintln!("added breakpoint at {ip}"),
+                        false => {
+                            self.breakpoints.remove(&ip);
+                            eprintln!("removed breakpoint at {ip}");
+                        }
+                    }
+                }
+            },
+            Some("r" | "registers") => self.print_state(),
+            Some("set") => {
+                let (Some(register), Some(value)) = (words.next(), words.next()) else {
+                    anyhow::bail!("usage: set REGISTER VALUE");
+                };
+
+                let register = match register {
+                    "A" | "a" => cpu::Register::A,
+                    "B" | "b" => cpu::Register::B,
+                    "C" | "c" => cpu::Register::C,
+                    _ => anyhow::bail!("unknown register {register:?}"),
+                };
+
+                self.machine.set_register(register, value.parse()?);
+                self.print_state();
+            }
+            Some("o" | "output") => eprintln!("output: {}", self.outputs.iter().join_with(Comma)),
+            Some("save") => {
+                let name = words.next().context("usage: save NAME")?;
+                self.saved.insert(name.to_owned(), self.snapshot());
+                eprintln!("saved {name:?}");
+            }
+            Some("load") => {
+                let name = words.next().context("usage: load NAME")?;
+                let snapshot = self
+                    .saved
+                    .get(name)
+                    .with_context(|| format!("no saved state named {name:?}"))?
+                    .clone();
+
+                self.restore(&snapshot);
+            }
+            Some("export") => {
+                let path = words.next().context("usage: export PATH")?;
+                let json = serde_json::to_string_pretty(&self.snapshot())?;
+                std::fs::write(path, json).with_context(|| format!("failed to write {path:?}"))?;
+                eprintln!("wrote state to {path:?}");
+            }
+            Some("import") => {
+                let path = words.next().context("usage: import PATH")?;
+                let json = std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read {path:?}"))?;
+                let snapshot: cpu::Snapshot<W> = serde_json::from_str(&json)
+                    .with_context(|| format!("failed to parse state from {path:?}"))?;
+
+                self.restore(&snapshot);
+            }
+            Some("q" | "quit") => return Ok(false),
+            _ => eprintln!("{}", Self::HELP),
+        }
 
+        Ok(true)
+    }
+
+    /// Run an interactive session, reading commands from `commands`. When
+    /// the session ends, the machine runs to completion, and its complete
+    /// output is returned.
+    fn session(mut self, commands: impl BufRead) -> anyhow::Result<Vec<cpu::Code>> {
+        eprintln!("{}", Self::HELP);
+        self.print_state();
+
+        for line in commands.lines() {
+            let line = line.context("failed to read command")?;
+
+            match self.run_command(line.trim()) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => eprintln!("error: {err:#}"),
+            }
+        }
+
+        let end = self.machine.run(&mut self.outputs);
+        check_run_end(end, self.machine.steps())?;
+
+        Ok(self.outputs)
+    }
+}
+
+/// Apply the parameters that change how the program runs: `--param
+/// program=PATH` replaces the puzzle's program with one assembled from the
+/// file at PATH (see `cpu::assemble`), `--param max_steps=N` stops any run
+/// of the program after N instructions, and `--param a=N` replaces the
+/// initial value of register A, to try out a candidate from part 2.
+fn configure(mut input: Input) -> anyhow::Result<Input> {
+    input.step_limit = params::get("max_steps")?;
+
+    if let Some(a) = params::get("a")? {
+        input.initial_registers[cpu::Register::A] = a;
+    }
+
+    if let Some(path) = params::get_raw("program") {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read program from {path:?}"))?;
+
+        input.program = cpu::assemble(&source)
+            .with_context(|| format!("failed to assemble program from {path:?}"))?;
+    }
+
+    Ok(input)
+}
+
+/// With `--param disasm=true`, print the program's disassembly to stderr
+fn print_requested_disassembly(input: &Input) -> anyhow::Result<()> {
+    if params::get_or("disasm", false)? {
+        eprint!("{}", cpu::Disassembly::new(&input.program));
+    }
+
+    Ok(())
+}
+
+/// Make sure a run of the machine ended normally
+fn check_run_end(end: cpu::RunEnd, steps: usize) -> anyhow::Result<()> {
+    match end {
+        cpu::RunEnd::Halted | cpu::RunEnd::Stopped => Ok(()),
+        cpu::RunEnd::StepLimit => anyhow::bail!("the program didn't halt within {steps} steps"),
+        cpu::RunEnd::Fault(error) => {
+            Err(error).with_context(|| format!("the program faulted after {steps} steps"))
+        }
+    }
+}
+
+/// Run the program, printing a line for each executed instruction (see
+/// `cpu::TraceStep`) to stderr, or to the file at `--param trace_file=PATH`.
+/// With `--param trace_limit=N`, only the first N instructions are traced,
+/// and the rest of the program runs silently.
+fn run_traced<W: cpu::Word>(machine: &mut cpu::Machine<'_, W>) -> anyhow::Result<Vec<cpu::Code>> {
+    let limit: Option<usize> = params::get("trace_limit")?;
+
+    let mut writer: Box<dyn Write> = match params::get_raw("trace_file") {
+        None => Box::new(io::stderr().lock()),
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).with_context(|| format!("failed to create {path:?}"))?,
+        )),
+    };
+
+    let mut traced = 0;
+    let mut error = None;
     let mut outs = Vec::new();
 
-    while let cpu::MachineState::Output(value) = machine.run_until_state() {
-        outs.push(value);
+    let end = machine.run_traced(&mut outs, |step| {
+        let result = match limit {
+            Some(limit) if traced > limit => return ControlFlow::Continue(()),
+            Some(limit) if traced == limit => writeln!(writer, "trace limit reached"),
+            _ => writeln!(writer, "{step}"),
+        };
+
+        traced += 1;
+
+        match result {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(err) => {
+                error = Some(err);
+                ControlFlow::Break(())
+            }
+        }
+    });
+
+    if let Some(err) = error {
+        return Err(err).context("failed to write trace");
     }
 
-    Ok(outs.join_with(Comma))
+    writer.flush().context("failed to write trace")?;
+    check_run_end(end, machine.steps())?;
+
+    Ok(outs)
 }
 
-pub fn part2(input: Input) -> anyhow::Result<usize> {
-    let mut candidate = 1 << 46;
+/// Run the program with registers of type `W`, and collect its output.
+fn run<W: cpu::Word>(input: &Input) -> anyhow::Result<Vec<cpu::Code>> {
+    let mut machine = input.machine::<W>()?;
+
+    // With `--param debug=true`, run the program in an interactive debugger
+    // instead, and return whatever it outputs.
+    if params::get_or("debug", false)? {
+        return Debugger::new(machine).session(terminal::open());
+    }
+
+    // With `--param trace=true`, show every instruction as it runs
+    if params::get_or("trace", false)? {
+        return run_traced(&mut machine);
+    }
 
-    loop {
-        let mut machine = input.machine();
-        machine.reinit(candidate);
+    // With `--param stream=true`, also show outputs on stderr as they happen
+    if params::get_or("stream", false)? {
+        let mut outs = Vec::new();
+        let mut sink = (&mut outs, cpu::WriteSink::new(io::stderr()));
+        let end = machine.run(&mut sink);
+        eprintln!();
 
-        let outs = machine.run_iter();
+        if let Some(err) = sink.1.error {
+            return Err(err).context("failed to stream output");
+        }
 
-        let miss = outs
-            .zip_longest(input.program.iter().copied())
-            .position(|pair| match pair {
-                EitherOrBoth::Both(out, target) => out != target,
-                EitherOrBoth::Left(_) => true,
-                EitherOrBoth::Right(_) => true,
-            });
+        check_run_end(end, machine.steps())?;
+        return Ok(outs);
+    }
 
-        match miss {
-            None => return Ok(candidate),
-            Some(idx) => {
-                if idx >= 9 {
-                    eprintln!("fixing {idx}");
-                }
+    let outcome = machine.run_to_outcome();
+    check_run_end(outcome.end, outcome.steps)?;
 
-                // Find the bit that fixes this index, and increment there
-                let bit = 1usize << (idx * 3);
-                candidate += bit;
+    Ok(outcome.outputs)
+}
+
+/// Select the register type with `--param registers=u64|u128`. Registers are
+/// 64 bits by default.
+pub fn part1(input: Input) -> anyhow::Result<Answer> {
+    let input = configure(input)?;
+    print_requested_disassembly(&input)?;
+
+    let outs = match params::get_raw("registers").unwrap_or("u64") {
+        "u64" => run::<u64>(&input)?,
+        "u128" => run::<u128>(&input)?,
+        registers => anyhow::bail!("unknown register type {registers:?}; expected u64 or u128"),
+    };
 
-                // Zero out the 3 bits to the right of that bit, because they
-                // need to be recalculated
-                let mask = 0b111 << (idx * 3);
-                let mask = mask >> 3;
-                candidate &= !mask;
+    Ok(Answer::list(outs.into_iter().map(u8::from)))
+}
+
+impl Input {
+    /// Search for every value of register A, whose low 3-bit digits extend
+    /// `prefix`, that makes the program output `program[position..]`. The
+    /// values are found lazily, in ascending order.
+    ///
+    /// This assumes the usual structure of these programs: a loop that
+    /// outputs one value computed from A, then shifts A right by 3 bits,
+    /// until A is 0. Under that assumption, the last output depends only on
+    /// the top digit of A, the second-to-last on the top two digits, and so
+    /// on, so we can build A one digit at a time, starting from the last
+    /// output and checking each candidate digit against the whole suffix of
+    /// the output that it determines. Trying digits in ascending order means
+    /// the solutions come out in ascending order, so the first one is the
+    /// smallest.
+    ///
+    /// `outputs_exactly` runs the program; it's either
+    /// `cpu::Machine::outputs_exactly` or a compiled version of it.
+    /// `progress` shows the most digits of A found so far, and each candidate
+    /// that matches more digits than any before it is sent to `updates`. The
+    /// search stops early if it's interrupted.
+    fn quines_from<'a, W: cpu::Word>(
+        &'a self,
+        outputs_exactly: &'a impl Fn(W, &[cpu::Code]) -> bool,
+        progress: &'a ProgressBar,
+        updates: &'a Updates,
+        prefix: W,
+        position: usize,
+    ) -> Box<dyn Iterator<Item = W> + 'a> {
+        let target = &self.program[position..];
+        let depth = target.len() as u64;
+
+        Box::new(
+            (0..8)
+                .take_while(|_| !cancel::requested())
+                .filter_map(move |digit| prefix.push_digit(digit))
+                .filter(move |&a| outputs_exactly(a, target))
+                .inspect(move |&a| {
+                    if depth > progress.position() {
+                        progress.set_position(depth);
+                        updates.send(format_args!("A = {a}, matching {depth} outputs"));
+                    }
+                })
+                .flat_map(move |a| match position.checked_sub(1) {
+                    None => Box::new(iter::once(a)),
+                    Some(position) => {
+                        self.quines_from(outputs_exactly, progress, updates, a, position)
+                    }
+                }),
+        )
+    }
+
+    /// Find the smallest value of register A that makes the program output
+    /// itself.
+    fn find_quine<W: cpu::Word>(
+        &self,
+        outputs_exactly: &impl Fn(W, &[cpu::Code]) -> bool,
+        updates: &Updates,
+    ) -> anyhow::Result<W> {
+        let last = self
+            .program
+            .len()
+            .checked_sub(1)
+            .context("the program is empty")?;
+
+        let progress = progress::bar(self.program.len() as u64, "digits of A");
+        let quines = self.quines_from(outputs_exactly, &progress, updates, W::ZERO, last);
+        let quine = smallest_quine(quines);
+        cancel::check()?;
+
+        quine
+    }
+
+    /// Unless `--param compile=false` is given, the search runs the program
+    /// as a native closure (see `cpu::CompiledLoop`) when it has the usual
+    /// shape. Other programs, or any program with a step limit, are
+    /// interpreted.
+    fn search<W: cpu::Word>(&self, updates: &Updates) -> anyhow::Result<u128> {
+        let compiled = match self.step_limit.is_none() && params::get_or("compile", true)? {
+            true => cpu::CompiledLoop::<W>::compile(&self.program),
+            false => None,
+        };
+
+        let found = match compiled {
+            Some(compiled) => self.find_quine(
+                &|a, expected: &[cpu::Code]| compiled.outputs_exactly(a, expected),
+                updates,
+            )?,
+            None => {
+                let machine = self.machine::<W>()?;
+                self.find_quine(
+                    &|a, expected: &[cpu::Code]| machine.outputs_exactly(a, expected),
+                    updates,
+                )?
             }
+        };
+
+        Ok(found.into())
+    }
+}
+
+/// Take the first of `quines`, which are in ascending order. With
+/// `--param quines=all`, also take every other one, and print them all to
+/// stderr, followed by their count and range.
+fn smallest_quine<W: Copy + Display>(mut quines: impl Iterator<Item = W>) -> anyhow::Result<W> {
+    let all = match params::get_raw("quines") {
+        None | Some("min") => false,
+        Some("all") => true,
+        Some(quines) => anyhow::bail!("unknown quines mode {quines:?}; expected min or all"),
+    };
+
+    let smallest = quines
+        .next()
+        .context("no value of register A makes the program output itself")?;
+
+    if all {
+        eprintln!("{smallest}");
+
+        let (count, largest) = quines.fold((1, smallest), |(count, _), a| {
+            eprintln!("{a}");
+            (count + 1, a)
+        });
+
+        eprintln!("{count} values of A; smallest {smallest}, largest {largest}");
+    }
+
+    Ok(smallest)
+}
+
+/// Without `--param max_steps`, the GPU gives up on the search if any
+/// candidate runs for this many steps, since a shader can't run forever
+#[cfg(feature = "gpu")]
+const GPU_STEP_LIMIT: u32 = 1 << 20;
+
+#[cfg(feature = "gpu")]
+const QUINE_SEARCH_SHADER: &str = include_str!("shaders/day17.wgsl");
+
+/// The search for values of register A that make the program output itself.
+/// On the GPU, it runs one digit at a time, like `Input::quines_from`, but
+/// breadth first: each round checks every extension of every surviving
+/// prefix at once. Either way, the candidates that match the most digits so
+/// far are sent to `updates`.
+struct QuineSearch<'a> {
+    input: &'a Input,
+    updates: &'a Updates,
+}
+
+impl Offload for QuineSearch<'_> {
+    type Output = u128;
+
+    fn cpu(&self) -> anyhow::Result<u128> {
+        match params::get_raw("registers").unwrap_or("u64") {
+            "u64" => self.input.search::<u64>(self.updates),
+            "u128" => self.input.search::<u128>(self.updates),
+            registers => anyhow::bail!("unknown register type {registers:?}; expected u64 or u128"),
         }
     }
+
+    #[cfg(feature = "gpu")]
+    fn gpu(&self, gpu: &gpu::Gpu) -> anyhow::Result<u128> {
+        use cpu::{Register, Word};
+
+        let input = self.input;
+
+        if let Some(registers) = params::get_raw("registers").filter(|&r| r != "u64") {
+            anyhow::bail!("the GPU only supports 64-bit registers, not {registers:?}");
+        }
+
+        let last = input
+            .program
+            .len()
+            .checked_sub(1)
+            .context("the program is empty")?;
+
+        let register = |register| -> anyhow::Result<u64> {
+            let value = input.initial_registers[register];
+            value
+                .try_into()
+                .with_context(|| format!("register {register:?} = {value} doesn't fit in 64 bits"))
+        };
+
+        let b = register(Register::B)?;
+        let c = register(Register::C)?;
+
+        let program: Vec<u32> = input.program.iter().map(|&code| code as u32).collect();
+
+        let (step_limit, give_up) = match input.step_limit {
+            Some(limit) => (limit.try_into().unwrap_or(u32::MAX), false),
+            None => (GPU_STEP_LIMIT, true),
+        };
+
+        let mut quines = vec![u64::ZERO];
+
+        let progress = progress::bar(input.program.len() as u64, "digits of A");
+
+        for position in (0..=last).rev() {
+            cancel::check()?;
+
+            let candidates: Vec<u64> = quines
+                .iter()
+                .flat_map(|&prefix| (0..8).filter_map(move |digit| prefix.push_digit(digit)))
+                .collect();
+
+            if candidates.is_empty() {
+                quines.clear();
+                break;
+            }
+
+            let count: u32 = candidates.len().try_into()?;
+
+            let params = [
+                program.len().try_into()?,
+                position as u32,
+                count,
+                step_limit,
+                give_up.into(),
+                b as u32,
+                (b >> 32) as u32,
+                c as u32,
+                (c >> 32) as u32,
+            ];
+
+            let words: Vec<u32> = candidates
+                .iter()
+                .flat_map(|&a| [a as u32, (a >> 32) as u32])
+                .collect();
+
+            let results = gpu.compute(
+                QUINE_SEARCH_SHADER,
+                &[&params, &program, &words],
+                candidates.len(),
+                count,
+            )?;
+
+            // See the result constants in the shader
+            if results.contains(&2) {
+                anyhow::bail!("the program didn't halt within {GPU_STEP_LIMIT} steps");
+            }
+
+            quines = candidates
+                .into_iter()
+                .zip(results)
+                .filter(|&(_, result)| result == 1)
+                .map(|(a, _)| a)
+                .collect();
+
+            if let Some(&a) = quines.first() {
+                let depth = program.len() - position;
+                self.updates
+                    .send(format_args!("A = {a}, matching {depth} outputs"));
+            }
+
+            progress.inc(1);
+        }
+
+        smallest_quine(quines.into_iter()).map(u128::from)
+    }
 }
+
+/// Select the register type with `--param registers=u64|u128`, as in part 1.
+/// Runs on the GPU with `--gpu`, which only supports 64-bit registers.
+/// Streams the candidate for A that matches the most outputs so far.
+pub fn part2(input: Input) -> anyhow::Result<Streaming<u128>> {
+    let input = configure(input)?;
+    print_requested_disassembly(&input)?;
+
+    Ok(Streaming::spawn(move |updates| {
+        gpu::run(&QuineSearch {
+            input: &input,
+            updates,
+        })
+    }))
+}
+
+solver!(Input, Answer, Streaming<u128>);
@@ -1,8 +1,6 @@
-use std::{
-    cmp::Ordering,
-    collections::{BinaryHeap, HashMap, HashSet},
-    fmt::Display,
-};
+//! Day 18: RAM Run
+
+use std::{collections::HashSet, iter};
 
 use anyhow::Context;
 use gridly::prelude::*;
@@ -17,11 +15,29 @@ use nom_supreme::{
     ParserExt, error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated,
 };
 use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    library::{
+        ITResult,
+        answer::Answer,
+        cancel,
+        grid::{GridExt, Path, Rect},
+        maze::Maze,
+        params,
+        progress::{self, ParallelProgressIterator, ProgressIterator},
+        solver::solver,
+        stats,
+        union_find::UnionFind,
+        validate,
+        visualize::{self, Frame, Visualize},
+    },
+    parser,
+};
 
-use crate::{library::ITResult, parser};
-
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
+    #[serde(serialize_with = "crate::library::inspect::locations")]
     incoming: Vec<Location>,
 }
 
@@ -49,187 +65,364 @@ impl TryFrom<&str> for Input {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-enum Cell {
-    #[default]
-    Safe,
-    Corrupt,
+/// The size of the memory space, and how many bytes have fallen by the time
+/// of part 1. The exit is always in the bottom right corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// The memory space is `size` cells on each side
+    pub size: isize,
+    pub bytes: usize,
 }
 
-struct SearchStep<const ROW: isize, const COL: isize> {
-    location: Location,
-    distance: isize,
-}
-
-impl<const ROW: isize, const COL: isize> SearchStep<ROW, COL> {
-    const fn dest() -> Location {
-        Location {
-            row: Row(ROW),
-            column: Column(COL),
-        }
+impl Config {
+    pub const PUZZLE: Self = Self {
+        size: 71,
+        bytes: 1024,
+    };
+
+    /// Get the config from `--param size=N` and `--param bytes=N`, using the
+    /// puzzle's values for either one that isn't given. The example from the
+    /// puzzle statement is `size=7`, `bytes=12`.
+    pub fn from_runner() -> anyhow::Result<Self> {
+        let config = Self {
+            size: params::get_or("size", Self::PUZZLE.size)?,
+            bytes: params::get_or("bytes", Self::PUZZLE.bytes)?,
+        };
+
+        anyhow::ensure!(
+            config.size > 0,
+            "the memory space must have a positive size"
+        );
+        Ok(config)
     }
 
-    fn cost(&self) -> isize {
-        self.distance + (Self::dest() - self.location).manhattan_length()
+    fn dimensions(&self) -> Vector {
+        Rows(self.size) + Columns(self.size)
     }
 
-    fn done(&self) -> bool {
-        self.location == Self::dest()
+    fn exit(&self) -> Location {
+        Location::new(Row(self.size - 1), Column(self.size - 1))
     }
 }
 
-impl<const ROW: isize, const COL: isize> Ord for SearchStep<ROW, COL> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        Ord::cmp(&other.cost(), &self.cost())
-    }
+/// Find a shortest path from the start of `maze` to its exit, through safe
+/// cells. The path includes both ends. Each call counts toward the
+/// `day18.searches` stat.
+fn find_path(maze: &Maze) -> Option<Path> {
+    stats::add("day18.searches", 1);
+    maze.shortest_path()
+}
+
+/// True if the exit of `maze` can be reached from its start at all. This
+/// only needs the distances, not a path, so it's a plain breadth-first
+/// search. Each call counts toward the `day18.searches` stat.
+fn escapable(maze: &Maze) -> bool {
+    stats::add("day18.searches", 1);
+    maze.distances_from(maze.start()).get(maze.end()).is_some()
+}
+
+/// Mark a fallen byte in the maze
+fn corrupt(maze: &mut Maze, location: Location) -> anyhow::Result<()> {
+    maze.add_wall(location)
+        .ok()
+        .context(lazy_format!("cell out of bounds: {location:?}"))
+}
+
+/// The memory space after each of `bytes` has fallen, as a maze from the top
+/// left corner to the exit, with a wall for each corrupted cell
+fn fallen_maze(bytes: &[Location], config: &Config) -> anyhow::Result<Maze> {
+    let mut maze = Maze::new(
+        Rect::new(Location::zero(), config.dimensions()),
+        Location::zero(),
+        config.exit(),
+    );
+
+    bytes
+        .iter()
+        .try_for_each(|&location| corrupt(&mut maze, location))?;
+
+    Ok(maze)
 }
 
-impl<const ROW: isize, const COL: isize> PartialOrd for SearchStep<ROW, COL> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+/// The memory space after the first `config.bytes` bytes have fallen
+fn part1_maze(input: &Input, config: &Config) -> anyhow::Result<Maze> {
+    let bytes = config.bytes.min(input.incoming.len());
+    fallen_maze(&input.incoming[..bytes], config)
+}
+
+/// A shortest path to the exit after the first `config.bytes` bytes have
+/// fallen, from the start to the exit inclusive
+pub fn escape_path(input: &Input, config: &Config) -> anyhow::Result<Path> {
+    let maze = part1_maze(input, config)?;
+    find_path(&maze).context("no path found")
+}
+
+/// Draw the memory space like the puzzle does: `#` for corrupted cells, `.`
+/// for safe ones, and `O` for the cells on `path`.
+fn path_frame(maze: &Maze, path: &Path) -> Frame {
+    let bounds = maze.bounds();
+    let mut frame = Frame::from_fn(
+        bounds.num_rows(),
+        bounds.num_columns(),
+        |location| match maze.is_wall(location) {
+            true => '#',
+            false => '.',
+        },
+    );
+
+    frame.mark(path, 'O');
+    frame
+}
+
+/// See `path_frame`
+pub fn render_path(maze: &Maze, path: &Path) -> String {
+    path_frame(maze, path).to_string()
+}
+
+/// A path through the memory space, drawn over the fallen bytes
+pub struct EscapeRoute<'a> {
+    pub maze: &'a Maze,
+    pub path: &'a Path,
+}
+
+impl Visualize for EscapeRoute<'_> {
+    fn frames(&self) -> impl Iterator<Item = Frame> + '_ {
+        let steps = self.path.steps();
+        iter::once(path_frame(self.maze, self.path).with_title(format!("{steps} steps")))
     }
 }
 
-impl<const ROW: isize, const COL: isize> PartialEq for SearchStep<ROW, COL> {
-    fn eq(&self, other: &Self) -> bool {
-        self.cost() == other.cost()
+/// With `--param render=true`, also draw the path on stderr.
+pub fn part1(input: Input) -> anyhow::Result<usize> {
+    let config = Config::from_runner()?;
+    let path = escape_path(&input, &config)?;
+
+    if params::get_or("render", false)? {
+        eprint!("{}", render_path(&part1_maze(&input, &config)?, &path));
     }
+
+    if visualize::enabled() {
+        let maze = part1_maze(&input, &config)?;
+        visualize::show(&EscapeRoute {
+            maze: &maze,
+            path: &path,
+        })?;
+    }
+
+    Ok(path.steps())
 }
 
-impl<const ROW: isize, const COL: isize> Eq for SearchStep<ROW, COL> {}
+/// Find the first byte that cuts off the exit by building the maze as of
+/// every candidate byte and searching it afresh (in parallel).
+fn first_blocking_byte_search(
+    incoming: &[Location],
+    config: &Config,
+) -> anyhow::Result<Option<usize>> {
+    // Check up front that every byte is in bounds, so that the mazes built
+    // for each candidate can't fail
+    fallen_maze(incoming, config)?;
+
+    // Every byte is a candidate, even the ones that part 1 lets fall, so
+    // that this agrees with the other strategies whatever `config.bytes` is
+    let found = (0..incoming.len())
+        .into_par_iter()
+        .progress_with(progress::bar(incoming.len() as u64, "timestamps"))
+        .find_first(|&i| {
+            // Once interrupted, end the search; the result is discarded
+            if cancel::requested() {
+                return true;
+            }
+
+            let maze = fallen_maze(&incoming[..=i], config).expect("bytes are in bounds");
+            !escapable(&maze)
+        });
 
-pub fn part1(input: Input) -> anyhow::Result<isize> {
-    let mut grid = VecGrid::new((Rows(71), Columns(71))).expect("dimensions are fine");
+    cancel::check()?;
+    Ok(found)
+}
 
-    input.incoming.iter().take(1024).try_for_each(|&cell| {
-        grid.set(cell, Cell::Corrupt)
+/// Find the first byte that cuts off the exit by running time backwards.
+/// Start with every byte fallen, with the safe cells joined into connected
+/// regions, then take the bytes away again in reverse order, joining each
+/// freed cell to its safe neighbors. The first byte whose removal connects
+/// the start to the exit is the one that cut them off.
+fn first_blocking_byte_reverse(
+    incoming: &[Location],
+    config: &Config,
+) -> anyhow::Result<Option<usize>> {
+    let dimensions = config.dimensions();
+    let index =
+        |location: Location| (location.row.0 * dimensions.columns.0 + location.column.0) as usize;
+
+    // When each cell is first corrupted, if it ever is
+    let mut fallen: VecGrid<Option<usize>> = VecGrid::new(dimensions).expect("dimensions are fine");
+
+    for (i, &location) in incoming.iter().enumerate() {
+        let cell = fallen
+            .get_mut(location)
             .ok()
-            .context(lazy_format!("cell out of bounds: {cell:?}"))
-    })?;
+            .context(lazy_format!("cell out of bounds: {location:?}"))?;
+
+        cell.get_or_insert(i);
+    }
+
+    let mut sets = UnionFind::new(index(Location::zero() + dimensions));
+    let is_safe = |location: Location, time: usize| matches!(fallen.get(location), Ok(&fallen) if fallen.is_none_or(|fallen| fallen >= time));
+
+    let join_safe_neighbors = |sets: &mut UnionFind, location: Location, time: usize| {
+        for direction in EACH_DIRECTION {
+            let neighbor = location + direction;
+
+            if is_safe(neighbor, time) {
+                sets.union(index(location), index(neighbor));
+            }
+        }
+    };
 
-    let mut seen = HashSet::new();
+    let end = incoming.len();
 
-    let mut queue = BinaryHeap::from([SearchStep::<70, 70> {
-        location: Location::zero(),
-        distance: 0,
-    }]);
+    // Start from the end of time, after every byte has fallen
+    let locations = fallen.iter_located().map(|(location, _)| location);
 
-    while let Some(step) = queue.pop() {
-        if step.done() {
-            return Ok(step.distance);
+    for location in locations {
+        if is_safe(location, end) {
+            join_safe_neighbors(&mut sets, location, end);
         }
+    }
+
+    let start = Location::zero();
+    let exit = config.exit();
+    let escapable = |sets: &mut UnionFind, time: usize| {
+        is_safe(start, time) && is_safe(exit, time) && sets.connected(index(start), index(exit))
+    };
 
-        if seen.replace(step.location).is_some() {
+    if escapable(&mut sets, end) {
+        return Ok(None);
+    }
+
+    for (time, &location) in incoming.iter().enumerate().rev() {
+        // A byte that lands on an already corrupted cell doesn't change
+        // anything
+        if fallen[location] != Some(time) {
             continue;
         }
 
-        for direction in EACH_DIRECTION {
-            let new_location = step.location + direction;
-            let distance = step.distance + 1;
-
-            match grid.get(new_location) {
-                Err(_) => continue,
-                Ok(&Cell::Corrupt) => continue,
-                Ok(&Cell::Safe) => {
-                    queue.push(SearchStep {
-                        location: new_location,
-                        distance,
-                    });
-                }
-            }
+        join_safe_neighbors(&mut sets, location, time);
+
+        if escapable(&mut sets, time) {
+            return Ok(Some(time));
         }
     }
 
-    anyhow::bail!("no path found")
+    // The exit is cut off before any bytes have fallen
+    Ok(None)
 }
 
-struct TimedGridAdapter<'a> {
-    dimensions: Vector,
-    cells: &'a HashMap<Location, usize>,
-    timestamp: usize,
+/// Track whether the exit is reachable as bytes fall, one at a time. This
+/// keeps a single path to the exit, and only searches for a new one when a
+/// byte lands on it; once the exit is cut off, it stays cut off, so there's
+/// nothing more to search for.
+#[derive(Debug, Clone)]
+pub struct OnlineEscape {
+    maze: Maze,
+
+    /// The cells of the current path to the exit, or None if there isn't one
+    path: Option<HashSet<Location>>,
 }
 
-impl GridBounds for TimedGridAdapter<'_> {
-    fn dimensions(&self) -> Vector {
-        self.dimensions
+impl OnlineEscape {
+    /// Start with an empty memory space
+    pub fn new(config: &Config) -> Self {
+        let maze = fallen_maze(&[], config).expect("no bytes have fallen");
+        let path = find_path(&maze).map(|path| path.iter().collect());
+
+        Self { maze, path }
     }
 
-    fn root(&self) -> Location {
-        Location::zero()
+    pub fn is_reachable(&self) -> bool {
+        self.path.is_some()
     }
-}
 
-impl Grid for TimedGridAdapter<'_> {
-    type Item = Cell;
+    /// Add a fallen byte, and report whether the exit is still reachable
+    pub fn add(&mut self, location: Location) -> anyhow::Result<bool> {
+        corrupt(&mut self.maze, location)?;
 
-    unsafe fn get_unchecked(&self, location: Location) -> &Self::Item {
-        match self.cells.get(&location) {
-            Some(&timestamp) if timestamp <= self.timestamp => &Cell::Corrupt,
-            _ => &Cell::Safe,
+        if self
+            .path
+            .as_ref()
+            .is_some_and(|path| path.contains(&location))
+        {
+            self.path = find_path(&self.maze).map(|path| path.iter().collect());
         }
+
+        Ok(self.is_reachable())
+    }
+
+    /// Add each byte from `bytes` as it arrives, reporting after each one
+    /// whether the exit is still reachable
+    pub fn reachability<'a>(
+        &'a mut self,
+        bytes: impl IntoIterator<Item = Location> + 'a,
+    ) -> impl Iterator<Item = anyhow::Result<bool>> + 'a {
+        bytes.into_iter().map(|location| self.add(location))
     }
 }
 
-pub fn part2(input: Input) -> anyhow::Result<impl Display> {
-    let cells = input
-        .incoming
-        .iter()
-        .enumerate()
-        .map(|(i, &location)| (location, i))
-        .collect();
+/// Find the first byte that cuts off the exit by feeding the bytes to an
+/// `OnlineEscape`.
+fn first_blocking_byte_incremental(
+    incoming: &[Location],
+    config: &Config,
+) -> anyhow::Result<Option<usize>> {
+    let mut escape = OnlineEscape::new(config);
 
-    // We know that at least the first 1024 cells are safe
-    let timestamp = (1000..input.incoming.len())
-        .into_par_iter()
-        .find_first(|&i| {
-            let grid = TimedGridAdapter {
-                dimensions: Rows(71) + Columns(71),
-                cells: &cells,
-                timestamp: i,
-            };
-
-            let mut seen = HashSet::new();
-
-            let mut queue = BinaryHeap::from([SearchStep::<70, 70> {
-                location: Location::zero(),
-                distance: 0,
-            }]);
-
-            while let Some(step) = queue.pop() {
-                if step.done() {
-                    return false;
-                }
-
-                if seen.replace(step.location).is_some() {
-                    continue;
-                }
-
-                for direction in EACH_DIRECTION {
-                    let new_location = step.location + direction;
-                    let distance = step.distance + 1;
-
-                    match grid.get(new_location) {
-                        Err(_) => continue,
-                        Ok(&Cell::Corrupt) => continue,
-                        Ok(&Cell::Safe) => {
-                            queue.push(SearchStep {
-                                location: new_location,
-                                distance,
-                            });
-                        }
-                    }
-                }
-            }
+    if !escape.is_reachable() {
+        return Ok(None);
+    }
+
+    let reachability = escape
+        .reachability(incoming.iter().copied())
+        .progress_with(progress::bar(incoming.len() as u64, "timestamps"));
+
+    for (time, reachable) in reachability.enumerate() {
+        cancel::check()?;
 
-            true
-        })
-        .context("no blocking location found")?;
+        if !reachable? {
+            return Ok(Some(time));
+        }
+    }
+
+    Ok(None)
+}
+
+/// `--param strategy=search|reverse|incremental` selects how the blocking
+/// byte is found; see `first_blocking_byte_search`,
+/// `first_blocking_byte_reverse`, and `first_blocking_byte_incremental`.
+pub fn part2(input: Input) -> anyhow::Result<Answer> {
+    let config = Config::from_runner()?;
+
+    let timestamp = validate::select(
+        "strategy",
+        &[
+            ("search", &|| {
+                first_blocking_byte_search(&input.incoming, &config)
+            }),
+            ("reverse", &|| {
+                first_blocking_byte_reverse(&input.incoming, &config)
+            }),
+            ("incremental", &|| {
+                first_blocking_byte_incremental(&input.incoming, &config)
+            }),
+        ],
+    )?
+    .context("no blocking location found")?;
 
     let location = input.incoming[timestamp];
 
-    Ok(lazy_format!(
-        "{x},{y}",
-        y = location.row.0,
-        x = location.column.0
-    ))
+    Ok(Answer::Point {
+        x: location.column.0 as i64,
+        y: location.row.0 as i64,
+    })
 }
+
+solver!(Input, usize, Answer);
@@ -1,7 +1,25 @@
-use std::{cmp::Ordering, collections::BTreeMap, iter::repeat};
+//! Day 19: Linen Layout
 
-use joinery::JoinableIterator;
-use lazy_format::lazy_format;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+use anyhow::Context;
+
+use crate::{
+    library::{
+        ITResult, params,
+        solver::solver,
+        stats::{self, Counter},
+        validate,
+    },
+    parser,
+};
+#[cfg(test)]
+use bumpalo::Bump;
+use joinery::{Joinable, JoinableIterator};
 use nom::{
     Parser,
     character::complete::{alpha1, multispace0, multispace1},
@@ -11,21 +29,22 @@ use nom_supreme::{
     ParserExt, error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated,
     tag::complete::tag,
 };
-use regex::Regex;
-
-use crate::{cmp_all, library::ITResult, parser};
+use rayon::prelude::*;
+use serde::Serialize;
 
-#[derive(Debug)]
+/// The lists of fragments and goals are either owned, or borrowed from an
+/// arena; see `parse_in`.
+#[derive(Debug, Serialize)]
 pub struct Input<'a> {
-    fragments: Vec<&'a str>,
-    goals: Vec<&'a str>,
+    fragments: Cow<'a, [&'a str]>,
+    goals: Cow<'a, [&'a str]>,
 }
 
-fn parse_input(input: &str) -> ITResult<&str, Input> {
+fn parse_input(input: &str) -> ITResult<&str, Input<'_>> {
     parser! {
         collect_separated_terminated(alpha1, tag(", "), multispace1) => fragments,
         collect_separated_terminated(alpha1.terminated(multispace0), success(()), eof) => goals;
-        Input{fragments, goals}
+        Input{fragments: Cow::Owned(fragments), goals: Cow::Owned(goals)}
     }
     .parse(input)
 }
@@ -38,85 +57,584 @@ impl<'a> TryFrom<&'a str> for Input<'a> {
     }
 }
 
-impl Input<'_> {
-    fn compile_components(&self) -> Regex {
-        // Safety: we know from the parser that only `[a-zA-Z]` are in
-        // the fragments
+/// Parse the input with the lists of fragments and goals allocated in
+/// `arena`, rather than each in a `Vec` of its own. Compared against the
+/// usual parser in the benchmarks, which
+/// Cargo builds under `cfg(test)`, like the tests.
+#[cfg(test)]
+pub(crate) fn parse_in<'a>(
+    arena: &'a Bump,
+    input: &'a str,
+) -> Result<Input<'a>, ErrorTree<nom_supreme::final_parser::Location>> {
+    use bumpalo::collections::Vec as BumpVec;
+    use nom_supreme::multi::parse_separated_terminated;
 
-        let alternations = self
-            .fragments
-            .iter()
-            .map(|fragment| lazy_format!("(:?{fragment})"))
-            .join_with('|');
+    use crate::express;
+
+    let parse_input = |input: &'a str| -> ITResult<&'a str, Input<'a>> {
+        parse_separated_terminated(
+            alpha1,
+            tag(", "),
+            multispace1,
+            || BumpVec::new_in(arena),
+            |fragments, fragment| express!(fragments.push(fragment)),
+        )
+        .and(parse_separated_terminated(
+            alpha1.terminated(multispace0),
+            success(()),
+            eof,
+            || BumpVec::new_in(arena),
+            |goals, goal| express!(goals.push(goal)),
+        ))
+        .map(|(fragments, goals)| Input {
+            fragments: Cow::Borrowed(fragments.into_bump_slice()),
+            goals: Cow::Borrowed(goals.into_bump_slice()),
+        })
+        .parse(input)
+    };
+
+    final_parser(parse_input)(input)
+}
+
+/// A trie of fragments, over bytes. Each node is a (possibly empty) prefix of
+/// one or more fragments.
+#[derive(Debug, Clone, Default)]
+struct Trie {
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    /// True if this node is an entire fragment
+    terminal: bool,
+
+    /// The child node for each byte that extends this prefix. There are
+    /// only ever a handful of distinct bytes, so a list is fine.
+    children: Vec<(u8, usize)>,
+}
+
+impl Trie {
+    fn new<'a>(fragments: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut trie = Trie {
+            nodes: vec![TrieNode::default()],
+        };
 
-        let looped = lazy_format!("(:?{alternations})*");
+        for fragment in fragments {
+            let mut node = 0;
 
-        let bounded = format!("^(:?{looped})$");
+            for &byte in fragment.as_bytes() {
+                node = match trie.child(node, byte) {
+                    Some(child) => child,
+                    None => {
+                        let child = trie.nodes.len();
+                        trie.nodes.push(TrieNode::default());
+                        trie.nodes[node].children.push((byte, child));
+                        child
+                    }
+                };
+            }
+
+            trie.nodes[node].terminal = true;
+        }
 
-        Regex::new(&bounded).expect("there shouldn't be a problem compiling this")
+        trie
+    }
+
+    fn child(&self, node: usize, byte: u8) -> Option<usize> {
+        self.nodes[node]
+            .children
+            .iter()
+            .find(|&&(b, _)| b == byte)
+            .map(|&(_, child)| child)
     }
 }
 
-pub fn part1(input: Input) -> anyhow::Result<usize> {
-    let regex = input.compile_components();
+/// How to find the fragments that are prefixes of a string
+#[derive(Debug, Clone)]
+enum Matcher<'a> {
+    /// Try every fragment in turn
+    Scan(&'a [&'a str]),
 
-    Ok(input
-        .goals
-        .iter()
-        .filter(|goal| regex.is_match(goal))
-        .count())
+    /// Walk down a trie, so that only fragments that actually match are
+    /// visited
+    Trie(Trie),
 }
 
-/// When searching for solutions, we use these keys, which are substrings that
-/// sort by length
-#[derive(Debug, Clone, Copy)]
-struct Key<'a>(&'a str);
+impl Matcher<'_> {
+    /// Call `f` with the length of each fragment that's a prefix of `key`
+    fn for_each_prefix(&self, key: &str, mut f: impl FnMut(usize)) {
+        match self {
+            Matcher::Scan(fragments) => fragments
+                .iter()
+                .filter(|fragment| key.starts_with(*fragment))
+                .for_each(|fragment| f(fragment.len())),
+            Matcher::Trie(trie) => {
+                let mut node = 0;
+
+                for (index, &byte) in key.as_bytes().iter().enumerate() {
+                    match trie.child(node, byte) {
+                        None => return,
+                        Some(child) => node = child,
+                    }
 
-impl Ord for Key<'_> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        cmp_all! {
-            self.0.len(), other.0.len();
-            self.0, other.0;
+                    if trie.nodes[node].terminal {
+                        f(index + 1);
+                    }
+                }
+            }
         }
     }
 }
 
-impl PartialOrd for Key<'_> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(Ord::cmp(self, other))
+/// The matchers that can be requested with `--param matcher=trie|scan`. The
+/// trie is the default; scanning is the original approach, and is kept for
+/// comparison.
+const MATCHERS: [&str; 2] = ["trie", "scan"];
+
+impl Input<'_> {
+    /// Build one of the `MATCHERS`
+    fn matcher_named(&self, name: &str) -> Matcher<'_> {
+        match name {
+            "trie" => Matcher::Trie(Trie::new(self.fragments.iter().copied())),
+            "scan" => Matcher::Scan(&self.fragments),
+            name => unreachable!("unknown matcher {name:?}"),
+        }
+    }
+
+    /// Get the matcher requested with `--param matcher`
+    fn matcher(&self) -> anyhow::Result<Matcher<'_>> {
+        validate::selected("matcher", &MATCHERS).map(|name| self.matcher_named(name))
     }
 }
 
-impl Eq for Key<'_> {}
+/// A suffix of a design, used as a cache key. Its hash is computed
+/// incrementally, from the end of the design, so that hashing a long suffix
+/// doesn't mean reading the whole thing again.
+#[derive(Debug, Clone, Copy)]
+struct Suffix<'d> {
+    text: &'d str,
+    hash: u64,
+}
 
-impl PartialEq for Key<'_> {
+impl Hash for Suffix<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl PartialEq for Suffix<'_> {
     fn eq(&self, other: &Self) -> bool {
-        matches!(Ord::cmp(self, other), Ordering::Equal)
+        self.hash == other.hash && self.text == other.text
     }
 }
 
-pub fn part2(input: Input) -> anyhow::Result<u64> {
-    let mut counts: BTreeMap<Key<'_>, u64> = input
-        .goals
-        .iter()
-        .copied()
-        .map(Key)
-        .zip(repeat(1))
-        .collect();
-
-    loop {
-        let (Key(key), count) = counts
-            .pop_last()
-            .expect("map always contains something until loop terminates");
-
-        if key == "" {
-            return Ok(count);
+impl Eq for Suffix<'_> {}
+
+/// The hash of every suffix of `design`, indexed by where it starts,
+/// including the empty suffix at the end
+fn suffix_hashes(design: &str) -> Vec<u64> {
+    let mut hashes: Vec<u64> = vec![0; design.len() + 1];
+
+    for (position, &byte) in design.as_bytes().iter().enumerate().rev() {
+        hashes[position] = hashes[position + 1]
+            .wrapping_mul(0x100000001b3)
+            .wrapping_add(u64::from(byte));
+    }
+
+    hashes
+}
+
+/// Counts the ways to arrange the fragments into designs. The count for
+/// every suffix of every design is cached, keyed by the suffix itself, so
+/// tails shared between designs are only counted once. Cache lookups count
+/// toward the `day19.cache_hits` and `day19.cache_misses` stats, and each
+/// counter records its final size toward `day19.caches` and
+/// `day19.cached_suffixes` when it's dropped.
+struct ArrangementCounter<'m, 'd> {
+    matcher: &'m Matcher<'m>,
+    cache: HashMap<Suffix<'d>, u64>,
+    hits: Counter,
+    misses: Counter,
+}
+
+impl<'m, 'd> ArrangementCounter<'m, 'd> {
+    fn new(matcher: &'m Matcher<'m>) -> Self {
+        Self {
+            matcher,
+            cache: HashMap::from([(Suffix { text: "", hash: 0 }, 1)]),
+            hits: Counter::new("day19.cache_hits"),
+            misses: Counter::new("day19.cache_misses"),
         }
+    }
 
-        for prefix in &input.fragments {
-            if let Some(suffix) = key.strip_prefix(prefix) {
-                *counts.entry(Key(suffix)).or_default() += count;
+    /// Count the number of ways to build `design`, or None if there are too
+    /// many to fit in a u64. Suffixes are counted shortest first, so that
+    /// whenever a suffix is counted, every shorter suffix it depends on is
+    /// already in the cache.
+    fn count(&mut self, design: &'d str) -> Option<u64> {
+        let hashes = suffix_hashes(design);
+        let suffix = |position: usize| Suffix {
+            text: &design[position..],
+            hash: hashes[position],
+        };
+
+        for position in (0..design.len()).rev() {
+            let key = suffix(position);
+
+            if self.cache.contains_key(&key) {
+                self.hits.increment();
+                continue;
             }
+
+            self.misses.increment();
+
+            let mut count = Some(0u64);
+            self.matcher.for_each_prefix(key.text, |length| {
+                count = count
+                    .and_then(|count| count.checked_add(self.cache[&suffix(position + length)]));
+            });
+
+            self.cache.insert(key, count?);
+        }
+
+        Some(self.cache[&suffix(0)])
+    }
+}
+
+impl Drop for ArrangementCounter<'_, '_> {
+    fn drop(&mut self) {
+        stats::add("day19.caches", 1);
+        stats::add("day19.cached_suffixes", self.cache.len() as u64);
+    }
+}
+
+/// Count the arrangements of every design with the requested matcher; with
+/// `--validate`, check that every matcher gives the same counts.
+fn count_arrangements(input: &Input<'_>) -> anyhow::Result<Vec<u64>> {
+    validate::select(
+        "matcher",
+        &[
+            ("trie", &|| {
+                count_arrangements_with(input, &input.matcher_named("trie"))
+            }),
+            ("scan", &|| {
+                count_arrangements_with(input, &input.matcher_named("scan"))
+            }),
+        ],
+    )
+}
+
+/// Count the arrangements of every design, sharing one cache between them.
+/// Fails if any design has too many arrangements to count in a u64. With
+/// `--param parallel=true`, the designs are instead split between threads,
+/// each with its own cache.
+fn count_arrangements_with(input: &Input<'_>, matcher: &Matcher<'_>) -> anyhow::Result<Vec<u64>> {
+    let counts: Vec<Option<u64>> = match params::get_or("parallel", false)? {
+        false => {
+            let mut counter = ArrangementCounter::new(matcher);
+            input
+                .goals
+                .iter()
+                .map(|design| counter.count(design))
+                .collect()
+        }
+        true => {
+            // Each fold produces the counts for a contiguous run of designs,
+            // and they're collected in order, so the counts stay in order.
+            let chunks: Vec<Vec<Option<u64>>> = input
+                .goals
+                .par_iter()
+                .fold(
+                    || (Vec::new(), ArrangementCounter::new(matcher)),
+                    |(mut counts, mut counter), design| {
+                        counts.push(counter.count(design));
+                        (counts, counter)
+                    },
+                )
+                .map(|(counts, _counter)| counts)
+                .collect();
+
+            chunks.into_iter().flatten().collect()
         }
+    };
+
+    counts
+        .into_iter()
+        .zip(input.goals.iter())
+        .map(|(count, design)| {
+            count.with_context(|| format!("{design} has too many arrangements to count"))
+        })
+        .collect()
+}
+
+/// The total number of arrangements of every design
+fn total_arrangements(counts: &[u64]) -> anyhow::Result<u64> {
+    counts
+        .iter()
+        .try_fold(0u64, |total, &count| total.checked_add(count))
+        .context("the designs have too many arrangements in total to count")
+}
+
+/// Check if `design` can be built from the fragments at all. Unlike
+/// counting its arrangements, this can't overflow, however many there are.
+fn is_buildable(matcher: &Matcher<'_>, design: &str) -> bool {
+    // For each position, whether the rest of the design can be built
+    let mut buildable = vec![false; design.len() + 1];
+    buildable[design.len()] = true;
+
+    for position in (0..design.len()).rev() {
+        matcher.for_each_prefix(&design[position..], |length| {
+            buildable[position] |= buildable[position + length];
+        });
+    }
+
+    buildable[0]
+}
+
+/// Count the designs that can be built with the requested matcher; with
+/// `--validate`, check that every matcher gives the same count.
+fn count_buildable(input: &Input<'_>) -> anyhow::Result<usize> {
+    let count_with = |name| {
+        let matcher = input.matcher_named(name);
+
+        Ok(input
+            .goals
+            .iter()
+            .filter(|design| is_buildable(&matcher, design))
+            .count())
+    };
+
+    validate::select(
+        "matcher",
+        &[
+            ("trie", &|| count_with("trie")),
+            ("scan", &|| count_with("scan")),
+        ],
+    )
+}
+
+/// Find one way to build `design` from the fragments, as the sequence of
+/// fragments in order, or None if it can't be built.
+fn witness<'d>(matcher: &Matcher<'_>, design: &'d str) -> Option<Vec<&'d str>> {
+    // For each position, the length of a fragment starting there that leaves
+    // a buildable suffix, if there is one. The empty suffix at the end is
+    // trivially buildable.
+    let mut choices: Vec<Option<usize>> = vec![None; design.len() + 1];
+    choices[design.len()] = Some(0);
+
+    for position in (0..design.len()).rev() {
+        matcher.for_each_prefix(&design[position..], |length| {
+            if choices[position].is_none() && choices[position + length].is_some() {
+                choices[position] = Some(length);
+            }
+        });
+    }
+
+    let mut position = 0;
+    let mut fragments = Vec::new();
+
+    while position < design.len() {
+        let length = choices[position]?;
+        fragments.push(&design[position..position + length]);
+        position += length;
+    }
+
+    Some(fragments)
+}
+
+/// A design, and one way to build it from the fragments, if there is one
+#[derive(Debug, Clone)]
+pub struct Witness<'a> {
+    pub design: &'a str,
+    pub fragments: Option<Vec<&'a str>>,
+}
+
+impl<'a> Input<'a> {
+    /// Find one decomposition of each design into fragments. See `witness`.
+    pub fn witnesses(&self) -> anyhow::Result<Vec<Witness<'a>>> {
+        let matcher = self.matcher()?;
+
+        Ok(self
+            .goals
+            .iter()
+            .map(|&design| Witness {
+                design,
+                fragments: witness(&matcher, design),
+            })
+            .collect())
+    }
+}
+
+/// Check if `fragment` can be built by concatenating two or more fragments.
+/// Any such arrangement only uses fragments shorter than this one, so it
+/// doesn't matter whether the matcher includes this fragment itself.
+fn is_redundant(matcher: &Matcher<'_>, fragment: &str) -> bool {
+    // For each position, whether the rest of the fragment can be built
+    let mut buildable = vec![false; fragment.len() + 1];
+    buildable[fragment.len()] = true;
+
+    for position in (0..fragment.len()).rev() {
+        matcher.for_each_prefix(&fragment[position..], |length| {
+            // Using the whole fragment in one piece doesn't count
+            if length < fragment.len() && buildable[position + length] {
+                buildable[position] = true;
+            }
+        });
+    }
+
+    buildable[0]
+}
+
+/// The fragments, split into the ones that can be built from other
+/// fragments, and the rest. The rest is the smallest subset of the fragments
+/// that can build everything the whole set can: a redundant fragment is
+/// built from shorter fragments, which are each either in the subset or
+/// built from even shorter ones, and nothing else can build the fragments
+/// in the subset.
+#[derive(Debug, Clone)]
+pub struct FragmentAnalysis<'a> {
+    pub minimal: Vec<&'a str>,
+    pub redundant: Vec<&'a str>,
+}
+
+impl<'a> Input<'a> {
+    pub fn analyze_fragments(&self) -> anyhow::Result<FragmentAnalysis<'a>> {
+        let matcher = self.matcher()?;
+
+        let (redundant, minimal) = self
+            .fragments
+            .iter()
+            .partition(|fragment| is_redundant(&matcher, fragment));
+
+        Ok(FragmentAnalysis { minimal, redundant })
+    }
+}
+
+/// With `--param analyze=true`, print which fragments are redundant to
+/// stderr, and compare the answers for both parts with and without them.
+fn print_requested_analysis(input: &Input<'_>) -> anyhow::Result<()> {
+    if !params::get_or("analyze", false)? {
+        return Ok(());
+    }
+
+    let analysis = input.analyze_fragments()?;
+
+    eprintln!(
+        "{} of {} fragments are redundant: {}",
+        analysis.redundant.len(),
+        input.fragments.len(),
+        analysis.redundant.iter().join_with(", "),
+    );
+    eprintln!(
+        "minimal subset ({} fragments): {}",
+        analysis.minimal.len(),
+        analysis.minimal.iter().join_with(", "),
+    );
+
+    let restricted = Input {
+        fragments: Cow::Owned(analysis.minimal),
+        goals: input.goals.clone(),
+    };
+
+    for (label, input) in [("all fragments", input), ("minimal subset", &restricted)] {
+        let counts = count_arrangements(input)?;
+
+        eprintln!(
+            "{label}: part 1 = {}, part 2 = {}",
+            count_buildable(input)?,
+            total_arrangements(&counts)?,
+        );
+    }
+
+    Ok(())
+}
+
+/// With `--param witness=true`, also print one decomposition of each design
+/// to stderr.
+pub fn part1(input: Input) -> anyhow::Result<usize> {
+    print_requested_analysis(&input)?;
+
+    if params::get_or("witness", false)? {
+        for Witness { design, fragments } in input.witnesses()? {
+            match fragments {
+                Some(fragments) => eprintln!("{design}: {}", fragments.join_with(", ")),
+                None => eprintln!("{design}: impossible"),
+            }
+        }
+    }
+
+    count_buildable(&input)
+}
+
+pub fn part2(input: Input) -> anyhow::Result<u64> {
+    print_requested_analysis(&input)?;
+    let counts = count_arrangements(&input)?;
+    total_arrangements(&counts)
+}
+
+solver!(Input<'a>, usize, u64);
+
+/// Brute-force versions of both parts, which try every fragment at every
+/// position of each design, with no trie and no caching. These take
+/// exponential time, so they're only useful on small inputs.
+#[cfg(feature = "naive")]
+#[allow(dead_code)]
+pub mod naive {
+    use super::Input;
+
+    fn count(fragments: &[&str], design: &str) -> u64 {
+        if design.is_empty() {
+            return 1;
+        }
+
+        fragments
+            .iter()
+            .filter(|fragment| !fragment.is_empty())
+            .filter_map(|fragment| design.strip_prefix(fragment))
+            .map(|rest| count(fragments, rest))
+            .sum()
+    }
+
+    fn counts<'a>(input: &'a Input<'_>) -> impl Iterator<Item = u64> + 'a {
+        input
+            .goals
+            .iter()
+            .map(|design| count(&input.fragments, design))
+    }
+
+    pub fn part1(input: Input) -> anyhow::Result<usize> {
+        Ok(counts(&input).filter(|&count| count > 0).count())
+    }
+
+    pub fn part2(input: Input) -> anyhow::Result<u64> {
+        Ok(counts(&input).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+        r, wr, b, g, bwu, rb, gb, br\n\
+        \n\
+        brwrr\n\
+        bggr\n\
+        gbbr\n\
+        rrbgbr\n\
+        ubwu\n\
+        bwurrg\n\
+        brgr\n\
+        bbrgwb\n";
+
+    #[test]
+    fn arena_parse_solves_the_example() {
+        let arena = Bump::new();
+
+        let input = parse_in(&arena, EXAMPLE).expect("example parses");
+        assert_eq!(part1(input).unwrap(), 6);
+
+        let input = parse_in(&arena, EXAMPLE).expect("example parses");
+        assert_eq!(part2(input).unwrap(), 16);
     }
 }
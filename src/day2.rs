@@ -1,7 +1,10 @@
+//! Day 2: Red-Nosed Reports
+
 use nom::{character::complete::digit1, Parser};
 use nom_supreme::{error::ErrorTree, final_parser::final_parser, ParserExt};
+use serde::Serialize;
 
-use crate::library::{split_parser, Definitely, ITResult, IterExt};
+use crate::library::{solver::solver, split_parser, Definitely, ITResult, IterExt};
 
 #[inline]
 fn ascending_rule(left: i32, right: i32) -> bool {
@@ -13,7 +16,7 @@ fn descending_rule(left: i32, right: i32) -> bool {
     matches!(left - right, 1..4)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 struct Report {
     levels: Vec<i32>,
 }
@@ -42,7 +45,7 @@ fn parse_report(input: &str) -> ITResult<&str, Report> {
         .parse(input)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
     reports: Vec<Report>,
 }
@@ -139,3 +142,5 @@ pub fn part2(input: Input) -> Definitely<usize> {
         .filter(|report| report.is_safe_with_damper())
         .count())
 }
+
+solver!(Input, usize, usize);
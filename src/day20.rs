@@ -1,151 +1,237 @@
+//! Day 20: Race Condition
+
 use std::{
-    collections::{HashMap, HashSet},
-    iter::{self, repeat},
-    mem,
+    cmp::{Ordering, Reverse},
+    collections::HashSet,
 };
 
 use anyhow::Context;
 use gridly::prelude::*;
+use rayon::prelude::*;
+use serde::Serialize;
 
-use crate::library::IterExt;
+use crate::library::{
+    bfs::DistanceField,
+    dump,
+    grid::Rect,
+    histogram::Histogram,
+    manhattan,
+    maze::{Maze, ParseMazeError},
+    params, render,
+    solver::solver,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
-    walls: HashSet<Location>,
-    start: Location,
-    end: Location,
+    #[serde(flatten, serialize_with = "crate::library::inspect::maze")]
+    maze: Maze,
 }
 
 impl TryFrom<&str> for Input {
-    type Error = anyhow::Error;
+    type Error = ParseMazeError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let mut walls = HashSet::new();
-        let mut start = None;
-        let mut end = None;
-
-        for (row, line) in value.lines().map(|line| line.trim()).with_rows(Row(0)) {
-            for (column, &cell) in line.as_bytes().iter().with_columns(Column(0)) {
-                let location = row + column;
-                match cell {
-                    b'#' => {
-                        walls.insert(location);
-                    }
-                    b'S' => {
-                        if start.replace(location).is_some() {
-                            anyhow::bail!("multiple start locations")
-                        }
-                    }
-                    b'E' => {
-                        if end.replace(location).is_some() {
-                            anyhow::bail!("multiple end locations")
-                        }
-                    }
-                    b'.' => continue,
-                    cell => anyhow::bail!("unrecognized cell {cell}", cell = cell as char),
-                }
-            }
-        }
+        Maze::parse(value).map(|maze| Input { maze })
+    }
+}
 
-        Ok(Input {
-            walls,
-            start: start.context("no start location")?,
-            end: end.context("no end location")?,
+/// A single cheat: disabling collisions at `start` and re-enabling them at
+/// `end`, which saves `savings` picoseconds over the best honest route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cheat {
+    pub start: Location,
+    pub end: Location,
+    pub savings: isize,
+}
+
+/// Everything needed to evaluate cheats of up to a certain length on a track
+pub struct Racetrack {
+    from_start: DistanceField,
+    to_end: DistanceField,
+    best: isize,
+    bounds: Rect,
+    max_cheat_distance: isize,
+}
+
+impl Racetrack {
+    pub fn new(input: &Input, max_cheat_distance: isize) -> anyhow::Result<Self> {
+        // The track might branch or loop, so rather than following a single
+        // route, we measure every cell's distance from both ends. A cheat from
+        // `a` to `b` then takes `from_start[a] + cheat + to_end[b]` picoseconds.
+        let from_start = input.maze.distances_from(input.maze.start());
+        let to_end = input.maze.distances_from(input.maze.end());
+
+        dump::artifact("distances-from-start", &from_start);
+        dump::artifact("distances-to-end", &to_end);
+
+        let best = from_start
+            .get(input.maze.end())
+            .context("the end isn't reachable from the start")?;
+
+        Ok(Self {
+            from_start,
+            to_end,
+            best,
+            bounds: input.maze.bounds(),
+            max_cheat_distance,
         })
     }
-}
 
-fn compute_distance_graph_rooted_at(
-    walls: &HashSet<Location>,
-    start: &Location,
-) -> HashMap<Location, isize> {
-    let mut distances = HashMap::new();
+    /// Find every distinct cheat that saves at least `min_savings` picoseconds
+    pub fn cheats(&self, min_savings: isize) -> impl ParallelIterator<Item = Cheat> + '_ {
+        let entrances: Vec<(Location, isize)> = self.from_start.reachable().collect();
 
-    let mut to_explore = HashSet::from([*start]);
+        entrances
+            .into_par_iter()
+            .flat_map_iter(move |(entrance, distance_before_cheat)| {
+                // Cheats of length 0 or 1 can't skip any walls.
+                (2..=self.max_cheat_distance)
+                    .flat_map(move |cheat_distance| {
+                        manhattan::ring_within(entrance, cheat_distance, self.bounds)
+                            .map(move |exit| (exit, cheat_distance))
+                    })
+                    .filter_map(move |(exit, cheat_distance)| {
+                        let distance_after_cheat = self.to_end.get(exit)?;
+                        let savings = self.best
+                            - (distance_before_cheat + cheat_distance + distance_after_cheat);
 
-    for distance in 0.. {
-        let current_step = mem::take(&mut to_explore);
+                        (savings >= min_savings).then_some(Cheat {
+                            start: entrance,
+                            end: exit,
+                            savings,
+                        })
+                    })
+            })
+    }
+}
 
-        if current_step.is_empty() {
-            return distances;
-        }
+/// The first wall a cheat passes through, walking from its start to its end
+/// vertically and then horizontally. A cheat that saves any time can't avoid
+/// walls along any route, so this is only None for cheats that don't help.
+fn first_wall(input: &Input, cheat: &Cheat) -> Option<Location> {
+    let mut location = cheat.start;
 
-        distances.extend(current_step.iter().copied().zip(repeat(distance)));
+    while location != cheat.end {
+        location = location
+            + match (
+                cheat.end.row.cmp(&location.row),
+                cheat.end.column.cmp(&location.column),
+            ) {
+                (Ordering::Less, _) => Up,
+                (Ordering::Greater, _) => Down,
+                (Ordering::Equal, Ordering::Less) => Left,
+                (Ordering::Equal, _) => Right,
+            };
 
-        for &current_location in &current_step {
-            for step in EACH_DIRECTION {
-                let neighbor = current_location + step;
-                if !walls.contains(&neighbor) && !distances.contains_key(&neighbor) {
-                    to_explore.insert(neighbor);
-                }
-            }
+        if input.maze.is_wall(location) {
+            return Some(location);
         }
     }
 
-    panic!("exhausted an infinite iterator")
+    None
 }
 
-fn cheat_vectors_distance(distance: isize) -> impl Iterator<Item = Vector> {
-    EACH_DIRECTION.iter().flat_map(move |&direction| {
-        (0..distance)
-            .map(move |turn_point| (turn_point, distance - turn_point))
-            .map(move |(d1, d2)| direction * d1 + direction.clockwise() * d2)
-    })
+/// Draw the racetrack with a single cheat on it, like the puzzle does: the
+/// first wall it passes through is marked `1`, and the cell where it
+/// rejoins the track is marked `2`.
+pub fn render_cheat(input: &Input, cheat: &Cheat) -> String {
+    let wall = first_wall(input, cheat);
+
+    render::grid(
+        input.maze.bounds().num_rows(),
+        input.maze.bounds().num_columns(),
+        |location| {
+            if Some(location) == wall {
+                '1'
+            } else if location == cheat.end {
+                '2'
+            } else {
+                input.maze.cell(location)
+            }
+        },
+    )
 }
 
-/// Create an iterator of each step in the path, starting at `start`, where each
-/// step is 1 distance away.
-fn route(
-    distance_graph: &HashMap<Location, isize>,
-    start: &Location,
-) -> impl Iterator<Item = Location> {
-    iter::successors(Some(*start), |current_location| {
-        let current_distance = distance_graph.get(current_location)?;
-        EACH_DIRECTION
-            .iter()
-            .map(|&direction| *current_location + direction)
-            .find(|new_location| {
-                distance_graph.get(new_location).copied() == Some(current_distance - 1)
-            })
-    })
+/// Draw the racetrack with the endpoints of every given cheat. Cells where
+/// cheats start are marked `1`, cells where they end are marked `2`, and
+/// cells that are both are marked `*`.
+pub fn render_endpoints(input: &Input, cheats: &[Cheat]) -> String {
+    let starts: HashSet<Location> = cheats.iter().map(|cheat| cheat.start).collect();
+    let ends: HashSet<Location> = cheats.iter().map(|cheat| cheat.end).collect();
+
+    render::grid(
+        input.maze.bounds().num_rows(),
+        input.maze.bounds().num_columns(),
+        |location| match (starts.contains(&location), ends.contains(&location)) {
+            (true, true) => '*',
+            (true, false) => '1',
+            (false, true) => '2',
+            (false, false) => input.maze.cell(location),
+        },
+    )
 }
 
+/// With `--param render=cheat`, draw a single cheat on stderr: the best one,
+/// or the Nth best with `--param cheat=N`. With `--param render=endpoints`,
+/// draw the endpoints of every counted cheat. With `--param render=savings`,
+/// draw a histogram of how much the counted cheats save, like the puzzle's
+/// list of how many cheats save each amount.
+fn print_requested_rendering(
+    input: &Input,
+    racetrack: &Racetrack,
+    min_savings: isize,
+) -> anyhow::Result<()> {
+    let Some(mode) = params::get_raw("render") else {
+        return Ok(());
+    };
+
+    let mut cheats: Vec<Cheat> = racetrack.cheats(min_savings).collect();
+
+    match mode {
+        "cheat" => {
+            cheats.sort_unstable_by_key(|cheat| {
+                (
+                    Reverse(cheat.savings),
+                    (cheat.start.row, cheat.start.column),
+                    (cheat.end.row, cheat.end.column),
+                )
+            });
+
+            let index: usize = params::get_or("cheat", 0)?;
+            let cheat = cheats.get(index).with_context(|| {
+                format!(
+                    "there are only {count} cheats that save at least {min_savings} picoseconds",
+                    count = cheats.len()
+                )
+            })?;
+
+            eprintln!("cheat saves {} picoseconds:", cheat.savings);
+            eprint!("{}", render_cheat(input, cheat));
+        }
+        "endpoints" => eprint!("{}", render_endpoints(input, &cheats)),
+        "savings" => {
+            let savings: Histogram = cheats.iter().map(|cheat| cheat.savings as i64).collect();
+            eprint!("{}", savings.bars(40));
+        }
+        other => {
+            anyhow::bail!("unknown render mode {other:?}; expected cheat, endpoints, or savings")
+        }
+    }
+
+    Ok(())
+}
+
+/// Count the cheats that save at least 100 picoseconds, or some other amount
+/// with `--param threshold=N`
 fn solve(input: &Input, max_cheat_distance: isize) -> anyhow::Result<usize> {
-    let distance_graph = compute_distance_graph_rooted_at(&input.walls, &input.end);
+    let racetrack = Racetrack::new(input, max_cheat_distance)?;
+    let min_savings = params::get_or("threshold", 100)?;
 
     eprintln!("finished computing distance graph");
 
-    let unique_cheats = route(&distance_graph, &input.start)
-        // Create an iterator of each (current_location, candidate_cheat_location)
-        .flat_map(|current_location| {
-            (2..=max_cheat_distance).flat_map(move |cheat_distance| {
-                cheat_vectors_distance(cheat_distance).map(move |cheat_vector| {
-                    (
-                        cheat_distance,
-                        current_location,
-                        current_location + cheat_vector,
-                    )
-                })
-            })
-        })
-        .filter(
-            |&(cheat_distance, ref current_location, ref cheat_location)| {
-                let Some(current_distance) = distance_graph.get(current_location) else {
-                    return false;
-                };
-                let Some(distance_after_cheat) = distance_graph.get(cheat_location) else {
-                    return false;
-                };
-
-                (distance_after_cheat + cheat_distance) <= (current_distance - 100)
-            },
-        )
-        .count();
-
-    Ok(unique_cheats)
-
-    // The problem description claims that there's only one route from start to finish.
-    // We take that to mean that the whole graph is a tree.
+    print_requested_rendering(input, &racetrack, min_savings)?;
+
+    Ok(racetrack.cheats(min_savings).count())
 }
 
 pub fn part1(input: Input) -> anyhow::Result<usize> {
@@ -155,3 +241,119 @@ pub fn part1(input: Input) -> anyhow::Result<usize> {
 pub fn part2(input: Input) -> anyhow::Result<usize> {
     solve(&input, 20)
 }
+
+solver!(Input, usize, usize);
+
+/// A brute-force count of the cheats, which tries every pair of reachable
+/// cells as a cheat's start and end, rather than only the cells within
+/// reach of each start. This is quadratic in the size of the track.
+#[cfg(feature = "naive")]
+#[allow(dead_code)]
+pub mod naive {
+    use std::collections::{HashMap, VecDeque};
+
+    use anyhow::Context;
+    use gridly::prelude::*;
+
+    use super::Input;
+    use crate::library::{manhattan, params};
+
+    /// The distance from `root` to every cell reachable from it
+    fn distances(input: &Input, root: Location) -> HashMap<Location, isize> {
+        let mut distances = HashMap::from([(root, 0)]);
+        let mut queue = VecDeque::from([root]);
+
+        while let Some(location) = queue.pop_front() {
+            let distance = distances[&location];
+
+            for direction in EACH_DIRECTION {
+                let neighbor = location + direction;
+
+                if input.maze.is_open(neighbor) && !distances.contains_key(&neighbor) {
+                    distances.insert(neighbor, distance + 1);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Count the cheats of length 2 through `max_cheat_distance` that save
+    /// at least `min_savings` picoseconds, or None if the end isn't
+    /// reachable from the start
+    pub fn count_cheats(
+        input: &Input,
+        max_cheat_distance: isize,
+        min_savings: isize,
+    ) -> Option<usize> {
+        let from_start = distances(input, input.maze.start());
+        let to_end = distances(input, input.maze.end());
+        let best = *from_start.get(&input.maze.end())?;
+
+        let count = from_start
+            .iter()
+            .flat_map(|(&entrance, &before)| {
+                to_end.iter().filter(move |&(&exit, &after)| {
+                    let length = manhattan::length(exit - entrance);
+
+                    (2..=max_cheat_distance).contains(&length)
+                        && best - (before + length + after) >= min_savings
+                })
+            })
+            .count();
+
+        Some(count)
+    }
+
+    fn solve(input: &Input, max_cheat_distance: isize) -> anyhow::Result<usize> {
+        let min_savings = params::get_or("threshold", 100)?;
+
+        count_cheats(input, max_cheat_distance, min_savings)
+            .context("the end isn't reachable from the start")
+    }
+
+    pub fn part1(input: Input) -> anyhow::Result<usize> {
+        solve(&input, 2)
+    }
+
+    pub fn part2(input: Input) -> anyhow::Result<usize> {
+        solve(&input, 20)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+###############
+#...#...#.....#
+#.#.#.#.#.###.#
+#S#...#.#.#...#
+#######.#.#.###
+#######.#.#...#
+#######.#.###.#
+###..E#...#...#
+###.#######.###
+#...###...#...#
+#.#####.#.###.#
+#.#...#.#.#...#
+#.#.#.#.#.#.###
+#...#...#...###
+###############
+";
+
+    #[test]
+    fn renders_the_best_cheat_like_the_puzzle() {
+        let input = Input::try_from(EXAMPLE).unwrap();
+        let racetrack = Racetrack::new(&input, 2).unwrap();
+        let cheats: Vec<Cheat> = racetrack.cheats(64).collect();
+        let [cheat] = cheats.as_slice() else {
+            panic!("expected exactly one cheat saving 64, got {cheats:?}");
+        };
+
+        let rendering = render_cheat(&input, cheat);
+        assert_eq!(rendering.lines().nth(7), Some("###..21...#...#"));
+    }
+}
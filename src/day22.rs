@@ -0,0 +1,117 @@
+//! Day 22: Monkey Market
+
+use nom::{
+    Parser,
+    character::complete::{digit1, multispace0, multispace1},
+    combinator::eof,
+};
+use nom_supreme::{
+    ParserExt, error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated,
+};
+use serde::Serialize;
+
+use crate::library::{ITResult, solver::solver};
+
+#[derive(Debug, Serialize)]
+pub struct Input {
+    secrets: Vec<u32>,
+}
+
+fn parse_input(input: &str) -> ITResult<&str, Input> {
+    collect_separated_terminated(
+        digit1.parse_from_str_cut::<u32>(),
+        multispace1,
+        multispace0.terminated(eof),
+    )
+    .map(|secrets| Input { secrets })
+    .parse(input)
+}
+
+impl TryFrom<&str> for Input {
+    type Error = ErrorTree<nom_supreme::final_parser::Location>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        final_parser(parse_input)(value)
+    }
+}
+
+/// Each buyer generates this many new secret numbers in a day
+const STEPS: usize = 2000;
+
+/// Secret numbers are always pruned to 24 bits
+const PRUNE_MASK: u32 = (1 << 24) - 1;
+
+fn evolve(secret: u32) -> u32 {
+    let secret = (secret ^ (secret << 6)) & PRUNE_MASK;
+    let secret = secret ^ (secret >> 5);
+    (secret ^ (secret << 11)) & PRUNE_MASK
+}
+
+/// Iterate over a buyer's prices: the ones digit of their initial secret and
+/// of each of the `STEPS` secrets that follow it.
+fn prices(secret: u32) -> impl Iterator<Item = u8> {
+    std::iter::successors(Some(secret), |&secret| Some(evolve(secret)))
+        .take(STEPS + 1)
+        .map(|secret| (secret % 10) as u8)
+}
+
+/// Each price change is in `-9..=9`, so there are 19 possible changes, and a
+/// window of 4 changes can be packed into a dense index below `19^4`.
+const CHANGE_RANGE: usize = 19;
+const WINDOWS: usize = CHANGE_RANGE.pow(4);
+
+pub fn part1(input: Input) -> anyhow::Result<u64> {
+    Ok(input
+        .secrets
+        .iter()
+        .map(|&secret| (0..STEPS).fold(secret, |secret, _| evolve(secret)))
+        .map(u64::from)
+        .sum())
+}
+
+pub fn part2(input: Input) -> anyhow::Result<u32> {
+    // The total number of bananas we'd get from each window of price changes
+    let mut totals = vec![0u32; WINDOWS];
+
+    // The last buyer (plus one, so that 0 means none) to have had each
+    // window. The monkey sells at the *first* occurrence of the window, so
+    // later occurrences from the same buyer are ignored.
+    let mut last_seen = vec![0u32; WINDOWS];
+
+    for (buyer, &secret) in (1..).zip(&input.secrets) {
+        let mut prices = prices(secret);
+        let mut previous = prices.next().expect("prices is never empty");
+        let mut window = 0;
+
+        for (step, price) in prices.enumerate() {
+            let change = (price as usize + 9) - previous as usize;
+            window = (window * CHANGE_RANGE + change) % WINDOWS;
+            previous = price;
+
+            // The first three prices don't have a full window behind them
+            if step >= 3 && last_seen[window] != buyer {
+                last_seen[window] = buyer;
+                totals[window] += u32::from(price);
+            }
+        }
+    }
+
+    Ok(totals.into_iter().max().unwrap_or(0))
+}
+
+solver!(Input, u64, u32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The examples from the puzzle: each part has its own list of buyers
+    #[test]
+    fn examples() {
+        let input = Input::try_from("1\n10\n100\n2024\n").expect("example parses");
+        assert_eq!(part1(input).unwrap(), 37327623);
+
+        let input = Input::try_from("1\n2\n3\n2024\n").expect("example parses");
+        assert_eq!(part2(input).unwrap(), 23);
+    }
+}
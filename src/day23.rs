@@ -0,0 +1,213 @@
+//! Day 23: LAN Party
+
+use std::collections::{HashMap, HashSet};
+
+use nom::{
+    Parser,
+    character::complete::{alpha1, multispace0, multispace1},
+    combinator::eof,
+};
+use nom_supreme::{
+    ParserExt, error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated,
+    tag::complete::tag,
+};
+use serde::Serialize;
+
+use crate::library::{ITResult, answer::Answer, solver::solver};
+
+type Computer<'a> = &'a str;
+
+#[derive(Debug, Serialize)]
+pub struct Input<'a> {
+    connections: Vec<(Computer<'a>, Computer<'a>)>,
+}
+
+fn parse_connection(input: &str) -> ITResult<&str, (Computer<'_>, Computer<'_>)> {
+    alpha1.terminated(tag("-")).and(alpha1.cut()).parse(input)
+}
+
+fn parse_input(input: &str) -> ITResult<&str, Input<'_>> {
+    collect_separated_terminated(parse_connection, multispace1, multispace0.terminated(eof))
+        .map(|connections| Input { connections })
+        .parse(input)
+}
+
+impl<'a> TryFrom<&'a str> for Input<'a> {
+    type Error = ErrorTree<nom_supreme::final_parser::Location>;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        final_parser(parse_input)(value)
+    }
+}
+
+/// An undirected graph of computers, as adjacency sets
+#[derive(Debug, Default)]
+struct Network<'a> {
+    neighbors: HashMap<Computer<'a>, HashSet<Computer<'a>>>,
+}
+
+impl<'a> Network<'a> {
+    fn new(connections: &[(Computer<'a>, Computer<'a>)]) -> Self {
+        let mut network = Self::default();
+
+        for &(a, b) in connections {
+            network.neighbors.entry(a).or_default().insert(b);
+            network.neighbors.entry(b).or_default().insert(a);
+        }
+
+        network
+    }
+
+    fn neighbors(&self, computer: Computer<'a>) -> &HashSet<Computer<'a>> {
+        &self.neighbors[computer]
+    }
+
+    /// Iterate over every set of three mutually connected computers. Each
+    /// triangle is yielded exactly once, in sorted order.
+    fn triangles(&self) -> impl Iterator<Item = [Computer<'a>; 3]> + '_ {
+        self.neighbors.iter().flat_map(move |(&a, a_neighbors)| {
+            a_neighbors
+                .iter()
+                .filter(move |&&b| a < b)
+                .flat_map(move |&b| {
+                    a_neighbors
+                        .intersection(self.neighbors(b))
+                        .filter(move |&&c| b < c)
+                        .map(move |&c| [a, b, c])
+                })
+        })
+    }
+
+    /// Find the largest set of mutually connected computers, with the
+    /// Bron–Kerbosch algorithm (with pivoting).
+    fn maximum_clique(&self) -> Vec<Computer<'a>> {
+        let mut best = Vec::new();
+
+        self.bron_kerbosch(
+            &mut Vec::new(),
+            self.neighbors.keys().copied().collect(),
+            HashSet::new(),
+            &mut best,
+        );
+
+        best
+    }
+
+    /// `clique` is the clique built so far, `candidates` are computers that
+    /// could extend it, and `excluded` are computers that could extend it but
+    /// whose cliques have already been explored.
+    fn bron_kerbosch(
+        &self,
+        clique: &mut Vec<Computer<'a>>,
+        mut candidates: HashSet<Computer<'a>>,
+        mut excluded: HashSet<Computer<'a>>,
+        best: &mut Vec<Computer<'a>>,
+    ) {
+        if candidates.is_empty() {
+            if excluded.is_empty() && clique.len() > best.len() {
+                best.clone_from(clique);
+            }
+
+            return;
+        }
+
+        // Any maximal clique contains either the pivot or one of its
+        // non-neighbors, so we only need to branch on those.
+        let pivot = candidates
+            .iter()
+            .chain(&excluded)
+            .copied()
+            .max_by_key(|&pivot| self.neighbors(pivot).intersection(&candidates).count())
+            .expect("candidates isn't empty");
+
+        let branches: Vec<Computer<'a>> = candidates
+            .difference(self.neighbors(pivot))
+            .copied()
+            .collect();
+
+        for computer in branches {
+            let neighbors = self.neighbors(computer);
+
+            clique.push(computer);
+            self.bron_kerbosch(
+                clique,
+                candidates.intersection(neighbors).copied().collect(),
+                excluded.intersection(neighbors).copied().collect(),
+                best,
+            );
+            clique.pop();
+
+            candidates.remove(computer);
+            excluded.insert(computer);
+        }
+    }
+}
+
+pub fn part1(input: Input) -> anyhow::Result<usize> {
+    let network = Network::new(&input.connections);
+
+    Ok(network
+        .triangles()
+        .filter(|triangle| triangle.iter().any(|computer| computer.starts_with('t')))
+        .count())
+}
+
+pub fn part2(input: Input) -> anyhow::Result<Answer> {
+    let network = Network::new(&input.connections);
+    let mut clique = network.maximum_clique();
+    clique.sort_unstable();
+
+    Ok(Answer::list(clique))
+}
+
+solver!(Input<'a>, usize, Answer);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+kh-tc
+qp-kh
+de-cg
+ka-co
+yn-aq
+qp-ub
+cg-tb
+vc-aq
+tb-ka
+wh-tc
+yn-cg
+kh-ub
+ta-co
+de-co
+tc-td
+tb-wq
+wh-td
+ta-ka
+td-qp
+aq-cg
+wq-ub
+ub-vc
+de-ta
+wq-aq
+wq-vc
+wh-yn
+ka-de
+kh-ta
+co-tc
+wh-qp
+tb-vc
+td-yn
+";
+
+    /// The example network: 7 of its triangles include a computer whose name
+    /// starts with `t`, and its largest clique is `co,de,ka,ta`
+    #[test]
+    fn example_network() {
+        let parse = || Input::try_from(EXAMPLE).expect("example parses");
+
+        assert_eq!(part1(parse()).unwrap(), 7);
+        assert_eq!(part2(parse()).unwrap().to_string(), "co,de,ka,ta");
+    }
+}
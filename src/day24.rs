@@ -0,0 +1,351 @@
+//! Day 24: Crossed Wires
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+
+use nom::{
+    Parser,
+    branch::alt,
+    character::complete::{alphanumeric1, line_ending, multispace0, multispace1, space1},
+    combinator::eof,
+    sequence::tuple,
+};
+use nom_supreme::{
+    ParserExt, error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated,
+    tag::complete::tag,
+};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::library::{ITResult, answer::Answer, dynamic, solver::solver};
+
+type Wire<'a> = &'a str;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+enum Operation {
+    And,
+    Or,
+    Xor,
+}
+
+impl Operation {
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            Operation::And => a & b,
+            Operation::Or => a | b,
+            Operation::Xor => a ^ b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Gate<'a> {
+    inputs: [Wire<'a>; 2],
+    operation: Operation,
+    output: Wire<'a>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Input<'a> {
+    initial: Vec<(Wire<'a>, bool)>,
+    gates: Vec<Gate<'a>>,
+}
+
+fn parse_initial(input: &str) -> ITResult<&str, (Wire<'_>, bool)> {
+    alphanumeric1
+        .terminated(tag(": "))
+        .and(alt((tag("0").value(false), tag("1").value(true))).cut())
+        .parse(input)
+}
+
+fn parse_operation(input: &str) -> ITResult<&str, Operation> {
+    alt((
+        tag("AND").value(Operation::And),
+        tag("OR").value(Operation::Or),
+        tag("XOR").value(Operation::Xor),
+    ))
+    .parse(input)
+}
+
+fn parse_gate(input: &str) -> ITResult<&str, Gate<'_>> {
+    tuple((
+        alphanumeric1.terminated(space1),
+        parse_operation.cut().terminated(space1),
+        alphanumeric1.cut().terminated(tag(" -> ").cut()),
+        alphanumeric1.cut(),
+    ))
+    .map(|(a, operation, b, output)| Gate {
+        inputs: [a, b],
+        operation,
+        output,
+    })
+    .parse(input)
+}
+
+fn parse_input(input: &str) -> ITResult<&str, Input<'_>> {
+    collect_separated_terminated(parse_initial, line_ending, line_ending.and(line_ending))
+        .and(collect_separated_terminated(
+            parse_gate,
+            multispace1,
+            multispace0.terminated(eof),
+        ))
+        .map(|(initial, gates)| Input { initial, gates })
+        .parse(input)
+}
+
+impl<'a> TryFrom<&'a str> for Input<'a> {
+    type Error = ErrorTree<nom_supreme::final_parser::Location>;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        final_parser(parse_input)(value)
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum CircuitError {
+    #[error("wire {0:?} has no initial value and isn't the output of any gate")]
+    Undriven(String),
+
+    #[error("wire {0:?} depends on its own value")]
+    Circular(String),
+
+    #[error("the circuit has {0} output wires, which doesn't fit in a u64")]
+    TooWide(usize),
+}
+
+/// Dynamic task computing the value of a wire, from the initial values and
+/// the gates driving it.
+struct WireValue<'a> {
+    initial: HashMap<Wire<'a>, bool>,
+    drivers: HashMap<Wire<'a>, Gate<'a>>,
+}
+
+impl<'a> dynamic::StatelessTask<Wire<'a>, bool, CircuitError> for WireValue<'a> {
+    fn solve<'sub>(
+        &self,
+        &wire: &Wire<'a>,
+        subtasker: &'sub impl dynamic::Subtask<Wire<'a>, bool>,
+    ) -> Result<bool, dynamic::TaskInterrupt<'sub, Wire<'a>, CircuitError>> {
+        if let Some(&value) = self.initial.get(wire) {
+            return Ok(value);
+        }
+
+        let gate = self.drivers.get(wire).ok_or_else(|| {
+            dynamic::TaskInterrupt::Error(CircuitError::Undriven(wire.to_owned()))
+        })?;
+
+        subtasker.precheck(gate.inputs)?;
+        let [a, b] = gate.inputs;
+
+        Ok(gate
+            .operation
+            .apply(*subtasker.solve(a)?, *subtasker.solve(b)?))
+    }
+}
+
+/// All the `z` output wires in the circuit, from least to most significant
+fn output_wires<'a>(input: &Input<'a>) -> Vec<Wire<'a>> {
+    let mut outputs: Vec<Wire<'a>> = input
+        .gates
+        .iter()
+        .map(|gate| gate.output)
+        .filter(|wire| wire.starts_with('z'))
+        .collect();
+
+    outputs.sort_unstable();
+    outputs
+}
+
+pub fn part1(input: Input) -> Result<u64, CircuitError> {
+    let outputs = output_wires(&input);
+
+    if outputs.len() > 64 {
+        return Err(CircuitError::TooWide(outputs.len()));
+    }
+
+    let task = WireValue {
+        initial: input.initial.iter().copied().collect(),
+        drivers: input
+            .gates
+            .iter()
+            .map(|&gate| (gate.output, gate))
+            .collect(),
+    };
+
+    let mut store = HashMap::new();
+
+    outputs.iter().rev().try_fold(0, |value, &wire| {
+        let bit = dynamic::execute(wire, &task, &mut store).map_err(|err| match err {
+            dynamic::DynamicError::CircularDependency(wire) => {
+                CircuitError::Circular(wire.to_owned())
+            }
+            dynamic::DynamicError::Error(err) => err,
+        })?;
+
+        Ok((value << 1) | u64::from(bit))
+    })
+}
+
+/// A circuit that's meant to be a ripple-carry adder, but with some pairs of
+/// gate outputs swapped. For each bit `i > 0`, a correct adder has:
+///
+/// - `partial = x_i XOR y_i`
+/// - `z_i = partial XOR carry`
+/// - `generate = x_i AND y_i`
+/// - `propagate = partial AND carry`
+/// - `next_carry = generate OR propagate`
+///
+/// Bit 0 is a half adder, so `z00 = x00 XOR y00` and the first carry is
+/// `x00 AND y00`; the carry out of the top bit is the last `z` wire.
+struct Adder<'a> {
+    gates: Vec<Gate<'a>>,
+    swaps: Vec<[Wire<'a>; 2]>,
+}
+
+impl<'a> Adder<'a> {
+    /// Find a gate with this operation that reads from `wire`, and return
+    /// its other input and its output.
+    fn find(&self, operation: Operation, wire: &str) -> Option<(Wire<'a>, Wire<'a>)> {
+        self.gates
+            .iter()
+            .filter(|gate| gate.operation == operation)
+            .find_map(|gate| match gate.inputs {
+                [a, b] if a == wire => Some((b, gate.output)),
+                [a, b] if b == wire => Some((a, gate.output)),
+                _ => None,
+            })
+    }
+
+    /// Find the output of the gate computing `a <operation> b`
+    fn output(&self, operation: Operation, a: &str, b: &str) -> anyhow::Result<Wire<'a>> {
+        match self.find(operation, a) {
+            Some((other, output)) if other == b => Ok(output),
+            _ => anyhow::bail!("there's no gate computing {a} {operation:?} {b}"),
+        }
+    }
+
+    /// Find the wire with this name, if some gate outputs to it
+    fn wire(&self, name: &str) -> anyhow::Result<Wire<'a>> {
+        self.gates
+            .iter()
+            .map(|gate| gate.output)
+            .find(|&output| output == name)
+            .with_context(|| format!("no gate outputs to {name}"))
+    }
+
+    fn swap(&mut self, a: Wire<'a>, b: Wire<'a>) {
+        for gate in &mut self.gates {
+            if gate.output == a {
+                gate.output = b;
+            } else if gate.output == b {
+                gate.output = a;
+            }
+        }
+
+        self.swaps.push([a, b]);
+    }
+
+    /// Make sure `wire` is the output `expected`, swapping it if not
+    fn expect_output(&mut self, wire: Wire<'a>, expected: &str) -> anyhow::Result<()> {
+        if wire != expected {
+            let expected = self.wire(expected)?;
+            self.swap(wire, expected);
+        }
+
+        Ok(())
+    }
+
+    /// Repair bit `bit`, given the (correct) carry into it, and return the
+    /// carry out of it. At most one of each of the bit's wires can have been
+    /// swapped.
+    fn repair_bit(&mut self, bit: usize, carry: Wire<'a>) -> anyhow::Result<Wire<'a>> {
+        let (x, y, z) = (
+            format!("x{bit:02}"),
+            format!("y{bit:02}"),
+            format!("z{bit:02}"),
+        );
+
+        // The sum gate reads both the partial sum and the carry, so whichever
+        // of those we can find it by, the other one should match.
+        let mut partial = self.output(Operation::Xor, &x, &y)?;
+        let mut carry = carry;
+
+        match self.find(Operation::Xor, carry) {
+            Some((other, _)) if other != partial => {
+                self.swap(partial, other);
+                partial = other;
+            }
+            Some(_) => {}
+            None => {
+                let (other, _) = self
+                    .find(Operation::Xor, partial)
+                    .with_context(|| format!("bit {bit}: can't find the sum gate"))?;
+
+                self.swap(carry, other);
+                carry = other;
+            }
+        }
+
+        let sum = self.output(Operation::Xor, partial, carry)?;
+        self.expect_output(sum, &z)?;
+
+        // Likewise, the carry gate reads both the generate and propagate
+        // wires.
+        let generate = self.output(Operation::And, &x, &y)?;
+        let propagate = self.output(Operation::And, partial, carry)?;
+
+        match (
+            self.find(Operation::Or, generate),
+            self.find(Operation::Or, propagate),
+        ) {
+            (Some((other, next_carry)), _) if other == propagate => Ok(next_carry),
+            (Some((other, next_carry)), None) => {
+                self.swap(propagate, other);
+                Ok(next_carry)
+            }
+            (None, Some((other, next_carry))) => {
+                self.swap(generate, other);
+                Ok(next_carry)
+            }
+            _ => anyhow::bail!("bit {bit}: can't find the carry gate"),
+        }
+    }
+}
+
+/// Find the pairs of gate outputs that need to be swapped to make the circuit
+/// a correct adder.
+fn find_swaps<'a>(input: &Input<'a>) -> anyhow::Result<Vec<[Wire<'a>; 2]>> {
+    let bits = input
+        .initial
+        .iter()
+        .filter(|(wire, _)| wire.starts_with('x'))
+        .count();
+
+    let mut adder = Adder {
+        gates: input.gates.clone(),
+        swaps: Vec::new(),
+    };
+
+    let sum = adder.output(Operation::Xor, "x00", "y00")?;
+    adder.expect_output(sum, "z00")?;
+
+    let mut carry = adder.output(Operation::And, "x00", "y00")?;
+
+    for bit in 1..bits {
+        carry = adder.repair_bit(bit, carry)?;
+    }
+
+    adder.expect_output(carry, &format!("z{bits:02}"))?;
+
+    Ok(adder.swaps)
+}
+
+pub fn part2(input: Input) -> anyhow::Result<Answer> {
+    let mut wires: Vec<Wire> = find_swaps(&input)?.into_iter().flatten().collect();
+    wires.sort_unstable();
+
+    Ok(Answer::list(wires))
+}
+
+solver!(Input<'a>, u64, Answer);
@@ -0,0 +1,154 @@
+//! Day 25: Code Chronicle
+
+use std::convert::Infallible;
+
+use nom::{Parser, bytes::complete::take_while1};
+use nom_supreme::{ParserExt, error::ErrorTree, final_parser::final_parser};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::library::{ITResult, solver::solver, split_parser};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Lock,
+    Key,
+}
+
+/// A lock or key, as the height of each of its columns (not counting the
+/// filled row at the top or bottom)
+#[derive(Debug, Clone)]
+struct Schematic {
+    kind: Kind,
+    heights: Vec<usize>,
+
+    /// The number of rows between the top and bottom rows, which is the
+    /// tallest a pin can be
+    space: usize,
+}
+
+#[derive(Debug, Clone, Error)]
+enum SchematicError {
+    #[error("schematic didn't have consistent row lengths")]
+    Ragged,
+
+    #[error("schematic has neither a filled top row (lock) nor a filled bottom row (key)")]
+    Unknown,
+
+    #[error("schematics don't all have the same dimensions")]
+    Mismatched,
+}
+
+impl Schematic {
+    fn from_rows(rows: Vec<Vec<bool>>) -> Result<Self, SchematicError> {
+        let (Some(top), Some(bottom)) = (rows.first(), rows.last()) else {
+            return Err(SchematicError::Unknown);
+        };
+
+        let width = top.len();
+
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(SchematicError::Ragged);
+        }
+
+        let filled = |row: &[bool]| row.iter().all(|&cell| cell);
+
+        let kind = match (filled(top), filled(bottom)) {
+            (true, false) => Kind::Lock,
+            (false, true) => Kind::Key,
+            _ => return Err(SchematicError::Unknown),
+        };
+
+        let heights = (0..width)
+            .map(|column| rows.iter().filter(|row| row[column]).count() - 1)
+            .collect();
+
+        Ok(Self {
+            kind,
+            heights,
+            space: rows.len() - 2,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Input {
+    locks: Vec<Vec<usize>>,
+    keys: Vec<Vec<usize>>,
+    space: usize,
+}
+
+impl Input {
+    fn from_schematics(schematics: Vec<Schematic>) -> Result<Self, SchematicError> {
+        let shape = schematics
+            .first()
+            .map(|schematic| (schematic.heights.len(), schematic.space));
+
+        let mut locks = Vec::new();
+        let mut keys = Vec::new();
+
+        for schematic in schematics {
+            if Some((schematic.heights.len(), schematic.space)) != shape {
+                return Err(SchematicError::Mismatched);
+            }
+
+            match schematic.kind {
+                Kind::Lock => locks.push(schematic.heights),
+                Kind::Key => keys.push(schematic.heights),
+            }
+        }
+
+        Ok(Input {
+            locks,
+            keys,
+            space: shape.map_or(0, |(_, space)| space),
+        })
+    }
+}
+
+/// Parse a row of a schematic, as a list of which cells are filled
+fn parse_row(input: &str) -> ITResult<&str, Vec<bool>> {
+    take_while1(|c| c == '#' || c == '.')
+        .map(|row: &str| row.bytes().map(|cell| cell == b'#').collect())
+        .parse(input)
+}
+
+fn parse_schematic(input: &str) -> ITResult<&str, Schematic> {
+    split_parser(parse_row, "\n")
+        .map_res_cut(Schematic::from_rows)
+        .parse(input)
+}
+
+fn parse_input(input: &str) -> ITResult<&str, Input> {
+    split_parser(parse_schematic, "\n\n")
+        .map_res_cut(Input::from_schematics)
+        .parse(input)
+}
+
+impl TryFrom<&str> for Input {
+    type Error = ErrorTree<nom_supreme::final_parser::Location>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        final_parser(parse_input)(value)
+    }
+}
+
+pub fn part1(input: Input) -> anyhow::Result<usize> {
+    Ok(input
+        .locks
+        .iter()
+        .map(|lock| {
+            input
+                .keys
+                .iter()
+                .filter(|key| lock.iter().zip(*key).all(|(a, b)| a + b <= input.space))
+                .count()
+        })
+        .sum())
+}
+
+pub fn part2(_input: Input) -> anyhow::Result<Infallible> {
+    anyhow::bail!("day 25 has no second puzzle")
+}
+
+solver!(Input, usize, Infallible);
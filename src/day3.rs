@@ -1,3 +1,5 @@
+//! Day 3: Mull It Over
+
 use std::iter;
 
 use memchr::memmem;
@@ -7,7 +9,10 @@ use nom::{
 };
 use nom_supreme::{tag::complete::tag, ParserExt};
 
-use crate::{library::Definitely, parser};
+use crate::{
+    library::{solver::solver, Definitely},
+    parser,
+};
 
 #[inline]
 fn parse_mul(input: &str) -> IResult<&str, (i32, i32), ()> {
@@ -70,3 +75,5 @@ pub fn part2(mut input: &str) -> Definitely<i32> {
         .filter_map(consume_mul_at_point)
         .sum())
 }
+
+solver!(&'a str, i32, i32);
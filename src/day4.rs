@@ -1,11 +1,18 @@
+//! Day 4: Ceres Search
+
+use std::str::FromStr;
+
 use anyhow::Context;
 use gridly::prelude::*;
 use gridly_grids::VecGrid;
+use serde::Serialize;
+use thiserror::Error;
 
-use crate::library::Definitely;
+use crate::library::{grid::GridExt, params, solver::solver};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
+    #[serde(serialize_with = "crate::library::inspect::byte_grid")]
     grid: VecGrid<u8>,
 }
 
@@ -19,51 +26,154 @@ impl TryFrom<&str> for Input {
     }
 }
 
-pub fn part1(input: Input) -> Definitely<usize> {
-    Ok(input
-        .grid
-        .rows()
-        .iter()
-        .flat_map(|row| row.iter_with_locations())
-        // For each location in the grid, iterate over the 8 directions
+/// The directions a word may run in, from its first letter to its last
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directions(Vec<Vector>);
+
+impl Directions {
+    pub fn all() -> Self {
+        Self(TOUCHING_ADJACENCIES.to_vec())
+    }
+
+    pub fn cardinal() -> Self {
+        Self(ORTHOGONAL_ADJACENCIES.to_vec())
+    }
+
+    pub fn diagonal() -> Self {
+        Self(DIAGONAL_ADJACENCIES.to_vec())
+    }
+
+    /// Get the directions requested with `--param directions=...`, or
+    /// `default`. The parameter is `all`, `cardinal`, `diagonal`, or a comma
+    /// separated list of directions, like `right,down-right`.
+    pub fn from_runner(default: Self) -> anyhow::Result<Self> {
+        params::get_or("directions", default)
+    }
+
+    /// The lines the directions run along, each with both of its directions,
+    /// one after the other
+    fn axes(&self) -> impl Iterator<Item = [Vector; 2]> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|&(index, &direction)| !self.0[..index].contains(&-direction))
+            .map(|(_, &direction)| [direction, -direction])
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+#[error(
+    "unrecognized direction {0:?}; expected all, cardinal, diagonal, or a list like up,down-left"
+)]
+pub struct DirectionsError(String);
+
+impl FromStr for Directions {
+    type Err = DirectionsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => return Ok(Self::all()),
+            "cardinal" => return Ok(Self::cardinal()),
+            "diagonal" => return Ok(Self::diagonal()),
+            _ => {}
+        }
+
+        // Each direction is only searched once, however many times it's
+        // listed, so that it isn't counted twice.
+        s.split(',')
+            .try_fold(Vec::new(), |mut directions, name| {
+                let direction =
+                    parse_direction(name.trim()).ok_or_else(|| DirectionsError(name.to_owned()))?;
+
+                if !directions.contains(&direction) {
+                    directions.push(direction);
+                }
+
+                Ok(directions)
+            })
+            .map(Self)
+    }
+}
+
+/// Parse a single direction, like `up` or `down-left`
+fn parse_direction(name: &str) -> Option<Vector> {
+    match name.split_once('-') {
+        None => Direction::from_name(name).map(Direction::unit_vec),
+        Some((vertical, horizontal)) => match (
+            Direction::from_name(vertical)?,
+            Direction::from_name(horizontal)?,
+        ) {
+            (vertical @ (Up | Down), horizontal @ (Left | Right)) => Some(vertical + horizontal),
+            _ => None,
+        },
+    }
+}
+
+/// Check if `word` is spelled out in the grid, starting at `location` and
+/// running in `direction`
+fn reads(grid: &impl Grid<Item = u8>, word: &str, location: Location, direction: Vector) -> bool {
+    word.bytes().zip(0isize..).all(|(byte, offset)| {
+        grid.get(location + (direction * offset))
+            .map(|&cell| cell == byte)
+            .unwrap_or(false)
+    })
+}
+
+/// Count every place `word` appears in the grid, running in any of the
+/// `directions`
+pub fn count_words(grid: &impl Grid<Item = u8>, word: &str, directions: &Directions) -> usize {
+    grid.iter_located()
+        // For each location in the grid, iterate over the directions
         .flat_map(|(location, _cell)| {
-            TOUCHING_ADJACENCIES
+            directions
+                .0
                 .iter()
                 .map(move |&direction| (location, direction))
         })
-        // For each candidate location and direction, check if the word "XMAS"
-        // is present
-        .filter(|&(location, direction)| {
-            "XMAS".bytes().zip(0isize..).all(|(byte, offset)| {
-                input
-                    .grid
-                    .get(location + (direction * offset))
-                    .map(|&cell| cell == byte)
-                    .unwrap_or(false)
+        // For each candidate location and direction, check if the word is
+        // present
+        .filter(|&(location, direction)| reads(grid, word, location, direction))
+        .count()
+}
+
+/// Count the cells where copies of `word`, which must have an odd length,
+/// cross each other: the cell is the middle letter of a copy running along
+/// each axis of `directions`, in either direction. With the diagonal
+/// directions, this is an X-shaped cross, like the puzzle's X-MAS.
+pub fn count_crosses(grid: &impl Grid<Item = u8>, word: &str, directions: &Directions) -> usize {
+    let middle = (word.len() / 2) as isize;
+
+    grid.iter_located()
+        .filter(|&(location, _cell)| {
+            directions.axes().all(|[forward, backward]| {
+                reads(grid, word, location - (forward * middle), forward)
+                    || reads(grid, word, location - (backward * middle), backward)
             })
         })
-        .count())
+        .count()
 }
 
-/// Test that an "X-MAS" appears in the grid at the given location.
-fn test_mas(grid: &impl Grid<Item = u8>, location: Location) -> bool {
-    grid.get(location).copied().ok() == Some(b'A')
-        && [Left, Right].iter().all(|&offset| {
-            let neighbor = Up + offset;
-
-            let above = grid.get(location + neighbor).copied();
-            let below = grid.get(location - neighbor).copied();
+/// Searches every direction, unless `--param directions` says otherwise
+pub fn part1(input: Input) -> anyhow::Result<usize> {
+    let directions = Directions::from_runner(Directions::all())?;
+    Ok(count_words(&input.grid, "XMAS", &directions))
+}
 
-            matches!((above, below), (Ok(b'M'), Ok(b'S')) | (Ok(b'S'), Ok(b'M')))
-        })
+/// Looks for diagonal crosses, unless `--param directions` says otherwise
+pub fn part2(input: Input) -> anyhow::Result<usize> {
+    let directions = Directions::from_runner(Directions::diagonal())?;
+    Ok(count_crosses(&input.grid, "MAS", &directions))
 }
 
-pub fn part2(input: Input) -> Definitely<usize> {
-    Ok(input
-        .grid
-        .rows()
-        .iter()
-        .flat_map(|row| row.iter_with_locations())
-        .filter(|&(location, _cell)| test_mas(&input.grid, location))
-        .count())
+solver!(Input, usize, usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_directions_are_searched_once() {
+        let directions: Directions = "right,left,right".parse().unwrap();
+        assert_eq!(directions.0, [Vector::new(0, 1), Vector::new(0, -1)]);
+    }
 }
@@ -1,21 +1,34 @@
+//! Day 5: Print Queue
+
 use std::{
+    borrow::Cow,
     collections::{HashMap, HashSet},
     mem::swap,
 };
 
+#[cfg(test)]
+use bumpalo::Bump;
+use joinery::JoinableIterator;
+
 use nom::{
-    character::complete::{char, digit1},
     Parser,
+    character::complete::{char, digit1},
 };
-use nom_supreme::{error::ErrorTree, final_parser::final_parser, ParserExt};
+use nom_supreme::{ParserExt, error::ErrorTree, final_parser::final_parser};
+use serde::Serialize;
 
 use crate::{
     express,
-    library::{split_once_parser, split_parser, Definitely, ITResult},
+    library::{
+        ITResult, params,
+        solver::solver,
+        split_once_parser, split_parser,
+        toposort::{Cycle, topological_sort},
+    },
     parser,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 struct PageNumber(u32);
 
 fn parse_page_number(input: &str) -> ITResult<&str, PageNumber> {
@@ -38,17 +51,25 @@ fn parse_rule(input: &str) -> ITResult<&str, Rule> {
     .parse(input)
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize)]
 struct PageRules {
+    #[serde(serialize_with = "crate::library::inspect::sorted")]
     successors: HashSet<PageNumber>,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize)]
 struct RuleSet {
+    #[serde(serialize_with = "crate::library::inspect::sorted_map")]
     rules: HashMap<PageNumber, PageRules>,
 }
 
 impl RuleSet {
+    fn edges(&self) -> impl Iterator<Item = (PageNumber, PageNumber)> + '_ {
+        self.rules
+            .iter()
+            .flat_map(|(&before, rules)| rules.successors.iter().map(move |&after| (before, after)))
+    }
+
     fn is_acceptable(&self, before: PageNumber, after: PageNumber) -> bool {
         match self.rules.get(&after) {
             None => true,
@@ -73,12 +94,14 @@ fn parse_rule_set(input: &str) -> ITResult<&str, RuleSet> {
     split_parser(parse_rule, "\n").parse(input)
 }
 
-#[derive(Debug, Default, Clone)]
-struct Update {
-    pages: Vec<PageNumber>,
+/// An update's pages are either owned, or borrowed from an arena; see
+/// `parse_in`.
+#[derive(Debug, Default, Clone, Serialize)]
+struct Update<'a> {
+    pages: Cow<'a, [PageNumber]>,
 }
 
-impl Update {
+impl Update<'_> {
     fn is_sorted(&self, rules: &RuleSet) -> bool {
         self.pages
             .iter()
@@ -96,7 +119,7 @@ impl Update {
     }
 
     fn sort_via_rules(&mut self, rules: &RuleSet) {
-        sort_via_rules(&mut self.pages, rules);
+        sort_via_rules(self.pages.to_mut(), rules);
     }
 }
 
@@ -130,34 +153,37 @@ fn sort_head_via_rules(page: &mut PageNumber, tail: &mut [PageNumber], rules: &R
     }
 }
 
-impl Extend<PageNumber> for Update {
+impl Extend<PageNumber> for Update<'_> {
     fn extend<T: IntoIterator<Item = PageNumber>>(&mut self, iter: T) {
-        self.pages.extend(iter)
+        self.pages.to_mut().extend(iter)
     }
 }
 
-fn parse_update(input: &str) -> ITResult<&str, Update> {
+fn parse_update(input: &str) -> ITResult<&str, Update<'static>> {
     split_parser(parse_page_number, ",").parse(input)
 }
 
-fn parse_updates(input: &str) -> ITResult<&str, Vec<Update>> {
+fn parse_updates(input: &str) -> ITResult<&str, Vec<Update<'static>>> {
     split_parser(parse_update, "\n").parse(input)
 }
 
-#[derive(Debug)]
-pub struct Input {
+#[derive(Debug, Serialize)]
+pub struct Input<'a> {
     rules: RuleSet,
-    updates: Vec<Update>,
+    updates: Cow<'a, [Update<'a>]>,
 }
 
-fn parse_input(input: &str) -> ITResult<&str, Input> {
+fn parse_input(input: &str) -> ITResult<&str, Input<'static>> {
     split_once_parser(parse_rule_set, "\n\n")
         .and(parse_updates)
-        .map(|(rules, updates)| Input { rules, updates })
+        .map(|(rules, updates)| Input {
+            rules,
+            updates: Cow::Owned(updates),
+        })
         .parse(input)
 }
 
-impl TryFrom<&str> for Input {
+impl TryFrom<&str> for Input<'static> {
     type Error = ErrorTree<nom_supreme::final_parser::Location>;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
@@ -165,7 +191,134 @@ impl TryFrom<&str> for Input {
     }
 }
 
-pub fn part1(input: Input) -> Definitely<u32> {
+/// Parse the input with every update's pages, and the list of updates
+/// itself, allocated in `arena`, rather than each in a `Vec` of its own.
+/// The rules are still in a `HashMap`. Compared against the usual parser in
+/// the benchmarks, which
+/// Cargo builds under `cfg(test)`, like the tests.
+#[cfg(test)]
+pub(crate) fn parse_in<'a>(
+    arena: &'a Bump,
+    input: &'a str,
+) -> Result<Input<'a>, ErrorTree<nom_supreme::final_parser::Location>> {
+    use crate::library::split_parser_in;
+
+    let update = split_parser_in(arena, parse_page_number, ",").map(|pages| Update {
+        pages: Cow::Borrowed(pages),
+    });
+
+    let parse_input = split_once_parser(parse_rule_set, "\n\n")
+        .and(split_parser_in(arena, update, "\n"))
+        .map(|(rules, updates)| Input {
+            rules,
+            updates: Cow::Borrowed(updates),
+        });
+
+    final_parser(parse_input)(input)
+}
+
+/// How an update compares to a global ordering of every page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateCheck {
+    /// The update's pages are already in the global order
+    pub in_global_order: bool,
+
+    /// Sorting the update with the rules puts its pages in the global order
+    pub sort_agrees: bool,
+}
+
+impl Input<'_> {
+    /// One ordering of every page mentioned in the rules that satisfies all
+    /// of them, if the rules don't have a cycle. Real inputs usually do;
+    /// they're only guaranteed to be consistent within each update.
+    pub fn global_order(&self) -> Result<Vec<u32>, Cycle<u32>> {
+        topological_sort(
+            [],
+            self.rules
+                .edges()
+                .map(|(PageNumber(before), PageNumber(after))| (before, after)),
+        )
+    }
+
+    /// Check each update against a global ordering from `global_order`
+    pub fn check_updates(&self, order: &[u32]) -> Vec<UpdateCheck> {
+        let position: HashMap<u32, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(index, &page)| (page, index))
+            .collect();
+
+        let in_global_order = |pages: &[PageNumber]| {
+            pages.is_sorted_by_key(|PageNumber(page)| position.get(page).copied())
+        };
+
+        self.updates
+            .iter()
+            .map(|update| {
+                let mut sorted = update.clone();
+                sorted.sort_via_rules(&self.rules);
+
+                UpdateCheck {
+                    in_global_order: in_global_order(&update.pages),
+                    sort_agrees: in_global_order(&sorted.pages),
+                }
+            })
+            .collect()
+    }
+}
+
+/// With `--param order=true`, print a global ordering of every page to
+/// stderr, and how each update compares to it.
+fn print_requested_order(input: &Input<'_>) -> anyhow::Result<()> {
+    if !params::get_or("order", false)? {
+        return Ok(());
+    }
+
+    let order = match input.global_order() {
+        Ok(order) => order,
+        Err(Cycle { remaining }) => {
+            eprintln!(
+                "no global order: the rules have a cycle; {} pages can't be placed: {}",
+                remaining.len(),
+                remaining.iter().join_with(","),
+            );
+            return Ok(());
+        }
+    };
+
+    eprintln!(
+        "global order of {} pages: {}",
+        order.len(),
+        order.iter().join_with(",")
+    );
+
+    for (update, check) in input.updates.iter().zip(input.check_updates(&order)) {
+        let pages = update
+            .pages
+            .iter()
+            .map(|PageNumber(page)| page)
+            .join_with(",");
+
+        match check {
+            UpdateCheck {
+                in_global_order: true,
+                ..
+            } => eprintln!("{pages}: in order"),
+            UpdateCheck {
+                sort_agrees: true, ..
+            } => eprintln!("{pages}: out of order; sorting agrees with the global order"),
+            UpdateCheck { .. } => {
+                eprintln!("{pages}: out of order; sorting DISAGREES with the global order")
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn part1(input: Input) -> anyhow::Result<u32> {
+    print_requested_order(&input)?;
+
     Ok(input
         .updates
         .iter()
@@ -175,9 +328,12 @@ pub fn part1(input: Input) -> Definitely<u32> {
         .sum())
 }
 
-pub fn part2(mut input: Input) -> Definitely<u32> {
+pub fn part2(mut input: Input) -> anyhow::Result<u32> {
+    print_requested_order(&input)?;
+
     let sum = input
         .updates
+        .to_mut()
         .iter_mut()
         .filter(|update| !update.is_sorted(&input.rules))
         .map(|update| express!(update.sort_via_rules(&input.rules)))
@@ -187,3 +343,5 @@ pub fn part2(mut input: Input) -> Definitely<u32> {
 
     Ok(sum)
 }
+
+solver!(Input<'static>, u32, u32);
@@ -1,88 +1,214 @@
-use std::collections::HashSet;
+//! Day 6: Guard Gallivant
 
-use anyhow::{Context, bail};
+use std::{collections::HashSet, iter};
+
+use anyhow::bail;
 use gridly::prelude::*;
 use gridly_grids::SparseGrid;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::Serialize;
 
-use crate::library::IterExt;
+use crate::library::{
+    cancel,
+    gpu::{self, Offload},
+    grid::{arrow, located_chars},
+    progress::{self, ParallelProgressIterator},
+    solver::solver,
+    visualize::{self, Frame, Visualize},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Obstacle;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 struct Guard {
+    #[serde(serialize_with = "crate::library::inspect::location")]
     position: Location,
+    #[serde(serialize_with = "crate::library::inspect::direction")]
     direction: Direction,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
+    #[serde(serialize_with = "crate::library::inspect::sparse_grid")]
     grid: SparseGrid<Option<Obstacle>>,
-    guard: Guard,
+
+    /// Every guard, in reading order, which is also the order in which
+    /// they get priority when they both want to move into the same cell
+    guards: Vec<Guard>,
 }
 
 impl TryFrom<&str> for Input {
     type Error = anyhow::Error;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let mut guard_position = None;
+        let mut guards = Vec::new();
         let mut grid = SparseGrid::new((Rows(0), Columns(0)));
 
-        for (row, line) in value.lines().with_rows(Row(0)) {
-            for (column, cell) in line.as_bytes().iter().copied().with_columns(Column(0)) {
-                let location = row.combine(column);
-
-                match cell {
-                    b'.' => continue,
-                    b'^' => {
-                        if guard_position.replace(location).is_some() {
-                            anyhow::bail!("multiple guards found in grid")
-                        }
-                    }
-                    b'#' => {
-                        grid.insert(location, Some(Obstacle));
-                    }
-                    cell => anyhow::bail!("unrecognized cell {:?} as {location:?}", cell as char),
+        for (location, cell) in located_chars(value) {
+            // Inserting an empty cell stores nothing, but it does grow the
+            // grid to cover it. The lab's edges are wherever the drawing's
+            // are, not just as far out as its obstacles reach.
+            grid.insert(location, None);
+
+            match cell {
+                '.' => continue,
+                '#' => {
+                    grid.insert(location, Some(Obstacle));
                 }
+                cell => match EACH_DIRECTION
+                    .into_iter()
+                    .find(|&direction| arrow(direction) == cell)
+                {
+                    Some(direction) => guards.push(Guard {
+                        position: location,
+                        direction,
+                    }),
+                    None => anyhow::bail!("unrecognized cell {cell:?} as {location:?}"),
+                },
             }
         }
 
-        let guard_position = guard_position.context("no guard was found in the grid")?;
+        if guards.is_empty() {
+            anyhow::bail!("no guard was found in the grid")
+        }
 
-        Ok(Input {
-            grid,
-            guard: Guard {
-                position: guard_position,
-                direction: Up,
-            },
-        })
+        Ok(Input { grid, guards })
     }
 }
 
-pub fn part1(Input { mut guard, grid }: Input) -> anyhow::Result<usize> {
-    let mut seen_places = HashSet::new();
+/// Move every guard still in the lab one step, all at the same time. Each
+/// guard turns clockwise until it faces a cell with neither an obstacle nor
+/// another guard in it, as of the start of the step, and then steps
+/// forward. If two guards step toward the same cell, only the one earlier in
+/// `guards` moves; the other stays where it is, without turning. A guard
+/// boxed in by other guards waits for them to move. Guards that step out of
+/// the lab are removed.
+fn step(grid: &impl Grid<Item = Option<Obstacle>>, guards: &mut Vec<Guard>) -> anyhow::Result<()> {
+    let is_obstacle = |location| grid.get(location).ok().copied() == Some(Some(Obstacle));
+    let occupied = |location| guards.iter().any(|guard| guard.position == location);
 
-    loop {
-        seen_places.insert(guard.position);
+    let moves: Vec<Option<Guard>> = guards
+        .iter()
+        .map(|guard| {
+            let turns = [Rotation::None, Clockwise, Rotation::Flip, Anticlockwise];
 
-        match [Rotation::None, Clockwise, Rotation::Flip, Anticlockwise]
-            .into_iter()
-            .find_map(|turn| {
+            if turns
+                .iter()
+                .all(|&turn| is_obstacle(guard.position + guard.direction.rotate(turn)))
+            {
+                bail!("No locations near the guard were available")
+            }
+
+            Ok(turns.into_iter().find_map(|turn| {
                 let direction = guard.direction.rotate(turn);
-                let destination = guard.position + direction;
-                (grid.get(destination).ok().copied() != Some(Some(Obstacle)))
-                    .then_some((destination, direction))
-            }) {
-            None => bail!("No locations near the guard were available"),
-            Some((position, direction)) => match grid.location_in_bounds(position) {
-                false => break Ok(seen_places.len()),
-                true => {
-                    guard.position = position;
-                    guard.direction = direction;
-                }
-            },
+                let position = guard.position + direction;
+                (!is_obstacle(position) && !occupied(position)).then_some(Guard {
+                    position,
+                    direction,
+                })
+            }))
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let mut claimed = Vec::with_capacity(moves.len());
+
+    let next = guards
+        .iter()
+        .zip(moves)
+        .filter_map(|(&guard, next)| match next {
+            None => Some(guard),
+            Some(next) if claimed.contains(&next.position) => Some(guard),
+            Some(next) => {
+                claimed.push(next.position);
+                grid.location_in_bounds(next.position).then_some(next)
+            }
+        });
+
+    *guards = next.collect();
+    Ok(())
+}
+
+enum Outcome {
+    Loop,
+    Exit,
+}
+
+/// Run the guards until they've all left the lab, calling `visit` with
+/// every guard's state at every step, or until they get stuck in a loop.
+/// Loops are found with Brent's algorithm: the guards' states are
+/// occasionally saved, at ever longer intervals, and they're in a loop once
+/// they get back to a saved state.
+fn patrol(
+    grid: &impl Grid<Item = Option<Obstacle>>,
+    mut guards: Vec<Guard>,
+    mut visit: impl FnMut(&Guard),
+) -> anyhow::Result<Outcome> {
+    let mut saved = guards.clone();
+    let mut interval: usize = 1;
+    let mut steps = 0;
+
+    loop {
+        guards.iter().for_each(&mut visit);
+
+        if guards.is_empty() {
+            return Ok(Outcome::Exit);
         }
+
+        step(grid, &mut guards)?;
+        steps += 1;
+
+        if guards == saved {
+            return Ok(Outcome::Loop);
+        }
+
+        if steps == interval {
+            saved.clone_from(&guards);
+            interval *= 2;
+            steps = 0;
+        }
+    }
+}
+
+/// The cells visited by any guard, before they all leave the lab or get
+/// stuck in a loop
+pub fn part1(Input { guards, grid }: Input) -> anyhow::Result<usize> {
+    let mut seen_places = HashSet::new();
+    patrol(&grid, guards, |guard| {
+        seen_places.insert(guard.position);
+    })?;
+
+    visualize::show(&Patrol {
+        grid: &grid,
+        visited: &seen_places,
+    })?;
+
+    Ok(seen_places.len())
+}
+
+/// The cells the guards visit before leaving the lab, drawn with an `X` like
+/// the puzzle does
+struct Patrol<'a> {
+    grid: &'a SparseGrid<Option<Obstacle>>,
+    visited: &'a HashSet<Location>,
+}
+
+impl Visualize for Patrol<'_> {
+    fn frames(&self) -> impl Iterator<Item = Frame> + '_ {
+        let mut frame =
+            Frame::from_fn(
+                self.grid.num_rows(),
+                self.grid.num_columns(),
+                |location| match self.grid.get(location) {
+                    Ok(Some(Obstacle)) => '#',
+                    _ => '.',
+                },
+            );
+
+        frame.mark(self.visited.iter().copied(), 'X');
+
+        let title = format!("{} distinct positions", self.visited.len());
+        iter::once(frame.with_title(title))
     }
 }
 
@@ -115,66 +241,134 @@ impl<G: Grid<Item = Option<Obstacle>>> Grid for ExtraObstacle<G> {
     }
 }
 
-enum Outcome {
-    Loop,
-    Exit,
+/// The search for places to put an extra obstacle that trap any of the
+/// guards in a loop. Every cell is tried independently, so on the GPU, each
+/// one gets its own invocation.
+struct LoopSearch<'a> {
+    grid: &'a SparseGrid<Option<Obstacle>>,
+    guards: &'a [Guard],
 }
 
-fn detect_loop(
-    grid: &impl Grid<Item = Option<Obstacle>>,
-    mut guard: Guard,
-) -> anyhow::Result<Outcome> {
-    let mut seen_states = HashSet::new();
+#[cfg(feature = "gpu")]
+const LOOP_SEARCH_SHADER: &str = include_str!("shaders/day6.wgsl");
 
-    loop {
-        if !seen_states.insert(guard) {
-            return Ok(Outcome::Loop);
-        }
+impl Offload for LoopSearch<'_> {
+    type Output = i32;
 
-        match [Rotation::None, Clockwise, Rotation::Flip, Anticlockwise]
-            .into_iter()
-            .find_map(|turn| {
-                let direction = guard.direction.rotate(turn);
-                let destination = guard.position + direction;
-                (grid.get(destination).ok().copied() != Some(Some(Obstacle)))
-                    .then_some((destination, direction))
-            }) {
-            None => bail!("No locations near the guard were available"),
-            Some((position, direction)) => match grid.location_in_bounds(position) {
-                false => break Ok(Outcome::Exit),
-                true => {
-                    guard.position = position;
-                    guard.direction = direction;
-                }
-            },
-        }
+    fn cpu(&self) -> anyhow::Result<i32> {
+        let Self { grid, guards } = *self;
+        let cells = grid.num_rows().0 * grid.num_columns().0;
+
+        // Why pay for all those cores if we're not gonna use 'em
+        (0..grid.num_rows().0)
+            .into_par_iter()
+            .map(Row)
+            .flat_map(|row| {
+                (0..grid.num_columns().0)
+                    .into_par_iter()
+                    .map(Column)
+                    .map(move |column| Location::new(row, column))
+            })
+            .progress_with(progress::bar(cells as u64, "obstacle candidates"))
+            .map(|location| ExtraObstacle { grid, location })
+            .map(|grid| {
+                cancel::check()?;
+                patrol(&grid, guards.to_vec(), |_| {})
+            })
+            .try_fold(
+                || 0,
+                |count, outcome| {
+                    outcome.map(|outcome| match outcome {
+                        Outcome::Loop => count + 1,
+                        Outcome::Exit => count,
+                    })
+                },
+            )
+            .try_reduce(|| 0, |a, b| Ok(a + b))
+    }
+
+    #[cfg(feature = "gpu")]
+    fn gpu(&self, gpu: &gpu::Gpu) -> anyhow::Result<i32> {
+        // The shader only knows how to simulate a single guard
+        let Self {
+            grid,
+            guards: &[guard],
+        } = *self
+        else {
+            return self.cpu();
+        };
+
+        let direction: u32 = match guard.direction {
+            Up => 0,
+            Right => 1,
+            Down => 2,
+            Left => 3,
+        };
+
+        // The shader reads these as i32, except for the direction
+        let params = [
+            grid.num_rows().0 as i32 as u32,
+            grid.num_columns().0 as i32 as u32,
+            guard.position.row.0 as i32 as u32,
+            guard.position.column.0 as i32 as u32,
+            direction,
+        ];
+
+        let obstacles: Vec<u32> = grid
+            .rows()
+            .iter()
+            .flat_map(|row| row.iter())
+            .map(|cell| cell.is_some().into())
+            .collect();
+
+        let cells = obstacles.len();
+        let outcomes = gpu.compute(
+            LOOP_SEARCH_SHADER,
+            &[&params, &obstacles],
+            cells,
+            cells.try_into()?,
+        )?;
+
+        // See the outcome constants in the shader
+        outcomes
+            .iter()
+            .try_fold(0, |count, &outcome| match outcome {
+                0 => Ok(count),
+                1 => Ok(count + 1),
+                _ => bail!("No locations near the guard were available"),
+            })
     }
 }
 
-pub fn part2(Input { grid, guard }: Input) -> anyhow::Result<i32> {
-    // Why pay for all those cores if we're not gonna use 'em
-    (0..grid.num_rows().0)
-        .into_par_iter()
-        .map(Row)
-        .flat_map(|row| {
-            (0..grid.num_columns().0)
-                .into_par_iter()
-                .map(Column)
-                .map(move |column| Location::new(row, column))
-        })
-        .map(|location| ExtraObstacle {
-            grid: &grid,
-            location,
-        })
-        .map(|grid| detect_loop(&grid, guard))
-        .try_fold(
-            || 0,
-            |count, outcome| {
-                outcome.map(|outcome| match outcome {
-                    Outcome::Loop => count + 1,
-                    Outcome::Exit => count,
-                })
-            },
-        )
-        .try_reduce(|| 0, |a, b| Ok(a + b))
+/// Runs on the GPU with `--gpu`, if there's only one guard
+pub fn part2(Input { grid, guards }: Input) -> anyhow::Result<i32> {
+    gpu::run(&LoopSearch {
+        grid: &grid,
+        guards: &guards,
+    })
+}
+
+solver!(Input, usize, i32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visited(text: &str) -> usize {
+        part1(Input::try_from(text).expect("lab parses")).unwrap()
+    }
+
+    /// Two guards walking toward each other; the lab has no obstacles at
+    /// all, so its size has to come from the drawing
+    #[test]
+    fn guards_in_an_empty_lab() {
+        assert_eq!(visited(".....\n.>.<.\n....."), 5);
+    }
+
+    /// The only obstacle is in the top row, but the guard walks the row
+    /// below it, all the way to the edge of the lab
+    #[test]
+    fn guard_beside_the_only_obstacle() {
+        assert_eq!(visited("....#\n.>...\n....."), 4);
+    }
 }
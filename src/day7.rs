@@ -1,27 +1,74 @@
+//! Day 7: Bridge Repair
+
+use std::str::FromStr;
+
 use nom::{
+    Parser,
     branch::alt,
     character::complete::{digit1, newline, space1},
     combinator::{eof, success},
-    Parser,
 };
 use nom_supreme::{
-    error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated,
-    tag::complete::tag, ParserExt,
+    ParserExt, error::ErrorTree, final_parser::final_parser, multi::collect_separated_terminated,
+    tag::complete::tag,
 };
+use serde::Serialize;
+use thiserror::Error;
 
-use crate::library::{Definitely, ITResult};
+use crate::library::{ITResult, params, solver::solver};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 struct Equation {
     value: i64,
     operands: Vec<i64>,
 }
 
 impl Equation {
-    fn valid(&self, allow_concat: bool) -> bool {
-        match self.operands.split_last() {
-            None => false,
-            Some((&tail, list)) => matches(self.value, list, tail, allow_concat),
+    fn valid(&self, allow_concat: bool, evaluation: Evaluation) -> bool {
+        match (self.operands.split_last(), evaluation) {
+            (None, _) => false,
+            (Some((&tail, list)), Evaluation::LeftToRight) => {
+                matches(self.value, list, tail, allow_concat)
+            }
+            (Some((&tail, list)), Evaluation::Precedence) => {
+                matches_precedence(self.value, list, tail, 1, allow_concat)
+            }
+        }
+    }
+}
+
+/// How the operators in an equation are evaluated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Evaluation {
+    /// Strictly left to right, as the puzzle specifies
+    #[default]
+    LeftToRight,
+
+    /// `*` binds tighter than `+`. Concatenation binds tightest of all, since
+    /// it builds a single number out of digits.
+    Precedence,
+}
+
+impl Evaluation {
+    /// Get the evaluation order requested with
+    /// `--param evaluation=left-to-right|precedence`
+    pub fn from_runner() -> anyhow::Result<Self> {
+        params::get_or("evaluation", Self::default())
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("evaluation must be left-to-right or precedence, not {0:?}")]
+pub struct EvaluationError(String);
+
+impl FromStr for Evaluation {
+    type Err = EvaluationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left-to-right" => Ok(Evaluation::LeftToRight),
+            "precedence" => Ok(Evaluation::Precedence),
+            s => Err(EvaluationError(s.to_owned())),
         }
     }
 }
@@ -42,7 +89,7 @@ fn parse_equation(input: &str) -> ITResult<&str, Equation> {
         .parse(input)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
     equations: Vec<Equation>,
 }
@@ -82,6 +129,36 @@ fn matches(target: i64, list: &[i64], tail: i64, allow_concat: bool) -> bool {
     }
 }
 
+/// Like `matches`, but with `*` binding tighter than `+`. Working from the
+/// right, a `+` closes off the rightmost term, which can then be subtracted
+/// from the target. A `*` can't be undone by dividing the target, though,
+/// since the terms to its left haven't been subtracted yet; instead, the
+/// product of the factors of the open term is carried along as `factor`, and
+/// `tail` is its next factor. Concatenation binds tightest, and it's
+/// associative, so it just merges `tail` into the operand to its left.
+fn matches_precedence(
+    target: i64,
+    list: &[i64],
+    tail: i64,
+    factor: i64,
+    allow_concat: bool,
+) -> bool {
+    let Some(term) = tail.checked_mul(factor).filter(|&term| term <= target) else {
+        return false;
+    };
+
+    let Some((&next, list)) = list.split_last() else {
+        return term == target;
+    };
+
+    matches_precedence(target - term, list, next, 1, allow_concat)
+        || matches_precedence(target, list, next, term, allow_concat)
+        || (allow_concat
+            && concat(next, tail).is_some_and(|merged| {
+                matches_precedence(target, list, merged, factor, allow_concat)
+            }))
+}
+
 pub fn count_digits(value: i64) -> u32 {
     match value {
         0 => 1,
@@ -97,19 +174,105 @@ fn unconcat(target: i64, value: i64) -> Option<i64> {
     (diff % factor == 0).then(|| diff / factor)
 }
 
-fn solve(input: &Input, allow_concat: bool) -> i64 {
+/// The digits of `head` followed by the digits of `tail`, if it fits
+fn concat(head: i64, tail: i64) -> Option<i64> {
+    head.checked_mul(10i64.checked_pow(count_digits(tail))?)?
+        .checked_add(tail)
+}
+
+/// The total of the test values of the equations that can be made true
+pub fn calibration(input: &Input, allow_concat: bool, evaluation: Evaluation) -> i64 {
     input
         .equations
         .iter()
-        .filter(|eq| eq.valid(allow_concat))
+        .filter(|eq| eq.valid(allow_concat, evaluation))
         .map(|eq| eq.value)
         .sum()
 }
 
-pub fn part1(input: Input) -> Definitely<i64> {
-    Ok(solve(&input, false))
+pub fn part1(input: Input) -> anyhow::Result<i64> {
+    Ok(calibration(&input, false, Evaluation::from_runner()?))
 }
 
-pub fn part2(input: Input) -> Definitely<i64> {
-    Ok(solve(&input, true))
+pub fn part2(input: Input) -> anyhow::Result<i64> {
+    Ok(calibration(&input, true, Evaluation::from_runner()?))
+}
+
+solver!(Input, i64, i64);
+
+/// Brute-force versions of the calibration, which evaluate every possible
+/// assignment of operators, front to back. These take exponential time, so
+/// they're only useful on short equations.
+#[cfg(feature = "naive")]
+#[allow(dead_code)]
+pub mod naive {
+    use itertools::Itertools;
+
+    use super::{Evaluation, Input, concat};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Operator {
+        Add,
+        Multiply,
+        Concat,
+    }
+
+    /// Evaluate `first`, followed by each operator and operand in turn, or
+    /// None if it overflows
+    pub fn evaluate(first: i64, rest: &[(Operator, i64)], evaluation: Evaluation) -> Option<i64> {
+        match evaluation {
+            Evaluation::LeftToRight => {
+                rest.iter()
+                    .try_fold(first, |value, &(op, operand)| match op {
+                        Operator::Add => value.checked_add(operand),
+                        Operator::Multiply => value.checked_mul(operand),
+                        Operator::Concat => concat(value, operand),
+                    })
+            }
+            Evaluation::Precedence => {
+                // The sum of the closed terms, the product of the closed
+                // factors of the open term, and the open factor
+                let (sum, product, factor) = rest.iter().try_fold(
+                    (0, 1, first),
+                    |(sum, product, factor): (i64, i64, i64), &(op, operand)| match op {
+                        Operator::Add => {
+                            Some((sum.checked_add(product.checked_mul(factor)?)?, 1, operand))
+                        }
+                        Operator::Multiply => Some((sum, product.checked_mul(factor)?, operand)),
+                        Operator::Concat => Some((sum, product, concat(factor, operand)?)),
+                    },
+                )?;
+
+                sum.checked_add(product.checked_mul(factor)?)
+            }
+        }
+    }
+
+    pub fn calibration(input: &Input, allow_concat: bool, evaluation: Evaluation) -> i64 {
+        let operators: &[Operator] = match allow_concat {
+            false => &[Operator::Add, Operator::Multiply],
+            true => &[Operator::Add, Operator::Multiply, Operator::Concat],
+        };
+
+        input
+            .equations
+            .iter()
+            .filter(|eq| {
+                let Some((&first, rest)) = eq.operands.split_first() else {
+                    return false;
+                };
+
+                rest.iter()
+                    .map(|_| operators.iter().copied())
+                    .multi_cartesian_product()
+                    .any(|ops| {
+                        let rest: Vec<(Operator, i64)> =
+                            ops.into_iter().zip(rest.iter().copied()).collect();
+
+                        evaluate(first, &rest, evaluation) == Some(eq.value)
+                    })
+            })
+            .map(|eq| eq.value)
+            .sum()
+    }
 }
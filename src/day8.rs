@@ -1,27 +1,69 @@
-use std::collections::{HashMap, HashSet};
+//! Day 8: Resonant Collinearity
+
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use gcd::Gcd;
 use gridly::prelude::*;
 use nom_supreme::error::ErrorTree;
+use serde::{Serialize, Serializer};
 
-use crate::library::{Definitely, IterExt};
+use crate::library::{
+    Definitely,
+    grid::{Rect, located_bytes},
+    inspect::AsLocation,
+    solver::solver,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Frequency(u8);
 
-#[derive(Debug)]
+impl Serialize for Frequency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(self.0 as char)
+    }
+}
+
+#[derive(Debug, Serialize)]
 pub struct Input {
+    #[serde(serialize_with = "serialize_antennas")]
     map: HashMap<Frequency, Vec<Location>>,
-    bounds: Vector,
+
+    #[serde(serialize_with = "serialize_bounds")]
+    bounds: Rect,
+}
+
+/// Serialize the antennas as a map from each frequency to its locations, in
+/// frequency order
+fn serialize_antennas<S: Serializer>(
+    map: &HashMap<Frequency, Vec<Location>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let antennas: BTreeMap<Frequency, Vec<AsLocation>> = map
+        .iter()
+        .map(|(&frequency, locations)| {
+            (
+                frequency,
+                locations.iter().copied().map(AsLocation).collect(),
+            )
+        })
+        .collect();
+
+    antennas.serialize(serializer)
+}
+
+/// Serialize the bounds as the dimensions of the map, which is always rooted
+/// at the origin
+fn serialize_bounds<S: Serializer>(bounds: &Rect, serializer: S) -> Result<S::Ok, S::Error> {
+    crate::library::inspect::vector(&bounds.dimensions(), serializer)
 }
 
 impl GridBounds for Input {
     fn dimensions(&self) -> Vector {
-        self.bounds
+        self.bounds.dimensions()
     }
 
     fn root(&self) -> Location {
-        Location::zero()
+        self.bounds.root()
     }
 }
 
@@ -30,30 +72,19 @@ impl TryFrom<&str> for Input {
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         let mut map: HashMap<Frequency, Vec<Location>> = HashMap::new();
-        let mut bound = Location::zero();
+        let mut bounds = Rect::default();
 
-        for (row, line) in value.lines().with_rows(Row(0)) {
-            for (column, cell) in line.trim().bytes().with_columns(Column(0)) {
-                let location = Location::new(row, column);
+        for (location, cell) in located_bytes(value) {
+            bounds = bounds.union(&Rect::from_corners(Location::zero(), location));
 
-                if location > bound {
-                    bound = location;
-                }
-
-                if cell == b'.' {
-                    continue;
-                }
-
-                map.entry(Frequency(cell))
-                    .or_default()
-                    .push(Location::new(row, column));
+            if cell == b'.' {
+                continue;
             }
+
+            map.entry(Frequency(cell)).or_default().push(location);
         }
 
-        Ok(Input {
-            map,
-            bounds: bound - (Row(-1), Column(-1)),
-        })
+        Ok(Input { map, bounds })
     }
 }
 
@@ -114,3 +145,5 @@ pub fn part2(input: Input) -> Definitely<usize> {
 
     Ok(count)
 }
+
+solver!(Input, usize, usize);
@@ -1,50 +1,127 @@
-use std::collections::VecDeque;
+//! Day 9: Disk Fragmenter
+//!
+//! Disk compaction. `Memory` is a small allocator simulation: a set of
+//! allocated file extents and a set of free extents, which can be compacted
+//! with any `CompactionPolicy`.
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display, Formatter},
+    iter,
+};
 
 use nom::{
-    character::complete::multispace0,
-    combinator::{eof, success},
-    error::ParseError,
+    character::complete::multispace0, combinator::eof, error::ParseError, multi::many0,
     Parser,
 };
-use nom_supreme::{
-    error::ErrorTree, final_parser::final_parser, multi::parse_separated_terminated, ParserExt,
-};
+use nom_supreme::{error::ErrorTree, final_parser::final_parser, ParserExt};
+use serde::Serialize;
 
-use crate::library::{Definitely, ITResult};
+use crate::library::{Definitely, ITResult, dump, solver::solver};
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-struct Block {
-    start: i64,
-    end: i64,
+/// A half-open range of memory cells, `start..end`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Block {
+    pub start: i64,
+    pub end: i64,
 }
 
 impl Block {
-    fn width(&self) -> i64 {
+    pub fn width(&self) -> i64 {
         self.end - self.start
     }
 
-    fn checksum_with(&self, factor: i64) -> i64 {
+    pub fn is_empty(&self) -> bool {
+        self.width() <= 0
+    }
+
+    pub fn checksum_with(&self, factor: i64) -> i64 {
         (self.start..self.end).map(|i| i * factor).sum()
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct FileID(u32);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct FileID(pub u32);
 
 impl FileID {
-    fn next(self) -> FileID {
+    pub fn next(self) -> FileID {
         FileID(self.0 + 1)
     }
 }
 
-#[derive(Debug, Clone, Default)]
-struct Memory {
+/// A strategy for moving allocated blocks leftward into free space.
+pub trait CompactionPolicy {
+    fn compact(&self, memory: &mut Memory);
+}
+
+/// Move individual cells into the leftmost free space, splitting files
+/// across as many blocks as necessary (part 1)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fragmenting;
+
+impl CompactionPolicy for Fragmenting {
+    fn compact(&self, memory: &mut Memory) {
+        memory.shift_all()
+    }
+}
+
+/// Move whole files into the leftmost free space large enough to hold them,
+/// trying each file exactly once, from right to left (part 2)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WholeFile;
+
+impl CompactionPolicy for WholeFile {
+    fn compact(&self, memory: &mut Memory) {
+        memory.shift_all_without_fragmentation()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Memory {
     allocated: VecDeque<(Block, FileID)>,
     free: VecDeque<Block>,
 }
 
 impl Memory {
-    fn shift_all(&mut self) {
+    /// Build a memory from a disk map: an alternating list of file widths
+    /// and free widths, starting with a file. Files are numbered from 0.
+    pub fn from_disk_map(widths: impl IntoIterator<Item = i64>) -> Self {
+        let mut memory = Memory::default();
+        let mut file = FileID(0);
+        let mut point = 0;
+
+        for (index, width) in widths.into_iter().enumerate() {
+            let block = Block {
+                start: point,
+                end: point + width,
+            };
+
+            point = block.end;
+
+            if index.is_multiple_of(2) {
+                memory.allocated.push_back((block, file));
+                file = file.next();
+            } else {
+                memory.free.push_back(block);
+            }
+        }
+
+        memory
+    }
+
+    /// All of the allocated extents, with their owning files. These are
+    /// sorted by position before compaction but not necessarily after.
+    pub fn allocated(&self) -> impl Iterator<Item = (&Block, FileID)> {
+        self.allocated.iter().map(|(block, file)| (block, *file))
+    }
+
+    pub fn compact(&mut self, policy: &impl CompactionPolicy) {
+        policy.compact(self)
+    }
+
+    /// Fragmenting compaction: repeatedly move the rightmost allocated cells
+    /// into the leftmost free cells.
+    pub fn shift_all(&mut self) {
         let Some((mut active_block, mut file_id)) = self.allocated.pop_back() else {
             return;
         };
@@ -52,7 +129,7 @@ impl Memory {
         while let Some(free_block) = self.free.pop_front() {
             let active_width = active_block.width();
 
-            if free_block.start > active_block.end {
+            if free_block.start >= active_block.end {
                 // Don't shift a block to the right, that's silly
                 break;
             }
@@ -102,7 +179,9 @@ impl Memory {
         self.allocated.push_back((active_block, file_id));
     }
 
-    fn shift_all_without_fragmentation(&mut self) {
+    /// Whole-file compaction: move each allocated block, from right to left,
+    /// into the leftmost free block that can hold all of it.
+    pub fn shift_all_without_fragmentation(&mut self) {
         for (active_block, _) in self.allocated.iter_mut().rev() {
             // Find a place to put it
             if let Some(free_block) = self
@@ -126,7 +205,7 @@ impl Memory {
         }
     }
 
-    fn compute_checksum(&self) -> i64 {
+    pub fn compute_checksum(&self) -> i64 {
         self.allocated
             .iter()
             .map(|&(ref block, FileID(file_id))| block.checksum_with(file_id as i64))
@@ -134,6 +213,91 @@ impl Memory {
     }
 }
 
+/// The parts of the allocator API that the puzzle itself doesn't need: for
+/// building memories and writing compaction policies other than the
+/// puzzle's. So far, only the property tests use them, so they're only built
+/// for tests.
+#[cfg(test)]
+impl Memory {
+    /// Build a memory from a list of file extents. The extents may be given
+    /// in any order, but must not overlap; the gaps between them become the
+    /// free list. A file may appear in more than one extent.
+    pub fn from_extents(extents: impl IntoIterator<Item = (Block, FileID)>) -> Self {
+        let mut allocated: Vec<(Block, FileID)> = extents
+            .into_iter()
+            .filter(|(block, _)| !block.is_empty())
+            .collect();
+
+        allocated.sort();
+
+        let free = allocated
+            .iter()
+            .zip(allocated.iter().skip(1))
+            .map(|((left, _), (right, _))| Block {
+                start: left.end,
+                end: right.start,
+            })
+            .filter(|block| !block.is_empty())
+            .collect();
+
+        Self {
+            allocated: allocated.into(),
+            free,
+        }
+    }
+
+    /// All of the free extents. Some may be empty after compaction.
+    pub fn free(&self) -> impl Iterator<Item = &Block> {
+        self.free.iter()
+    }
+
+    /// Direct access to the allocated and free lists, for use by custom
+    /// compaction policies
+    pub fn raw_parts_mut(&mut self) -> (&mut VecDeque<(Block, FileID)>, &mut VecDeque<Block>) {
+        (&mut self.allocated, &mut self.free)
+    }
+
+    /// All of the extents belonging to a particular file, sorted by position
+    pub fn extents(&self, file: FileID) -> Vec<Block> {
+        let mut extents: Vec<Block> = self
+            .allocated
+            .iter()
+            .filter(|&&(ref block, id)| id == file && !block.is_empty())
+            .map(|(block, _)| block.clone())
+            .collect();
+
+        extents.sort();
+        extents
+    }
+}
+
+/// One extent per line, in order of position, such as `10..13 file 4` or
+/// `13..15 free`. Free extents are the gaps between files, rather than the
+/// free list, which compaction doesn't keep complete.
+impl Display for Memory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut allocated: Vec<(&Block, FileID)> = self
+            .allocated()
+            .filter(|(block, _)| !block.is_empty())
+            .collect();
+
+        allocated.sort();
+
+        let mut point = allocated.first().map_or(0, |(block, _)| block.start);
+
+        for (&Block { start, end }, FileID(file)) in allocated {
+            if point < start {
+                writeln!(f, "{point}..{start} free")?;
+            }
+
+            writeln!(f, "{start}..{end} file {file}")?;
+            point = end;
+        }
+
+        Ok(())
+    }
+}
+
 fn parse_digit(input: &str) -> ITResult<&str, i64> {
     let mut chars = input.chars();
 
@@ -157,55 +321,25 @@ fn parse_digit(input: &str) -> ITResult<&str, i64> {
     Ok((tail, value.into()))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Input {
-    memory: Memory,
+    pub memory: Memory,
 }
 
 fn parse_input(input: &str) -> ITResult<&str, Input> {
-    let (input, initial_width) = parse_digit(input)?;
-
-    parse_separated_terminated(
-        parse_digit.and(parse_digit),
-        success(()),
-        multispace0.terminated(eof),
-        // Initialize a new memory with the initial file
-        move || {
-            let mut memory = Memory::default();
-            memory.allocated.push_back((
-                Block {
-                    start: 0,
-                    end: initial_width,
-                },
-                FileID(0),
-            ));
-
-            (memory, FileID(1), initial_width)
-        },
-        // For each pair, insert a free block and an allocated block for the
-        // next file.
-        move |(mut memory, file, free_point), (buffer_width, file_width)| {
-            let file_start = free_point + buffer_width;
-            let file_end = file_start + file_width;
-
-            memory.free.push_back(Block {
-                start: free_point,
-                end: file_start,
-            });
-
-            memory.allocated.push_back((
-                Block {
-                    start: file_start,
-                    end: file_end,
-                },
-                file,
-            ));
-
-            (memory, file.next(), file_end)
-        },
-    )
-    .map(|(memory, _, _)| Input { memory })
-    .parse(input)
+    // There might not be any pairs at all, if the disk holds a single file
+    parse_digit
+        .and(many0(parse_digit.and(parse_digit)))
+        .terminated(multispace0.terminated(eof))
+        .map(|(initial_width, pairs)| {
+            let widths = iter::once(initial_width)
+                .chain(pairs.into_iter().flat_map(|(free, file)| [free, file]));
+
+            Input {
+                memory: Memory::from_disk_map(widths),
+            }
+        })
+        .parse(input)
 }
 
 impl TryFrom<&str> for Input {
@@ -216,12 +350,104 @@ impl TryFrom<&str> for Input {
     }
 }
 
-pub fn part1(mut input: Input) -> Definitely<i64> {
-    input.memory.shift_all();
-    Ok(input.memory.compute_checksum())
+/// Compact the memory with `policy`, dumping the layout before and after
+fn compact_and_checksum(mut memory: Memory, policy: &impl CompactionPolicy) -> i64 {
+    dump::artifact("memory-before", &memory);
+    memory.compact(policy);
+    dump::artifact("memory-after", &memory);
+
+    memory.compute_checksum()
+}
+
+pub fn part1(input: Input) -> Definitely<i64> {
+    Ok(compact_and_checksum(input.memory, &Fragmenting))
+}
+
+pub fn part2(input: Input) -> Definitely<i64> {
+    Ok(compact_and_checksum(input.memory, &WholeFile))
 }
 
-pub fn part2(mut input: Input) -> Definitely<i64> {
-    input.memory.shift_all_without_fragmentation();
-    Ok(input.memory.compute_checksum())
+solver!(Input, i64, i64);
+
+/// Brute-force versions of both parts, which lay the disk out cell by cell
+/// and move one cell or file at a time, for checking the extent-based
+/// compaction against. These are quadratic in the size of the disk.
+#[cfg(feature = "naive")]
+#[allow(dead_code)]
+pub mod naive {
+    use super::{Block, FileID, Input};
+    use crate::library::Definitely;
+
+    /// The owner of every cell, from 0 to the end of the last file
+    fn cells(input: &Input) -> Vec<Option<FileID>> {
+        let end = input
+            .memory
+            .allocated()
+            .map(|(block, _)| block.end)
+            .max()
+            .unwrap_or(0);
+
+        let mut cells = vec![None; end as usize];
+
+        for (block, file) in input.memory.allocated() {
+            cells[block.start as usize..block.end as usize].fill(Some(file));
+        }
+
+        cells
+    }
+
+    fn checksum(cells: &[Option<FileID>]) -> i64 {
+        cells
+            .iter()
+            .enumerate()
+            .filter_map(|(position, &file)| Some(position as i64 * i64::from(file?.0)))
+            .sum()
+    }
+
+    /// Move the last used cell into the first free one, until there are no
+    /// gaps left
+    pub fn part1(input: Input) -> Definitely<i64> {
+        let mut cells = cells(&input);
+
+        while let (Some(gap), Some(last)) = (
+            cells.iter().position(Option::is_none),
+            cells.iter().rposition(Option::is_some),
+        ) {
+            if gap > last {
+                break;
+            }
+
+            cells.swap(gap, last);
+        }
+
+        Ok(checksum(&cells))
+    }
+
+    /// Move each file, from right to left, into the first gap to its left
+    /// that can hold all of it
+    pub fn part2(input: Input) -> Definitely<i64> {
+        let mut cells = cells(&input);
+
+        let mut extents: Vec<(Block, FileID)> = input
+            .memory
+            .allocated()
+            .map(|(block, file)| (block.clone(), file))
+            .collect();
+
+        extents.sort();
+
+        for (Block { start, end }, file) in extents.into_iter().rev() {
+            let (start, end) = (start as usize, end as usize);
+            let width = end - start;
+
+            let gap = (0..start).find(|&gap| cells[gap..gap + width].iter().all(Option::is_none));
+
+            if let Some(gap) = gap {
+                cells[gap..gap + width].fill(Some(file));
+                cells[start..end].fill(None);
+            }
+        }
+
+        Ok(checksum(&cells))
+    }
 }
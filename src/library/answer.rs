@@ -0,0 +1,164 @@
+//! Puzzle answers. Every part's solution converts into an [`Answer`], which
+//! knows what kind of value it is (a number, some text, a list, a point, or
+//! a grid), so that it can be shown in whichever [`Format`] the runner's
+//! `--answer-format` asks for, and compared structurally rather than as
+//! text. Parts that return plain integers or strings get this for free,
+//! through the `From` conversions here.
+
+use std::{
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+    sync::OnceLock,
+};
+
+use joinery::JoinableIterator;
+use serde::Serialize;
+
+/// How to show an answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Format {
+    /// The answer on its own line, with grids drawn one row per line
+    #[default]
+    Text,
+
+    /// Pretty-printed JSON, tagged with the kind of answer, like
+    /// `{"type": "point", "value": {"x": 6, "y": 1}}`
+    Json,
+
+    /// Exactly what you'd type into the puzzle's answer box, without a
+    /// trailing newline, for piping into a clipboard tool
+    Clipboard,
+}
+
+static FORMAT: OnceLock<Format> = OnceLock::new();
+
+/// Set the format answers are shown in. Should be called exactly once,
+/// before any solution runs.
+pub fn init(format: Format) {
+    if FORMAT.set(format).is_err() {
+        panic!("answer::init called more than once")
+    }
+}
+
+/// The format answers are shown in, which is text unless the runner asked
+/// for something else
+pub fn format() -> Format {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// The answer to one part of a puzzle
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Answer {
+    Integer(i128),
+
+    /// An integer too large for `Integer`, in decimal. Serialized as a
+    /// string, since JSON readers rarely handle numbers this big.
+    BigInteger(String),
+
+    Text(String),
+
+    /// Several values, shown separated by commas, like day 17's output
+    List(Vec<Answer>),
+
+    /// A pair of coordinates, shown as `x,y`
+    Point {
+        x: i64,
+        y: i64,
+    },
+
+    /// A picture, as a list of rows, for answers that have to be read off
+    /// a drawing
+    Grid(Vec<String>),
+}
+
+impl Answer {
+    /// A list of answers, from anything that converts into one
+    pub fn list<T: Into<Answer>>(items: impl IntoIterator<Item = T>) -> Self {
+        Self::List(items.into_iter().map(Into::into).collect())
+    }
+
+    /// Show this answer in `format`. Everything but the clipboard format
+    /// ends with a newline.
+    pub fn show(&self, format: Format) -> anyhow::Result<String> {
+        Ok(match format {
+            Format::Text => format!("{self}\n"),
+            Format::Json => serde_json::to_string_pretty(self)? + "\n",
+            Format::Clipboard => self.to_string(),
+        })
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Integer(value) => value.fmt(f),
+            Answer::BigInteger(value) | Answer::Text(value) => f.write_str(value),
+            Answer::List(items) => items.iter().join_with(',').fmt(f),
+            Answer::Point { x, y } => write!(f, "{x},{y}"),
+            Answer::Grid(rows) => rows.iter().join_with('\n').fmt(f),
+        }
+    }
+}
+
+macro_rules! integer_answer {
+    ($($type:ty)*) => {$(
+        impl From<$type> for Answer {
+            fn from(value: $type) -> Self {
+                Answer::Integer(value.into())
+            }
+        }
+    )*};
+}
+
+integer_answer! {i8 i16 i32 i64 i128 u8 u16 u32 u64}
+
+impl From<isize> for Answer {
+    fn from(value: isize) -> Self {
+        Answer::Integer(value as i128)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Answer::Integer(value as i128)
+    }
+}
+
+impl From<u128> for Answer {
+    fn from(value: u128) -> Self {
+        match value.try_into() {
+            Ok(value) => Answer::Integer(value),
+            Err(_) => Answer::BigInteger(value.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl From<num_bigint::BigUint> for Answer {
+    fn from(value: num_bigint::BigUint) -> Self {
+        match value.try_into() {
+            Ok(value) => Answer::Integer(value),
+            Err(err) => Answer::BigInteger(err.into_original().to_string()),
+        }
+    }
+}
+
+impl From<String> for Answer {
+    fn from(value: String) -> Self {
+        Answer::Text(value)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(value: &str) -> Self {
+        Answer::Text(value.to_owned())
+    }
+}
+
+/// For parts that never have an answer
+impl From<Infallible> for Answer {
+    fn from(value: Infallible) -> Self {
+        match value {}
+    }
+}
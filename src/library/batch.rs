@@ -0,0 +1,182 @@
+//! Running one day against a whole directory of inputs, for
+//! `advent2024 batch`. Every file in the directory is solved in turn, in
+//! name order, and the results are collected into a [`Table`], with a row
+//! for each input and a column for each part. Inputs are normalized, as
+//! they are for a single run, and a file that fails doesn't stop the rest.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use joinery::JoinableIterator;
+
+use super::{answer::Answer, normalize_input};
+
+/// The result of solving one part of one input: the answer, shown on a
+/// single line, or the error, with its causes
+type Outcome = Result<String, String>;
+
+/// The results of solving one input
+#[derive(Debug, Clone)]
+struct Row {
+    name: String,
+    outcomes: Vec<Outcome>,
+    elapsed: Duration,
+}
+
+/// The results of solving every input in a directory. Displays as an
+/// aligned table, with errors in place of answers where solving failed.
+#[derive(Debug, Clone)]
+pub struct Table {
+    parts: Vec<&'static str>,
+    rows: Vec<Row>,
+}
+
+impl Table {
+    /// The number of inputs that were solved, successfully or not
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Every error, as (input name, part, error), in table order
+    pub fn errors(&self) -> impl Iterator<Item = (&str, &'static str, &str)> {
+        self.rows.iter().flat_map(|row| {
+            self.parts
+                .iter()
+                .zip(&row.outcomes)
+                .filter_map(|(&part, outcome)| {
+                    Some((row.name.as_str(), part, outcome.as_ref().err()?.as_str()))
+                })
+        })
+    }
+
+    /// The number of inputs where any part failed
+    pub fn failures(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|row| row.outcomes.iter().any(Result::is_err))
+            .count()
+    }
+}
+
+/// Solve each of `parts` with every file in `directory` as its input. Each
+/// part is given as a column heading and whatever `solve` needs to know
+/// which part to solve.
+pub fn run<P: Copy>(
+    directory: &Path,
+    parts: &[(&'static str, P)],
+    mut solve: impl FnMut(P, &str) -> anyhow::Result<Answer>,
+) -> anyhow::Result<Table> {
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(directory)
+        .with_context(|| format!("failed to open input directory {:?}", directory.display()))?
+    {
+        let entry = entry.context("failed to read input directory")?;
+
+        if entry
+            .file_type()
+            .context("failed to read input directory")?
+            .is_file()
+        {
+            files.push(entry.path());
+        }
+    }
+
+    files.sort_unstable();
+
+    let rows = files
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let start = Instant::now();
+
+            let outcomes = match fs::read_to_string(path) {
+                Ok(input) => {
+                    let input = normalize_input(&input);
+
+                    parts
+                        .iter()
+                        .map(|&(_, part)| match solve(part, &input) {
+                            Ok(answer) => {
+                                Ok(answer.to_string().lines().join_with(" / ").to_string())
+                            }
+                            Err(err) => Err(format!("{err:#}")),
+                        })
+                        .collect()
+                }
+                Err(err) => {
+                    let err = format!("failed to read input: {err}");
+                    parts.iter().map(|_| Err(err.clone())).collect()
+                }
+            };
+
+            Row {
+                name,
+                outcomes,
+                elapsed: start.elapsed(),
+            }
+        })
+        .collect();
+
+    Ok(Table {
+        parts: parts.iter().map(|&(heading, _)| heading).collect(),
+        rows,
+    })
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let header = ["input"]
+            .into_iter()
+            .chain(self.parts.iter().copied())
+            .chain(["time"])
+            .map(str::to_owned)
+            .collect();
+
+        let lines: Vec<Vec<String>> = [header]
+            .into_iter()
+            .chain(self.rows.iter().map(|row| {
+                [row.name.clone()]
+                    .into_iter()
+                    .chain(row.outcomes.iter().map(|outcome| match outcome {
+                        Ok(answer) => answer.clone(),
+                        Err(_) => "error".to_owned(),
+                    }))
+                    .chain([format!("{:.2?}", row.elapsed)])
+                    .collect()
+            }))
+            .collect();
+
+        let widths: Vec<usize> = (0..self.parts.len() + 2)
+            .map(|column| {
+                lines
+                    .iter()
+                    .map(|line| line[column].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        lines.iter().try_for_each(|line| {
+            let cells = line
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell:width$}"));
+
+            writeln!(f, "{}", cells.join_with("  ").to_string().trim_end())
+        })
+    }
+}
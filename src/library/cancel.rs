@@ -0,0 +1,72 @@
+//! Interrupting long-running parts with Ctrl-C. The runner installs a
+//! handler with [`install`]; the first Ctrl-C asks the running solution to
+//! stop, and a second one exits immediately. Solutions that can run for a
+//! long time [`check`] for a request at their loop boundaries, and return
+//! [`Cancelled`] when they see one, so that the runner can still report
+//! what it has so far. The runner also records the latest update from a
+//! streaming part (see `stream`) with [`record_best`], and reports it if the
+//! part is interrupted.
+//!
+//! Solutions that never check run to completion, as they always have.
+
+use std::{
+    fmt::Display,
+    process,
+    sync::{
+        Mutex, MutexGuard,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use anyhow::Context;
+use thiserror::Error;
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// The best candidate recorded by the running solution, if any
+static BEST: Mutex<Option<String>> = Mutex::new(None);
+
+/// The error returned by a solution that stopped because it was interrupted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("interrupted")]
+pub struct Cancelled;
+
+fn lock_best() -> MutexGuard<'static, Option<String>> {
+    BEST.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Handle Ctrl-C by asking the running solution to stop, or by exiting if
+/// that was already asked for. Should be called at most once.
+pub fn install() -> anyhow::Result<()> {
+    ctrlc::set_handler(|| {
+        if REQUESTED.swap(true, Ordering::Relaxed) {
+            process::exit(130)
+        }
+    })
+    .context("failed to install the Ctrl-C handler")
+}
+
+/// True if the running solution has been asked to stop
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
+
+/// Fail with [`Cancelled`] if the running solution has been asked to stop
+pub fn check() -> Result<(), Cancelled> {
+    if requested() { Err(Cancelled) } else { Ok(()) }
+}
+
+/// True if `error` is, or was caused by, [`Cancelled`]
+pub fn is_cancelled(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<Cancelled>())
+}
+
+/// Record the best candidate found so far, replacing any earlier one
+pub fn record_best(candidate: impl Display) {
+    *lock_best() = Some(candidate.to_string());
+}
+
+/// Take the best candidate recorded so far, if any
+pub fn take_best() -> Option<String> {
+    lock_best().take()
+}
@@ -0,0 +1,77 @@
+//! Intermediate state, dumped for debugging. Solutions record named
+//! artifacts with [`artifact`] as they go; if the runner was given
+//! `--dump-state DIR`, it writes each one to `DIR/<name>.txt` once the
+//! solution is done, even if it failed. Otherwise, recording does nothing.
+
+use std::{
+    fmt::Display,
+    fs,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use anyhow::Context;
+
+static DIRECTORY: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Artifacts recorded so far, as (name, contents), in the order they were
+/// recorded. Solutions might record from any thread.
+static ARTIFACTS: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Install the directory to dump state into, or None to disable dumping.
+/// Should be called exactly once, before any solution runs.
+pub fn init(directory: Option<PathBuf>) {
+    if DIRECTORY.set(directory).is_err() {
+        panic!("dump::init called more than once")
+    }
+}
+
+fn directory() -> Option<&'static PathBuf> {
+    DIRECTORY.get()?.as_ref()
+}
+
+/// True if the runner asked for state to be dumped. `artifact` only renders
+/// its contents when this is true, but days can check it before doing any
+/// extra work to build them.
+pub fn enabled() -> bool {
+    directory().is_some()
+}
+
+/// Record an artifact. If an artifact with the same name is recorded more
+/// than once, the last one wins.
+pub fn artifact(name: impl Into<String>, contents: impl Display) {
+    if enabled() {
+        ARTIFACTS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push((name.into(), contents.to_string()));
+    }
+}
+
+/// Write every recorded artifact to the dump directory, creating it if
+/// necessary, and report each file written to stderr.
+pub fn write_all() -> anyhow::Result<()> {
+    let Some(directory) = directory() else {
+        return Ok(());
+    };
+
+    let artifacts = std::mem::take(
+        &mut *ARTIFACTS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    );
+
+    fs::create_dir_all(directory)
+        .with_context(|| format!("failed to create {:?}", directory.display()))?;
+
+    for (name, contents) in artifacts {
+        let path = directory.join(format!("{name}.txt"));
+
+        fs::write(&path, contents)
+            .with_context(|| format!("failed to write {:?}", path.display()))?;
+
+        eprintln!("dumped {}", path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+//! Encode a sequence of frames as an animated GIF. Each cell is drawn as a
+//! solid square in the fill color of its [`svg::char_style`]; shapes and
+//! labels are dropped, since there's no room for them at a few pixels per
+//! cell.
+
+use std::{collections::HashMap, io::Write, time::Duration};
+
+use anyhow::Context;
+use gridly::prelude::*;
+
+use crate::library::{svg, visualize::Frame};
+
+/// Cells with no fill color are drawn in the background color
+const BACKGROUND: [u8; 3] = [0xff, 0xff, 0xff];
+
+/// Writes a sequence of frames as a looping animated GIF
+#[derive(Debug, Clone, Copy)]
+pub struct Recording {
+    /// How long each frame is shown for. GIFs count in hundredths of a
+    /// second, so this is rounded down to that, with a minimum of one.
+    pub delay: Duration,
+
+    /// The width and height of each cell, in pixels
+    pub cell_size: u16,
+}
+
+impl Recording {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            cell_size: 4,
+        }
+    }
+
+    pub fn cell_size(self, cell_size: u16) -> Self {
+        Self { cell_size, ..self }
+    }
+
+    /// Encode every frame, in order. The image is sized to fit the first
+    /// frame; nothing is written if there are no frames.
+    pub fn write(
+        &self,
+        out: impl Write,
+        frames: impl IntoIterator<Item = Frame>,
+    ) -> anyhow::Result<()> {
+        let mut frames = frames.into_iter().peekable();
+        let Some(first) = frames.peek() else {
+            return Ok(());
+        };
+
+        let (width, height) = self.image_size(first)?;
+        let mut encoder =
+            ::gif::Encoder::new(out, width, height, &[]).context("failed to start GIF")?;
+        encoder
+            .set_repeat(::gif::Repeat::Infinite)
+            .context("failed to write GIF header")?;
+
+        let delay = (self.delay.as_millis() / 10).clamp(1, u16::MAX.into()) as u16;
+        let mut palette = Palette::default();
+
+        for frame in frames {
+            let (width, height) = self.image_size(&frame)?;
+            let pixels = self.pixels(&frame, &mut palette)?;
+
+            let mut image = ::gif::Frame::from_palette_pixels(
+                width,
+                height,
+                pixels,
+                palette.colors.as_slice(),
+                None,
+            );
+            image.delay = delay;
+
+            encoder
+                .write_frame(&image)
+                .context("failed to write GIF frame")?;
+        }
+
+        Ok(())
+    }
+
+    /// The size of a frame's image, in pixels
+    fn image_size(&self, frame: &Frame) -> anyhow::Result<(u16, u16)> {
+        let scale = |cells: isize| {
+            u16::try_from(cells)
+                .ok()
+                .and_then(|cells| cells.checked_mul(self.cell_size))
+        };
+
+        scale(frame.num_columns().0)
+            .zip(scale(frame.num_rows().0))
+            .context("frame is too large to encode as a GIF")
+    }
+
+    /// Draw a frame as palette indexes, in row-major order
+    fn pixels(&self, frame: &Frame, palette: &mut Palette) -> anyhow::Result<Vec<u8>> {
+        let cell_size = usize::from(self.cell_size);
+        let mut pixels = Vec::new();
+
+        for row in frame.rows().iter() {
+            let cells = row
+                .iter()
+                .map(|cell| palette.index(svg::char_style(cell).fill.as_deref()))
+                .collect::<anyhow::Result<Vec<u8>>>()?;
+
+            let line: Vec<u8> = cells
+                .iter()
+                .flat_map(|&index| std::iter::repeat_n(index, cell_size))
+                .collect();
+
+            for _ in 0..cell_size {
+                pixels.extend_from_slice(&line);
+            }
+        }
+
+        Ok(pixels)
+    }
+}
+
+/// The colors used so far, shared between every frame so that the same
+/// cell keeps the same index
+#[derive(Debug, Default)]
+struct Palette {
+    /// Flat RGB triples, as the GIF encoder wants them
+    colors: Vec<u8>,
+    indexes: HashMap<[u8; 3], u8>,
+}
+
+impl Palette {
+    fn index(&mut self, fill: Option<&str>) -> anyhow::Result<u8> {
+        let color = match fill {
+            None => BACKGROUND,
+            Some(fill) => {
+                parse_color(fill).with_context(|| format!("can't draw color {fill:?} in a GIF"))?
+            }
+        };
+
+        if let Some(&index) = self.indexes.get(&color) {
+            return Ok(index);
+        }
+
+        let index = u8::try_from(self.indexes.len()).context("too many colors for a GIF")?;
+        self.colors.extend_from_slice(&color);
+        self.indexes.insert(color, index);
+
+        Ok(index)
+    }
+}
+
+/// Parse a `#rrggbb` color
+fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
@@ -0,0 +1,233 @@
+//! Compute shader backends for the brute-force days. A day whose search can
+//! run on the GPU wraps it in an [`Offload`], which has both a CPU and a GPU
+//! implementation, and runs it with [`run`]. The runner's `--gpu` selects
+//! the GPU implementation; otherwise the CPU one runs. Days without a GPU
+//! implementation ignore `--gpu`.
+//!
+//! The GPU implementations use wgpu, which is only built with the `gpu`
+//! feature. Without it, asking for the GPU is an error.
+
+use std::sync::OnceLock;
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Turn the GPU backend on or off. Should be called exactly once, before any
+/// solution runs.
+pub fn init(enabled: bool) {
+    if ENABLED.set(enabled).is_err() {
+        panic!("gpu::init called more than once")
+    }
+}
+
+/// True if the runner asked for GPU implementations to be used
+pub fn enabled() -> bool {
+    ENABLED.get().copied().unwrap_or(false)
+}
+
+/// True if this build can run anything on the GPU
+pub const fn available() -> bool {
+    cfg!(feature = "gpu")
+}
+
+/// A computation that can run on either the CPU or the GPU, with the same
+/// result
+pub trait Offload {
+    type Output;
+
+    /// Compute the result on the CPU
+    fn cpu(&self) -> anyhow::Result<Self::Output>;
+
+    /// Compute the result with compute shaders
+    #[cfg(feature = "gpu")]
+    fn gpu(&self, gpu: &Gpu) -> anyhow::Result<Self::Output>;
+}
+
+/// Compute a result on the GPU if the runner asked for it, or on the CPU
+/// otherwise
+pub fn run<T: Offload>(task: &T) -> anyhow::Result<T::Output> {
+    if !enabled() {
+        return task.cpu();
+    }
+
+    #[cfg(feature = "gpu")]
+    return task.gpu(Gpu::get()?);
+
+    #[cfg(not(feature = "gpu"))]
+    anyhow::bail!("--gpu requires building with the gpu feature")
+}
+
+#[cfg(feature = "gpu")]
+pub use self::backend::Gpu;
+
+#[cfg(feature = "gpu")]
+mod backend {
+    use std::sync::{OnceLock, mpsc};
+
+    use anyhow::Context;
+    use wgpu::util::DeviceExt;
+
+    /// The number of invocations in each workgroup. Every shader run with
+    /// [`Gpu::compute`] must declare `@workgroup_size(64)`.
+    pub const WORKGROUP_SIZE: u32 = 64;
+
+    /// A connection to a GPU, shared by every computation
+    #[derive(Debug)]
+    pub struct Gpu {
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+    }
+
+    static GPU: OnceLock<Result<Gpu, String>> = OnceLock::new();
+
+    impl Gpu {
+        /// Connect to the GPU, the first time it's needed
+        pub fn get() -> anyhow::Result<&'static Self> {
+            GPU.get_or_init(|| Self::connect().map_err(|err| format!("{err:#}")))
+                .as_ref()
+                .map_err(|err| anyhow::anyhow!("failed to connect to a GPU: {err}"))
+        }
+
+        fn connect() -> anyhow::Result<Self> {
+            let instance = wgpu::Instance::new(
+                wgpu::InstanceDescriptor::new_without_display_handle_from_env(),
+            );
+
+            let adapter =
+                pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    ..Default::default()
+                }))
+                .context("no GPU adapter is available")?;
+
+            let (device, queue) = pollster::block_on(adapter.request_device(&Default::default()))
+                .context("failed to open the GPU device")?;
+
+            Ok(Self { device, queue })
+        }
+
+        /// Run the `main` entry point of the WGSL `shader` once for each of
+        /// `invocations`, and return its output. Each of `inputs` is bound
+        /// as a read-only storage buffer, at bindings 0 onward in group 0,
+        /// which the shader can declare as any type made of 32-bit words;
+        /// the output is a read-write `array<u32>` of `output_len` zeroes,
+        /// bound after them. Shaders should ignore
+        /// invocations past the end of their work, since the last
+        /// workgroup may have some extra.
+        pub fn compute(
+            &self,
+            shader: &str,
+            inputs: &[&[u32]],
+            output_len: usize,
+            invocations: u32,
+        ) -> anyhow::Result<Vec<u32>> {
+            let workgroups = invocations.div_ceil(WORKGROUP_SIZE);
+            let max_workgroups = self.device.limits().max_compute_workgroups_per_dimension;
+
+            anyhow::ensure!(
+                workgroups <= max_workgroups,
+                "{invocations} invocations is too many to dispatch at once"
+            );
+
+            let module = self
+                .device
+                .create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: None,
+                    source: wgpu::ShaderSource::Wgsl(shader.into()),
+                });
+
+            let pipeline = self
+                .device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: None,
+                    layout: None,
+                    module: &module,
+                    entry_point: Some("main"),
+                    compilation_options: Default::default(),
+                    cache: None,
+                });
+
+            // Storage buffers can't be empty, so every buffer has at least
+            // one word
+            let input_buffers: Vec<wgpu::Buffer> = inputs
+                .iter()
+                .map(|&input| {
+                    let contents = if input.is_empty() { &[0] } else { input };
+
+                    self.device
+                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                            label: None,
+                            contents: bytemuck::cast_slice(contents),
+                            usage: wgpu::BufferUsages::STORAGE,
+                        })
+                })
+                .collect();
+
+            let output_size = (output_len.max(1) * size_of::<u32>()) as wgpu::BufferAddress;
+
+            let output = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: output_size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+
+            let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: output_size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            let entries: Vec<wgpu::BindGroupEntry> = input_buffers
+                .iter()
+                .chain([&output])
+                .enumerate()
+                .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect();
+
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &pipeline.get_bind_group_layout(0),
+                entries: &entries,
+            });
+
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+
+            {
+                let mut pass = encoder.begin_compute_pass(&Default::default());
+                pass.set_pipeline(&pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.dispatch_workgroups(workgroups, 1, 1);
+            }
+
+            encoder.copy_buffer_to_buffer(&output, 0, &readback, 0, output_size);
+            self.queue.submit([encoder.finish()]);
+
+            let (sender, receiver) = mpsc::channel();
+            readback.map_async(wgpu::MapMode::Read, .., move |result| {
+                let _ = sender.send(result);
+            });
+
+            self.device
+                .poll(wgpu::PollType::wait_indefinitely())
+                .context("failed waiting for the GPU")?;
+
+            receiver
+                .recv()
+                .context("the GPU never finished reading back the output")?
+                .context("failed to read back the output from the GPU")?;
+
+            let view = readback
+                .get_mapped_range(..)
+                .context("failed to read back the output from the GPU")?;
+
+            let mut result: Vec<u32> = bytemuck::cast_slice(&view).to_vec();
+            result.truncate(output_len);
+
+            Ok(result)
+        }
+    }
+}
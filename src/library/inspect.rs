@@ -0,0 +1,212 @@
+//! Showing a day's parsed input, for `advent2024 inspect`. Every day's input
+//! type implements `Serialize` for this. Grid types from gridly don't, so
+//! this module also has `serialize_with` helpers for them: locations are
+//! written as `[row, column]`, vectors as `[rows, columns]`, and grids as a
+//! list of rows.
+
+use gridly::prelude::*;
+use serde::{Serialize, Serializer};
+
+use crate::library::{grid::GridExt, maze::Maze, solver::Solver};
+
+/// How to show a parsed input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Format {
+    /// Pretty-printed JSON
+    #[default]
+    Json,
+
+    /// Rust's pretty-printed debug representation
+    Debug,
+}
+
+/// Parse `input` with `S`, and show it, without solving anything
+pub fn show<S: Solver>(input: &str, format: Format) -> anyhow::Result<String> {
+    let input = S::parse(input)?;
+
+    Ok(match format {
+        Format::Json => serde_json::to_string_pretty(&input)?,
+        Format::Debug => format!("{input:#?}"),
+    })
+}
+
+/// Serialize a location as `[row, column]`
+pub fn location<S: Serializer>(location: &Location, serializer: S) -> Result<S::Ok, S::Error> {
+    (location.row.0, location.column.0).serialize(serializer)
+}
+
+/// Serialize a vector as `[rows, columns]`
+pub fn vector<S: Serializer>(vector: &Vector, serializer: S) -> Result<S::Ok, S::Error> {
+    (vector.rows.0, vector.columns.0).serialize(serializer)
+}
+
+/// Serialize a direction by name
+pub fn direction<S: Serializer>(direction: &Direction, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(match *direction {
+        Up => "up",
+        Right => "right",
+        Down => "down",
+        Left => "left",
+    })
+}
+
+/// Serialize a list of directions by name
+pub fn directions<S: Serializer>(
+    directions: &[Direction],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(directions.iter().map(|&direction| AsDirection(direction)))
+}
+
+/// Serialize a sequence of locations as a list of `[row, column]`, in order
+pub fn locations<'a, S: Serializer>(
+    locations: impl IntoIterator<Item = &'a Location>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(locations.into_iter().copied().map(AsLocation))
+}
+
+/// Serialize an unordered collection of locations as a list of
+/// `[row, column]`, in row-major order, so that the output is stable
+pub fn location_set<'a, S: Serializer>(
+    locations: impl IntoIterator<Item = &'a Location>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut locations: Vec<Location> = locations.into_iter().copied().collect();
+    locations.sort_unstable_by_key(|location| (location.row, location.column));
+
+    serializer.collect_seq(locations.into_iter().map(AsLocation))
+}
+
+/// Serialize a maze as its start, end, dimensions, and walls, with the walls
+/// in row-major order
+pub fn maze<S: Serializer>(maze: &Maze, serializer: S) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct AsMaze {
+        start: AsLocation,
+        end: AsLocation,
+        #[serde(serialize_with = "vector")]
+        dimensions: Vector,
+        walls: Vec<AsLocation>,
+    }
+
+    AsMaze {
+        start: AsLocation(maze.start()),
+        end: AsLocation(maze.end()),
+        dimensions: maze.bounds().dimensions(),
+        walls: maze.walls().iter().map(AsLocation).collect(),
+    }
+    .serialize(serializer)
+}
+
+/// Serialize an unordered collection as a sorted list, so that the output is
+/// stable
+pub fn sorted<'a, T, S>(
+    items: impl IntoIterator<Item = &'a T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: Ord + Serialize + 'a,
+    S: Serializer,
+{
+    let mut items: Vec<&T> = items.into_iter().collect();
+    items.sort_unstable();
+
+    serializer.collect_seq(items)
+}
+
+/// Serialize an unordered map in key order, so that the output is stable
+pub fn sorted_map<'a, K, V, S>(
+    entries: impl IntoIterator<Item = (&'a K, &'a V)>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    K: Ord + Serialize + 'a,
+    V: Serialize + 'a,
+    S: Serializer,
+{
+    let mut entries: Vec<(&K, &V)> = entries.into_iter().collect();
+    entries.sort_unstable_by_key(|&(key, _)| key);
+
+    serializer.collect_map(entries)
+}
+
+/// Serialize a grid as a list of rows, each of which is a list of cells
+pub fn grid<G, S>(grid: &G, serializer: S) -> Result<S::Ok, S::Error>
+where
+    G: Grid<Item: Serialize>,
+    S: Serializer,
+{
+    serializer.collect_seq(grid.rows().iter().map(|row| row.iter().collect::<Vec<_>>()))
+}
+
+/// Serialize a grid of ASCII characters as a list of rows, each of which is
+/// a string
+pub fn byte_grid<G, S>(grid: &G, serializer: S) -> Result<S::Ok, S::Error>
+where
+    G: Grid<Item = u8>,
+    S: Serializer,
+{
+    serializer.collect_seq(
+        grid.rows()
+            .iter()
+            .map(|row| row.iter().map(|&cell| char::from(cell)).collect::<String>()),
+    )
+}
+
+/// Serialize rows of ASCII characters as a list of strings
+pub fn byte_rows<S: Serializer>(rows: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(rows.iter().map(|row| String::from_utf8_lossy(row)))
+}
+
+/// Serialize a grid where most cells are empty as its dimensions and the
+/// locations of the cells that aren't, as
+/// `{"dimensions": [rows, columns], "occupied": [[row, column], ...]}`
+pub fn sparse_grid<G, T, S>(grid: &G, serializer: S) -> Result<S::Ok, S::Error>
+where
+    G: Grid<Item = Option<T>>,
+    S: Serializer,
+{
+    #[derive(Serialize)]
+    struct Sparse {
+        #[serde(serialize_with = "vector")]
+        dimensions: Vector,
+
+        #[serde(serialize_with = "locations")]
+        occupied: Vec<Location>,
+    }
+
+    let occupied = grid
+        .iter_located()
+        .filter(|(_, cell)| cell.is_some())
+        .map(|(location, _)| location)
+        .collect();
+
+    Sparse {
+        dimensions: grid.dimensions(),
+        occupied,
+    }
+    .serialize(serializer)
+}
+
+/// A location that serializes as `[row, column]`, for use inside other
+/// serialized structures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AsLocation(pub Location);
+
+impl Serialize for AsLocation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        location(&self.0, serializer)
+    }
+}
+
+/// A direction that serializes by name, for use inside other serialized
+/// structures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AsDirection(pub Direction);
+
+impl Serialize for AsDirection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        direction(&self.0, serializer)
+    }
+}
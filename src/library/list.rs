@@ -0,0 +1,168 @@
+//! An overview of every day, for `advent2024 list`: its title, which parts
+//! have a solution, whether its puzzle input is cached, which parts have an
+//! expected answer recorded, and how long each part took the last time it
+//! was benchmarked.
+//!
+//! A day's input is cached in `inputs/dayN.txt`, which is also where `run
+//! --input inputs` looks for it. Its expected answers are recorded in
+//! `inputs/answers/dayN.txt`, one line per part, starting with part 1.
+//! Benchmark times come from the estimates criterion leaves behind in the
+//! target directory, so they're only there after `cargo bench` has been run.
+
+use std::{
+    env,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::PathBuf,
+    time::Duration,
+};
+
+use joinery::JoinableIterator;
+use serde::Deserialize;
+
+/// What's known about one day
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub day: u8,
+    pub title: &'static str,
+
+    /// The parts that have a solution
+    pub parts: &'static [u8],
+}
+
+impl Entry {
+    /// True if this day's puzzle input is cached
+    fn has_input(&self) -> bool {
+        project_root()
+            .join("inputs")
+            .join(format!("day{}.txt", self.day))
+            .is_file()
+    }
+
+    /// The parts that have an expected answer recorded
+    fn answered(&self) -> Vec<u8> {
+        let path = project_root()
+            .join("inputs/answers")
+            .join(format!("day{}.txt", self.day));
+
+        let Ok(answers) = fs::read_to_string(path) else {
+            return Vec::new();
+        };
+
+        answers
+            .lines()
+            .zip(1..)
+            .filter(|(answer, _)| !answer.trim().is_empty())
+            .map(|(_, part)| part)
+            .collect()
+    }
+
+    /// The mean time it took to solve `part` (from an already parsed input)
+    /// the last time it was benchmarked, if it ever was
+    fn benchmarked(&self, part: u8) -> Option<Duration> {
+        #[derive(Deserialize)]
+        struct Estimates {
+            mean: Estimate,
+        }
+
+        #[derive(Deserialize)]
+        struct Estimate {
+            point_estimate: f64,
+        }
+
+        let path = target_directory()
+            .join("criterion")
+            .join(format!("day{}", self.day))
+            .join(format!("part{part}"))
+            .join("new/estimates.json");
+
+        let estimates: Estimates = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        Some(Duration::from_secs_f64(estimates.mean.point_estimate / 1e9))
+    }
+}
+
+fn project_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Where cargo builds (and criterion records) everything, which can be moved
+/// with `CARGO_TARGET_DIR`
+fn target_directory() -> PathBuf {
+    env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| project_root().join("target"))
+}
+
+/// Every day's entry, displayed as an aligned table
+#[derive(Debug, Clone)]
+pub struct Listing {
+    entries: Vec<Entry>,
+}
+
+impl Listing {
+    pub fn new(entries: impl IntoIterator<Item = Entry>) -> Self {
+        Self {
+            entries: entries.into_iter().collect(),
+        }
+    }
+}
+
+impl Display for Listing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let header = [
+            "day",
+            "title",
+            "parts",
+            "input",
+            "answers",
+            "part 1 bench",
+            "part 2 bench",
+        ]
+        .map(str::to_owned);
+
+        let lines: Vec<[String; 7]> = [header]
+            .into_iter()
+            .chain(self.entries.iter().map(|entry| {
+                let benchmarked = |part| match entry.benchmarked(part) {
+                    Some(elapsed) => format!("{elapsed:.2?}"),
+                    None => "-".to_owned(),
+                };
+
+                [
+                    entry.day.to_string(),
+                    entry.title.to_owned(),
+                    entry.parts.iter().join_with(", ").to_string(),
+                    match entry.has_input() {
+                        true => "cached".to_owned(),
+                        false => "-".to_owned(),
+                    },
+                    match entry.answered().as_slice() {
+                        [] => "-".to_owned(),
+                        parts => parts.iter().join_with(", ").to_string(),
+                    },
+                    benchmarked(1),
+                    benchmarked(2),
+                ]
+            }))
+            .collect();
+
+        let widths: Vec<usize> = (0..7)
+            .map(|column| {
+                lines
+                    .iter()
+                    .map(|line| line[column].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        lines.iter().try_for_each(|line| {
+            let cells = line
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell:width$}"));
+
+            writeln!(f, "{}", cells.join_with("  ").to_string().trim_end())
+        })
+    }
+}
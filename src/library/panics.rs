@@ -0,0 +1,110 @@
+//! Turning panics in solutions into errors. A part that panics is reported
+//! as a [`Panicked`] error, with the panic's message and where it happened,
+//! like any other failure; the commands that solve many inputs in one
+//! process (`run`, `batch`, and `repl`) carry on with the next part or
+//! input.
+//!
+//! While a panic is being caught, the default panic message isn't printed,
+//! so that it doesn't break up a batch table, or the output of a run or a
+//! session; the error says everything the message would have. Panics
+//! outside of [`catch`] are reported as usual.
+
+use std::{
+    any::Any,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Mutex, MutexGuard, Once,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// The number of calls to `catch` that are running. This is global, rather
+/// than per-thread, since a panic in a rayon worker is caught on the thread
+/// that started the parallel work.
+static CATCHING: AtomicUsize = AtomicUsize::new(0);
+
+/// Where the most recent caught panic happened
+static LOCATION: Mutex<Option<String>> = Mutex::new(None);
+
+static INSTALL_HOOK: Once = Once::new();
+
+/// The error for a part that panicked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panicked {
+    pub message: String,
+
+    /// The source location of the panic, if it's known
+    pub location: Option<String>,
+}
+
+impl Display for Panicked {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "panicked at {location}: {}", self.message),
+            None => write!(f, "panicked: {}", self.message),
+        }
+    }
+}
+
+impl Error for Panicked {}
+
+fn lock_location() -> MutexGuard<'static, Option<String>> {
+    LOCATION
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Wrap the current panic hook with one that records where caught panics
+/// happen, instead of printing them
+fn install_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let default = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            if CATCHING.load(Ordering::Relaxed) > 0 {
+                *lock_location() = info.location().map(|location| {
+                    // Days are included from the build directory with a
+                    // relative `#[path]`, so drop the part that climbs back
+                    // out of it
+                    let file = location.file();
+                    let file = file.rsplit_once("../").map_or(file, |(_, file)| file);
+
+                    format!("{file}:{}:{}", location.line(), location.column())
+                });
+            } else {
+                default(info)
+            }
+        }));
+    });
+}
+
+/// The message a panic was raised with, if it was a string (which it is for
+/// `panic!`, `expect`, `unwrap`, and friends)
+fn message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => (*message).to_owned(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "(no message)".to_owned(),
+        },
+    }
+}
+
+/// Call `f`, turning a panic into a [`Panicked`] error. Solutions don't
+/// share any state between runs that a panic could leave broken (the
+/// runner's own global state is all behind poison-tolerant locks), so `f`
+/// doesn't need to be `UnwindSafe`.
+pub fn catch<T>(f: impl FnOnce() -> T) -> Result<T, Panicked> {
+    install_hook();
+
+    CATCHING.fetch_add(1, Ordering::Relaxed);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.fetch_sub(1, Ordering::Relaxed);
+
+    result.map_err(|payload| Panicked {
+        message: message(&*payload),
+        location: lock_location().take(),
+    })
+}
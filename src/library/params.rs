@@ -0,0 +1,121 @@
+//! Day-specific runtime parameters, given on the command line with
+//! `--param name=value`. These are set once, at startup, before any solution
+//! runs, and are then available read-only to every day.
+
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Mutex, MutexGuard, OnceLock},
+};
+
+use anyhow::Context;
+use thiserror::Error;
+
+static PARAMS: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Parameters installed by [`with_overrides`], which take precedence over
+/// the ones given on the command line
+static OVERRIDES: Mutex<Vec<(&'static str, &'static str)>> = Mutex::new(Vec::new());
+
+/// A single `name=value` parameter, as given on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("parameter {0:?} must be given as name=value")]
+pub struct ParamError(String);
+
+impl FromStr for Param {
+    type Err = ParamError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((name, value)) if !name.is_empty() => Ok(Param {
+                name: name.to_owned(),
+                value: value.to_owned(),
+            }),
+            _ => Err(ParamError(s.to_owned())),
+        }
+    }
+}
+
+/// Install the set of parameters. Should be called exactly once, before any
+/// solution runs. If a parameter is given more than once, the last one wins.
+pub fn init(params: impl IntoIterator<Item = Param>) {
+    let params = params
+        .into_iter()
+        .map(|Param { name, value }| (name, value))
+        .collect();
+
+    if PARAMS.set(params).is_err() {
+        panic!("params::init called more than once")
+    }
+}
+
+fn lock_overrides() -> MutexGuard<'static, Vec<(&'static str, &'static str)>> {
+    OVERRIDES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Run `body` as if `params` had also been given on the command line.
+/// This lets a single process, like the benchmarks, run a day with several
+/// different sets of parameters. The overrides apply to every thread, so
+/// this shouldn't be used concurrently.
+pub fn with_overrides<T>(params: &[(&'static str, &'static str)], body: impl FnOnce() -> T) -> T {
+    struct Restore(Vec<(&'static str, &'static str)>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            *lock_overrides() = std::mem::take(&mut self.0);
+        }
+    }
+
+    let previous = {
+        let mut current = lock_overrides();
+        let previous = current.clone();
+        current.extend_from_slice(params);
+        previous
+    };
+
+    let _restore = Restore(previous);
+    body()
+}
+
+/// Get the raw string value of a parameter, if it was given
+pub fn get_raw(name: &str) -> Option<&'static str> {
+    let overridden = lock_overrides()
+        .iter()
+        .rev()
+        .find(|&&(overridden, _)| overridden == name)
+        .map(|&(_, value)| value);
+
+    overridden.or_else(|| PARAMS.get()?.get(name).map(|value| value.as_str()))
+}
+
+/// Get a parameter and parse it, if it was given
+pub fn get<T>(name: &str) -> anyhow::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    get_raw(name)
+        .map(|value| {
+            value
+                .parse()
+                .with_context(|| format!("failed to parse parameter {name}={value:?}"))
+        })
+        .transpose()
+}
+
+/// Get a parameter and parse it, or use a default if it wasn't given
+pub fn get_or<T>(name: &str, default: T) -> anyhow::Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    get(name).map(|value| value.unwrap_or(default))
+}
@@ -0,0 +1,68 @@
+//! Progress bars for long-running parts. Bars are drawn to stderr, along with
+//! the runner's other diagnostics, and only if stderr is a terminal; the
+//! runner's `--quiet` turns them off entirely. A solution that knows how much
+//! work it has to do asks for a [`bar`], advances it as it goes, and drops it
+//! when it's done, which clears it.
+//!
+//! Bars share the screen with the runner's [`status`] line, which shows the
+//! latest update from a streaming part (see `stream`).
+//!
+//! [`ProgressBar`] is cheap to clone and can be advanced from any thread; for
+//! rayon iterators, `ParallelProgressIterator::progress_with` advances a bar
+//! once for each item.
+
+use std::sync::{LazyLock, OnceLock};
+
+use indicatif::{MultiProgress, ProgressStyle};
+pub use indicatif::{ParallelProgressIterator, ProgressBar, ProgressIterator};
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Every bar currently shown, so that they're drawn together rather than
+/// over each other
+static BARS: LazyLock<MultiProgress> = LazyLock::new(MultiProgress::new);
+
+/// Turn progress bars on or off. Should be called exactly once, before any
+/// solution runs.
+pub fn init(enabled: bool) {
+    if ENABLED.set(enabled).is_err() {
+        panic!("progress::init called more than once")
+    }
+}
+
+/// True unless the runner was asked to be quiet. Progress bars are off when
+/// the runner isn't involved at all, like in benchmarks and tests.
+pub fn enabled() -> bool {
+    ENABLED.get().copied().unwrap_or(false)
+}
+
+/// Start a progress bar for `len` steps of work, labeled with `message`. If
+/// progress bars are off, the bar is hidden, but can still be advanced.
+pub fn bar(len: u64, message: &'static str) -> ProgressBar {
+    if !enabled() {
+        return ProgressBar::hidden();
+    }
+
+    let style =
+        ProgressStyle::with_template("{msg} [{elapsed}] {wide_bar} {pos}/{len} (eta {eta})")
+            .expect("progress bar template is valid");
+
+    BARS.add(
+        ProgressBar::new(len)
+            .with_style(style)
+            .with_message(message),
+    )
+}
+
+/// Start a line of text, shown along with any progress bars, for the
+/// runner's report of what a solution has found so far. Hidden if progress
+/// bars are off.
+pub fn status() -> ProgressBar {
+    if !enabled() {
+        return ProgressBar::hidden();
+    }
+
+    let style = ProgressStyle::with_template("{msg}").expect("status template is valid");
+
+    BARS.add(ProgressBar::new_spinner().with_style(style))
+}
@@ -0,0 +1,310 @@
+//! An interactive session for iterating on one day, for `advent2024 repl`.
+//! The session keeps a current input, which can be pasted in or edited in
+//! `$EDITOR`, and re-solves every part whenever it changes. Each run is kept
+//! in the session's history, and its answers are shown as a diff against the
+//! run before, so it's easy to see what an edit to the input (or a rebuilt
+//! solution, with the same input restored) changed.
+//!
+//! Commands are read one per line from stdin; `help` lists them.
+
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+
+use super::{answer::Answer, normalize_input};
+
+const HELP: &str = "\
+commands:
+  run, r          solve the current input again
+  edit, e         edit the current input in $VISUAL or $EDITOR, then solve it
+  paste, p        replace the current input with lines typed or pasted in,
+                  ending with a line containing only `.`, then solve it
+  show, s         print the current input
+  history, h      list every run so far
+  restore N       make the input from run N current again, then solve it
+  help, ?         show this message
+  quit, q         end the session (so does end of input)";
+
+/// The result of solving one part: the answer, as text, or the error, with
+/// its causes
+type Outcome = Result<String, String>;
+
+/// One run of every part against an input
+#[derive(Debug, Clone)]
+struct Run {
+    input: String,
+    outcomes: Vec<Outcome>,
+    elapsed: Duration,
+}
+
+/// The state of a session: the current input and every run so far
+struct Session<'a, P, F> {
+    parts: &'a [(&'static str, P)],
+    solve: F,
+    input: String,
+    runs: Vec<Run>,
+
+    /// The file the input is edited in, which is kept for the whole
+    /// session, so that the editor opens it with the previous edit's state
+    scratch: PathBuf,
+}
+
+impl<P, F> Session<'_, P, F>
+where
+    P: Copy,
+    F: FnMut(P, &str) -> anyhow::Result<Answer>,
+{
+    /// Solve every part with the current input, and show how the answers
+    /// differ from the previous run's
+    fn run(&mut self) {
+        let start = Instant::now();
+
+        let outcomes: Vec<Outcome> = self
+            .parts
+            .iter()
+            .map(|&(_, part)| match (self.solve)(part, &self.input) {
+                Ok(answer) => Ok(answer.to_string()),
+                Err(err) => Err(format!("{err:#}")),
+            })
+            .collect();
+
+        let run = Run {
+            input: self.input.clone(),
+            outcomes,
+            elapsed: start.elapsed(),
+        };
+
+        println!("run {} ({:.2?})", self.runs.len() + 1, run.elapsed);
+
+        for (i, &(heading, _)) in self.parts.iter().enumerate() {
+            let previous = self.runs.last().map(|previous| &previous.outcomes[i]);
+            print!("{}", describe(heading, previous, &run.outcomes[i]));
+        }
+
+        self.runs.push(run);
+    }
+
+    /// Replace the current input with whatever the user saves in their
+    /// editor
+    fn edit(&mut self) -> anyhow::Result<()> {
+        fs::write(&self.scratch, &self.input).context("failed to write input for editing")?;
+        open_editor(&self.scratch)?;
+
+        let edited = fs::read_to_string(&self.scratch).context("failed to read edited input")?;
+        self.input = normalize_input(&edited).into_owned();
+        Ok(())
+    }
+
+    fn history(&self) {
+        for (i, run) in self.runs.iter().enumerate() {
+            let answers = self
+                .parts
+                .iter()
+                .zip(&run.outcomes)
+                .map(|(&(heading, _), outcome)| {
+                    let shown = match outcome {
+                        Ok(answer) if answer.contains('\n') => "(multiline)",
+                        Ok(answer) => answer,
+                        Err(_) => "error",
+                    };
+
+                    format!("{heading}: {shown}")
+                });
+
+            println!(
+                "{:>3}  {} lines  {}  ({:.2?})",
+                i + 1,
+                run.input.lines().count(),
+                answers.collect::<Vec<_>>().join("  "),
+                run.elapsed
+            );
+        }
+    }
+
+    /// Make the input from the run numbered `index` (counting from 1)
+    /// current again
+    fn restore(&mut self, index: &str) -> anyhow::Result<()> {
+        let index: usize = index
+            .parse()
+            .with_context(|| format!("{index:?} isn't a run number"))?;
+
+        let run = index
+            .checked_sub(1)
+            .and_then(|i| self.runs.get(i))
+            .with_context(|| format!("there is no run {index}"))?;
+
+        self.input = run.input.clone();
+        Ok(())
+    }
+}
+
+/// Open `path` in the user's editor, and wait for them to close it. The
+/// editor command may include arguments, like `code --wait`.
+fn open_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_owned());
+
+    let mut words = editor.split_whitespace();
+    let program = words.next().context("the editor command is empty")?;
+
+    let status = process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .with_context(|| format!("failed to run editor {editor:?}"))?;
+
+    anyhow::ensure!(status.success(), "editor {editor:?} failed ({status})");
+    Ok(())
+}
+
+/// Show one part's outcome from a run, compared to the previous run's, if
+/// there was one. Answers that fit on one line are shown inline; longer ones
+/// (like grids) are shown line by line, with changed lines marked `-` and
+/// `+`.
+fn describe(heading: &str, previous: Option<&Outcome>, outcome: &Outcome) -> String {
+    fn lines(outcome: &Outcome) -> Vec<String> {
+        match outcome {
+            Ok(answer) => answer.lines().map(str::to_owned).collect(),
+            Err(err) => vec![format!("error: {err}")],
+        }
+    }
+
+    let current = lines(outcome);
+
+    match (previous.map(lines), current.as_slice()) {
+        (None, [line]) => format!("{heading}: {line}\n"),
+        (Some(previous), [line]) if previous == current => {
+            format!("{heading}: {line} (unchanged)\n")
+        }
+        (Some(previous), _) if previous == current => format!("{heading}: unchanged\n"),
+        (Some(previous), [line]) if previous.len() == 1 => {
+            format!("{heading}: {line} (was {})\n", previous[0])
+        }
+        (None, current) => {
+            let mut out = format!("{heading}:\n");
+            current
+                .iter()
+                .for_each(|line| out += &format!("  {line}\n"));
+            out
+        }
+        (Some(previous), current) => {
+            let mut out = format!("{heading}: changed\n");
+
+            for i in 0..previous.len().max(current.len()) {
+                match (previous.get(i), current.get(i)) {
+                    (Some(old), Some(new)) if old == new => out += &format!("  {new}\n"),
+                    (old, new) => {
+                        old.into_iter().for_each(|old| out += &format!("- {old}\n"));
+                        new.into_iter().for_each(|new| out += &format!("+ {new}\n"));
+                    }
+                }
+            }
+
+            out
+        }
+    }
+}
+
+/// Read lines of input until a line containing only `.`, or the end of
+/// stdin
+fn read_pasted(lines: &mut impl Iterator<Item = io::Result<String>>) -> anyhow::Result<String> {
+    let mut input = String::new();
+
+    for line in lines {
+        let line = line.context("failed to read pasted input")?;
+
+        if line.trim_end() == "." {
+            break;
+        }
+
+        input.push_str(&line);
+        input.push('\n');
+    }
+
+    Ok(normalize_input(&input).into_owned())
+}
+
+/// Run an interactive session for solving each of `parts`, labeled `name`,
+/// starting with `input`. Each part is given as a heading and whatever
+/// `solve` needs to know which part to solve. If there's an initial input,
+/// it's solved right away.
+pub fn run<P: Copy>(
+    name: &str,
+    parts: &[(&'static str, P)],
+    input: Option<String>,
+    solve: impl FnMut(P, &str) -> anyhow::Result<Answer>,
+) -> anyhow::Result<()> {
+    let mut session = Session {
+        parts,
+        solve,
+        input: input.clone().unwrap_or_default(),
+        runs: Vec::new(),
+        scratch: env::temp_dir().join(format!("advent2024-{name}-{}.txt", process::id())),
+    };
+
+    println!("{name}: type `help` for a list of commands");
+
+    if input.is_some() {
+        session.run();
+    }
+
+    let mut lines = io::stdin().lock().lines();
+
+    loop {
+        print!("{name}> ");
+        io::stdout().flush().context("failed to write prompt")?;
+
+        let Some(line) = lines.next() else {
+            println!();
+            break;
+        };
+
+        let line = line.context("failed to read command")?;
+        let mut words = line.split_whitespace();
+
+        let result = match (words.next(), words.next()) {
+            (None, _) => Ok(()),
+            (Some("run" | "r"), None) => {
+                session.run();
+                Ok(())
+            }
+            (Some("edit" | "e"), None) => session.edit().map(|()| session.run()),
+            (Some("paste" | "p"), None) => read_pasted(&mut lines).map(|input| {
+                session.input = input;
+                session.run();
+            }),
+            (Some("show" | "s"), None) => {
+                print!("{}", session.input);
+                Ok(())
+            }
+            (Some("history" | "h"), None) => {
+                session.history();
+                Ok(())
+            }
+            (Some("restore"), Some(index)) => session.restore(index).map(|()| session.run()),
+            (Some("help" | "?"), None) => {
+                println!("{HELP}");
+                Ok(())
+            }
+            (Some("quit" | "q"), None) => break,
+            _ => Err(anyhow::anyhow!(
+                "unknown command {line:?}; type `help` for a list"
+            )),
+        };
+
+        if let Err(err) = result {
+            println!("{err:#}");
+        }
+    }
+
+    // The scratch file only exists if the input was ever edited
+    let _ = fs::remove_file(&session.scratch);
+    Ok(())
+}
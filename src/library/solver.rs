@@ -0,0 +1,87 @@
+//! The interface every day shares. Each day module declares a `Puzzle` type
+//! with the [`solver!`] macro, which implements [`Solver`] for it from the
+//! module's `Input` type and its `part1` and `part2` functions, however
+//! those are written. The runner's registry (the `Day` enum, generated by
+//! the build script) hands each day's `Puzzle` to a [`Visitor`], so code
+//! that works with any day is written once, generically, rather than once
+//! per day.
+
+use std::fmt::Debug;
+
+use serde::Serialize;
+
+use super::{answer::Answer, stream::Solution};
+
+/// A day's puzzle: how to parse its input, and how to solve each part
+pub trait Solver {
+    /// The parsed puzzle input, which may borrow from the puzzle text
+    type Input<'a>: Debug + Serialize;
+
+    type Answer1: Solution;
+    type Answer2: Solution;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input<'_>>;
+    fn part1(input: Self::Input<'_>) -> anyhow::Result<Self::Answer1>;
+    fn part2(input: Self::Input<'_>) -> anyhow::Result<Self::Answer2>;
+}
+
+/// Something to do with a day's solver, whichever day it is. Closures can't
+/// be generic over a type, so this takes their place.
+pub trait Visitor {
+    type Output;
+
+    fn visit<S: Solver>(self) -> Self::Output;
+}
+
+/// Solve part 1 of an already parsed input, passing each streamed update to
+/// `on_update`
+pub fn finish_part1<S: Solver>(
+    input: S::Input<'_>,
+    on_update: &mut dyn FnMut(&str),
+) -> anyhow::Result<Answer> {
+    S::part1(input)
+        .and_then(|solution| solution.finish(on_update))
+        .map(Into::into)
+}
+
+/// Solve part 2 of an already parsed input, passing each streamed update to
+/// `on_update`
+pub fn finish_part2<S: Solver>(
+    input: S::Input<'_>,
+    on_update: &mut dyn FnMut(&str),
+) -> anyhow::Result<Answer> {
+    S::part2(input)
+        .and_then(|solution| solution.finish(on_update))
+        .map(Into::into)
+}
+
+/// Declare a day's `Puzzle`, given its input type and the types of its
+/// answers: `solver!(Input<'a>, usize, Streaming<u64>)`. The input type may
+/// borrow from the puzzle text with the lifetime `'a`. The parts are the
+/// module's `part1` and `part2`, which may fail with any error.
+macro_rules! solver {
+    ($input:ty, $answer1:ty, $answer2:ty $(,)?) => {
+        /// This day's puzzle; see `library::solver`
+        pub struct Puzzle;
+
+        impl $crate::library::solver::Solver for Puzzle {
+            type Input<'a> = $input;
+            type Answer1 = $answer1;
+            type Answer2 = $answer2;
+
+            fn parse(input: &str) -> anyhow::Result<Self::Input<'_>> {
+                <Self::Input<'_>>::try_from(input).map_err(Into::into)
+            }
+
+            fn part1(input: Self::Input<'_>) -> anyhow::Result<Self::Answer1> {
+                part1(input).map_err(Into::into)
+            }
+
+            fn part2(input: Self::Input<'_>) -> anyhow::Result<Self::Answer2> {
+                part2(input).map_err(Into::into)
+            }
+        }
+    };
+}
+
+pub(crate) use solver;
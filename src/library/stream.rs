@@ -0,0 +1,131 @@
+//! Intermediate values from long-running parts. A part that can say how
+//! it's getting on, like the best candidate it's found so far, returns a
+//! [`Streaming`] answer instead of a plain one. That's an iterator of
+//! [`Step`]s: any number of updates, and then the answer. The runner shows
+//! each update live while the part runs, includes them in the `--stats`
+//! report, and reports the latest one if the part is interrupted.
+//!
+//! Every part's answer is a [`Solution`], streaming or not, so the runner,
+//! the benchmarks, and the tests handle them all the same way.
+//!
+//! Searches are rarely written as iterators, so a streaming part runs on
+//! its own thread, with [`Streaming::spawn`], and sends its updates through
+//! [`Updates`] from wherever it finds them.
+
+use std::{
+    fmt::Display,
+    panic,
+    sync::mpsc,
+    thread::{self, JoinHandle},
+};
+
+use super::answer::Answer;
+
+/// One step of a streaming part
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step<T> {
+    /// A value found on the way to the answer
+    Update(String),
+
+    /// The answer, which is always the last step
+    Done(T),
+}
+
+/// Sends updates from a part running with [`Streaming::spawn`]. Updates can
+/// be sent from any thread.
+#[derive(Debug, Clone)]
+pub struct Updates {
+    sender: mpsc::Sender<String>,
+}
+
+impl Updates {
+    /// Send an intermediate value, like the best candidate found so far
+    pub fn send(&self, update: impl Display) {
+        // If nobody's listening, the update can be dropped
+        let _ = self.sender.send(update.to_string());
+    }
+}
+
+/// A part's answer, computed on its own thread, along with the updates it
+/// sends on the way. Iterating over it waits for each update in turn, and
+/// then for the answer.
+#[derive(Debug)]
+pub struct Streaming<T> {
+    updates: mpsc::Receiver<String>,
+    solver: Option<JoinHandle<anyhow::Result<T>>>,
+}
+
+impl<T: Send + 'static> Streaming<T> {
+    /// Start computing an answer with `solve` on a new thread. If `solve`
+    /// panics, the panic is resumed on whichever thread waits for the
+    /// answer.
+    pub fn spawn(solve: impl FnOnce(&Updates) -> anyhow::Result<T> + Send + 'static) -> Self {
+        let (sender, updates) = mpsc::channel();
+        let solver = thread::spawn(move || solve(&Updates { sender }));
+
+        Self {
+            updates,
+            solver: Some(solver),
+        }
+    }
+}
+
+impl<T> Iterator for Streaming<T> {
+    type Item = anyhow::Result<Step<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The channel stays open until the solver is finished, one way or
+        // another, and drops its `Updates`
+        if let Ok(update) = self.updates.recv() {
+            return Some(Ok(Step::Update(update)));
+        }
+
+        let solver = self.solver.take()?;
+
+        Some(match solver.join() {
+            Ok(answer) => answer.map(Step::Done),
+            Err(panic) => panic::resume_unwind(panic),
+        })
+    }
+}
+
+/// Anything a part can return: either its answer, or a [`Streaming`] answer
+pub trait Solution {
+    type Answer: Into<Answer>;
+
+    /// Wait for the answer, passing each update to `on_update` as it
+    /// arrives
+    fn finish(self, on_update: &mut dyn FnMut(&str)) -> anyhow::Result<Self::Answer>;
+
+    /// Wait for the answer, ignoring any updates
+    fn answer(self) -> anyhow::Result<Self::Answer>
+    where
+        Self: Sized,
+    {
+        self.finish(&mut |_| {})
+    }
+}
+
+/// A plain answer is ready right away, with no updates
+impl<T: Into<Answer>> Solution for T {
+    type Answer = T;
+
+    fn finish(self, _on_update: &mut dyn FnMut(&str)) -> anyhow::Result<T> {
+        Ok(self)
+    }
+}
+
+impl<T: Into<Answer>> Solution for Streaming<T> {
+    type Answer = T;
+
+    fn finish(self, on_update: &mut dyn FnMut(&str)) -> anyhow::Result<T> {
+        for step in self {
+            match step? {
+                Step::Update(update) => on_update(&update),
+                Step::Done(answer) => return Ok(answer),
+            }
+        }
+
+        anyhow::bail!("the solution stopped without an answer")
+    }
+}
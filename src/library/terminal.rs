@@ -0,0 +1,185 @@
+//! Helpers for interactive, terminal-based modes
+
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::{
+    QueueableCommand, cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    style::Print,
+    terminal::{self, Clear, ClearType},
+};
+
+/// Open the terminal for reading interactive commands. Puzzle input might
+/// have come from stdin, so prefer the controlling terminal directly.
+pub fn open() -> Box<dyn BufRead> {
+    match File::open("/dev/tty") {
+        Ok(tty) => Box::new(BufReader::new(tty)),
+        Err(_) => Box::new(io::stdin().lock()),
+    }
+}
+
+/// Plays a sequence of frames in the terminal (on stderr), redrawing the
+/// screen for each one. If the terminal supports it, playback can be
+/// controlled from the keyboard:
+///
+/// - space: pause or resume
+/// - n / →: step forward one frame while paused
+/// - + / -: play faster or slower
+/// - q / esc: stop playback early
+#[derive(Debug, Clone, Copy)]
+pub struct Animation {
+    /// How long each frame is shown for
+    pub delay: Duration,
+}
+
+/// What to do after a frame has been shown
+enum Control {
+    Next,
+    Redraw,
+    Quit,
+}
+
+/// Puts the terminal in raw mode, so that keys can be read without waiting
+/// for enter, and restores it on drop.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> Option<Self> {
+        terminal::enable_raw_mode().ok().map(|()| RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl Animation {
+    pub fn new(delay: Duration) -> Self {
+        Self { delay }
+    }
+
+    /// Play every frame, in order. Returns once the last frame has been shown
+    /// for `delay`, or once playback is stopped.
+    pub fn play<T: Display>(&self, frames: impl IntoIterator<Item = T>) -> io::Result<()> {
+        let mut out = io::stderr().lock();
+
+        // Without a terminal to read keys from, just play straight through
+        let raw_mode = RawMode::enable();
+        let mut state = Playback {
+            delay: self.delay,
+            paused: false,
+            interactive: raw_mode.is_some(),
+        };
+
+        out.queue(cursor::Hide)?;
+        let result = state.play(&mut out, frames);
+        out.queue(cursor::Show)?;
+        drop(raw_mode);
+
+        // Leave the last frame on the screen, and move past it
+        writeln!(out)?;
+        result
+    }
+}
+
+struct Playback {
+    delay: Duration,
+    paused: bool,
+    interactive: bool,
+}
+
+impl Playback {
+    fn play<T: Display>(
+        &mut self,
+        out: &mut impl Write,
+        frames: impl IntoIterator<Item = T>,
+    ) -> io::Result<()> {
+        for frame in frames {
+            loop {
+                self.draw(out, &frame)?;
+
+                match self.wait()? {
+                    Control::Next => break,
+                    Control::Redraw => continue,
+                    Control::Quit => return Ok(()),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&self, out: &mut impl Write, frame: &impl Display) -> io::Result<()> {
+        out.queue(cursor::MoveTo(0, 0))?
+            .queue(Clear(ClearType::All))?;
+
+        // In raw mode, a newline doesn't return the cursor to the start of
+        // the line
+        for line in frame.to_string().lines() {
+            out.queue(Print(line))?.queue(Print("\r\n"))?;
+        }
+
+        if self.interactive {
+            let status = match self.paused {
+                true => "[paused] space: resume, n: step, q: quit",
+                false => "space: pause, +/-: speed, q: quit",
+            };
+
+            out.queue(Print(status))?;
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Wait until it's time for the next frame, handling any keys pressed in
+    /// the meantime
+    fn wait(&mut self) -> io::Result<Control> {
+        if !self.interactive {
+            thread::sleep(self.delay);
+            return Ok(Control::Next);
+        }
+
+        let deadline = Instant::now() + self.delay;
+
+        loop {
+            // While paused, block until a key is pressed
+            if !self.paused && !event::poll(deadline.saturating_duration_since(Instant::now()))? {
+                return Ok(Control::Next);
+            }
+
+            let Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) = event::read()?
+            else {
+                continue;
+            };
+
+            match code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(Control::Quit),
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(Control::Quit);
+                }
+                KeyCode::Char(' ') => {
+                    // Redraw the same frame, to update the status line, and
+                    // show it for a full delay once we resume
+                    self.paused = !self.paused;
+                    return Ok(Control::Redraw);
+                }
+                KeyCode::Char('n') | KeyCode::Right if self.paused => return Ok(Control::Next),
+                KeyCode::Char('+') => self.delay /= 2,
+                KeyCode::Char('-') => self.delay = (self.delay * 2).max(Duration::from_millis(1)),
+                _ => {}
+            }
+        }
+    }
+}
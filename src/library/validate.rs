@@ -0,0 +1,104 @@
+//! Alternative algorithms, and cross-validation between them. A day that has
+//! more than one way to compute something lists them as named variants, and
+//! the runner's `--param` picks which one runs. With `--validate`, every
+//! variant runs instead, and the runner fails unless they all agree.
+
+use std::{fmt::Debug, sync::OnceLock, time::Instant};
+
+use joinery::JoinableIterator;
+
+use crate::library::params;
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// A named way of computing some result
+pub type Variant<'a, T> = (&'static str, &'a dyn Fn() -> anyhow::Result<T>);
+
+/// Turn validation on or off. Should be called exactly once, before any
+/// solution runs.
+pub fn init(enabled: bool) {
+    if ENABLED.set(enabled).is_err() {
+        panic!("validate::init called more than once")
+    }
+}
+
+/// True if the runner asked for every variant to be cross-checked
+pub fn enabled() -> bool {
+    ENABLED.get().copied().unwrap_or(false)
+}
+
+/// Get the variant name selected by `--param {param}=NAME`, which must be
+/// one of `names`. The first name is the default.
+pub fn selected<'n>(param: &str, names: &[&'n str]) -> anyhow::Result<&'n str> {
+    let Some(&default) = names.first() else {
+        anyhow::bail!("no variants of {param} to select from");
+    };
+
+    match params::get_raw(param) {
+        None => Ok(default),
+        Some(name) => names
+            .iter()
+            .copied()
+            .find(|&candidate| candidate == name)
+            .ok_or_else(|| {
+                anyhow::anyhow!("unknown {param} {name:?}; expected {}", alternatives(names))
+            }),
+    }
+}
+
+/// List names as `a or b`, or `a, b, or c`
+fn alternatives(names: &[&str]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => (*name).to_owned(),
+        [first, second] => format!("{first} or {second}"),
+        [init @ .., last] => format!("{}, or {last}", init.iter().join_with(", ")),
+    }
+}
+
+/// Compute a result with the variant selected by `--param {param}=NAME`.
+/// With `--validate`, run every variant instead, reporting each result and
+/// how long it took to stderr, and fail if any of them disagree; the result
+/// of the selected variant is returned.
+pub fn select<T: PartialEq + Debug>(param: &str, variants: &[Variant<'_, T>]) -> anyhow::Result<T> {
+    let names: Vec<&str> = variants.iter().map(|&(name, _)| name).collect();
+    let name = selected(param, &names)?;
+
+    let run = |&(name, variant): &Variant<'_, T>| -> anyhow::Result<T> {
+        variant().map_err(|err| err.context(format!("{param}={name} failed")))
+    };
+
+    if !enabled() {
+        let variant = variants
+            .iter()
+            .find(|&&(candidate, _)| candidate == name)
+            .expect("selected a known variant");
+
+        return run(variant);
+    }
+
+    let mut results = Vec::with_capacity(variants.len());
+
+    for variant in variants {
+        let start = Instant::now();
+        let result = run(variant)?;
+        eprintln!("{param}={}: {result:?} in {:?}", variant.0, start.elapsed());
+
+        results.push((variant.0, result));
+    }
+
+    let (first_name, first) = &results[0];
+
+    if let Some((other_name, other)) = results.iter().find(|(_, result)| result != first) {
+        anyhow::bail!(
+            "{param} variants disagree: {first_name} gave {first:?}, but {other_name} gave {other:?}"
+        );
+    }
+
+    let index = names
+        .iter()
+        .position(|&candidate| candidate == name)
+        .expect("selected a known variant");
+
+    Ok(results.swap_remove(index).1)
+}
@@ -0,0 +1,275 @@
+//! A shared outlet for visualizations. Days describe what they want drawn as
+//! a sequence of [`Frame`]s, by implementing [`Visualize`], and the runner's
+//! `--visualize` flag picks the backend that draws them.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    sync::OnceLock,
+    time::Duration,
+};
+
+use anyhow::Context;
+use gridly::prelude::*;
+
+use crate::library::{gif::Recording, grid::Rect, render, svg, terminal::Animation};
+
+/// A single picture: a grid of cells, each drawn as a character, with an
+/// optional caption. Backends that don't draw text map each character to a
+/// style of their own. Frames are gridly grids, with their root at the
+/// origin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    title: Option<String>,
+    rows: Rows,
+    columns: Columns,
+
+    /// Cells, in row-major order
+    cells: Vec<char>,
+}
+
+impl Frame {
+    /// Create a frame with every cell set to `fill`
+    pub fn new(rows: Rows, columns: Columns, fill: char) -> Self {
+        let len = rows.0.max(0) as usize * columns.0.max(0) as usize;
+
+        Self {
+            title: None,
+            rows,
+            columns,
+            cells: vec![fill; len],
+        }
+    }
+
+    /// Create a frame by computing each cell from its location
+    pub fn from_fn(rows: Rows, columns: Columns, mut cell: impl FnMut(Location) -> char) -> Self {
+        let mut frame = Self::new(rows, columns, ' ');
+
+        for row in (0..rows.0).map(Row) {
+            for column in (0..columns.0).map(Column) {
+                frame.set(row + column, cell(row + column));
+            }
+        }
+
+        frame
+    }
+
+    pub fn with_title(self, title: impl Into<String>) -> Self {
+        Self {
+            title: Some(title.into()),
+            ..self
+        }
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    fn index(&self, location: Location) -> Option<usize> {
+        let Location { row, column } = location;
+
+        ((0..self.rows.0).contains(&row.0) && (0..self.columns.0).contains(&column.0))
+            .then(|| (row.0 * self.columns.0 + column.0) as usize)
+    }
+
+    /// Set the cell at `location`. Locations outside the frame are ignored.
+    pub fn set(&mut self, location: Location, cell: char) {
+        if let Some(index) = self.index(location) {
+            self.cells[index] = cell;
+        }
+    }
+
+    /// Set every cell in `locations` (such as a path, or a set of robots)
+    pub fn mark(&mut self, locations: impl IntoIterator<Item = Location>, cell: char) {
+        locations
+            .into_iter()
+            .for_each(|location| self.set(location, cell));
+    }
+
+    /// Cut out the part of the frame inside `rect`, as a new frame with its
+    /// own root at the origin, keeping the title. Whatever part of `rect`
+    /// is outside the frame is dropped.
+    pub fn crop(&self, rect: &Rect) -> Frame {
+        let rect = rect
+            .intersection(&Rect::new(Location::zero(), self.dimensions()))
+            .unwrap_or_default();
+
+        let frame = Frame::from_fn(rect.num_rows(), rect.num_columns(), |location| {
+            *self
+                .get(location + (rect.root() - Location::zero()))
+                .expect("cropped locations are in bounds")
+        });
+
+        Frame {
+            title: self.title.clone(),
+            ..frame
+        }
+    }
+}
+
+impl GridBounds for Frame {
+    fn dimensions(&self) -> Vector {
+        Vector::new(self.rows, self.columns)
+    }
+
+    fn root(&self) -> Location {
+        Location::zero()
+    }
+}
+
+impl Grid for Frame {
+    type Item = char;
+
+    unsafe fn get_unchecked(&self, location: Location) -> &char {
+        let index = self.index(location).expect("location is in bounds");
+        &self.cells[index]
+    }
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(title) = &self.title {
+            writeln!(f, "{title}")?;
+        }
+
+        let grid = render::grid(self.rows, self.columns, |location| {
+            *self
+                .get(location)
+                .expect("rendered locations are in bounds")
+        });
+
+        f.write_str(&grid)
+    }
+}
+
+/// Something that can be drawn as a sequence of frames. Most visualizations
+/// are a single frame; simulations produce one per step.
+pub trait Visualize {
+    fn frames(&self) -> impl Iterator<Item = Frame> + '_;
+
+    /// Draw this visualization as an SVG document, for the `svg` backend. By
+    /// default this is the last frame, with the default character palette;
+    /// override it to draw something richer, like highlights or paths.
+    fn svg(&self) -> Option<String> {
+        self.frames()
+            .last()
+            .map(|frame| svg::Document::new(&frame, svg::char_style).render())
+    }
+}
+
+/// How frames are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Backend {
+    /// Print every frame as text, one after another
+    Text,
+
+    /// Animate the frames in the terminal
+    Terminal,
+
+    /// Draw the last frame as an SVG image
+    Svg,
+
+    /// Encode every frame as an animated GIF. Needs an output file.
+    Gif,
+}
+
+/// The runner's visualization settings
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub backend: Backend,
+
+    /// Where to write the visualization. If omitted, text is written to
+    /// stderr.
+    pub output: Option<PathBuf>,
+
+    /// How long each frame is shown for, in animated backends
+    pub delay: Duration,
+}
+
+static CONFIG: OnceLock<Option<Config>> = OnceLock::new();
+
+/// Install the visualization settings, or None to disable visualization.
+/// Should be called exactly once, before any solution runs.
+pub fn init(config: Option<Config>) {
+    if CONFIG.set(config).is_err() {
+        panic!("visualize::init called more than once")
+    }
+}
+
+fn config() -> Option<&'static Config> {
+    CONFIG.get()?.as_ref()
+}
+
+/// True if the runner asked for a visualization. Days can check this before
+/// doing any extra work to build one.
+pub fn enabled() -> bool {
+    config().is_some()
+}
+
+/// Draw a visualization with the selected backend, if any
+pub fn show(visualization: &impl Visualize) -> anyhow::Result<()> {
+    let Some(config) = config() else {
+        return Ok(());
+    };
+
+    if config.backend == Backend::Terminal {
+        if config.output.is_some() {
+            anyhow::bail!("the terminal backend can't write to a file");
+        }
+
+        return Animation::new(config.delay)
+            .play(visualization.frames())
+            .context("failed to animate visualization");
+    }
+
+    if config.backend == Backend::Gif {
+        let Some(path) = &config.output else {
+            anyhow::bail!("the gif backend needs an output file (--visualize-output)");
+        };
+
+        let file =
+            File::create(path).with_context(|| format!("failed to create {:?}", path.display()))?;
+
+        return Recording::new(config.delay)
+            .write(BufWriter::new(file), visualization.frames())
+            .context("failed to record visualization");
+    }
+
+    let mut out: Box<dyn Write> = match &config.output {
+        Some(path) => Box::new(
+            File::create(path).with_context(|| format!("failed to create {:?}", path.display()))?,
+        ),
+        None => Box::new(io::stderr().lock()),
+    };
+
+    match config.backend {
+        Backend::Text => write_text(&mut out, visualization.frames()),
+        Backend::Svg => write_svg(&mut out, visualization.svg()),
+        Backend::Terminal | Backend::Gif => {
+            unreachable!("animated backends don't write to a text stream")
+        }
+    }
+    .context("failed to write visualization")
+}
+
+fn write_svg(out: &mut impl Write, document: Option<String>) -> io::Result<()> {
+    if let Some(document) = document {
+        out.write_all(document.as_bytes())?;
+    }
+
+    out.flush()
+}
+
+fn write_text(out: &mut impl Write, frames: impl Iterator<Item = Frame>) -> io::Result<()> {
+    for (i, frame) in frames.enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+
+        write!(out, "{frame}")?;
+    }
+
+    out.flush()
+}
@@ -1,17 +1,21 @@
 #![feature(array_windows)]
-#![feature(array_chunks)]
-#![feature(try_trait_v2)]
 
 include!(concat!(env!("OUT_DIR"), "/generated.rs"));
 
 mod library;
 
+use aoc_toolkit::{direction_map, express, normalize_input, parser};
+
+#[cfg(test)]
+mod proptests;
+
 use std::{
     fs::File,
     io::{self, Read},
     num::ParseIntError,
     path::PathBuf,
     str::FromStr,
+    time::{Duration, Instant},
 };
 
 use anyhow::Context;
@@ -19,6 +23,11 @@ use clap::Parser;
 use lazy_format::lazy_format;
 use thiserror::Error;
 
+use crate::library::{
+    answer::Answer,
+    solver::{Solver, Visitor},
+};
+
 #[derive(Debug, Clone, Error)]
 pub enum DayError {
     #[error("Failed to parse day")]
@@ -43,6 +52,28 @@ pub enum PartError {
     BadPart(u8),
 }
 
+impl Part {
+    fn number(self) -> u8 {
+        match self {
+            Part::Part1 => 1,
+            Part::Part2 => 2,
+        }
+    }
+
+    /// Solve this part of an already parsed input, passing each streamed
+    /// update to `on_update`
+    fn finish<S: Solver>(
+        self,
+        input: S::Input<'_>,
+        on_update: &mut dyn FnMut(&str),
+    ) -> anyhow::Result<Answer> {
+        match self {
+            Part::Part1 => library::solver::finish_part1::<S>(input, on_update),
+            Part::Part2 => library::solver::finish_part2::<S>(input, on_update),
+        }
+    }
+}
+
 impl FromStr for Part {
     type Err = PartError;
 
@@ -57,53 +88,560 @@ impl FromStr for Part {
     }
 }
 
-/// Solve an Advent of Code 2022 problem for the given day and part. Unless
-/// --string or --file are given, input is read from standard input. The
-/// solution is always written to standard output.
-#[derive(Parser)]
-#[command(group(clap::ArgGroup::new("input")))]
-struct Args {
+/// Where to read a puzzle input from. Unless --string or --file are given,
+/// input is read from standard input. However it's read, the input is
+/// normalized before it's parsed, so that Windows line endings and trailing
+/// whitespace don't trip up any day's parser.
+#[derive(clap::Args)]
+#[group(multiple = false)]
+struct InputArgs {
+    /// If given, read input from this file
+    #[arg(short, long)]
+    file: Option<PathBuf>,
+
+    /// If given, use this as the puzzle input directly
+    #[arg(short, long)]
+    string: Option<String>,
+}
+
+impl InputArgs {
+    fn read(self) -> anyhow::Result<String> {
+        if let Some(buf) = self.string {
+            return Ok(normalize_input(&buf).into_owned());
+        }
+
+        let mut buf = String::new();
+        match self.file {
+            Some(file) => File::open(&file)
+                .context(lazy_format!("failed to open file: {:?}", file.display()))?
+                .read_to_string(&mut buf)
+                .context("failed to read puzzle input from file")?,
+            None => io::stdin()
+                .read_to_string(&mut buf)
+                .context("failed to read puzzle input from stdin")?,
+        };
+
+        Ok(normalize_input(&buf).into_owned())
+    }
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Solve one or more days, one after another. Takes the same options as
+    /// solving a single day.
+    Run(RunArgs),
+
+    /// Parse a day's input and print the parsed result, without solving
+    /// anything
+    Inspect(InspectArgs),
+
+    /// Solve a day with every file in a directory as its input, and print a
+    /// table of the answers
+    Batch(BatchArgs),
+
+    /// Start an interactive session for one day, which solves both parts
+    /// every time the input is pasted in or edited, and shows how the
+    /// answers changed since the last run
+    Repl(ReplArgs),
+
+    /// List every day, with its title, which parts are solved, whether its
+    /// input is cached, which parts have an expected answer recorded, and how
+    /// long each part took when last benchmarked
+    List,
+}
+
+#[derive(clap::Args)]
+struct RunArgs {
+    /// The advent of code days to solve, in order. May be given more than
+    /// once, or as a comma separated list.
+    #[arg(short, long, required = true, value_delimiter = ',')]
+    day: Vec<Day>,
+
+    /// Which part of each day to solve. Every part that has a solution is
+    /// solved unless this is given.
+    #[arg(short, long)]
+    part: Option<Part>,
+
+    /// The puzzle input: a file, or a directory with an input for each day,
+    /// named like `day17.txt`. If not given, the input is read from standard
+    /// input, which only works for a single day.
+    #[arg(short, long, value_name = "PATH")]
+    input: Option<PathBuf>,
+
+    #[command(flatten)]
+    options: SolveOptions,
+}
+
+impl RunArgs {
+    /// Read each day's input, up front, so that a missing one is found
+    /// before anything is solved
+    fn read_inputs(&self) -> anyhow::Result<Vec<(Day, String)>> {
+        let directory = self.input.as_deref().filter(|path| path.is_dir());
+
+        if directory.is_none() && self.day.len() > 1 {
+            anyhow::bail!("solving several days requires --input to be a directory");
+        }
+
+        self.day
+            .iter()
+            .map(|&day| {
+                let file = match directory {
+                    Some(directory) => Some(directory.join(format!("day{}.txt", day.number()))),
+                    None => self.input.clone(),
+                };
+
+                let input = InputArgs { file, string: None }.read()?;
+                Ok((day, input))
+            })
+            .collect()
+    }
+}
+
+#[derive(clap::Args)]
+struct InspectArgs {
+    /// The advent of code day whose input to parse
+    #[arg(short, long)]
+    day: Day,
+
+    /// How to print the parsed input
+    #[arg(long, value_enum, default_value = "json")]
+    format: library::inspect::Format,
+
+    #[command(flatten)]
+    input: InputArgs,
+}
+
+#[derive(clap::Args)]
+struct BatchArgs {
     /// The advent of code day to solve
     #[arg(short, long)]
     day: Day,
 
-    /// Which part of the day to solve
+    /// Which part of the day to solve. Both parts are solved unless this is
+    /// given.
     #[arg(short, long)]
-    part: Part,
+    part: Option<Part>,
+
+    /// The directory of puzzle inputs. Every file in it is solved.
+    #[arg(long, value_name = "DIR")]
+    inputs: PathBuf,
+
+    /// A day-specific parameter, given as name=value, used for every input.
+    /// May be given more than once.
+    #[arg(short = 'P', long = "param", value_name = "NAME=VALUE")]
+    params: Vec<library::params::Param>,
+}
 
+#[derive(clap::Args)]
+struct ReplArgs {
+    /// The advent of code day to solve
+    #[arg(short, long)]
+    day: Day,
+
+    /// If given, start with the puzzle input from this file. Otherwise, the
+    /// session starts without an input, until one is pasted in or edited.
+    #[arg(short, long)]
+    file: Option<PathBuf>,
+
+    /// A day-specific parameter, given as name=value, used for every run.
+    /// May be given more than once.
+    #[arg(short = 'P', long = "param", value_name = "NAME=VALUE")]
+    params: Vec<library::params::Param>,
+}
+
+/// How to solve a puzzle, for running a single day, or several with `run`
+#[derive(clap::Args)]
+struct SolveOptions {
     /// If given, before the solution is printed, the parsed input for the
     /// problem will be printed to stderr
     #[arg(short = 'v', long)]
     show_input: bool,
 
-    /// If given, read input from this file
-    #[arg(short, long, group = "input")]
-    file: Option<PathBuf>,
+    /// A day-specific parameter, given as name=value. May be given more than
+    /// once. See each day for the parameters it understands.
+    #[arg(short = 'P', long = "param", value_name = "NAME=VALUE")]
+    params: Vec<library::params::Param>,
 
-    /// If given, use this as the puzzle input directly
-    #[arg(short, long, group = "input")]
-    string: Option<String>,
+    /// Draw a visualization of the solution with this backend, for days that
+    /// support one
+    #[arg(long, value_name = "BACKEND")]
+    visualize: Option<library::visualize::Backend>,
+
+    /// Where to write the visualization. Defaults to stderr.
+    #[arg(long, value_name = "PATH", requires = "visualize")]
+    visualize_output: Option<PathBuf>,
+
+    /// How long to show each frame of an animated visualization, in
+    /// milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 100)]
+    visualize_delay: u64,
+
+    /// Write intermediate state recorded by the solution (for days that
+    /// record any) into this directory, one file per artifact
+    #[arg(long, value_name = "DIR")]
+    dump_state: Option<PathBuf>,
+
+    /// Run every alternative algorithm (for days that have more than one),
+    /// instead of only the one selected with --param, and fail unless they
+    /// all agree
+    #[arg(long)]
+    validate: bool,
+
+    /// Run brute-force searches as compute shaders on the GPU, for days that
+    /// can. Requires building with the gpu feature.
+    #[arg(long)]
+    gpu: bool,
+
+    /// Don't show progress bars for long-running parts
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// How to print the solution
+    #[arg(long, value_enum, default_value = "text")]
+    answer_format: library::answer::Format,
+
+    /// Print how long parsing and solving took to stderr, along with any
+    /// counters the solution recorded, like the number of states a search
+    /// expanded
+    #[arg(long)]
+    stats: bool,
+}
+
+impl SolveOptions {
+    /// Set up the runner's global state from these options. Returns whether
+    /// to show each parsed input.
+    fn init(self) -> anyhow::Result<bool> {
+        library::params::init(self.params);
+        library::visualize::init(self.visualize.map(|backend| library::visualize::Config {
+            backend,
+            output: self.visualize_output,
+            delay: Duration::from_millis(self.visualize_delay),
+        }));
+        library::dump::init(self.dump_state);
+        library::validate::init(self.validate);
+
+        if self.gpu && !library::gpu::available() {
+            anyhow::bail!("--gpu requires building with the gpu feature");
+        }
+
+        library::gpu::init(self.gpu);
+        library::stats::init(self.stats);
+        library::progress::init(!self.quiet);
+        library::answer::init(self.answer_format);
+
+        Ok(self.show_input)
+    }
+}
+
+/// Solve an Advent of Code 2024 problem for the given day and part. The
+/// solution is always written to standard output.
+#[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The advent of code day to solve
+    #[arg(short, long, required = true)]
+    day: Option<Day>,
+
+    /// Which part of the day to solve
+    #[arg(short, long, required = true)]
+    part: Option<Part>,
+
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    options: SolveOptions,
+}
+
+/// Parse `input` with `S`, solve one part of it, and print the solution, in
+/// the format chosen with `--answer-format`. If the solution streams
+/// updates, show the latest one while it runs. With `--stats`, also report
+/// timing, counters, and every update to stderr, even if solving failed. If
+/// the solution was interrupted with Ctrl-C, report how long it ran and the
+/// best candidate it found, if it recorded one.
+fn solve<S: Solver>(input: &str, part: Part, show_input: bool) -> anyhow::Result<()> {
+    let start = Instant::now();
+    let input = S::parse(input).context("failed to parse input")?;
+    let parsed = start.elapsed();
+
+    if show_input {
+        eprintln!("Parsed input:\n{input:#?}");
+    }
+
+    let start = Instant::now();
+    let status = library::progress::status();
+    let mut updates = Vec::new();
+
+    let solution = part.finish::<S>(input, &mut |update| {
+        status.set_message(format!("so far: {update}"));
+        library::cancel::record_best(update);
+        updates.push((start.elapsed(), update.to_owned()));
+    });
+
+    let solved = start.elapsed();
+    status.finish_and_clear();
+
+    if library::stats::enabled() {
+        eprintln!("parsed in {parsed:?}");
+        eprintln!("solved in {solved:?}");
+        eprint!("{}", library::stats::take());
+
+        for (elapsed, update) in &updates {
+            eprintln!("update after {elapsed:?}: {update}");
+        }
+    }
+
+    match solution {
+        Ok(answer) => print!("{}", answer.show(library::answer::format())?),
+        Err(err) if library::cancel::is_cancelled(&err) => {
+            eprintln!("interrupted after {:?}", parsed + solved);
+
+            if let Some(best) = library::cancel::take_best() {
+                eprintln!("best candidate so far: {best}");
+            }
+
+            return Err(err);
+        }
+        Err(err) => return Err(err.context("failed to compute solution after successful parse")),
+    }
+
+    Ok(())
+}
+
+/// Parse `input` with `S` and solve one part of it, without printing or
+/// reporting anything
+fn compute_answer<S: Solver>(input: &str, part: Part) -> anyhow::Result<Answer> {
+    let input = S::parse(input).context("failed to parse input")?;
+
+    part.finish::<S>(input, &mut |_| {})
+        .context("failed to compute solution after successful parse")
+}
+
+/// Solve one part of a day's input, and print the solution; see `solve`. A
+/// panic while solving is reported as a failure, like any other error.
+fn run_solution(day: Day, part: Part, input: &str, show_input: bool) -> anyhow::Result<()> {
+    struct Run<'a> {
+        part: Part,
+        input: &'a str,
+        show_input: bool,
+    }
+
+    impl Visitor for Run<'_> {
+        type Output = Result<anyhow::Result<()>, library::panics::Panicked>;
+
+        fn visit<S: Solver>(self) -> Self::Output {
+            library::panics::catch(|| solve::<S>(self.input, self.part, self.show_input))
+        }
+    }
+
+    day.visit(Run {
+        part,
+        input,
+        show_input,
+    })
+    .map_err(anyhow::Error::new)
+    .and_then(|solved| solved)
+    .with_context(|| {
+        format!(
+            "failed to solve day {}, part {}",
+            day.number(),
+            part.number()
+        )
+    })
+}
+
+/// Solve one part of a day's input. Answers are computed for commands that
+/// solve many inputs in one run, which shouldn't end at the first panic.
+fn answer_solution(day: Day, part: Part, input: &str) -> anyhow::Result<Answer> {
+    struct Compute<'a> {
+        part: Part,
+        input: &'a str,
+    }
+
+    impl Visitor for Compute<'_> {
+        type Output = Result<anyhow::Result<Answer>, library::panics::Panicked>;
+
+        fn visit<S: Solver>(self) -> Self::Output {
+            library::panics::catch(|| compute_answer::<S>(self.input, self.part))
+        }
+    }
+
+    day.visit(Compute { part, input }).with_context(|| {
+        format!(
+            "failed to solve day {}, part {}",
+            day.number(),
+            part.number()
+        )
+    })?
+}
+
+/// Parse a day's input, and show it; see `library::inspect`
+fn inspect_input(
+    day: Day,
+    input: &str,
+    format: library::inspect::Format,
+) -> anyhow::Result<String> {
+    struct Inspect<'a> {
+        input: &'a str,
+        format: library::inspect::Format,
+    }
+
+    impl Visitor for Inspect<'_> {
+        type Output = anyhow::Result<String>;
+
+        fn visit<S: Solver>(self) -> Self::Output {
+            library::inspect::show::<S>(self.input, self.format)
+        }
+    }
+
+    day.visit(Inspect { input, format })
+        .with_context(|| format!("failed to parse input for day {}", day.number()))
+}
+
+fn inspect(args: InspectArgs) -> anyhow::Result<()> {
+    let input = args.input.read()?;
+    let shown = inspect_input(args.day, &input, args.format)?;
+    println!("{shown}");
+
+    Ok(())
+}
+
+/// Print the table of answers, followed by the details of any errors on
+/// stderr. Fails if any input did.
+fn batch(args: BatchArgs) -> anyhow::Result<()> {
+    library::params::init(args.params);
+
+    let parts: Vec<_> = [("part 1", Part::Part1), ("part 2", Part::Part2)]
+        .into_iter()
+        .filter(|&(_, part)| args.part.is_none_or(|only| only == part))
+        .collect();
+
+    let table = library::batch::run(&args.inputs, &parts, |part, input| {
+        answer_solution(args.day, part, input)
+    })?;
+
+    print!("{table}");
+
+    for (name, part, err) in table.errors() {
+        eprintln!("{name}, {part}: {err}");
+    }
+
+    match table.failures() {
+        0 => Ok(()),
+        failures => anyhow::bail!("{failures} of {} inputs failed", table.len()),
+    }
+}
+
+/// Run an interactive session for one day, reading commands from stdin
+fn repl(args: ReplArgs) -> anyhow::Result<()> {
+    library::params::init(args.params);
+
+    let input = args
+        .file
+        .map(|file| {
+            InputArgs {
+                file: Some(file),
+                string: None,
+            }
+            .read()
+        })
+        .transpose()?;
+
+    let name = format!("{:?}", args.day).to_lowercase();
+    let parts = [("part 1", Part::Part1), ("part 2", Part::Part2)];
+
+    library::repl::run(&name, &parts, input, |part, input| {
+        answer_solution(args.day, part, input)
+    })
+}
+
+/// Solve each requested part of each day in turn. When there's more than one,
+/// each answer is headed by its day and part. A part that fails or panics
+/// doesn't stop the rest, unless it was interrupted with Ctrl-C.
+fn run(args: RunArgs) -> anyhow::Result<()> {
+    let inputs = args.read_inputs()?;
+    let show_input = args.options.init()?;
+    library::cancel::install()?;
+
+    let runs: Vec<(Day, Part, &str)> = inputs
+        .iter()
+        .flat_map(|(day, input)| {
+            [Part::Part1, Part::Part2]
+                .into_iter()
+                .filter(|&part| match args.part {
+                    Some(only) => only == part,
+                    None => day.parts().contains(&part.number()),
+                })
+                .map(|part| (*day, part, input.as_str()))
+        })
+        .collect();
+
+    let mut failures = 0;
+
+    for &(day, part, input) in &runs {
+        if runs.len() > 1 {
+            println!("day {}, part {}:", day.number(), part.number());
+        }
+
+        if let Err(err) = run_solution(day, part, input, show_input) {
+            if library::cancel::is_cancelled(&err) {
+                return Err(err);
+            }
+
+            eprintln!("Error: {err:#}");
+            failures += 1;
+        }
+    }
+
+    library::dump::write_all().context("failed to dump state")?;
+
+    match failures {
+        0 => Ok(()),
+        failures => anyhow::bail!("{failures} of {} parts failed", runs.len()),
+    }
+}
+
+fn list() {
+    let entries = Day::ALL.iter().map(|&day| library::list::Entry {
+        day: day.number(),
+        title: day.title(),
+        parts: day.parts(),
+    });
+
+    print!("{}", library::list::Listing::new(entries));
 }
 
 fn main() -> anyhow::Result<()> {
     let args: Args = Args::parse();
 
-    let buf = match args.string {
-        Some(buf) => buf,
-        None => {
-            let mut buf = String::new();
-            match args.file {
-                Some(file) => File::open(&file)
-                    .context(lazy_format!("failed to open file: {:?}", file.display()))?
-                    .read_to_string(&mut buf)
-                    .context("failed to read puzzle input from file")?,
-                None => io::stdin()
-                    .read_to_string(&mut buf)
-                    .context("failed to read puzzle input from stdin")?,
-            };
-            buf
+    match args.command {
+        Some(Command::Run(args)) => return run(args),
+        Some(Command::Inspect(args)) => return inspect(args),
+        Some(Command::Batch(args)) => return batch(args),
+        Some(Command::Repl(args)) => return repl(args),
+        Some(Command::List) => {
+            list();
+            return Ok(());
         }
+        None => {}
+    }
+
+    let (Some(day), Some(part)) = (args.day, args.part) else {
+        unreachable!("clap requires --day and --part without a subcommand")
     };
 
-    run_solution(args.day, args.part, &buf, args.show_input)
+    let show_input = args.options.init()?;
+    let buf = args.input.read()?;
+
+    // Only after reading the input, so that Ctrl-C while reading from stdin
+    // still exits right away
+    library::cancel::install()?;
+
+    let solution = run_solution(day, part, &buf, show_input);
+    let dumped = library::dump::write_all().context("failed to dump state");
+
+    solution.and(dumped)
 }
@@ -0,0 +1,919 @@
+//! Property tests over generated puzzle inputs. Each day's strategies
+//! generate valid puzzle text, usually along with whatever a simple
+//! reference needs to compute the expected answer, so that the properties
+//! can check both that the solutions don't panic and that they agree with a
+//! more obviously correct algorithm.
+//!
+//! Days 7, 9, 13, 19, and 20 also have brute-force `naive` versions of their
+//! parts, which only exist with the `naive` feature; run
+//! `cargo test --features naive` to check the real solutions against them.
+//!
+//! The days are also checked against their example inputs, through the
+//! same registry the runner uses.
+//!
+//! These run with the default runner parameters; `params::init` is never
+//! called, so every `--param` lookup sees nothing.
+
+use std::{fs, path::Path};
+
+use proptest::prelude::*;
+
+fn example(day: u8) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs/examples")
+        .join(format!("day{day}.txt"));
+
+    fs::read_to_string(path).expect("every day has an example input")
+}
+
+mod day5 {
+    use super::*;
+    use bumpalo::Bump;
+
+    use crate::day5::{Input, parse_in, part1, part2};
+
+    /// A page ordering puzzle. There's a hidden total order over the pages,
+    /// and every pair of pages gets a rule, so every update has exactly one
+    /// correct order.
+    #[derive(Debug, Clone)]
+    pub struct Puzzle {
+        /// Every page, in the hidden order
+        pub order: Vec<u32>,
+        pub rules: Vec<(u32, u32)>,
+        pub updates: Vec<Vec<u32>>,
+    }
+
+    impl Puzzle {
+        pub fn text(&self) -> String {
+            let rules = self
+                .rules
+                .iter()
+                .map(|(before, after)| format!("{before}|{after}\n"));
+
+            let updates = self.updates.iter().map(|update| {
+                let pages: Vec<String> = update.iter().map(u32::to_string).collect();
+                format!("{}\n", pages.join(","))
+            });
+
+            rules.chain(["\n".to_owned()]).chain(updates).collect()
+        }
+
+        fn sorted(&self, update: &[u32]) -> Vec<u32> {
+            let mut sorted = update.to_vec();
+            sorted.sort_by_key(|page| self.order.iter().position(|p| p == page));
+            sorted
+        }
+
+        /// The expected answers: the middle pages of the updates that are
+        /// already in order, and of the others once they're sorted
+        pub fn answers(&self) -> (u32, u32) {
+            self.updates
+                .iter()
+                .fold((0, 0), |(in_order, reordered), update| {
+                    let sorted = self.sorted(update);
+                    let middle = sorted[sorted.len() / 2];
+
+                    match sorted == *update {
+                        true => (in_order + middle, reordered),
+                        false => (in_order, reordered + middle),
+                    }
+                })
+        }
+    }
+
+    pub fn puzzle() -> impl Strategy<Value = Puzzle> {
+        proptest::sample::subsequence((10..100).collect::<Vec<u32>>(), 2..15)
+            .prop_shuffle()
+            .prop_flat_map(|order| {
+                let rules: Vec<(u32, u32)> = order
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, &before)| {
+                        order[i + 1..].iter().map(move |&after| (before, after))
+                    })
+                    .collect();
+
+                let len = order.len();
+                let update = proptest::sample::subsequence(order.clone(), 1..=len).prop_shuffle();
+
+                (
+                    Just(order),
+                    Just(rules).prop_shuffle(),
+                    prop::collection::vec(update, 1..10),
+                )
+            })
+            .prop_map(|(order, rules, updates)| Puzzle {
+                order,
+                rules,
+                updates,
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn parts_agree_with_hidden_order(puzzle in puzzle()) {
+            let text = puzzle.text();
+            let (in_order, reordered) = puzzle.answers();
+
+            let input = Input::try_from(text.as_str()).expect("generated input parses");
+            prop_assert_eq!(part1(input).unwrap(), in_order);
+
+            let input = Input::try_from(text.as_str()).expect("generated input parses");
+            prop_assert_eq!(part2(input).unwrap(), reordered);
+        }
+
+        #[test]
+        fn arena_parse_agrees_with_hidden_order(puzzle in puzzle()) {
+            let text = puzzle.text();
+            let (in_order, reordered) = puzzle.answers();
+            let arena = Bump::new();
+
+            let input = parse_in(&arena, &text).expect("generated input parses");
+            prop_assert_eq!(part1(input).unwrap(), in_order);
+
+            let input = parse_in(&arena, &text).expect("generated input parses");
+            prop_assert_eq!(part2(input).unwrap(), reordered);
+        }
+    }
+}
+
+#[cfg(feature = "naive")]
+mod day7 {
+    use super::*;
+    use crate::day7::{
+        Evaluation, Input, calibration,
+        naive::{self, Operator},
+    };
+
+    /// An equation that's usually true under `evaluation`, built from a
+    /// random assignment of operators, with its value sometimes nudged
+    fn equation(evaluation: Evaluation) -> impl Strategy<Value = String> {
+        let operator =
+            prop::sample::select(vec![Operator::Add, Operator::Multiply, Operator::Concat]);
+
+        (
+            1..=20i64,
+            prop::collection::vec((operator, 1..=20i64), 0..=5),
+            prop::sample::select(vec![0, 0, 1]),
+        )
+            .prop_map(move |(first, rest, nudge)| {
+                let value = naive::evaluate(first, &rest, evaluation)
+                    .expect("short equations don't overflow")
+                    + nudge;
+
+                let operands: Vec<String> = std::iter::once(first)
+                    .chain(rest.iter().map(|&(_, operand)| operand))
+                    .map(|operand| operand.to_string())
+                    .collect();
+
+                format!("{value}: {}", operands.join(" "))
+            })
+    }
+
+    fn text() -> impl Strategy<Value = (Evaluation, String)> {
+        prop::sample::select(vec![Evaluation::LeftToRight, Evaluation::Precedence]).prop_flat_map(
+            |evaluation| {
+                prop::collection::vec(equation(evaluation), 1..=6)
+                    .prop_map(move |equations| (evaluation, equations.join("\n")))
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn calibration_agrees_with_naive(
+            (evaluation, text) in text(),
+            allow_concat in any::<bool>(),
+        ) {
+            let input = Input::try_from(text.as_str()).expect("generated input parses");
+
+            prop_assert_eq!(
+                calibration(&input, allow_concat, evaluation),
+                naive::calibration(&input, allow_concat, evaluation),
+            );
+        }
+    }
+}
+
+mod day9 {
+    use super::*;
+    #[cfg(feature = "naive")]
+    use crate::day9::naive;
+    use crate::day9::{
+        Block, CompactionPolicy, FileID, Fragmenting, Input, Memory, WholeFile, part1, part2,
+    };
+
+    /// A disk map: alternating file and free widths, starting and ending
+    /// with a file
+    pub fn disk_map() -> impl Strategy<Value = Vec<u32>> {
+        (1..=9u32, prop::collection::vec((0..=9u32, 1..=9u32), 0..40)).prop_map(|(first, rest)| {
+            std::iter::once(first)
+                .chain(rest.into_iter().flat_map(|(free, file)| [free, file]))
+                .collect()
+        })
+    }
+
+    fn text(disk_map: &[u32]) -> String {
+        disk_map.iter().map(u32::to_string).collect()
+    }
+
+    /// Lay out the disk cell by cell
+    fn cells(disk_map: &[u32]) -> Vec<Option<u32>> {
+        disk_map
+            .iter()
+            .enumerate()
+            .flat_map(|(index, &width)| {
+                let cell = index.is_multiple_of(2).then_some(index as u32 / 2);
+                std::iter::repeat_n(cell, width as usize)
+            })
+            .collect()
+    }
+
+    fn checksum(cells: &[Option<u32>]) -> i64 {
+        cells
+            .iter()
+            .enumerate()
+            .filter_map(|(position, &file)| Some(position as i64 * i64::from(file?)))
+            .sum()
+    }
+
+    /// Move single cells from the end into the leftmost gap
+    fn reference_fragmenting(disk_map: &[u32]) -> i64 {
+        let mut cells = cells(disk_map);
+        let mut left = 0;
+        let mut right = cells.len();
+
+        loop {
+            while left < right && cells[left].is_some() {
+                left += 1;
+            }
+
+            while left < right && cells[right - 1].is_none() {
+                right -= 1;
+            }
+
+            if left + 1 >= right {
+                break;
+            }
+
+            cells.swap(left, right - 1);
+        }
+
+        checksum(&cells)
+    }
+
+    /// Move whole files, highest ID first, into the leftmost gap that fits
+    fn reference_whole_file(disk_map: &[u32]) -> i64 {
+        let mut cells = cells(disk_map);
+        let files = disk_map.len().div_ceil(2) as u32;
+
+        for file in (0..files).rev() {
+            let start = cells.iter().position(|&cell| cell == Some(file)).unwrap();
+            let width = disk_map[file as usize * 2] as usize;
+
+            let gap = (0..start).find(|&gap| cells[gap..gap + width].iter().all(Option::is_none));
+
+            if let Some(gap) = gap {
+                cells[gap..gap + width].fill(Some(file));
+                cells[start..start + width].fill(None);
+            }
+        }
+
+        checksum(&cells)
+    }
+
+    fn memory(disk_map: &[u32]) -> Memory {
+        Memory::from_disk_map(disk_map.iter().map(|&width| i64::from(width)))
+    }
+
+    /// Whole-file compaction written against the raw allocated and free
+    /// lists, as a policy from outside the module would be
+    struct FirstFit;
+
+    impl CompactionPolicy for FirstFit {
+        fn compact(&self, memory: &mut Memory) {
+            let (allocated, free) = memory.raw_parts_mut();
+
+            for (block, _) in allocated.iter_mut().rev() {
+                let width = block.width();
+
+                if let Some(gap) = free
+                    .iter_mut()
+                    .find(|gap| gap.start < block.start && gap.width() >= width)
+                {
+                    *block = Block {
+                        start: gap.start,
+                        end: gap.start + width,
+                    };
+
+                    gap.start += width;
+                }
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn fragmenting_agrees_with_reference(disk_map in disk_map()) {
+            let input = Input::try_from(text(&disk_map).as_str()).expect("generated input parses");
+            prop_assert_eq!(part1(input).unwrap(), reference_fragmenting(&disk_map));
+        }
+
+        #[test]
+        fn whole_file_agrees_with_reference(disk_map in disk_map()) {
+            let input = Input::try_from(text(&disk_map).as_str()).expect("generated input parses");
+            prop_assert_eq!(part2(input).unwrap(), reference_whole_file(&disk_map));
+        }
+
+        #[test]
+        fn extents_round_trip(disk_map in disk_map()) {
+            let memory = memory(&disk_map);
+            let extents = memory.allocated().map(|(block, file)| (block.clone(), file));
+            let rebuilt = Memory::from_extents(extents);
+
+            prop_assert_eq!(rebuilt.to_string(), memory.to_string());
+
+            let gaps: Vec<&Block> = memory.free().filter(|gap| !gap.is_empty()).collect();
+            prop_assert_eq!(rebuilt.free().collect::<Vec<_>>(), gaps);
+        }
+
+        #[test]
+        fn fragmenting_keeps_every_cell(disk_map in disk_map()) {
+            let mut memory = memory(&disk_map);
+            memory.compact(&Fragmenting);
+
+            for (file, &width) in disk_map.iter().step_by(2).enumerate() {
+                let extents = memory.extents(FileID(file as u32));
+                let total: i64 = extents.iter().map(Block::width).sum();
+
+                prop_assert_eq!(total, i64::from(width));
+                prop_assert!(extents.windows(2).all(|pair| pair[0].end <= pair[1].start));
+            }
+        }
+
+        #[test]
+        fn custom_policy_agrees_with_whole_file(disk_map in disk_map()) {
+            let mut first_fit = memory(&disk_map);
+            first_fit.compact(&FirstFit);
+
+            let mut whole_file = memory(&disk_map);
+            whole_file.compact(&WholeFile);
+
+            prop_assert_eq!(first_fit.to_string(), whole_file.to_string());
+        }
+    }
+
+    #[cfg(feature = "naive")]
+    proptest! {
+        #[test]
+        fn fragmenting_agrees_with_naive(disk_map in disk_map()) {
+            let parse = || Input::try_from(text(&disk_map).as_str()).expect("generated input parses");
+            prop_assert_eq!(part1(parse()).unwrap(), naive::part1(parse()).unwrap());
+        }
+
+        #[test]
+        fn whole_file_agrees_with_naive(disk_map in disk_map()) {
+            let parse = || Input::try_from(text(&disk_map).as_str()).expect("generated input parses");
+            prop_assert_eq!(part2(parse()).unwrap(), naive::part2(parse()).unwrap());
+        }
+    }
+}
+
+#[cfg(feature = "naive")]
+mod day13 {
+    use super::*;
+    use crate::day13::{Input, naive, part1};
+
+    /// A button that never moves the claw backward, as `(x, y, cost)`
+    fn button() -> impl Strategy<Value = (u32, u32, u32)> {
+        (0..=12u32, 0..=12u32, 1..=4u32)
+    }
+
+    /// A claw machine with one to four buttons and a nearby prize
+    fn machine() -> impl Strategy<Value = String> {
+        (
+            prop::collection::vec(button(), 1..=4),
+            0..=120u32,
+            0..=120u32,
+        )
+            .prop_map(|(buttons, x, y)| {
+                let buttons: String = buttons
+                    .iter()
+                    .zip('A'..)
+                    .map(|(&(dx, dy, cost), id)| {
+                        format!("Button {id}: X+{dx}, Y+{dy}, Cost={cost}\n")
+                    })
+                    .collect();
+
+                format!("{buttons}Prize: X={x}, Y={y}\n")
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn min_cost_agrees_with_naive(machines in prop::collection::vec(machine(), 1..5)) {
+            let text = machines.join("\n");
+            let parse = || Input::try_from(text.as_str()).expect("generated input parses");
+
+            prop_assert_eq!(part1(parse()).unwrap(), naive::part1(parse()).unwrap());
+        }
+    }
+}
+
+mod day15 {
+    use std::collections::HashSet;
+
+    use super::*;
+    use bumpalo::Bump;
+
+    use crate::day15::{Input, Simulation, parse_in, part1, part2};
+
+    /// A warehouse, as rows of characters, surrounded by walls, with exactly
+    /// one robot; and a list of moves, as `^>v<` characters
+    #[derive(Debug, Clone)]
+    pub struct Warehouse {
+        pub rows: Vec<Vec<char>>,
+        pub moves: String,
+    }
+
+    impl Warehouse {
+        pub fn text(&self) -> String {
+            let mut text = String::new();
+
+            for row in &self.rows {
+                text.extend(row);
+                text.push('\n');
+            }
+
+            text.push('\n');
+
+            // Wrap the moves, like the real input
+            for line in self.moves.as_bytes().chunks(70) {
+                text.push_str(std::str::from_utf8(line).unwrap());
+                text.push('\n');
+            }
+
+            text
+        }
+
+        /// The warehouse with every tile doubled in width, as in part 2
+        fn widened(&self) -> Vec<Vec<char>> {
+            self.rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .flat_map(|&cell| match cell {
+                            '#' => ['#', '#'],
+                            'O' => ['[', ']'],
+                            '@' => ['@', '.'],
+                            _ => ['.', '.'],
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+    }
+
+    pub fn warehouse() -> impl Strategy<Value = Warehouse> {
+        (1..10usize, 1..10usize)
+            .prop_flat_map(|(height, width)| {
+                let cell = prop_oneof![4 => Just('.'), 1 => Just('#'), 3 => Just('O')];
+                let interior = prop::collection::vec(prop::collection::vec(cell, width), height);
+                let robot = (0..height, 0..width);
+                let moves =
+                    prop::collection::vec(prop::sample::select(vec!['^', '>', 'v', '<']), 1..200);
+
+                (interior, robot, moves)
+            })
+            .prop_map(|(mut interior, (robot_row, robot_column), moves)| {
+                interior[robot_row][robot_column] = '@';
+
+                let width = interior[0].len() + 2;
+                let wall = vec!['#'; width];
+
+                let rows = std::iter::once(wall.clone())
+                    .chain(interior.into_iter().map(|row| {
+                        std::iter::once('#')
+                            .chain(row)
+                            .chain(std::iter::once('#'))
+                            .collect()
+                    }))
+                    .chain(std::iter::once(wall))
+                    .collect();
+
+                Warehouse {
+                    rows,
+                    moves: moves.into_iter().collect(),
+                }
+            })
+    }
+
+    fn step(direction: char) -> (isize, isize) {
+        match direction {
+            '^' => (-1, 0),
+            '>' => (0, 1),
+            'v' => (1, 0),
+            '<' => (0, -1),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Run every move on a grid of characters, where boxes are either `O`
+    /// or `[]`, by finding every cell that would be pushed and then moving
+    /// them all at once. Returns the GPS sum of the final warehouse.
+    fn reference_gps(mut rows: Vec<Vec<char>>, moves: &str) -> isize {
+        let mut robot = rows
+            .iter()
+            .enumerate()
+            .find_map(|(r, row)| Some((r as isize, row.iter().position(|&c| c == '@')? as isize)))
+            .unwrap();
+
+        let at = |rows: &Vec<Vec<char>>, (r, c): (isize, isize)| rows[r as usize][c as usize];
+
+        for direction in moves.chars() {
+            let (dr, dc) = step(direction);
+
+            // Every cell that moves, in the order they were found
+            let mut pushed = vec![robot];
+            let mut seen = HashSet::from([robot]);
+            let mut blocked = false;
+            let mut index = 0;
+
+            while let Some(&(r, c)) = pushed.get(index) {
+                index += 1;
+                let next = (r + dr, c + dc);
+
+                let mut also = match at(&rows, next) {
+                    '#' => {
+                        blocked = true;
+                        break;
+                    }
+                    '.' => vec![],
+                    'O' => vec![next],
+                    '[' if dr != 0 => vec![next, (next.0, next.1 + 1)],
+                    ']' if dr != 0 => vec![next, (next.0, next.1 - 1)],
+                    _ => vec![next],
+                };
+
+                also.retain(|cell| seen.insert(*cell));
+                pushed.extend(also);
+            }
+
+            if blocked {
+                continue;
+            }
+
+            let contents: Vec<char> = pushed.iter().map(|&cell| at(&rows, cell)).collect();
+
+            for &(r, c) in &pushed {
+                rows[r as usize][c as usize] = '.';
+            }
+
+            for (&(r, c), content) in pushed.iter().zip(contents) {
+                rows[(r + dr) as usize][(c + dc) as usize] = content;
+            }
+
+            robot = (robot.0 + dr, robot.1 + dc);
+        }
+
+        rows.iter()
+            .enumerate()
+            .flat_map(|(r, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|&(_, &cell)| cell == 'O' || cell == '[')
+                    .map(move |(c, _)| r as isize * 100 + c as isize)
+            })
+            .sum()
+    }
+
+    proptest! {
+        #[test]
+        fn narrow_boxes_agree_with_reference(warehouse in warehouse()) {
+            let input = Input::try_from(warehouse.text().as_str()).expect("generated input parses");
+            let expected = reference_gps(warehouse.rows.clone(), &warehouse.moves);
+
+            prop_assert_eq!(part1(input).unwrap(), expected);
+        }
+
+        #[test]
+        fn wide_boxes_agree_with_reference(warehouse in warehouse()) {
+            let input = Input::try_from(warehouse.text().as_str()).expect("generated input parses");
+            let expected = reference_gps(warehouse.widened(), &warehouse.moves);
+
+            prop_assert_eq!(part2(input).unwrap(), expected);
+        }
+
+        #[test]
+        fn arena_parse_agrees_with_reference(warehouse in warehouse()) {
+            let text = warehouse.text();
+            let arena = Bump::new();
+
+            let input = parse_in(&arena, &text).expect("generated input parses");
+            let expected = reference_gps(warehouse.rows.clone(), &warehouse.moves);
+            prop_assert_eq!(part1(input).unwrap(), expected);
+
+            let input = parse_in(&arena, &text).expect("generated input parses");
+            let expected = reference_gps(warehouse.widened(), &warehouse.moves);
+            prop_assert_eq!(part2(input).unwrap(), expected);
+        }
+
+        #[test]
+        fn undoing_every_move_restores_the_warehouse(warehouse in warehouse()) {
+            let input = Input::try_from(warehouse.text().as_str()).expect("generated input parses");
+            let initial = input.map.scaled(2).frame();
+            let mut simulation = Simulation::new(input.map.scaled(2), input.instructions);
+
+            while simulation.step().is_some() {}
+            while simulation.undo().is_some() {}
+
+            prop_assert_eq!(simulation.into_map().frame(), initial);
+        }
+    }
+}
+
+mod day17 {
+    use super::*;
+    use crate::day17::{Input, part1, part2};
+    use crate::library::stream::Solution;
+
+    fn text(a: u64, b: u64, c: u64, program: &[u8]) -> String {
+        let program: Vec<String> = program.iter().map(u8::to_string).collect();
+
+        format!(
+            "Register A: {a}\nRegister B: {b}\nRegister C: {c}\n\nProgram: {}\n",
+            program.join(",")
+        )
+    }
+
+    fn run(text: &str) -> anyhow::Result<String> {
+        let input = Input::try_from(text).expect("generated input parses");
+        part1(input).map(|output| output.to_string())
+    }
+
+    /// An instruction that isn't a jump, with any operand, including the
+    /// reserved combo operand 7
+    fn straight_instruction() -> impl Strategy<Value = [u8; 2]> {
+        (prop::sample::select(vec![0u8, 1, 2, 4, 5, 6, 7]), 0..8u8)
+            .prop_map(|(opcode, operand)| [opcode, operand])
+    }
+
+    /// A program with no jumps, which always halts
+    pub fn straight_program() -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(straight_instruction(), 1..12)
+            .prop_map(|instructions| instructions.concat())
+    }
+
+    /// A program with the usual shape: a loop that shifts A right by 3 bits
+    /// exactly once and outputs at least once, then jumps back to the start
+    /// until A is 0. Nothing else writes to A, so these always halt.
+    pub fn loop_program() -> impl Strategy<Value = Vec<u8>> {
+        let body_instruction = (prop::sample::select(vec![1u8, 2, 4, 6, 7]), 0..7u8)
+            .prop_map(|(opcode, operand)| [opcode, operand]);
+
+        (
+            prop::collection::vec(body_instruction, 0..5),
+            0..7u8,
+            any::<prop::sample::Index>(),
+            any::<prop::sample::Index>(),
+        )
+            .prop_map(|(mut body, out_operand, adv_index, out_index)| {
+                body.insert(adv_index.index(body.len() + 1), [0, 3]);
+                body.insert(out_index.index(body.len() + 1), [5, out_operand]);
+                body.push([3, 0]);
+                body.concat()
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn straight_programs_never_panic(
+            a in any::<u64>(),
+            b in any::<u64>(),
+            c in any::<u64>(),
+            program in straight_program(),
+        ) {
+            // Bad operands are errors, not panics
+            let _ = run(&text(a, b, c, &program));
+        }
+
+        #[test]
+        fn loop_programs_run(a in 0..1u64 << 30, program in loop_program()) {
+            prop_assert!(run(&text(a, 0, 0, &program)).is_ok());
+        }
+
+        #[test]
+        fn quines_output_the_program(program in loop_program()) {
+            let input = Input::try_from(text(0, 0, 0, &program).as_str()).expect("generated input parses");
+
+            // Most random programs have no quine, which is fine; but when
+            // part 2 finds one, running it must really output the program.
+            if let Ok(a) = part2(input).and_then(Solution::answer) {
+                let a = u64::try_from(a).expect("u64 search finds a u64");
+                let expected: Vec<String> = program.iter().map(u8::to_string).collect();
+
+                prop_assert_eq!(run(&text(a, 0, 0, &program)).unwrap(), expected.join(","));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "naive")]
+mod day19 {
+    use super::*;
+    use crate::day19::{Input, naive, part1, part2};
+
+    fn stripes(length: std::ops::RangeInclusive<usize>) -> impl Strategy<Value = String> {
+        prop::collection::vec(prop::sample::select(vec!['w', 'u', 'b', 'r', 'g']), length)
+            .prop_map(String::from_iter)
+    }
+
+    /// A few short towels, and some designs to make from them
+    fn text() -> impl Strategy<Value = String> {
+        (
+            prop::collection::btree_set(stripes(1..=3), 1..=6),
+            prop::collection::vec(stripes(1..=10), 1..=6),
+        )
+            .prop_map(|(towels, designs)| {
+                let towels: Vec<String> = towels.into_iter().collect();
+                format!("{}\n\n{}\n", towels.join(", "), designs.join("\n"))
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn possible_designs_agree_with_naive(text in text()) {
+            let parse = || Input::try_from(text.as_str()).expect("generated input parses");
+            prop_assert_eq!(part1(parse()).unwrap(), naive::part1(parse()).unwrap());
+        }
+
+        #[test]
+        fn arrangement_counts_agree_with_naive(text in text()) {
+            let parse = || Input::try_from(text.as_str()).expect("generated input parses");
+            prop_assert_eq!(part2(parse()).unwrap(), naive::part2(parse()).unwrap());
+        }
+    }
+}
+
+#[cfg(feature = "naive")]
+mod day20 {
+    use super::*;
+    use rayon::iter::ParallelIterator;
+
+    use crate::day20::{Input, Racetrack, naive};
+
+    /// A small grid of walls and open cells, with the start and end on two
+    /// different cells. The end isn't necessarily reachable.
+    fn text() -> impl Strategy<Value = String> {
+        (3..=8usize, 3..=8usize)
+            .prop_flat_map(|(rows, columns)| {
+                let cells = rows * columns;
+
+                (
+                    Just(columns),
+                    prop::collection::vec(prop::bool::weighted(0.3), cells),
+                    0..cells,
+                    0..cells - 1,
+                )
+            })
+            .prop_map(|(columns, walls, start, end)| {
+                // Skip over the start, so that the end is somewhere else
+                let end = if end >= start { end + 1 } else { end };
+
+                let cells: Vec<char> = walls
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &wall)| match index {
+                        _ if index == start => 'S',
+                        _ if index == end => 'E',
+                        _ if wall => '#',
+                        _ => '.',
+                    })
+                    .collect();
+
+                cells
+                    .chunks(columns)
+                    .map(|row| row.iter().collect::<String>() + "\n")
+                    .collect()
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn cheats_agree_with_naive(
+            text in text(),
+            max_cheat_distance in 2..=6isize,
+            min_savings in 1..=10isize,
+        ) {
+            let input = Input::try_from(text.as_str()).expect("generated input parses");
+
+            let cheats = Racetrack::new(&input, max_cheat_distance)
+                .ok()
+                .map(|racetrack| racetrack.cheats(min_savings).count());
+
+            prop_assert_eq!(cheats, naive::count_cheats(&input, max_cheat_distance, min_savings));
+        }
+    }
+}
+
+mod normalization {
+    use super::*;
+
+    use crate::{Day, inspect_input, library::inspect::Format, normalize_input};
+
+    macro_rules! day_numbers {
+        ($($module:ident $day:literal,)*) => {
+            [$($day),*]
+        };
+    }
+
+    const DAYS: &[u8] = &for_each_day!(day_numbers);
+
+    /// A day's parsed input, as JSON, which `inspect` writes in a stable
+    /// order even for hash maps
+    fn parsed(day: u8, input: &str) -> String {
+        let day: Day = day.to_string().parse().expect("example days are days");
+        inspect_input(day, input, Format::Json).expect("example inputs parse")
+    }
+
+    /// A day's example input, mangled the way an editor or a browser might:
+    /// some lines end with `\r\n` and some have trailing spaces or tabs, and
+    /// there may be extra whitespace and blank lines at the end
+    fn mangled_example() -> impl Strategy<Value = (u8, String)> {
+        prop::sample::select(DAYS)
+            .prop_flat_map(|day| {
+                let lines = example(day).lines().count();
+
+                (
+                    Just(day),
+                    prop::collection::vec((any::<bool>(), "[ \t]{0,3}"), lines),
+                    "[ \t\r\n]{0,6}",
+                )
+            })
+            .prop_map(|(day, endings, tail)| {
+                let lines: String = example(day)
+                    .lines()
+                    .zip(endings)
+                    .map(|(line, (crlf, trailing))| match crlf {
+                        true => format!("{line}{trailing}\r\n"),
+                        false => format!("{line}{trailing}\n"),
+                    })
+                    .collect();
+
+                (day, lines + &tail)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn examples_are_already_normal(day in prop::sample::select(DAYS)) {
+            let input = example(day);
+            prop_assert_eq!(normalize_input(&input), input.as_str());
+        }
+
+        #[test]
+        fn mangled_examples_parse_like_the_originals((day, input) in mangled_example()) {
+            prop_assert_eq!(parsed(day, &normalize_input(&input)), parsed(day, &example(day)));
+        }
+    }
+}
+
+mod registry {
+    use super::*;
+
+    use crate::{Day, Part, answer_solution};
+
+    /// Every day in the registry, solved the same way: each part that has a
+    /// solution solves the day's example input. Days whose examples only make
+    /// sense with parameters (see the benchmarks) are skipped, since setting
+    /// them here would leak into the other tests.
+    #[test]
+    fn every_day_solves_its_example() {
+        const NEEDS_PARAMS: &[u8] = &[14, 18, 20];
+
+        // The example circuit isn't an adder with swapped wires
+        const UNSOLVABLE: &[(u8, u8)] = &[(24, 2)];
+
+        for &day in Day::ALL {
+            let number = day.number();
+
+            if NEEDS_PARAMS.contains(&number) {
+                continue;
+            }
+
+            let input = example(number);
+
+            for &part in day.parts() {
+                if UNSOLVABLE.contains(&(number, part)) {
+                    continue;
+                }
+
+                let part: Part = part.to_string().parse().expect("parts are parts");
+
+                if let Err(err) = answer_solution(day, part, &input) {
+                    panic!("{err:#}");
+                }
+            }
+        }
+    }
+}
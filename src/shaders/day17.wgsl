@@ -0,0 +1,123 @@
+// Day 17, part 2: run the program once for each candidate value of register
+// A, and check that it outputs exactly `program[position..]` and halts. This
+// is `cpu::Machine::outputs_exactly` in day17.rs, with 64-bit registers, each
+// stored as vec2(low, high), since WGSL doesn't have 64-bit integers.
+
+const MISMATCH: u32 = 0u;
+const MATCH: u32 = 1u;
+const GAVE_UP: u32 = 2u;
+
+struct Params {
+    program_len: u32,
+    position: u32,
+    candidates: u32,
+    step_limit: u32,
+
+    // 1 if reaching the step limit means giving up on the whole search,
+    // rather than a mismatch
+    give_up: u32,
+
+    b_low: u32,
+    b_high: u32,
+    c_low: u32,
+    c_high: u32,
+}
+
+@group(0) @binding(0) var<storage, read> params: Params;
+@group(0) @binding(1) var<storage, read> program: array<u32>;
+@group(0) @binding(2) var<storage, read> candidates: array<vec2<u32>>;
+@group(0) @binding(3) var<storage, read_write> results: array<u32>;
+
+// `value >> shift`, or 0 if that would shift out every bit
+fn shr_or_zero(value: vec2<u32>, shift: vec2<u32>) -> vec2<u32> {
+    if shift.y != 0u || shift.x >= 64u {
+        return vec2(0u);
+    }
+
+    if shift.x >= 32u {
+        return vec2(value.y >> (shift.x - 32u), 0u);
+    }
+
+    if shift.x == 0u {
+        return value;
+    }
+
+    return vec2((value.x >> shift.x) | (value.y << (32u - shift.x)), value.y >> shift.x);
+}
+
+fn run(index: u32) -> u32 {
+    var a = candidates[index];
+    var b = vec2(params.b_low, params.b_high);
+    var c = vec2(params.c_low, params.c_high);
+
+    var instruction_pointer = 0u;
+    var matched = params.position;
+
+    for (var steps = 0u; steps < params.step_limit; steps++) {
+        if instruction_pointer + 1u >= params.program_len {
+            return select(MISMATCH, MATCH, matched == params.program_len);
+        }
+
+        let instruction = program[instruction_pointer];
+        let operand = program[instruction_pointer + 1u];
+
+        var combo = vec2(operand, 0u);
+
+        switch operand {
+            case 4u: { combo = a; }
+            case 5u: { combo = b; }
+            case 6u: { combo = c; }
+            default: {}
+        }
+
+        // The reserved combo operand faults, which is never a match. Only
+        // bxl, jnz, and bxc don't take a combo operand.
+        let takes_combo = instruction != 1u && instruction != 3u && instruction != 4u;
+
+        if takes_combo && operand == 7u {
+            return MISMATCH;
+        }
+
+        var next = instruction_pointer + 2u;
+
+        switch instruction {
+            // adv
+            case 0u: { a = shr_or_zero(a, combo); }
+            // bxl
+            case 1u: { b ^= vec2(operand, 0u); }
+            // bst
+            case 2u: { b = vec2(combo.x & 7u, 0u); }
+            // jnz
+            case 3u: {
+                if any(a != vec2(0u)) {
+                    next = operand;
+                }
+            }
+            // bxc
+            case 4u: { b ^= c; }
+            // out
+            case 5u: {
+                if matched >= params.program_len || program[matched] != (combo.x & 7u) {
+                    return MISMATCH;
+                }
+
+                matched++;
+            }
+            // bdv
+            case 6u: { b = shr_or_zero(a, combo); }
+            // cdv
+            default: { c = shr_or_zero(a, combo); }
+        }
+
+        instruction_pointer = next;
+    }
+
+    return select(MISMATCH, GAVE_UP, params.give_up != 0u);
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.candidates {
+        results[id.x] = run(id.x);
+    }
+}
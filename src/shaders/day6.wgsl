@@ -0,0 +1,88 @@
+// Day 6, part 2: for every cell in the lab, put an extra obstacle there and
+// walk the guard until it leaves or repeats itself. Moves work the same way
+// as `detect_loop` in day6.rs.
+
+const EXIT: u32 = 0u;
+const LOOP: u32 = 1u;
+const STUCK: u32 = 2u;
+
+struct Params {
+    rows: i32,
+    columns: i32,
+    guard_row: i32,
+    guard_column: i32,
+
+    // 0 through 3, for up, right, down, and left, so that turning clockwise
+    // adds 1
+    guard_direction: u32,
+}
+
+@group(0) @binding(0) var<storage, read> params: Params;
+
+// One word per cell, in row-major order: 1 for an obstacle, 0 otherwise
+@group(0) @binding(1) var<storage, read> obstacles: array<u32>;
+
+// One outcome per cell, for an extra obstacle placed there
+@group(0) @binding(2) var<storage, read_write> outcomes: array<u32>;
+
+fn offset(direction: u32) -> vec2<i32> {
+    switch direction {
+        case 0u: { return vec2(-1, 0); }
+        case 1u: { return vec2(0, 1); }
+        case 2u: { return vec2(1, 0); }
+        default: { return vec2(0, -1); }
+    }
+}
+
+fn in_bounds(position: vec2<i32>) -> bool {
+    return all(position >= vec2(0)) && position.x < params.rows && position.y < params.columns;
+}
+
+fn blocked(position: vec2<i32>, extra: vec2<i32>) -> bool {
+    if !in_bounds(position) {
+        return false;
+    }
+
+    return all(position == extra) || obstacles[position.x * params.columns + position.y] != 0u;
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let cells = u32(params.rows * params.columns);
+
+    if id.x >= cells {
+        return;
+    }
+
+    let extra = vec2(i32(id.x) / params.columns, i32(id.x) % params.columns);
+
+    var position = vec2(params.guard_row, params.guard_column);
+    var direction = params.guard_direction;
+
+    // The guard can only be in this many different states, so if it hasn't
+    // left after this many moves, it's repeating itself
+    let max_moves = 4u * cells;
+
+    for (var moves = 0u; moves < max_moves; moves++) {
+        var turn = 0u;
+
+        while blocked(position + offset((direction + turn) % 4u), extra) {
+            turn++;
+
+            if turn == 4u {
+                outcomes[id.x] = STUCK;
+                return;
+            }
+        }
+
+        direction = (direction + turn) % 4u;
+        position += offset(direction);
+
+        if !in_bounds(position) {
+            outcomes[id.x] = EXIT;
+            return;
+        }
+    }
+
+    outcomes[id.x] = LOOP;
+}
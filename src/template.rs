@@ -1,8 +1,10 @@
+//! Day N: Title of the puzzle
+
 use std::convert::Infallible;
 
 use nom_supreme::{error::ErrorTree, final_parser::final_parser};
 
-use crate::library::ITResult;
+use crate::library::{ITResult, solver::solver};
 
 #[derive(Debug)]
 pub struct Input {
@@ -33,3 +35,5 @@ pub fn part1(input: Input) -> anyhow::Result<Infallible> {
 pub fn part2(input: Input) -> anyhow::Result<Infallible> {
     anyhow::bail!("not implemented yet")
 }
+
+solver!(Input, Infallible, Infallible);